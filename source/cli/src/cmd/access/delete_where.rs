@@ -0,0 +1,59 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{
+    cmd::{self, RequestDeleteAccessKeysWhere},
+    outputln, permission, session,
+};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct CommandArgument {
+    unused_since: Option<String>,
+    disabled: Option<bool>,
+    permission: Option<permission::VaultRoles>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum Response {
+    Deleted { access_keys: Vec<String> },
+    MissingFilter,
+    Denied,
+}
+
+pub async fn delete_where(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument =
+        cmd::parse_arguments(command_argument.context("missing arguments")?)?;
+
+    let response: Response = session::send_request(cmd::Request::DeleteAccessKeysWhere(
+        RequestDeleteAccessKeysWhere {
+            vault,
+            unused_since: command_argument.unused_since,
+            disabled: command_argument.disabled,
+            permission: command_argument.permission,
+        },
+    ))
+    .await?;
+
+    outputln!(
+        "{}",
+        serde_json::to_string(&response).context("failed to serialize the response")?
+    );
+
+    Ok(())
+}
@@ -30,11 +30,26 @@ struct ResponseEntry {
     created: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     last_used: Option<String>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_by: Option<vaulty_client::response::Actor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    name_prefixes: Vec<String>,
+    /* absent from servers that don't report it */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective_status: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 enum Response {
-    Found(ResponseEntry),
+    Found(Box<ResponseEntry>),
     NotFound,
 }
 
@@ -24,25 +24,44 @@ use crate::{
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct CommandArgument {
-    permission: Vec<permission::VaultRoles>,
-    sg: Vec<String>,
+    permission: Option<Vec<permission::VaultRoles>>,
+    preset: Option<permission::VaultRolesPreset>,
+    sg: Option<Vec<String>>,
+    expires_in_secs: Option<u64>,
+    name_prefixes: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct Response {
     access_key: String,
     secret_access_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    authorization: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    curl: Option<String>,
 }
 
 pub async fn insert(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
-    let command_argument: CommandArgument =
-        cmd::parse_arguments(command_argument.context("missing arguments")?)?;
+    let command_argument: CommandArgument = if let Some(command_argument) = command_argument {
+        cmd::parse_arguments(command_argument)?
+    } else {
+        CommandArgument {
+            permission: None,
+            preset: None,
+            sg: None,
+            expires_in_secs: None,
+            name_prefixes: None,
+        }
+    };
 
     let response: Response =
         session::send_request(cmd::Request::CreateAccessKey(RequestCreateAccessKey {
             vault,
             permission: command_argument.permission,
+            preset: command_argument.preset,
             sg: command_argument.sg,
+            expires_in_secs: command_argument.expires_in_secs,
+            name_prefixes: command_argument.name_prefixes,
         }))
         .await?;
 
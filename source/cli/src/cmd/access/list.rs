@@ -30,6 +30,28 @@ struct ResponseEntry {
     created: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     last_used: Option<String>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_by: Option<vaulty_client::response::Actor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    name_prefixes: Vec<String>,
+    /* absent from servers that don't report it */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct CommandArgument {
+    sort_by: Option<cmd::AccessKeySortBy>,
+    order: Option<cmd::SortOrder>,
+    name_prefix: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -37,10 +59,18 @@ struct Response {
     access_keys: Vec<ResponseEntry>,
 }
 
-pub async fn list(vault: String) -> anyhow::Result<()> {
+pub async fn list(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument = match command_argument {
+        Some(command_argument) => cmd::parse_arguments(command_argument)?,
+        None => CommandArgument::default(),
+    };
+
     let response: Response =
         session::send_request(cmd::Request::ListAccessKeys(RequestListAccessKeys {
             vault,
+            sort_by: command_argument.sort_by,
+            order: command_argument.order,
+            name_prefix: command_argument.name_prefix,
         }))
         .await?;
 
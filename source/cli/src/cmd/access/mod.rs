@@ -18,13 +18,23 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 mod change_permission;
 mod change_sg;
 mod delete;
+mod delete_where;
 mod find;
 mod insert;
 mod list;
+mod rotate_secret;
+mod set_enabled;
+mod simulate;
+mod verify_secret;
 
 pub use change_permission::change_permission;
 pub use change_sg::change_sg;
 pub use delete::delete;
+pub use delete_where::delete_where;
 pub use find::find;
 pub use insert::insert;
 pub use list::list;
+pub use rotate_secret::rotate_secret;
+pub use set_enabled::set_enabled;
+pub use simulate::simulate;
+pub use verify_secret::verify_secret;
@@ -0,0 +1,73 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{
+    cmd::{self, RequestVerifyAccessKeySecret},
+    outputln, session, term,
+};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct CommandArgument {
+    secret_access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum ResponseResult {
+    Verified,
+    NotFound,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Response {
+    result: ResponseResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    matches: Option<bool>,
+}
+
+pub async fn verify_secret(
+    vault: String,
+    access_key: String,
+    command_argument: Option<String>,
+) -> anyhow::Result<()> {
+    let secret_access_key = match command_argument {
+        Some(command_argument) => {
+            let command_argument: CommandArgument = cmd::parse_arguments(command_argument)?;
+
+            command_argument.secret_access_key
+        }
+        None => term::prompt_password("secret access key")?,
+    };
+
+    let response: Response = session::send_request(cmd::Request::VerifyAccessKeySecret(
+        RequestVerifyAccessKeySecret {
+            vault,
+            access_key,
+            secret_access_key,
+        },
+    ))
+    .await?;
+
+    outputln!(
+        "{}",
+        serde_json::to_string(&response).context("failed to serialize the response")?
+    );
+
+    Ok(())
+}
@@ -16,154 +16,35 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 pub mod access;
+pub mod role;
 pub mod secret;
+pub mod session;
+pub mod stats;
 pub mod user;
 pub mod vault;
 
-use anyhow::Context;
+pub use vaulty_client::request::*;
 
-use crate::permission;
+/// JSON when it opens with a quoted key, `{"name": ...}` or `[{"name": ...}]`,
+/// the flow style YAML the commands are documented with leaves its keys bare.
+fn is_json(arguments: &str) -> bool {
+    let mut chars = arguments.chars().filter(|c| !c.is_whitespace());
 
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestCreateUser {
-    pub username: String,
-    pub password: String,
-    pub role: permission::UserRole,
-    pub sg: Vec<String>,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestFindUser {
-    pub username: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestDeleteUser {
-    pub username: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestPromoteUser {
-    pub username: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestDemoteUser {
-    pub username: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestChangePasswordForUser {
-    pub username: String,
-    pub password: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestChangeSgForUser {
-    pub username: String,
-    pub sg: Vec<String>,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestCreateAccessKey {
-    pub vault: String,
-    pub permission: Vec<permission::VaultRoles>,
-    pub sg: Vec<String>,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestListAccessKeys {
-    pub vault: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestFindAccessKey {
-    pub vault: String,
-    pub access_key: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestDeleteAccessKey {
-    pub vault: String,
-    pub access_key: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestChangePermissionsForAccessKey {
-    pub vault: String,
-    pub access_key: String,
-    pub permission: Vec<permission::VaultRoles>,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestChangeSgForAccessKey {
-    pub vault: String,
-    pub access_key: String,
-    pub sg: Vec<String>,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestFindVault {
-    pub vault: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestDeleteVault {
-    pub vault: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestInsertSecret {
-    pub secret_name: String,
-    pub vault: String,
-    pub data: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestListSecrets {
-    pub vault: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestFindSecret {
-    pub vault: String,
-    pub secret_name: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct RequestDeleteSecret {
-    pub vault: String,
-    pub secret_name: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub enum Request {
-    CreateUser(RequestCreateUser),
-    ListUsers(),
-    FindUser(RequestFindUser),
-    DeleteUser(RequestDeleteUser),
-    PromoteUser(RequestPromoteUser),
-    DemoteUser(RequestDemoteUser),
-    ChangePasswordForUser(RequestChangePasswordForUser),
-    ChangeSgForUser(RequestChangeSgForUser),
-    CreateAccessKey(RequestCreateAccessKey),
-    ListAccessKeys(RequestListAccessKeys),
-    FindAccessKey(RequestFindAccessKey),
-    DeleteAccessKey(RequestDeleteAccessKey),
-    ChangePermissionForAccessKey(RequestChangePermissionsForAccessKey),
-    ChangeSgForAccessKey(RequestChangeSgForAccessKey),
-    ListVaults(),
-    FindVault(RequestFindVault),
-    DeleteVault(RequestDeleteVault),
-    InsertSecret(RequestInsertSecret),
-    ListSecrets(RequestListSecrets),
-    FindSecret(RequestFindSecret),
-    DeleteSecret(RequestDeleteSecret),
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some('{'), Some('"' | '}'), _)
+            | (Some('['), Some('"' | ']'), _)
+            | (Some('['), Some('{'), Some('"' | '}'))
+    )
 }
 
 pub fn parse_arguments<T>(arguments: String) -> anyhow::Result<T>
 where
     T: serde::de::DeserializeOwned,
 {
-    Ok(serde_yaml::from_str(&arguments).context("invalid arguments")?)
+    if is_json(&arguments) {
+        serde_json::from_str(&arguments).map_err(|e| anyhow::anyhow!("invalid JSON arguments: {e}"))
+    } else {
+        serde_yaml::from_str(&arguments).map_err(|e| anyhow::anyhow!("invalid YAML arguments: {e}"))
+    }
 }
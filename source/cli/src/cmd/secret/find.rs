@@ -25,47 +25,135 @@ use crate::{
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct CommandArgument {
     decode: Option<bool>,
+    expected_sha256: Option<String>,
+    save_to: Option<String>,
+    overwrite: Option<bool>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct ResponseEntry {
     created: String,
     secret: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_by: Option<vaulty_client::response::Actor>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 enum Response {
     Found(ResponseEntry),
+    Unchanged,
     NotFound,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+struct SavedEntry {
+    created: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_by: Option<vaulty_client::response::Actor>,
+    saved_to: String,
+    bytes: usize,
+}
+
+/// Writes the raw secret to the file, readable by the owner only. An
+/// existing file is only replaced with `overwrite`.
+pub(crate) fn save_secret(filename: &str, data: &[u8], overwrite: bool) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    let mut file = match options.open(filename) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(anyhow::anyhow!(
+                "'{filename}' already exists, pass overwrite: true to replace it"
+            ))
+        }
+        Err(e) => return Err(e).context(format!("failed to open '{filename}' for writing")),
+    };
+
+    /* the mode only applies to a new file, an overwritten one is narrowed too */
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .context(format!("failed to set the permissions of '{filename}'"))?;
+    }
+
+    file.write_all(data)
+        .context(format!("failed to write to '{filename}'"))?;
+
+    Ok(())
+}
+
 pub async fn find(
     vault: String,
     secret_name: String,
     command_argument: Option<String>,
 ) -> anyhow::Result<()> {
-    let decode = {
-        if let Some(command_argument) = command_argument {
-            let command_argument: CommandArgument = cmd::parse_arguments(command_argument)?;
-
-            if let Some(decode) = command_argument.decode {
-                decode
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+    let command_argument: CommandArgument = match command_argument {
+        Some(command_argument) => cmd::parse_arguments(command_argument)?,
+        None => CommandArgument {
+            decode: None,
+            expected_sha256: None,
+            save_to: None,
+            overwrite: None,
+        },
     };
+    let decode = command_argument.decode.unwrap_or(false);
+
+    if decode && command_argument.save_to.is_some() {
+        return Err(anyhow::anyhow!("decode and save_to can't be used together"));
+    }
 
     let response: Response = session::send_request(cmd::Request::FindSecret(RequestFindSecret {
         vault,
         secret_name,
+        expected_sha256: command_argument.expected_sha256,
+        compression: None,
     }))
     .await?;
 
+    if let Response::Unchanged = response {
+        outputln!(
+            "{}",
+            serde_json::to_string(&response).context("failed to serialize the response")?
+        );
+    }
+
     if let Response::Found(mut document) = response {
-        if decode {
+        if let Some(save_to) = command_argument.save_to {
+            let data = base64_simd::STANDARD
+                .decode_to_vec(document.secret)
+                .context("failed to decode the response")?;
+
+            save_secret(&save_to, &data, command_argument.overwrite.unwrap_or(false))?;
+
+            outputln!(
+                "{}",
+                serde_json::to_string(&SavedEntry {
+                    created: document.created,
+                    created_by: document.created_by,
+                    saved_to: save_to,
+                    bytes: data.len(),
+                })
+                .context("failed to serialize the response")?
+            );
+        } else if decode {
             let data = base64_simd::STANDARD
                 .decode_to_vec(document.secret)
                 .context("failed to decode the response")?;
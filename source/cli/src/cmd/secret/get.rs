@@ -0,0 +1,83 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{cmd, outputln, session};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct CommandArgument {
+    names: Vec<String>,
+    decode: Option<bool>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct FoundEntry {
+    created: String,
+    secret: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum EntryResult {
+    Found(FoundEntry),
+    NotFound,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct ResponseEntry {
+    secret_name: String,
+    result: EntryResult,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum Response {
+    Secrets(Vec<ResponseEntry>),
+    TooLarge { max_secret_bytes: usize },
+    Denied,
+}
+
+pub async fn get(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument =
+        cmd::parse_arguments(command_argument.context("missing arguments")?)?;
+    let decode = command_argument.decode.unwrap_or(false);
+
+    let mut response: Response =
+        session::send_request(cmd::Request::GetSecrets(cmd::RequestGetSecrets {
+            vault,
+            names: command_argument.names,
+        }))
+        .await?;
+
+    if let (Response::Secrets(entries), true) = (&mut response, decode) {
+        for entry in entries {
+            if let EntryResult::Found(document) = &mut entry.result {
+                let data = base64_simd::STANDARD
+                    .decode_to_vec(&document.secret)
+                    .context("failed to decode the response")?;
+
+                document.secret = String::from_utf8_lossy(&data).to_string();
+            }
+        }
+    }
+
+    outputln!(
+        "{}",
+        serde_json::to_string(&response).context("failed to serialize the response")?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{
+    cmd::{self, RequestSecretHistory},
+    outputln, session,
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Response {
+    entries: Vec<vaulty_client::response::SecretHistoryEntry>,
+}
+
+pub async fn history(vault: String, secret_name: String) -> anyhow::Result<()> {
+    let response: Response =
+        session::send_request(cmd::Request::SecretHistory(RequestSecretHistory {
+            vault,
+            secret_name,
+        }))
+        .await?;
+
+    for entry in response.entries {
+        outputln!(
+            "{}",
+            serde_json::to_string(&entry).context("failed to serialize the response")?
+        );
+    }
+
+    Ok(())
+}
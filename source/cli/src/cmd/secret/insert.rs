@@ -25,18 +25,26 @@ use crate::{
 };
 
 const MAXIMUM_DATA_SIZE: usize = 128 * 1042 * 1024 - 1024;
+const PROGRESS_THRESHOLD: u64 = 1024 * 1024;
+const READ_CHUNK_SIZE: usize = 3 * 64 * 1024;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct CommandArgument {
     text: Option<String>,
     binary: Option<String>,
     file: Option<String>,
+    alias: Option<cmd::SecretAlias>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 enum ResponseResult {
     Inserted,
     Updated,
+    InvalidName,
+    NameTaken,
+    InvalidValue,
+    AliasTargetMissing,
+    CircularAlias,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -44,6 +52,94 @@ struct Response {
     result: ResponseResult,
 }
 
+/// Reads and base64-encodes the file chunk by chunk, each chunk is a
+/// multiple of 3 bytes so the encoded chunks join into one valid string.
+/// Files above `PROGRESS_THRESHOLD` report progress on stderr, which is
+/// only drawn when stderr is a terminal.
+fn read_file(filename: &str) -> anyhow::Result<String> {
+    read_file_to(filename, indicatif::ProgressDrawTarget::stderr())
+}
+
+pub fn read_file_to(
+    filename: &str,
+    progress_target: indicatif::ProgressDrawTarget,
+) -> anyhow::Result<String> {
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(false)
+        .open(filename)
+        .context(format!("failed to open '{filename}' for reading"))?;
+
+    let length = file
+        .metadata()
+        .context(format!("failed to read from {filename}"))?
+        .len();
+    let encoded_length = (length as usize).div_ceil(3) * 4;
+
+    if encoded_length > MAXIMUM_DATA_SIZE {
+        return Err(anyhow::anyhow!("the data is too big"));
+    }
+
+    let progress = if length > PROGRESS_THRESHOLD {
+        let progress = indicatif::ProgressBar::with_draw_target(Some(length), progress_target);
+
+        if let Ok(style) = indicatif::ProgressStyle::with_template(
+            "{msg} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec})",
+        ) {
+            progress.set_style(style.progress_chars("=> "));
+        }
+
+        progress.set_message(format!("reading {filename}"));
+
+        Some(progress)
+    } else {
+        None
+    };
+
+    let mut data = String::with_capacity(encoded_length);
+    let mut chunk = vec![0; READ_CHUNK_SIZE];
+
+    loop {
+        let mut filled = 0;
+
+        while filled < chunk.len() {
+            let read = file
+                .read(&mut chunk[filled..])
+                .context(format!("failed to read from {filename}"))?;
+
+            if read == 0 {
+                break;
+            }
+
+            filled += read;
+        }
+
+        if filled == 0 {
+            break;
+        }
+
+        base64_simd::STANDARD.encode_append(&chunk[..filled], &mut data);
+
+        if let Some(progress) = &progress {
+            progress.inc(filled as u64);
+        }
+
+        if data.len() > MAXIMUM_DATA_SIZE {
+            return Err(anyhow::anyhow!("the data is too big"));
+        }
+
+        if filled < chunk.len() {
+            break;
+        }
+    }
+
+    if let Some(progress) = progress {
+        progress.finish_and_clear();
+    }
+
+    Ok(data)
+}
+
 pub async fn insert(
     vault: String,
     secret_name: String,
@@ -72,24 +168,37 @@ pub async fn insert(
         ));
     }
 
+    if let Some(alias) = command_argument.alias {
+        if command_argument.text.is_some()
+            || command_argument.binary.is_some()
+            || command_argument.file.is_some()
+        {
+            return Err(anyhow::anyhow!("an alias can't hold a value"));
+        }
+
+        let response: Response =
+            session::send_request(cmd::Request::InsertSecret(RequestInsertSecret {
+                vault,
+                secret_name,
+                data: String::new(),
+                alias: Some(alias),
+            }))
+            .await?;
+
+        outputln!(
+            "{}",
+            serde_json::to_string(&response).context("failed to serialize the response")?
+        );
+
+        return Ok(());
+    }
+
     let data = if let Some(data) = command_argument.text {
         base64_simd::STANDARD.encode_to_string(data)
     } else if let Some(data) = command_argument.binary {
         data
     } else if let Some(filename) = command_argument.file {
-        let mut content = Vec::new();
-
-        let mut file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(false)
-            .open(&filename)
-            .context(format!("failed to open '{filename}' for reading"))?;
-
-        let _ = file
-            .read_to_end(&mut content)
-            .context(format!("failed to read from {filename}"))?;
-
-        base64_simd::STANDARD.encode_to_string(content)
+        read_file(&filename)?
     } else {
         return Err(anyhow::anyhow!("invalid data"));
     };
@@ -103,6 +212,7 @@ pub async fn insert(
             vault,
             secret_name,
             data,
+            alias: None,
         }))
         .await?;
 
@@ -26,6 +26,9 @@ use crate::{
 struct ResponseEntry {
     created: String,
     secret_name: String,
+    last_accessed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_by: Option<vaulty_client::response::Actor>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
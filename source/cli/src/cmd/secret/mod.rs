@@ -16,11 +16,21 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 mod delete;
-mod find;
-mod insert;
+mod exists;
+pub(crate) mod find;
+mod generate;
+mod get;
+mod history;
+pub(crate) mod insert;
 mod list;
+mod restore;
 
 pub use delete::delete;
+pub use exists::exists;
 pub use find::find;
+pub use generate::generate;
+pub use get::get;
+pub use history::history;
 pub use insert::insert;
 pub use list::list;
+pub use restore::restore;
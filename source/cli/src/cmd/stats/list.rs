@@ -0,0 +1,60 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{cmd, outputln, session};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SessionEntry {
+    id: u64,
+    ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    requests: u64,
+    bytes_received: u64,
+    bytes_sent: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AccessKeyEntry {
+    vault: String,
+    access_key: String,
+    requests: u64,
+    bytes_received: u64,
+    bytes_sent: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum Response {
+    Stats {
+        sessions: Vec<SessionEntry>,
+        access_keys: Vec<AccessKeyEntry>,
+    },
+    Denied,
+}
+
+pub async fn list() -> anyhow::Result<()> {
+    let response: Response = session::send_request(cmd::Request::SessionStats()).await?;
+
+    outputln!(
+        "{}",
+        serde_json::to_string(&response).context("failed to serialize the response")?
+    );
+
+    Ok(())
+}
@@ -59,6 +59,7 @@ pub async fn change_password(
         cmd::RequestChangePasswordForUser {
             username: username,
             password: password,
+            current_password: None,
         },
     ))
     .await?;
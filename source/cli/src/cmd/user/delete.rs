@@ -23,6 +23,7 @@ use crate::{cmd, outputln, session};
 enum ResponseState {
     Deleted,
     NotFound,
+    Protected,
     Denied,
 }
 
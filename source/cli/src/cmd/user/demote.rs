@@ -25,6 +25,8 @@ enum ResponseResult {
     Demoted,
     NoChange,
     NotFound,
+    LastAdmin,
+    Protected,
     Denied,
 }
 
@@ -29,6 +29,8 @@ struct ResponseEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     last_login: Option<String>,
     sg: Vec<String>,
+    #[serde(default)]
+    locked: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
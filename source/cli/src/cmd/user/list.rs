@@ -17,7 +17,17 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anyhow::Context;
 
-use crate::{cmd, outputln, permission, session};
+use crate::{
+    cmd::{self, RequestListUsers},
+    outputln, permission, session,
+};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct CommandArgument {
+    after: Option<String>,
+    limit: Option<usize>,
+    role: Option<permission::UserRole>,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct ResponseEntry {
@@ -26,15 +36,29 @@ struct ResponseEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     last_login: Option<String>,
     sg: Vec<String>,
+    #[serde(default)]
+    locked: bool,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 struct Response {
     users: Vec<ResponseEntry>,
+    #[serde(default)]
+    next: Option<String>,
 }
 
-pub async fn list() -> anyhow::Result<()> {
-    let response: Response = session::send_request(cmd::Request::ListUsers()).await?;
+pub async fn list(command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument = match command_argument {
+        Some(command_argument) => cmd::parse_arguments(command_argument)?,
+        None => CommandArgument::default(),
+    };
+
+    let response: Response = session::send_request(cmd::Request::ListUsers(RequestListUsers {
+        after: command_argument.after,
+        limit: command_argument.limit,
+        role: command_argument.role,
+    }))
+    .await?;
 
     for user in response.users {
         outputln!(
@@ -43,5 +67,13 @@ pub async fn list() -> anyhow::Result<()> {
         );
     }
 
+    if let Some(next) = response.next {
+        outputln!(
+            "{}",
+            serde_json::to_string(&serde_json::json!({ "next": next }))
+                .context("failed to serialize the response")?
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,61 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{cmd, outputln, permission, session};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct CommandArgument {
+    permission: Option<Vec<permission::VaultRoles>>,
+    sg: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum ResponseResult {
+    Created,
+    AlreadyExists,
+    InvalidName,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+pub async fn create(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument = match command_argument {
+        Some(command_argument) => cmd::parse_arguments(command_argument)?,
+        None => CommandArgument::default(),
+    };
+
+    let response: Response =
+        session::send_request(cmd::Request::CreateVault(cmd::RequestCreateVault {
+            vault,
+            permission: command_argument.permission,
+            sg: command_argument.sg,
+        }))
+        .await?;
+
+    outputln!(
+        "{}",
+        serde_json::to_string(&response).context("failed to serialize the response")?
+    );
+
+    Ok(())
+}
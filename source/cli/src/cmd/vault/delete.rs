@@ -19,13 +19,21 @@ use anyhow::Context;
 
 use crate::{
     cmd::{self, RequestDeleteVault},
-    outputln, session,
+    outputln, session, term,
 };
 
+#[derive(Debug, Clone, serde::Deserialize)]
+struct CommandArgument {
+    confirm: Option<String>,
+    force: Option<bool>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 enum ResponseResult {
     Deleted,
     NotFound,
+    ConfirmationMismatch,
+    Denied,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -33,9 +41,32 @@ struct Response {
     result: ResponseResult,
 }
 
-pub async fn delete(vault: String) -> anyhow::Result<()> {
-    let response: Response =
-        session::send_request(cmd::Request::DeleteVault(RequestDeleteVault { vault })).await?;
+pub async fn delete(vault: String, command_argument: Option<String>) -> anyhow::Result<()> {
+    let (confirm, force) = if let Some(command_argument) = command_argument {
+        let command_argument: CommandArgument = cmd::parse_arguments(command_argument)?;
+
+        (
+            command_argument.confirm,
+            command_argument.force.unwrap_or(false),
+        )
+    } else {
+        (None, false)
+    };
+
+    let confirm = if confirm.is_none() && !force {
+        Some(term::prompt_input(&format!(
+            "type '{vault}' to confirm the deletion"
+        ))?)
+    } else {
+        confirm
+    };
+
+    let response: Response = session::send_request(cmd::Request::DeleteVault(RequestDeleteVault {
+        vault,
+        confirm,
+        force,
+    }))
+    .await?;
 
     outputln!(
         "{}",
@@ -19,7 +19,7 @@ use anyhow::Context;
 
 use crate::{
     cmd::{self, RequestFindVault},
-    outputln, session,
+    outputln, permission, session,
 };
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -28,6 +28,10 @@ struct ResponseEntry {
     created: String,
     secrets_count: i64,
     access_keys_count: i64,
+    default_permissions: Option<Vec<permission::VaultRoles>>,
+    default_sg: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value_schema: Option<cmd::ValueSchema>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
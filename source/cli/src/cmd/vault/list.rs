@@ -17,7 +17,17 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anyhow::Context;
 
-use crate::{cmd, outputln, session};
+use crate::{
+    cmd::{self, RequestListVaults},
+    outputln, session,
+};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct CommandArgument {
+    sort_by: Option<cmd::VaultSortBy>,
+    order: Option<cmd::SortOrder>,
+    name_prefix: Option<String>,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct ResponseEntry {
@@ -32,8 +42,18 @@ struct Response {
     vaults: Vec<ResponseEntry>,
 }
 
-pub async fn list() -> anyhow::Result<()> {
-    let response: Response = session::send_request(cmd::Request::ListVaults()).await?;
+pub async fn list(command_argument: Option<String>) -> anyhow::Result<()> {
+    let command_argument: CommandArgument = match command_argument {
+        Some(command_argument) => cmd::parse_arguments(command_argument)?,
+        None => CommandArgument::default(),
+    };
+
+    let response: Response = session::send_request(cmd::Request::ListVaults(RequestListVaults {
+        sort_by: command_argument.sort_by,
+        order: command_argument.order,
+        name_prefix: command_argument.name_prefix,
+    }))
+    .await?;
 
     for user in response.vaults {
         outputln!(
@@ -0,0 +1,81 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use anyhow::Context;
+
+use crate::{cmd, outputln, session};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct FailedEntry {
+    vault: String,
+    secret_name: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct DriftEntry {
+    vault: String,
+    secrets_count: Option<i64>,
+    actual_secrets_count: i64,
+    access_keys_count: Option<i64>,
+    actual_access_keys_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+enum ResponseResult {
+    Failed(FailedEntry),
+    Drift(DriftEntry),
+    Done {
+        secrets: i64,
+        failed: i64,
+        drifted: i64,
+    },
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+pub async fn verify_integrity(vault: Option<String>) -> anyhow::Result<()> {
+    /* the results are streamed for as long as the scan runs, so the command
+    timeout doesn't apply */
+    let mut response: Response = session::send_request_with_timeout(
+        cmd::Request::VerifyIntegrity(cmd::RequestVerifyIntegrity { vault }),
+        None,
+    )
+    .await?;
+
+    loop {
+        outputln!(
+            "{}",
+            serde_json::to_string(&response).context("failed to serialize the response")?
+        );
+
+        if matches!(
+            response.result,
+            ResponseResult::Done { .. } | ResponseResult::Denied
+        ) {
+            break;
+        }
+
+        response = session::receive_response().await?;
+    }
+
+    Ok(())
+}
@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::term;
+use crate::{term, warnln};
 
 #[derive(Debug, Clone, Default)]
 pub struct Arguments {
@@ -25,15 +25,44 @@ pub struct Arguments {
     pub script_file: Option<String>,
     pub tls: bool,
     pub disabled_tls_verification: bool,
+    pub tls_pin: Option<String>,
+    pub timeout: Option<std::time::Duration>,
+    /// Set by `--no-insecure-http-warning`, for plaintext connections made on purpose.
+    pub silence_insecure_warning: bool,
 }
 
-fn process_command_line() -> anyhow::Result<Arguments> {
+fn parse_timeout(value: &str) -> anyhow::Result<std::time::Duration> {
+    match value.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(std::time::Duration::from_secs(seconds)),
+        _ => Err(anyhow::anyhow!(
+            "invalid timeout '{value}', expected a positive number of seconds"
+        )),
+    }
+}
+
+/// Parses the arguments given after the program's name.
+pub(crate) fn parse(args: &[String]) -> anyhow::Result<Arguments> {
     let mut result = Arguments::default();
     let mut url_parsed = false;
-    let args: Vec<String> = std::env::args().collect();
-    let mut args = args[1..].iter();
+    let mut args = args.iter();
 
     while let Some(arg) = args.next() {
+        if arg == "--timeout" {
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("missing value for '--timeout'"))?;
+
+            result.timeout = Some(parse_timeout(value)?);
+
+            continue;
+        }
+
+        if arg == "--no-insecure-http-warning" {
+            result.silence_insecure_warning = true;
+
+            continue;
+        }
+
         let uri = url::Url::parse(&arg)
             .map_err(|_| anyhow::anyhow!("the argument '{arg}' is invalid URI"))?;
 
@@ -65,6 +94,20 @@ fn process_command_line() -> anyhow::Result<Arguments> {
                     "1" | "true" => result.disabled_tls_verification = true,
                     _ => {}
                 },
+                "timeout" if result.timeout.is_none() => {
+                    result.timeout = Some(parse_timeout(&value)?);
+                }
+                "tlspin" => {
+                    let pin = value.replace(':', "").to_lowercase();
+
+                    if pin.len() != 64 || !pin.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(anyhow::anyhow!(
+                            "invalid 'tlsPin' in URI '{arg}', expected SHA-256 in hex"
+                        ));
+                    }
+
+                    result.tls_pin = Some(pin);
+                }
                 _ => {}
             }
         }
@@ -98,6 +141,51 @@ fn process_command_line() -> anyhow::Result<Arguments> {
     Ok(result)
 }
 
+fn process_command_line() -> anyhow::Result<Arguments> {
+    let args: Vec<String> = std::env::args().collect();
+
+    parse(&args[1..])
+}
+
+fn is_loopback(address: &str) -> bool {
+    address.eq_ignore_ascii_case("localhost")
+        || address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|v| v.is_loopback())
+}
+
+/// What the user should know about the connection before any credentials are sent.
+pub fn security_warnings(arguments: &Arguments) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    /* a pin always means TLS */
+    let plaintext = !arguments.tls && arguments.tls_pin.is_none();
+    let remote = arguments
+        .remote_address
+        .as_ref()
+        .is_some_and(|(address, _)| !is_loopback(address));
+
+    if plaintext && remote && !arguments.silence_insecure_warning {
+        warnings.push(
+            "WARNING: TLS is off, the password and the secrets travel in cleartext, \
+             add tls=true to the URI or --no-insecure-http-warning if that's intended"
+                .to_owned(),
+        );
+    }
+
+    if arguments.disabled_tls_verification && arguments.tls_pin.is_none() {
+        warnings.push(
+            "WARNING: the server certificate isn't verified (tlsAllowInvalidCerts), \
+             anyone in the way can read the connection, consider tlsPin instead"
+                .to_owned(),
+        );
+    }
+
+    warnings
+}
+
 pub async fn initialize() -> anyhow::Result<Arguments> {
     let mut arguments = process_command_line()?;
 
@@ -109,6 +197,10 @@ pub async fn initialize() -> anyhow::Result<Arguments> {
         }
     }
 
+    for warning in security_warnings(&arguments) {
+        warnln!("{warning}");
+    }
+
     if arguments.username.is_none() {
         arguments.username = Some(term::prompt_input("username")?);
     }
@@ -25,6 +25,9 @@ pub mod permission;
 pub mod session;
 pub mod term;
 
+#[cfg(test)]
+mod tests;
+
 #[cfg(not(target_os = "windows"))]
 fn fix_terminal() -> std::io::Result<()> {
     let stdin = std::io::stdin();
@@ -35,22 +35,20 @@ pub fn parse<'a>(command: &str) -> Result<Vec<(String, Option<String>)>, &'a str
 
     for (len, c) in command.chars().enumerate() {
         if in_argument {
-            if c == '"' {
-                if let Some(pc) = previous_char {
-                    if pc != '\\' {
-                        in_quote1 = !in_quote1;
-                    }
-                } else {
+            /* the quotes are kept, the argument is YAML or JSON and they mean
+            the same there, they're only tracked so a ')' inside them doesn't end it */
+            if c == '"' && !in_quote2 {
+                if previous_char != Some('\\') {
                     in_quote1 = !in_quote1;
                 }
-            } else if c == '\'' {
-                if let Some(pc) = previous_char {
-                    if pc != '\\' {
-                        in_quote2 = !in_quote2;
-                    }
-                } else {
+
+                argument.push(c);
+            } else if c == '\'' && !in_quote1 {
+                if previous_char != Some('\\') {
                     in_quote2 = !in_quote2;
                 }
+
+                argument.push(c);
             } else if c == ')' && !in_quote1 && !in_quote2 {
                 in_argument = false;
                 argument_already_defined = true;
@@ -15,25 +15,4 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
-pub enum UserRole {
-    Admin,
-    User,
-}
-
-impl ToString for UserRole {
-    fn to_string(&self) -> String {
-        match self {
-            UserRole::Admin => "Admin".to_owned(),
-            UserRole::User => "User".to_owned(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
-pub enum VaultRoles {
-    ListSecrets,
-    DeleteSecrets,
-    CreateSecrets,
-    DecryptSecrets,
-}
+pub use vaulty_client::permission::*;
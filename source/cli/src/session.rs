@@ -16,18 +16,11 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use anyhow::Context;
-use futures::{SinkExt, StreamExt};
-use std::sync::Arc;
-use tungstenite::{http::StatusCode, Message};
 
-use crate::{cmd, cmdline, errorln, outputln, parser, term};
+use crate::{cmd, cmdline, errorln, outputln, parser, permission, term};
 
-static COMMAND_STREAM_TX: once_cell::sync::Lazy<
-    Arc<tokio::sync::Mutex<Option<tokio::sync::mpsc::Sender<Message>>>>,
-> = once_cell::sync::Lazy::new(|| Arc::new(tokio::sync::Mutex::new(None)));
-static COMMAND_STREAM_RX: once_cell::sync::Lazy<
-    Arc<tokio::sync::Mutex<Option<tokio::sync::mpsc::Receiver<Message>>>>,
-> = once_cell::sync::Lazy::new(|| Arc::new(tokio::sync::Mutex::new(None)));
+static CLIENT: once_cell::sync::Lazy<tokio::sync::Mutex<Option<vaulty_client::Client>>> =
+    once_cell::sync::Lazy::new(|| tokio::sync::Mutex::new(None));
 
 async fn commands_handle(
     command_input_object: &mut term::CommandInput,
@@ -49,7 +42,16 @@ async fn commands_handle(
         }
     };
 
-    let command = parser::parse(&input).map_err(|e| anyhow::anyhow!("{}", e.to_string()))?;
+    execute(&input, username).await
+}
+
+/* pressing enter on an empty prompt just shows it again */
+pub(crate) async fn execute(input: &str, username: &str) -> anyhow::Result<()> {
+    let command = parser::parse(input).map_err(|e| anyhow::anyhow!("{}", e.to_string()))?;
+
+    if command.is_empty() {
+        return Ok(());
+    }
 
     let command: Vec<(&str, Option<String>)> = command
         .iter()
@@ -57,8 +59,11 @@ async fn commands_handle(
         .collect();
 
     match &command[..] {
+        [("passwd", None)] => {
+            return cmd::user::passwd(username.to_string(), term::prompt_password).await
+        }
         [("user", None), ("insert", arg)] => return cmd::user::insert(arg.clone()).await,
-        [("user", None), ("list", None)] => return cmd::user::list().await,
+        [("user", None), ("list", arg)] => return cmd::user::list(arg.clone()).await,
         [("user", None), (username, None), ("find", None)] => {
             return cmd::user::find(username.to_string()).await
         }
@@ -77,15 +82,48 @@ async fn commands_handle(
         [("user", None), (username, None), ("demote", None)] => {
             return cmd::user::demote(username.to_string()).await
         }
-        [("vault", None), ("list", None)] => return cmd::vault::list().await,
+        [("user", None), (username, None), ("changeRole", arg)] => {
+            return cmd::user::change_role(username.to_string(), arg.clone()).await
+        }
+        [("user", None), (username, None), ("lock", None)] => {
+            return cmd::user::lock(username.to_string()).await
+        }
+        [("user", None), (username, None), ("unlock", None)] => {
+            return cmd::user::unlock(username.to_string()).await
+        }
+        [("vault", None), ("list", arg)] => return cmd::vault::list(arg.clone()).await,
+        [("stats", None), ("list", None)] => return cmd::stats::list().await,
+        [("session", None), ("list", None)] => return cmd::session::list().await,
+        [("session", None), (id, None), ("kill", None)] => {
+            return cmd::session::kill(id.to_string()).await
+        }
+        [("role", None), ("list", None)] => return cmd::role::list().await,
+        [("vault", None), ("verifyIntegrity", None)] => {
+            return cmd::vault::verify_integrity(None).await
+        }
         [("vault", None), (vault, None), ("find", None)] => {
             return cmd::vault::find(vault.to_string()).await
         }
-        [("vault", None), (vault, None), ("delete", None)] => {
-            return cmd::vault::delete(vault.to_string()).await
+        [("vault", None), (vault, None), ("create", arg)] => {
+            return cmd::vault::create(vault.to_string(), arg.clone()).await
         }
-        [("access", None), (vault, None), ("list", None)] => {
-            return cmd::access::list(vault.to_string()).await
+        [("vault", None), (vault, None), ("delete", arg)] => {
+            return cmd::vault::delete(vault.to_string(), arg.clone()).await
+        }
+        [("vault", None), (vault, None), ("rename", arg)] => {
+            return cmd::vault::rename(vault.to_string(), arg.clone()).await
+        }
+        [("vault", None), (vault, None), ("setDefaults", arg)] => {
+            return cmd::vault::set_defaults(vault.to_string(), arg.clone()).await
+        }
+        [("vault", None), (vault, None), ("setValueSchema", arg)] => {
+            return cmd::vault::set_value_schema(vault.to_string(), arg.clone()).await
+        }
+        [("vault", None), (vault, None), ("verifyIntegrity", None)] => {
+            return cmd::vault::verify_integrity(Some(vault.to_string())).await
+        }
+        [("access", None), (vault, None), ("list", arg)] => {
+            return cmd::access::list(vault.to_string(), arg.clone()).await
         }
         [("access", None), (vault, None), (access_key, None), ("find", None)] => {
             return cmd::access::find(vault.to_string(), access_key.to_string()).await
@@ -93,6 +131,9 @@ async fn commands_handle(
         [("access", None), (vault, None), ("insert", arg)] => {
             return cmd::access::insert(vault.to_string(), arg.clone()).await
         }
+        [("access", None), (vault, None), ("deleteWhere", arg)] => {
+            return cmd::access::delete_where(vault.to_string(), arg.clone()).await
+        }
         [("access", None), (vault, None), (access_key, None), ("delete", None)] => {
             return cmd::access::delete(vault.to_string(), access_key.to_string()).await
         }
@@ -108,19 +149,56 @@ async fn commands_handle(
             return cmd::access::change_sg(vault.to_string(), access_key.to_string(), arg.clone())
                 .await
         }
+        [("access", None), (vault, None), (access_key, None), ("enable", None)] => {
+            return cmd::access::set_enabled(vault.to_string(), access_key.to_string(), true).await
+        }
+        [("access", None), (vault, None), (access_key, None), ("disable", None)] => {
+            return cmd::access::set_enabled(vault.to_string(), access_key.to_string(), false).await
+        }
+        [("access", None), (vault, None), (access_key, None), ("rotateSecret", None)] => {
+            return cmd::access::rotate_secret(vault.to_string(), access_key.to_string()).await
+        }
+        [("access", None), (vault, None), (access_key, None), ("simulate", arg)] => {
+            return cmd::access::simulate(vault.to_string(), access_key.to_string(), arg.clone())
+                .await
+        }
+        [("access", None), (vault, None), (access_key, None), ("verifySecret", arg)] => {
+            return cmd::access::verify_secret(
+                vault.to_string(),
+                access_key.to_string(),
+                arg.clone(),
+            )
+            .await
+        }
         [("secret", None), (vault, None), ("list", None)] => {
             return cmd::secret::list(vault.to_string()).await
         }
+        [("secret", None), (vault, None), ("get", arg)] => {
+            return cmd::secret::get(vault.to_string(), arg.clone()).await
+        }
         [("secret", None), (vault, None), (secret_name, None), ("insert", arg)] => {
             return cmd::secret::insert(vault.to_string(), secret_name.to_string(), arg.clone())
                 .await
         }
+        [("secret", None), (vault, None), (secret_name, None), ("generate", arg)] => {
+            return cmd::secret::generate(vault.to_string(), secret_name.to_string(), arg.clone())
+                .await
+        }
         [("secret", None), (vault, None), (secret_name, None), ("find", arg)] => {
             return cmd::secret::find(vault.to_string(), secret_name.to_string(), arg.clone()).await
         }
         [("secret", None), (vault, None), (secret_name, None), ("delete", None)] => {
             return cmd::secret::delete(vault.to_string(), secret_name.to_string()).await
         }
+        [("secret", None), (vault, None), (secret_name, None), ("restore", None)] => {
+            return cmd::secret::restore(vault.to_string(), secret_name.to_string()).await
+        }
+        [("secret", None), (vault, None), (secret_name, None), ("exists", None)] => {
+            return cmd::secret::exists(vault.to_string(), secret_name.to_string()).await
+        }
+        [("secret", None), (vault, None), (secret_name, None), ("history", None)] => {
+            return cmd::secret::history(vault.to_string(), secret_name.to_string()).await
+        }
         _ => {}
     }
 
@@ -128,33 +206,26 @@ async fn commands_handle(
 }
 
 async fn login(username: &str, password: &str) -> anyhow::Result<String> {
-    #[derive(Debug, Clone, serde::Serialize)]
-    struct Request {
-        username: String,
-        password: String,
-    }
+    let mut client = CLIENT.lock().await;
+    let client = client.as_mut().expect("session hasn't been initialized");
 
-    #[derive(Debug, Clone, serde::Deserialize)]
-    enum ResponseResult {
-        Granted,
-        Denied,
-    }
+    let response = client.login(username, password).await?;
 
-    #[derive(Debug, Clone, serde::Deserialize)]
-    struct Response {
-        result: ResponseResult,
-        node_name: Option<String>,
+    if let Some(permission::UserRole::User) = response.role {
+        outputln!("logged in as a non-admin user, admin only commands will be denied");
     }
 
-    let response: Response = send_request(&Request {
-        username: username.to_owned(),
-        password: password.to_owned(),
-    })
-    .await?;
+    Ok(response.node_name.unwrap_or("N/A".to_owned()))
+}
 
-    match response.result {
-        ResponseResult::Granted => Ok(response.node_name.unwrap_or("N/A".to_owned())),
-        ResponseResult::Denied => Err(anyhow::anyhow!("invalid credentials")),
+/* the code stays the same between releases, so scripts match on it
+rather than on the message */
+pub(crate) fn request_error(e: vaulty_client::Error) -> anyhow::Error {
+    match e {
+        vaulty_client::Error::Server(code, message) => {
+            anyhow::anyhow!("{} [{:?}]", message, code)
+        }
+        e => e.into(),
     }
 }
 
@@ -163,39 +234,37 @@ where
     Input: serde::Serialize,
     Output: serde::de::DeserializeOwned,
 {
-    let request = serde_json::to_string(&data).context("failed to serialize the request")?;
-
-    let mut rx = COMMAND_STREAM_RX.lock().await;
-    let rx = rx.as_mut().expect("session hasn't been initialized");
+    let mut client = CLIENT.lock().await;
+    let client = client.as_mut().expect("session hasn't been initialized");
 
-    let tx = COMMAND_STREAM_TX.lock().await;
-    let tx = tx.as_ref().expect("session hasn't been initialized");
+    client.request(&data).await.map_err(request_error)
+}
 
-    tx.send(Message::Text(request))
-        .await
-        .context("failed to send data to the command stream")?;
+pub async fn send_request_with_timeout<Input, Output>(
+    data: Input,
+    timeout: Option<std::time::Duration>,
+) -> anyhow::Result<Output>
+where
+    Input: serde::Serialize,
+    Output: serde::de::DeserializeOwned,
+{
+    let mut client = CLIENT.lock().await;
+    let client = client.as_mut().expect("session hasn't been initialized");
 
-    let response = rx
-        .recv()
+    client
+        .request_with_timeout(&data, timeout)
         .await
-        .context("failed to read from command stream")?;
-
-    let mut response = response.to_string();
-
-    let response: serde_json::Value =
-        serde_json::from_str(&mut response).context("failed to deserialize the return data")?;
+        .map_err(request_error)
+}
 
-    if let Some(error) = response.get("error") {
-        Err(anyhow::anyhow!(
-            "{}",
-            error.as_str().unwrap_or("N/A").to_string()
-        ))
-    } else {
-        let response: Output =
-            serde_json::from_value(response).context("failed to deserialize the return data")?;
+pub async fn receive_response<Output>() -> anyhow::Result<Output>
+where
+    Output: serde::de::DeserializeOwned,
+{
+    let mut client = CLIENT.lock().await;
+    let client = client.as_mut().expect("session hasn't been initialized");
 
-        Ok(response)
-    }
+    client.receive().await.map_err(request_error)
 }
 
 async fn command_loop(arguments: cmdline::Arguments) -> anyhow::Result<()> {
@@ -223,62 +292,51 @@ async fn command_loop(arguments: cmdline::Arguments) -> anyhow::Result<()> {
     }
 }
 
-pub async fn handle(arguments: cmdline::Arguments) -> anyhow::Result<()> {
+/* what the client connects with, the way the command line asked for */
+pub(crate) fn connect_options(
+    arguments: &cmdline::Arguments,
+) -> anyhow::Result<vaulty_client::ConnectOptions> {
     let (remote_address, remote_port) = arguments
         .remote_address
         .clone()
         .context("arguments.remote_address is None")?;
 
-    outputln!("connecting to {}:{}", remote_address, remote_port);
-
-    let (stream, response) = if arguments.tls {
-        let url = format!("wss://{}:{}", remote_address, remote_port);
-
-        let tls_config = native_tls::TlsConnector::builder()
-            .danger_accept_invalid_certs(arguments.disabled_tls_verification)
-            .build()
-            .context("failed to create a TLS config")?;
-
-        let tls_connector = tokio_tungstenite::Connector::NativeTls(tls_config);
-
-        tokio_tungstenite::connect_async_tls_with_config(url, None, false, Some(tls_connector))
-            .await
-            .context("failed to connect")?
-    } else {
-        let url = format!("ws://{}:{}", remote_address, remote_port);
-
-        tokio_tungstenite::connect_async(url)
-            .await
-            .context("failed to connect")?
-    };
+    Ok(vaulty_client::ConnectOptions {
+        address: remote_address,
+        port: remote_port,
+        tls: arguments.tls,
+        allow_invalid_certs: arguments.disabled_tls_verification,
+        tls_pin: arguments.tls_pin.clone(),
+        timeout: arguments.timeout,
+    })
+}
 
-    let response_status = response.status();
+/* the client is kept for the commands, the returned future resolves once
+the connection is gone */
+pub(crate) async fn connect(
+    arguments: &cmdline::Arguments,
+) -> anyhow::Result<impl std::future::Future<Output = ()> + Send + 'static> {
+    let options = connect_options(arguments)?;
 
-    if response_status != StatusCode::SWITCHING_PROTOCOLS {
-        return Err(anyhow::anyhow!(
-            "server responded with status code {}",
-            response_status.to_string()
-        ));
-    }
+    outputln!("connecting to {}:{}", options.address, options.port);
 
-    let (mut write, mut read) = stream.split();
+    let client = vaulty_client::Client::connect(options).await?;
 
     outputln!("connected");
 
-    let (command_in_tx, mut command_in_rx) = tokio::sync::mpsc::channel::<Message>(1);
-    let (command_out_tx, command_out_rx) = tokio::sync::mpsc::channel::<Message>(1);
+    let closed = client.closed();
 
     {
-        let mut stream_tx = COMMAND_STREAM_TX.lock().await;
+        let mut stream = CLIENT.lock().await;
 
-        *stream_tx = Some(command_in_tx);
+        *stream = Some(client);
     }
 
-    {
-        let mut stream_rx = COMMAND_STREAM_RX.lock().await;
+    Ok(closed)
+}
 
-        *stream_rx = Some(command_out_rx);
-    }
+pub async fn handle(arguments: cmdline::Arguments) -> anyhow::Result<()> {
+    let closed = connect(&arguments).await?;
 
     tokio::spawn(async move {
         if let Err(e) = command_loop(arguments).await {
@@ -287,30 +345,8 @@ pub async fn handle(arguments: cmdline::Arguments) -> anyhow::Result<()> {
         }
     });
 
-    loop {
-        tokio::select! {
-            command = command_in_rx.recv() => {
-                if let Some(command) = command {
-                    write.send(command).await.context("failed to send data")?;
-                }
-            },
-            data = read.next() => {
-                if let Some(data) = data {
-                    match data {
-                        Ok(data) => {
-                            if !matches!(data, Message::Ping(_)) {
-                                command_out_tx.send(data).await.context("failed to push data to command stream")?
-                            }
-                        },
-                        Err(_) => {
-                            outputln!("stream closed");
-                            std::process::exit(0);
-                        }
-                    }
-                } else {
-                    return Err(anyhow::anyhow!("stream closed"));
-                }
-            }
-        }
-    }
+    closed.await;
+
+    outputln!("stream closed");
+    std::process::exit(0);
 }
@@ -33,6 +33,21 @@ macro_rules! outputln {
     };
 }
 
+#[macro_export]
+macro_rules! warnln {
+    ($fmt:expr $(, $args:expr)*) => {
+        {
+            use std::io::Write;
+            use crossterm::style::Stylize;
+
+            let mut stderr = std::io::stderr();
+            let message = format!($fmt $(, $args)*).yellow().bold();
+
+            let _ = write!(stderr, "{message}{}", "\n".reset());
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! errorln {
     ($fmt:expr $(, $args:expr)*) => {
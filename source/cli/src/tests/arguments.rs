@@ -0,0 +1,103 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{cmd, parser};
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct InsertSecretArgument {
+    text: Option<String>,
+    binary: Option<String>,
+    file: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct InsertUserArgument {
+    username: String,
+    password: Option<String>,
+    role: String,
+    sg: Vec<String>,
+}
+
+fn argument<T>(command: &str) -> anyhow::Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let command = parser::parse(command).unwrap();
+    let argument = command
+        .last()
+        .and_then(|(_, argument)| argument.clone())
+        .expect("command has no argument");
+
+    cmd::parse_arguments(argument)
+}
+
+#[test]
+fn json_and_yaml_insert_secret() {
+    let expected = InsertSecretArgument {
+        text: Some("hunter2, really: (yes)".to_owned()),
+        binary: None,
+        file: None,
+    };
+
+    let json: InsertSecretArgument =
+        argument(r#"secret.vault.password.insert({"text": "hunter2, really: (yes)"})"#).unwrap();
+    let yaml: InsertSecretArgument =
+        argument(r#"secret.vault.password.insert({ text: "hunter2, really: (yes)" })"#).unwrap();
+
+    assert_eq!(json, expected);
+    assert_eq!(yaml, expected);
+
+    let yaml: InsertSecretArgument =
+        argument("secret.vault.password.insert({ text: 'hunter2, really: (yes)' })").unwrap();
+
+    assert_eq!(yaml, expected);
+}
+
+#[test]
+fn json_and_yaml_insert_user() {
+    let expected = InsertUserArgument {
+        username: "bob".to_owned(),
+        password: Some("12345".to_owned()),
+        role: "Admin".to_owned(),
+        sg: vec!["127.0.0.1/32".to_owned(), "10.0.0.0/8".to_owned()],
+    };
+
+    let json: InsertUserArgument = argument(
+        r#"user.insert({"username": "bob", "password": "12345", "role": "Admin", "sg": ["127.0.0.1/32", "10.0.0.0/8"]})"#,
+    )
+    .unwrap();
+    let yaml: InsertUserArgument = argument(
+        r#"user.insert({ username: bob, password: "12345", role: Admin, sg: [127.0.0.1/32, 10.0.0.0/8] })"#,
+    )
+    .unwrap();
+
+    assert_eq!(json, expected);
+    assert_eq!(yaml, expected);
+}
+
+#[test]
+fn format_specific_errors() {
+    let error =
+        argument::<InsertSecretArgument>(r#"secret.vault.password.insert({"text": "hunter2",})"#)
+            .unwrap_err();
+    assert!(error.to_string().starts_with("invalid JSON arguments"));
+
+    let error =
+        argument::<InsertSecretArgument>("secret.vault.password.insert({ text: [hunter2 })")
+            .unwrap_err();
+    assert!(error.to_string().starts_with("invalid YAML arguments"));
+}
@@ -0,0 +1,99 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cmdline;
+
+fn arguments(address: &str) -> cmdline::Arguments {
+    cmdline::Arguments {
+        remote_address: Some((address.to_owned(), 8080)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plaintext_remote_warns() {
+    let warnings = cmdline::security_warnings(&arguments("vault.example.com"));
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("cleartext"));
+
+    assert_eq!(cmdline::security_warnings(&arguments("10.0.0.1")).len(), 1);
+}
+
+#[test]
+fn plaintext_warning_suppressed() {
+    let mut arguments = arguments("vault.example.com");
+    arguments.silence_insecure_warning = true;
+
+    assert!(cmdline::security_warnings(&arguments).is_empty());
+}
+
+#[test]
+fn loopback_and_tls_dont_warn() {
+    for address in ["localhost", "127.0.0.1", "::1", "[::1]"] {
+        assert!(cmdline::security_warnings(&arguments(address)).is_empty());
+    }
+
+    let mut arguments = arguments("vault.example.com");
+    arguments.tls = true;
+
+    assert!(cmdline::security_warnings(&arguments).is_empty());
+}
+
+#[test]
+fn invalid_certs_warn() {
+    let mut arguments = arguments("localhost");
+    arguments.tls = true;
+    arguments.disabled_tls_verification = true;
+
+    let warnings = cmdline::security_warnings(&arguments);
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("tlsAllowInvalidCerts"));
+
+    /* the pin takes over the verification */
+    arguments.tls_pin = Some("00".repeat(32));
+
+    assert!(cmdline::security_warnings(&arguments).is_empty());
+}
+
+fn parse(uri: &str) -> anyhow::Result<cmdline::Arguments> {
+    cmdline::parse(&[uri.to_owned()])
+}
+
+#[test]
+fn tls_pin_parsed() {
+    let pin = "ab".repeat(32);
+
+    let arguments = parse(&format!("vaulty://root@localhost:8443?tlsPin={pin}")).unwrap();
+    assert_eq!(arguments.tls_pin, Some(pin.clone()));
+
+    /* the form openssl prints is taken as the same pin */
+    let printed = vec!["AB"; 32].join(":");
+    let arguments = parse(&format!("vaulty://root@localhost:8443?tlspin={printed}")).unwrap();
+    assert_eq!(arguments.tls_pin, Some(pin));
+
+    assert_eq!(parse("vaulty://root@localhost:8443").unwrap().tls_pin, None);
+}
+
+#[test]
+fn invalid_tls_pin_refused() {
+    for pin in ["ab".repeat(31), "ab".repeat(33), "zz".repeat(32)] {
+        let error = parse(&format!("vaulty://root@localhost:8443?tlsPin={pin}")).unwrap_err();
+        assert!(error.to_string().contains("invalid 'tlsPin'"), "{error}");
+    }
+}
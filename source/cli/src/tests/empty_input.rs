@@ -0,0 +1,29 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::session;
+
+/* the session isn't initialized here, so a request would panic */
+#[tokio::test]
+async fn empty_input_is_ignored() {
+    session::execute("", "root").await.unwrap();
+    session::execute("   ", "root").await.unwrap();
+    session::execute("\t \t", "root").await.unwrap();
+
+    let error = session::execute("nonsense", "root").await.unwrap_err();
+    assert_eq!(error.to_string(), "unknown command");
+}
@@ -0,0 +1,32 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use vaulty_client::{response::ErrorCode, Error};
+
+use crate::session;
+
+#[test]
+fn server_error_shows_the_code() {
+    let e = session::request_error(Error::Server(
+        ErrorCode::InvalidArgument,
+        "invalid security group".to_owned(),
+    ));
+    assert_eq!(e.to_string(), "invalid security group [InvalidArgument]");
+
+    let e = session::request_error(Error::InvalidCredentials);
+    assert_eq!(e.to_string(), "invalid credentials");
+}
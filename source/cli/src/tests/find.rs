@@ -0,0 +1,51 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cmd;
+
+use super::temp_filename;
+
+#[test]
+fn save_binary_secret() {
+    let filename = temp_filename("binary");
+    let filename_str = filename.to_str().unwrap();
+    let data: Vec<u8> = (0..=255).chain([0, 0xff, 0x80]).collect();
+
+    let secret = base64_simd::STANDARD.encode_to_string(&data);
+    let decoded = base64_simd::STANDARD.decode_to_vec(&secret).unwrap();
+
+    cmd::secret::find::save_secret(filename_str, &decoded, false).unwrap();
+    assert_eq!(std::fs::read(&filename).unwrap(), data);
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&filename).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    /* an existing file is left alone unless asked */
+    let error = cmd::secret::find::save_secret(filename_str, b"other", false).unwrap_err();
+    assert!(error.to_string().contains("already exists"), "{error}");
+    assert_eq!(std::fs::read(&filename).unwrap(), data);
+
+    cmd::secret::find::save_secret(filename_str, b"other", true).unwrap();
+    assert_eq!(std::fs::read(&filename).unwrap(), b"other");
+
+    std::fs::remove_file(&filename).unwrap();
+}
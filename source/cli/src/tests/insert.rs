@@ -0,0 +1,120 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cmd;
+
+use super::temp_filename;
+
+/* what the progress bar would draw on a terminal */
+#[derive(Debug, Clone, Default)]
+struct Recorder(std::sync::Arc<std::sync::Mutex<String>>);
+
+impl indicatif::TermLike for Recorder {
+    fn width(&self) -> u16 {
+        120
+    }
+
+    fn move_cursor_up(&self, _: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> std::io::Result<()> {
+        self.write_str(&format!("{s}\n"))
+    }
+
+    fn write_str(&self, s: &str) -> std::io::Result<()> {
+        self.0.lock().unwrap().push_str(s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn write_file(name: &str, length: usize) -> (std::path::PathBuf, Vec<u8>) {
+    let filename = temp_filename(name);
+    /* not a multiple of 3, so the last chunk is padded */
+    let data: Vec<u8> = (0..length).map(|v| (v * 7 % 251) as u8).collect();
+
+    std::fs::write(&filename, &data).unwrap();
+
+    (filename, data)
+}
+
+#[test]
+fn large_file_progress() {
+    let (filename, data) = write_file("progress", 3 * 1024 * 1024 + 1);
+    let recorder = Recorder::default();
+
+    let encoded = cmd::secret::insert::read_file_to(
+        filename.to_str().unwrap(),
+        indicatif::ProgressDrawTarget::term_like(Box::new(recorder.clone())),
+    )
+    .unwrap();
+    assert_eq!(base64_simd::STANDARD.decode_to_vec(&encoded).unwrap(), data);
+
+    let drawn = recorder.0.lock().unwrap().clone();
+    assert!(drawn.contains("reading "), "{drawn}");
+
+    std::fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn quiet_progress() {
+    let (filename, data) = write_file("quiet", 3 * 1024 * 1024 + 2);
+
+    /* stderr when it isn't a terminal */
+    let encoded = cmd::secret::insert::read_file_to(
+        filename.to_str().unwrap(),
+        indicatif::ProgressDrawTarget::hidden(),
+    )
+    .unwrap();
+    assert_eq!(base64_simd::STANDARD.decode_to_vec(&encoded).unwrap(), data);
+
+    std::fs::remove_file(&filename).unwrap();
+
+    /* below the threshold nothing is drawn, even on a terminal */
+    let (filename, data) = write_file("small", 1024);
+    let recorder = Recorder::default();
+
+    let encoded = cmd::secret::insert::read_file_to(
+        filename.to_str().unwrap(),
+        indicatif::ProgressDrawTarget::term_like(Box::new(recorder.clone())),
+    )
+    .unwrap();
+    assert_eq!(base64_simd::STANDARD.decode_to_vec(&encoded).unwrap(), data);
+    assert!(recorder.0.lock().unwrap().is_empty());
+
+    std::fs::remove_file(&filename).unwrap();
+}
@@ -0,0 +1,30 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+mod arguments;
+mod cmdline;
+mod empty_input;
+mod errors;
+mod find;
+mod insert;
+mod passwd;
+mod pinning;
+mod timeout;
+
+fn temp_filename(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("vaulty-cli-{}-{}", std::process::id(), name))
+}
@@ -0,0 +1,44 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{cmd, parser};
+
+#[tokio::test]
+async fn mismatched_passwords_are_rejected() {
+    let mut input = std::io::Cursor::new("hunter2\nhunter3\nhunter4\n");
+
+    /* the session was never connected, a request would panic */
+    let result = cmd::user::passwd("root".to_owned(), |message| {
+        Ok(rpassword::prompt_password_from_bufread(
+            &mut input,
+            &mut std::io::sink(),
+            message,
+        )?)
+    })
+    .await;
+
+    assert_eq!(result.unwrap_err().to_string(), "passwords didn't match");
+    assert_eq!(input.position(), 24);
+}
+
+#[test]
+fn passwd_parses() {
+    assert_eq!(
+        parser::parse("passwd").unwrap(),
+        [("passwd".to_owned(), None)]
+    );
+}
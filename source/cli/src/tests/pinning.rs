@@ -0,0 +1,125 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use futures::{SinkExt, StreamExt};
+
+use crate::{cmdline, session};
+
+/* self-signed and issued to another name, only a pin vouches for it */
+fn certificate() -> (openssl::x509::X509, native_tls::Identity) {
+    let key = openssl::pkey::PKey::from_ec_key(
+        openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let mut name = openssl::x509::X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "vaulty.invalid").unwrap();
+    let name = name.build();
+
+    let mut builder = openssl::x509::X509Builder::new().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&openssl::asn1::Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    builder
+        .sign(&key, openssl::hash::MessageDigest::sha256())
+        .unwrap();
+    let certificate = builder.build();
+
+    let identity = native_tls::Identity::from_pkcs8(
+        &certificate.to_pem().unwrap(),
+        &key.private_key_to_pem_pkcs8().unwrap(),
+    )
+    .unwrap();
+
+    (certificate, identity)
+}
+
+/* echoes the text messages of every connection that gets through the handshake */
+async fn echo_server(listener: tokio::net::TcpListener, identity: native_tls::Identity) {
+    let acceptor =
+        tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+
+    loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let acceptor = acceptor.clone();
+
+        tokio::spawn(async move {
+            let Ok(stream) = acceptor.accept(stream).await else {
+                return;
+            };
+            let Ok(mut stream) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+
+            while let Some(Ok(message)) = stream.next().await {
+                if message.is_text() && stream.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[tokio::test]
+async fn pinned_certificate() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (certificate, identity) = certificate();
+
+    tokio::spawn(echo_server(listener, identity));
+
+    let connect = |tls_pin: String| async move {
+        let arguments =
+            cmdline::parse(&[format!("vaulty://root@127.0.0.1:{port}?tlsPin={tls_pin}")]).unwrap();
+
+        vaulty_client::Client::connect(session::connect_options(&arguments).unwrap()).await
+    };
+
+    let fingerprint: String = certificate
+        .digest(openssl::hash::MessageDigest::sha256())
+        .unwrap()
+        .iter()
+        .map(|v| format!("{v:02X}"))
+        .collect::<Vec<String>>()
+        .join(":");
+
+    let mut client = connect(fingerprint)
+        .await
+        .expect("the pinned certificate was refused");
+    let response: serde_json::Value = client
+        .request(&serde_json::json!({ "n": 1 }))
+        .await
+        .unwrap();
+    assert_eq!(response["n"], 1);
+
+    let Err(error) = connect("00".repeat(32)).await else {
+        panic!("a certificate that doesn't match the pin was accepted");
+    };
+    assert!(
+        error.to_string().contains("doesn't match the pinned one"),
+        "{error}"
+    );
+}
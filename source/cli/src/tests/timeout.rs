@@ -0,0 +1,92 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{cmdline, session};
+
+async fn next_request(
+    stream: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+) -> serde_json::Value {
+    loop {
+        match stream.next().await {
+            Some(Ok(Message::Text(data))) => return serde_json::from_str(&data).unwrap(),
+            Some(Ok(_)) => {}
+            message => panic!("unexpected message: {message:?}"),
+        }
+    }
+}
+
+/* holds back the answer to the first request until the second one arrives,
+then answers both and never again */
+async fn wedged_server(listener: tokio::net::TcpListener) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+    let first = next_request(&mut stream).await;
+    let second = next_request(&mut stream).await;
+
+    for request in [first, second] {
+        stream
+            .send(Message::Text(
+                serde_json::json!({ "answer": request["n"] }).to_string(),
+            ))
+            .await
+            .unwrap();
+    }
+
+    while stream.next().await.is_some() {}
+}
+
+#[tokio::test]
+async fn unanswered_request_times_out() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(wedged_server(listener));
+
+    let _closed = session::connect(&cmdline::Arguments {
+        remote_address: Some(("127.0.0.1".to_owned(), port)),
+        timeout: Some(std::time::Duration::from_secs(1)),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let error = session::send_request::<_, serde_json::Value>(serde_json::json!({ "n": 1 }))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "the server didn't respond within 1 seconds"
+    );
+
+    /* the late answer to the first request isn't taken for this one */
+    let response: serde_json::Value = session::send_request(serde_json::json!({ "n": 2 }))
+        .await
+        .unwrap();
+    assert_eq!(response["answer"], 2);
+
+    let error = session::send_request::<_, serde_json::Value>(serde_json::json!({ "n": 3 }))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "the server didn't respond within 1 seconds"
+    );
+}
@@ -0,0 +1,566 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures::{SinkExt, StreamExt};
+use sha2::Digest;
+use tungstenite::{http::StatusCode, Message};
+
+use crate::{
+    error::{Error, Result},
+    protocol,
+    request::{self, Request},
+    response,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    pub address: String,
+    pub port: u16,
+    pub tls: bool,
+    pub allow_invalid_certs: bool,
+    /// SHA-256 of the server's certificate in hex, implies TLS and replaces
+    /// the usual certificate and hostname verification.
+    pub tls_pin: Option<String>,
+    /// How long to wait for each response, unset waits indefinitely.
+    pub timeout: Option<std::time::Duration>,
+}
+
+pub struct Client {
+    tx: tokio::sync::mpsc::Sender<Message>,
+    rx: tokio::sync::mpsc::Receiver<Message>,
+    closed: tokio::sync::watch::Receiver<bool>,
+    timeout: Option<std::time::Duration>,
+    /* responses to timed out requests that haven't arrived yet, the server
+    answers in order so they're the next ones to be read and must be dropped */
+    stale_responses: usize,
+}
+
+async fn connect_pinned(
+    address: &str,
+    port: u16,
+    tls_pin: &str,
+) -> Result<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>> {
+    let tls_config = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| Error::Connect(format!("failed to create a TLS config: {e}")))?;
+
+    let tcp_stream = tokio::net::TcpStream::connect((address, port))
+        .await
+        .map_err(|e| Error::Connect(format!("failed to connect: {e}")))?;
+
+    let tls_stream = tokio_native_tls::TlsConnector::from(tls_config)
+        .connect(address, tcp_stream)
+        .await
+        .map_err(|e| Error::Connect(format!("failed to establish a TLS connection: {e}")))?;
+
+    let certificate = tls_stream
+        .get_ref()
+        .peer_certificate()
+        .map_err(|e| Error::Connect(format!("failed to read the server's certificate: {e}")))?
+        .ok_or_else(|| Error::Connect("server didn't present a certificate".to_owned()))?
+        .to_der()
+        .map_err(|e| Error::Connect(format!("failed to encode the server's certificate: {e}")))?;
+
+    let fingerprint: String = sha2::Sha256::digest(certificate)
+        .iter()
+        .map(|v| format!("{v:02x}"))
+        .collect();
+
+    let tls_pin = tls_pin.replace(':', "").to_lowercase();
+
+    if fingerprint != tls_pin {
+        return Err(Error::Connect(format!(
+            "server's certificate fingerprint {fingerprint} doesn't match the pinned one"
+        )));
+    }
+
+    Ok(tokio_tungstenite::MaybeTlsStream::NativeTls(tls_stream))
+}
+
+impl Client {
+    pub async fn connect(options: ConnectOptions) -> Result<Client> {
+        let address = options.address.as_str();
+        let port = options.port;
+
+        let (stream, response) = if let Some(tls_pin) = &options.tls_pin {
+            tokio_tungstenite::client_async(
+                format!("wss://{address}:{port}"),
+                connect_pinned(address, port, tls_pin).await?,
+            )
+            .await
+        } else if options.tls {
+            let tls_config = native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(options.allow_invalid_certs)
+                .build()
+                .map_err(|e| Error::Connect(format!("failed to create a TLS config: {e}")))?;
+
+            tokio_tungstenite::connect_async_tls_with_config(
+                format!("wss://{address}:{port}"),
+                None,
+                false,
+                Some(tokio_tungstenite::Connector::NativeTls(tls_config)),
+            )
+            .await
+        } else {
+            tokio_tungstenite::connect_async(format!("ws://{address}:{port}")).await
+        }
+        .map_err(|e| Error::Connect(format!("failed to connect: {e}")))?;
+
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(Error::Connect(format!(
+                "server responded with status code {}",
+                response.status()
+            )));
+        }
+
+        let (mut write, mut read) = stream.split();
+
+        let (command_in_tx, mut command_in_rx) = tokio::sync::mpsc::channel::<Message>(1);
+        let (command_out_tx, command_out_rx) = tokio::sync::mpsc::channel::<Message>(1);
+        let (closed_tx, closed_rx) = tokio::sync::watch::channel(false);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    command = command_in_rx.recv() => {
+                        match command {
+                            Some(command) => {
+                                if write.send(command).await.is_err() {
+                                    break;
+                                }
+                            }
+                            /* the client was dropped */
+                            None => break,
+                        }
+                    },
+                    data = read.next() => {
+                        match data {
+                            Some(Ok(Message::Ping(_))) => {}
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            Some(Ok(data)) => {
+                                if command_out_tx.send(data).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let _ = closed_tx.send(true);
+        });
+
+        Ok(Client {
+            tx: command_in_tx,
+            rx: command_out_rx,
+            closed: closed_rx,
+            timeout: options.timeout,
+            stale_responses: 0,
+        })
+    }
+
+    /// Resolves once the connection to the server is gone.
+    pub fn closed(&self) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let mut closed = self.closed.clone();
+
+        async move {
+            let _ = closed.wait_for(|v| *v).await;
+        }
+    }
+
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<response::Login> {
+        #[derive(serde::Serialize)]
+        struct Request<'a> {
+            username: &'a str,
+            password: &'a str,
+            protocol_version: u32,
+        }
+
+        #[derive(serde::Deserialize)]
+        enum ResponseResult {
+            Granted,
+            Denied,
+            SessionLimitReached,
+            UnsupportedVersion,
+            TlsRequired,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: ResponseResult,
+            /* servers from before the version was exchanged don't send it */
+            #[serde(default)]
+            protocol_version: u32,
+            #[serde(default)]
+            min_client_version: Option<u32>,
+            #[serde(flatten)]
+            login: response::Login,
+        }
+
+        let response: Response = self
+            .request(&Request {
+                username,
+                password,
+                protocol_version: protocol::VERSION,
+            })
+            .await?;
+
+        match response.result {
+            ResponseResult::UnsupportedVersion => Err(Error::UnsupportedClient(
+                response.min_client_version.unwrap_or(protocol::VERSION + 1),
+            )),
+            _ if response.protocol_version < protocol::MIN_SERVER_VERSION => {
+                Err(Error::UnsupportedServer(response.protocol_version))
+            }
+            ResponseResult::Granted => Ok(response.login),
+            ResponseResult::Denied => Err(Error::InvalidCredentials),
+            ResponseResult::SessionLimitReached => Err(Error::SessionLimitReached),
+            ResponseResult::TlsRequired => Err(Error::TlsRequired),
+        }
+    }
+
+    /// Sends any request and reads its response, the typed methods below
+    /// cover the common commands.
+    pub async fn request<Input, Output>(&mut self, data: &Input) -> Result<Output>
+    where
+        Input: serde::Serialize,
+        Output: serde::de::DeserializeOwned,
+    {
+        self.request_with_timeout(data, self.timeout).await
+    }
+
+    pub async fn request_with_timeout<Input, Output>(
+        &mut self,
+        data: &Input,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<Output>
+    where
+        Input: serde::Serialize,
+        Output: serde::de::DeserializeOwned,
+    {
+        let request = serde_json::to_string(data)
+            .map_err(|e| Error::Serialization(format!("failed to serialize the request: {e}")))?;
+
+        self.tx
+            .send(Message::Text(request))
+            .await
+            .map_err(|_| Error::Stream("stream closed".to_owned()))?;
+
+        let Client {
+            rx,
+            stale_responses,
+            ..
+        } = self;
+
+        let response = async {
+            while *stale_responses > 0 {
+                rx.recv()
+                    .await
+                    .ok_or_else(|| Error::Stream("stream closed".to_owned()))?;
+
+                *stale_responses -= 1;
+            }
+
+            read_response(rx).await
+        };
+
+        match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, response).await {
+                Ok(response) => response,
+                Err(_) => {
+                    self.stale_responses += 1;
+
+                    Err(Error::Timeout(timeout))
+                }
+            },
+            None => response.await,
+        }
+    }
+
+    /// Reads one more response, for commands that stream their results.
+    pub async fn receive<Output>(&mut self) -> Result<Output>
+    where
+        Output: serde::de::DeserializeOwned,
+    {
+        read_response(&mut self.rx).await
+    }
+
+    pub async fn create_user(
+        &mut self,
+        data: request::RequestCreateUser,
+    ) -> Result<response::CreateUserResult> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: response::CreateUserResult,
+        }
+
+        let response: Response = self.request(&Request::CreateUser(data)).await?;
+
+        Ok(response.result)
+    }
+
+    pub async fn list_users(&mut self) -> Result<Vec<response::UserEntry>> {
+        let response = self
+            .list_users_paged(request::RequestListUsers::default())
+            .await?;
+
+        Ok(response.users)
+    }
+
+    pub async fn list_users_paged(
+        &mut self,
+        data: request::RequestListUsers,
+    ) -> Result<response::UsersPage> {
+        self.request(&Request::ListUsers(data)).await
+    }
+
+    pub async fn create_access_key(
+        &mut self,
+        data: request::RequestCreateAccessKey,
+    ) -> Result<response::AccessKey> {
+        self.request(&Request::CreateAccessKey(data)).await
+    }
+
+    pub async fn list_vaults(
+        &mut self,
+        data: request::RequestListVaults,
+    ) -> Result<Vec<response::VaultEntry>> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            vaults: Vec<response::VaultEntry>,
+        }
+
+        let response: Response = self.request(&Request::ListVaults(data)).await?;
+
+        Ok(response.vaults)
+    }
+
+    pub async fn insert_secret(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+        secret: &[u8],
+    ) -> Result<response::InsertSecretResult> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: response::InsertSecretResult,
+        }
+
+        let response: Response = self
+            .request(&Request::InsertSecret(request::RequestInsertSecret {
+                vault: vault.to_owned(),
+                secret_name: secret_name.to_owned(),
+                data: base64_encode(secret),
+                alias: None,
+            }))
+            .await?;
+
+        Ok(response.result)
+    }
+
+    /// Stores `secret_name` as an alias, reading it reads `target` instead.
+    pub async fn insert_alias(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+        target: request::SecretAlias,
+    ) -> Result<response::InsertSecretResult> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: response::InsertSecretResult,
+        }
+
+        let response: Response = self
+            .request(&Request::InsertSecret(request::RequestInsertSecret {
+                vault: vault.to_owned(),
+                secret_name: secret_name.to_owned(),
+                data: String::new(),
+                alias: Some(target),
+            }))
+            .await?;
+
+        Ok(response.result)
+    }
+
+    /// The server picks the value, read it back with `find_secret`.
+    pub async fn generate_secret(
+        &mut self,
+        data: request::RequestGenerateSecret,
+    ) -> Result<response::GenerateSecretResult> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: response::GenerateSecretResult,
+        }
+
+        let response: Response = self.request(&Request::GenerateSecret(data)).await?;
+
+        Ok(response.result)
+    }
+
+    pub async fn list_secrets(&mut self, vault: &str) -> Result<Vec<response::SecretEntry>> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            secrets: Vec<response::SecretEntry>,
+        }
+
+        let response: Response = self
+            .request(&Request::ListSecrets(request::RequestListSecrets {
+                vault: vault.to_owned(),
+            }))
+            .await?;
+
+        Ok(response.secrets)
+    }
+
+    pub async fn find_secret(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+    ) -> Result<Option<response::Secret>> {
+        #[derive(serde::Deserialize)]
+        enum Response {
+            Found(response::Secret),
+            NotFound,
+        }
+
+        let response: Response = self
+            .request(&Request::FindSecret(request::RequestFindSecret {
+                vault: vault.to_owned(),
+                secret_name: secret_name.to_owned(),
+                expected_sha256: None,
+                compression: None,
+            }))
+            .await?;
+
+        Ok(match response {
+            Response::Found(secret) => Some(secret),
+            Response::NotFound => None,
+        })
+    }
+
+    /// Like `find_secret`, the value is only sent when its SHA-256 differs
+    /// from `expected_sha256`, a hex digest.
+    pub async fn find_secret_if_changed(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+        expected_sha256: &str,
+    ) -> Result<response::FindSecretResult> {
+        self.request(&Request::FindSecret(request::RequestFindSecret {
+            vault: vault.to_owned(),
+            secret_name: secret_name.to_owned(),
+            expected_sha256: Some(expected_sha256.to_owned()),
+            compression: None,
+        }))
+        .await
+    }
+
+    pub async fn delete_secret(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+    ) -> Result<response::DeleteSecretResult> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: response::DeleteSecretResult,
+        }
+
+        let response: Response = self
+            .request(&Request::DeleteSecret(request::RequestDeleteSecret {
+                vault: vault.to_owned(),
+                secret_name: secret_name.to_owned(),
+            }))
+            .await?;
+
+        Ok(response.result)
+    }
+
+    /// Who changed the secret and when, oldest first. Only the most recent
+    /// changes are kept.
+    pub async fn secret_history(
+        &mut self,
+        vault: &str,
+        secret_name: &str,
+    ) -> Result<Vec<response::SecretHistoryEntry>> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            entries: Vec<response::SecretHistoryEntry>,
+        }
+
+        let response: Response = self
+            .request(&Request::SecretHistory(request::RequestSecretHistory {
+                vault: vault.to_owned(),
+                secret_name: secret_name.to_owned(),
+            }))
+            .await?;
+
+        Ok(response.entries)
+    }
+}
+
+async fn read_response<Output>(rx: &mut tokio::sync::mpsc::Receiver<Message>) -> Result<Output>
+where
+    Output: serde::de::DeserializeOwned,
+{
+    let response = rx
+        .recv()
+        .await
+        .ok_or_else(|| Error::Stream("stream closed".to_owned()))?;
+
+    let response: serde_json::Value = serde_json::from_str(&response.to_string())
+        .map_err(|e| Error::Serialization(format!("failed to deserialize the return data: {e}")))?;
+
+    if let Some(error) = response.get("error") {
+        let code = response
+            .get("code")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or(response::ErrorCode::Unknown);
+
+        Err(Error::Server(
+            code,
+            error.as_str().unwrap_or("N/A").to_owned(),
+        ))
+    } else {
+        serde_json::from_value(response).map_err(|e| {
+            Error::Serialization(format!("failed to deserialize the return data: {e}"))
+        })
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let value = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
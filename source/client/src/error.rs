@@ -0,0 +1,73 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{protocol, response};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// Connecting, the TLS handshake or the websocket upgrade failed.
+    Connect(String),
+    /// The connection dropped or a message couldn't be sent or read.
+    Stream(String),
+    /// A request couldn't be encoded or a response decoded.
+    Serialization(String),
+    /// The server answered with `{"error": ..., "code": ...}`.
+    Server(response::ErrorCode, String),
+    /// The server didn't answer within the configured timeout.
+    Timeout(std::time::Duration),
+    InvalidCredentials,
+    SessionLimitReached,
+    /// The user is an admin and the server only lets admins in over TLS.
+    TlsRequired,
+    /// The server requires at least this protocol version.
+    UnsupportedClient(u32),
+    /// The server speaks this protocol version, older than the client supports.
+    UnsupportedServer(u32),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Connect(message) => write!(f, "{message}"),
+            Error::Stream(message) => write!(f, "{message}"),
+            Error::Serialization(message) => write!(f, "{message}"),
+            Error::Server(_, message) => write!(f, "{message}"),
+            Error::Timeout(timeout) => write!(
+                f,
+                "the server didn't respond within {} seconds",
+                timeout.as_secs()
+            ),
+            Error::InvalidCredentials => write!(f, "invalid credentials"),
+            Error::SessionLimitReached => write!(f, "maximum number of sessions reached"),
+            Error::TlsRequired => write!(f, "the server only lets admins log in over TLS"),
+            Error::UnsupportedClient(version) => write!(
+                f,
+                "the server requires protocol version {version} or newer, this client speaks {}, upgrade it",
+                protocol::VERSION
+            ),
+            Error::UnsupportedServer(version) => write!(
+                f,
+                "the server speaks protocol version {version}, this client requires {} or newer, upgrade the server",
+                protocol::MIN_SERVER_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,43 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Client for the vaulty admin websocket protocol, the one the CLI speaks.
+//!
+//! ```no_run
+//! # async fn example() -> vaulty_client::Result<()> {
+//! let mut client = vaulty_client::Client::connect(vaulty_client::ConnectOptions {
+//!     address: "127.0.0.1".to_owned(),
+//!     port: 8080,
+//!     ..Default::default()
+//! })
+//! .await?;
+//!
+//! client.login("root", "password").await?;
+//! client.insert_secret("vault", "password", b"hunter2").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+pub mod permission;
+pub mod protocol;
+pub mod request;
+pub mod response;
+
+pub use client::{Client, ConnectOptions};
+pub use error::{Error, Result};
@@ -0,0 +1,383 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestCreateUser {
+    pub username: String,
+    pub password: String,
+    pub role: permission::UserRole,
+    pub sg: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct RequestListUsers {
+    pub after: Option<String>,
+    pub limit: Option<usize>,
+    pub role: Option<permission::UserRole>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestFindUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDeleteUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestPromoteUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDemoteUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestChangeUserRole {
+    pub username: String,
+    pub role: permission::UserRole,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestLockUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestUnlockUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestChangePasswordForUser {
+    pub username: String,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestChangeSgForUser {
+    pub username: String,
+    pub sg: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestCreateAccessKey {
+    pub vault: String,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub preset: Option<permission::VaultRolesPreset>,
+    pub sg: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<u64>,
+    /// Limits the key to the secrets whose names start with one of these.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_prefixes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum AccessKeySortBy {
+    Name,
+    Created,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum VaultSortBy {
+    Name,
+    Created,
+    SecretsCount,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestListAccessKeys {
+    pub vault: String,
+    pub sort_by: Option<AccessKeySortBy>,
+    pub order: Option<SortOrder>,
+    pub name_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct RequestListVaults {
+    pub sort_by: Option<VaultSortBy>,
+    pub order: Option<SortOrder>,
+    pub name_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestFindAccessKey {
+    pub vault: String,
+    pub access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDeleteAccessKey {
+    pub vault: String,
+    pub access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDeleteAccessKeysWhere {
+    pub vault: String,
+    pub unused_since: Option<String>,
+    pub disabled: Option<bool>,
+    pub permission: Option<permission::VaultRoles>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestChangePermissionsForAccessKey {
+    pub vault: String,
+    pub access_key: String,
+    pub permission: Vec<permission::VaultRoles>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestSetAccessKeyEnabled {
+    pub vault: String,
+    pub access_key: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestSimulateAccess {
+    pub vault: String,
+    pub access_key: String,
+    pub ip: String,
+    pub permission: permission::VaultRoles,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestVerifyAccessKeySecret {
+    pub vault: String,
+    pub access_key: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestRotateAccessKeySecret {
+    pub vault: String,
+    pub access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestChangeSgForAccessKey {
+    pub vault: String,
+    pub access_key: String,
+    pub sg: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestFindVault {
+    pub vault: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDeleteVault {
+    pub vault: String,
+    pub confirm: Option<String>,
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestRenameVault {
+    pub vault: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestCreateVault {
+    pub vault: String,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub sg: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestSetVaultDefaults {
+    pub vault: String,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub sg: Option<Vec<String>>,
+}
+
+/// What a vault's secret values must be, `None` takes anything.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub enum ValueSchema {
+    None,
+    Utf8,
+    Json,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestSetVaultValueSchema {
+    pub vault: String,
+    pub value_schema: ValueSchema,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestVerifyIntegrity {
+    pub vault: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestInsertSecret {
+    pub secret_name: String,
+    pub vault: String,
+    /// Base64 encoded, left empty for an alias.
+    pub data: String,
+    /// Store the name as an alias of another secret, read in its place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<SecretAlias>,
+}
+
+/// The secret an alias is read in place of.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SecretAlias {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+/// How `FindSecret` compresses a value before it's base64 encoded.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub enum Charset {
+    Alphanumeric,
+    Numeric,
+    Hex,
+    Symbols,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestGenerateSecret {
+    pub vault: String,
+    pub secret_name: String,
+    pub length: Option<usize>,
+    pub charset: Option<Charset>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestListSecrets {
+    pub vault: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestFindSecret {
+    pub vault: String,
+    pub secret_name: String,
+    /// Hex SHA-256 of the value the caller holds, answered with `Unchanged`
+    /// instead of the value when it matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestGetSecrets {
+    pub vault: String,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestDeleteSecret {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestRestoreSecret {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestSecretHistory {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestExistsSecret {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RequestKillSession {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub enum Request {
+    CreateUser(RequestCreateUser),
+    ListUsers(RequestListUsers),
+    FindUser(RequestFindUser),
+    DeleteUser(RequestDeleteUser),
+    PromoteUser(RequestPromoteUser),
+    DemoteUser(RequestDemoteUser),
+    ChangeUserRole(RequestChangeUserRole),
+    LockUser(RequestLockUser),
+    UnlockUser(RequestUnlockUser),
+    ChangePasswordForUser(RequestChangePasswordForUser),
+    ChangeSgForUser(RequestChangeSgForUser),
+    CreateAccessKey(RequestCreateAccessKey),
+    ListAccessKeys(RequestListAccessKeys),
+    FindAccessKey(RequestFindAccessKey),
+    DeleteAccessKey(RequestDeleteAccessKey),
+    DeleteAccessKeysWhere(RequestDeleteAccessKeysWhere),
+    ChangePermissionForAccessKey(RequestChangePermissionsForAccessKey),
+    ChangeSgForAccessKey(RequestChangeSgForAccessKey),
+    SetAccessKeyEnabled(RequestSetAccessKeyEnabled),
+    RotateAccessKeySecret(RequestRotateAccessKeySecret),
+    SimulateAccess(RequestSimulateAccess),
+    VerifyAccessKeySecret(RequestVerifyAccessKeySecret),
+    ListVaults(RequestListVaults),
+    FindVault(RequestFindVault),
+    CreateVault(RequestCreateVault),
+    DeleteVault(RequestDeleteVault),
+    RenameVault(RequestRenameVault),
+    SetVaultDefaults(RequestSetVaultDefaults),
+    SetVaultValueSchema(RequestSetVaultValueSchema),
+    VerifyIntegrity(RequestVerifyIntegrity),
+    InsertSecret(RequestInsertSecret),
+    GenerateSecret(RequestGenerateSecret),
+    ListSecrets(RequestListSecrets),
+    FindSecret(RequestFindSecret),
+    GetSecrets(RequestGetSecrets),
+    DeleteSecret(RequestDeleteSecret),
+    RestoreSecret(RequestRestoreSecret),
+    ExistsSecret(RequestExistsSecret),
+    SecretHistory(RequestSecretHistory),
+    SessionStats(),
+    ListSessions(),
+    KillSession(RequestKillSession),
+    ListRoles(),
+}
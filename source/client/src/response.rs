@@ -0,0 +1,170 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{permission, request};
+
+/// Why the server failed a command, stable where the message isn't.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ErrorCode {
+    /// The request wasn't valid JSON or didn't match any command.
+    InvalidRequest,
+    UnknownCommand,
+    /// One of the request's values was rejected.
+    InvalidArgument,
+    /// The server's database was busy, sending the request again may work.
+    Busy,
+    Internal,
+    /// Sent by a newer server, or no code was sent at all.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Login {
+    pub node_name: Option<String>,
+    #[serde(default)]
+    pub role: Option<permission::UserRole>,
+}
+
+/// Who wrote a secret or created an access key.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Actor {
+    User(String),
+    AccessKey(String),
+    Token(String),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum CreateUserResult {
+    Created,
+    Exists,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserEntry {
+    pub username: String,
+    pub role: permission::UserRole,
+    pub last_login: Option<String>,
+    pub sg: Vec<String>,
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// One page of `ListUsers`, pass `next` as `after` to fetch the following one.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UsersPage {
+    pub users: Vec<UserEntry>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccessKey {
+    pub access_key: String,
+    pub secret_access_key: String,
+    /// The `Authorization` header for the REST API, only sent by newer servers.
+    #[serde(default)]
+    pub authorization: Option<String>,
+    /// An example `curl` command using that header.
+    #[serde(default)]
+    pub curl: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct VaultEntry {
+    pub vault: String,
+    pub created: String,
+    pub secrets_count: i64,
+    pub access_keys_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum InsertSecretResult {
+    Inserted,
+    Updated,
+    InvalidName,
+    NameTaken,
+    /// The value doesn't match the vault's value schema.
+    InvalidValue,
+    /// The alias' target doesn't exist.
+    AliasTargetMissing,
+    /// Following the alias' target would lead back to it.
+    CircularAlias,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum GenerateSecretResult {
+    Inserted,
+    Updated,
+    InvalidName,
+    NameTaken,
+    InvalidLength,
+    InvalidValue,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SecretEntry {
+    pub created: String,
+    pub secret_name: String,
+    pub last_accessed: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<Actor>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Secret {
+    pub created: String,
+    #[serde(default)]
+    pub created_by: Option<Actor>,
+    /// Base64 encoded, as stored by `insert_secret`.
+    pub secret: String,
+    /// Set when the value was compressed before it was encoded.
+    #[serde(default)]
+    pub compression: Option<request::Compression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SecretAction {
+    Inserted,
+    Updated,
+    Deleted,
+    Restored,
+}
+
+/// One change of a secret, as listed by `secret_history`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SecretHistoryEntry {
+    pub timestamp: String,
+    pub actor: Option<Actor>,
+    pub action: SecretAction,
+}
+
+/// What `find_secret_if_changed` got back.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum FindSecretResult {
+    Found(Secret),
+    /// The stored value hashes to the given digest, so it isn't sent.
+    Unchanged,
+    NotFound,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum DeleteSecretResult {
+    Deleted,
+    NotFound,
+}
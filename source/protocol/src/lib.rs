@@ -0,0 +1,32 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Version of the websocket protocol, exchanged at login.
+//!
+//! It's bumped whenever a change would make an older CLI or server
+//! misunderstand the other side. The server refuses clients below its
+//! `server.min_client_version` and a client refuses servers below
+//! [`MIN_SERVER_VERSION`].
+
+/// The version the server and the client built alongside it speak.
+pub const VERSION: u32 = 1;
+
+/// The oldest client a server accepts unless configured otherwise.
+pub const MIN_CLIENT_VERSION: u32 = 1;
+
+/// The oldest server a client is able to talk to.
+pub const MIN_SERVER_VERSION: u32 = 1;
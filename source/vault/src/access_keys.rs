@@ -19,22 +19,44 @@ use std::io::Read;
 
 use maplit::hashmap;
 use p256::ecdsa::signature::{Signer, Verifier};
-use rand::Rng;
 use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
 
 use crate::{
-    app_error::{AppError, AppErrorResult, AppResult},
-    config, db, permission,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult, ErrorCode},
+    clock, config, db, log, permission, secrets, sg,
 };
 
+const DEFAULT_STALE_CHECK_INTERVAL_SECS: u64 = 60 * 60;
+
 static mut ECDSA_SIGNING_KEY: Option<p256::ecdsa::SigningKey> = None;
 static mut ECDSA_VERIFYING_KEY: Option<p256::ecdsa::VerifyingKey> = None;
+/* the verifying key of the signing key that was rotated out, while it's still accepted */
+static mut PREVIOUS_VERIFYING_KEY: Option<p256::ecdsa::VerifyingKey> = None;
 static mut DELAY_ON_UNSUCCESS: Option<u64> = None;
+static mut DELAY_JITTER: u64 = 0;
+static mut PEPPER: Vec<u8> = Vec::new();
+static mut ACCEPT_UNPEPPERED: bool = false;
 
 pub async fn delay() {
     let ms = unsafe { DELAY_ON_UNSUCCESS.expect("module IAM is not initialized") };
 
-    tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+    tokio::time::sleep(clock::jittered(ms, unsafe { DELAY_JITTER })).await;
+}
+
+/* with jitter configured a granted request waits as well, so the time taken
+doesn't tell it from a refused one */
+pub async fn delay_granted() {
+    if unsafe { DELAY_JITTER } > 0 {
+        delay().await;
+    }
+}
+
+/* a request refused after `delay_granted` was waited for makes up what's
+left of the delay, so it isn't told apart from one refused right away */
+pub async fn delay_refused_after_granted() {
+    if unsafe { DELAY_JITTER } == 0 {
+        delay().await;
+    }
 }
 
 fn load_pem(filename: &str) -> AppResult<String> {
@@ -51,6 +73,7 @@ fn load_pem(filename: &str) -> AppResult<String> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned()
             }),
+            code: None,
         })?;
 
     file.read_to_string(&mut result).map_app_err(|e| AppError {
@@ -59,71 +82,87 @@ fn load_pem(filename: &str) -> AppResult<String> {
         attr: Some(hashmap! {
             "filename".to_owned() => filename.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
-fn initialize_verifying_key() -> AppResult<()> {
-    let config_clone = config::get_clone();
-    let filename = config_clone.access_keys.verifying_key;
-    let file_content = load_pem(&filename)?;
-
-    let result =
-        p256::ecdsa::VerifyingKey::from_public_key_pem(&file_content).map_app_err(|e| {
-            AppError {
-                message: "failed to load public key".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "filename".to_owned() => filename.to_owned()
-                }),
-            }
-        })?;
+pub fn load_verifying_key(filename: &str) -> AppResult<p256::ecdsa::VerifyingKey> {
+    let file_content = load_pem(filename)?;
 
-    unsafe {
-        ECDSA_VERIFYING_KEY = Some(result);
-    }
+    p256::ecdsa::VerifyingKey::from_public_key_pem(&file_content).map_app_err(|e| AppError {
+        message: "failed to load public key".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned()
+        }),
+        code: None,
+    })
+}
 
-    Ok(())
+pub fn load_signing_key(filename: &str) -> AppResult<p256::ecdsa::SigningKey> {
+    let file_content = load_pem(filename)?;
+
+    p256::ecdsa::SigningKey::from_pkcs8_pem(&file_content).map_app_err(|e| AppError {
+        message: "failed to load private key".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned()
+        }),
+        code: None,
+    })
 }
 
-fn initialize_signing_key() -> AppResult<()> {
+pub fn initialize() -> AppResult<()> {
     let config_clone = config::get_clone();
-    let filename = config_clone.access_keys.signing_key;
-    let file_content = load_pem(&filename)?;
 
-    let result =
-        p256::ecdsa::SigningKey::from_pkcs8_pem(&file_content).map_app_err(|e| AppError {
-            message: "failed to load private key".to_owned(),
-            error: Some(e.to_string()),
-            attr: Some(hashmap! {
-                "filename".to_owned() => filename.to_owned()
-            }),
-        })?;
+    let verifying_key = load_verifying_key(&config_clone.access_keys.verifying_key)?;
+    let signing_key = load_signing_key(&config_clone.access_keys.signing_key)?;
 
-    unsafe {
-        ECDSA_SIGNING_KEY = Some(result);
+    if config_clone.access_keys.self_test.unwrap_or(true) {
+        self_test(&signing_key, &verifying_key)?;
     }
 
-    Ok(())
-}
-
-pub fn initialize() -> AppResult<()> {
-    let config_clone = config::get_clone();
+    let previous_verifying_key = match &config_clone.access_keys.previous_verifying_key {
+        Some(filename) => Some(load_verifying_key(filename)?),
+        None => None,
+    };
 
-    initialize_verifying_key()?;
-    initialize_signing_key()?;
+    let pepper = match &config_clone.access_keys.pepper {
+        /* a trailing newline from the editor isn't part of the pepper */
+        Some(filename) => load_pem(filename)?.trim_end().as_bytes().to_vec(),
+        None => Vec::new(),
+    };
 
     unsafe {
+        ECDSA_VERIFYING_KEY = Some(verifying_key);
+        PREVIOUS_VERIFYING_KEY = previous_verifying_key;
+        ECDSA_SIGNING_KEY = Some(signing_key);
         DELAY_ON_UNSUCCESS = Some(config_clone.access_keys.delay_unsuccessful_attempts_millis);
+        DELAY_JITTER = config_clone.access_keys.delay_jitter_millis.unwrap_or(0);
+        PEPPER = pepper;
+        ACCEPT_UNPEPPERED = config_clone.access_keys.accept_unpeppered.unwrap_or(false);
     }
 
     Ok(())
 }
 
-pub fn verify_access_key(key: &str, signature: &[u8]) -> AppResult<bool> {
-    let verifying_key = unsafe { ECDSA_VERIFYING_KEY.clone().unwrap() };
+#[inline]
+fn peppered(pepper: &[u8], secret: &str) -> Vec<u8> {
+    [pepper, secret.as_bytes()].concat()
+}
 
+/// Checks a secret access key against its stored signature, made over the
+/// pepper followed by the secret. With `accept_unpeppered` a signature over
+/// the bare secret, as made before a pepper was configured, passes as well.
+pub fn verify_signature(
+    verifying_key: &p256::ecdsa::VerifyingKey,
+    pepper: &[u8],
+    accept_unpeppered: bool,
+    key: &str,
+    signature: &[u8],
+) -> AppResult<bool> {
     let signature: p256::ecdsa::Signature = p256::ecdsa::Signature::from_der(signature)
         .map_app_err(|e| AppError {
             message: "invalid signature".to_owned(),
@@ -131,69 +170,137 @@ pub fn verify_access_key(key: &str, signature: &[u8]) -> AppResult<bool> {
             attr: Some(hashmap! {
                 "signature".to_owned() => base64_simd::STANDARD.encode_to_string(signature)
             }),
+            code: None,
         })?;
 
-    Ok(verifying_key.verify(key.as_bytes(), &signature).is_ok())
+    if verifying_key
+        .verify(&peppered(pepper, key), &signature)
+        .is_ok()
+    {
+        return Ok(true);
+    }
+
+    Ok(accept_unpeppered
+        && !pepper.is_empty()
+        && verifying_key.verify(key.as_bytes(), &signature).is_ok())
 }
 
-fn sign_secret(secret: &str) -> String {
-    let siging_key = unsafe { ECDSA_SIGNING_KEY.clone().unwrap() };
-    let signature: p256::ecdsa::Signature = siging_key.sign(secret.as_bytes());
+/// Signs a known string and verifies it, failing when the two keys aren't
+/// from the same pair.
+pub fn self_test(
+    signing_key: &p256::ecdsa::SigningKey,
+    verifying_key: &p256::ecdsa::VerifyingKey,
+) -> AppResult<()> {
+    let signature: p256::ecdsa::Signature = signing_key.sign(b"vaulty self-test");
+
+    verifying_key
+        .verify(b"vaulty self-test", &signature)
+        .map_app_err(|e| AppError {
+            message: "access keys self-test failed".to_owned(),
+            error: Some(format!(
+                "the verifying key doesn't match the signing key: {}",
+                e
+            )),
+            attr: None,
+            code: None,
+        })
+}
+
+pub fn sign_with(signing_key: &p256::ecdsa::SigningKey, pepper: &[u8], secret: &str) -> String {
+    let signature: p256::ecdsa::Signature = signing_key.sign(&peppered(pepper, secret));
     let signature = signature.to_der().to_bytes();
 
     base64_simd::STANDARD.encode_to_string(&signature)
 }
 
-pub struct CreateAccessKeyResult {
-    pub access_key: String,
-    pub secret_access_key: String,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignatureMatch {
+    Current,
+    /// Only the previous verifying key accepts it, the secret was signed
+    /// before the signing key was rotated.
+    Previous,
+    Mismatch,
 }
 
-pub fn create(
-    vault: &str,
-    sg: Vec<String>,
-    permission: Vec<permission::VaultRoles>,
-) -> AppResult<CreateAccessKeyResult> {
-    const ALLOWED_CHARS: &str = "1234567890qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM";
-
-    let config_clone = config::get_clone();
+/// Checks a secret access key with the current verifying key and, while a
+/// signing key rotation is underway, the previous one.
+pub fn verify_rotating(
+    verifying_key: &p256::ecdsa::VerifyingKey,
+    previous_verifying_key: Option<&p256::ecdsa::VerifyingKey>,
+    pepper: &[u8],
+    accept_unpeppered: bool,
+    key: &str,
+    signature: &[u8],
+) -> AppResult<SignatureMatch> {
+    if verify_signature(verifying_key, pepper, accept_unpeppered, key, signature)? {
+        return Ok(SignatureMatch::Current);
+    }
 
-    let mut random = rand::thread_rng();
-    let mut access_key = String::new();
-    let mut secret_access_key = String::new();
-
-    loop {
-        for _ in 0..config_clone.access_keys.acces_key_length {
-            access_key.push(
-                ALLOWED_CHARS
-                    .chars()
-                    .nth(random.gen::<usize>() % ALLOWED_CHARS.len())
-                    .unwrap(),
-            );
+    match previous_verifying_key {
+        Some(previous)
+            if verify_signature(previous, pepper, accept_unpeppered, key, signature)? =>
+        {
+            Ok(SignatureMatch::Previous)
         }
+        _ => Ok(SignatureMatch::Mismatch),
+    }
+}
 
-        if db::access::find(vault, &access_key)?.is_some() {
-            access_key = String::new();
+/* the server only holds signatures, so a key signed with the previous signing
+key can't be re-signed until its client sends the secret with a rotation */
+pub fn verify_access_key(
+    vault: &str,
+    access_key: &str,
+    key: &str,
+    signature: &[u8],
+) -> AppResult<bool> {
+    let verifying_key = unsafe { ECDSA_VERIFYING_KEY.unwrap() };
+    let previous_verifying_key = unsafe { PREVIOUS_VERIFYING_KEY };
+    let (pepper, accept_unpeppered) = unsafe { (&*std::ptr::addr_of!(PEPPER), ACCEPT_UNPEPPERED) };
+
+    match verify_rotating(
+        &verifying_key,
+        previous_verifying_key.as_ref(),
+        pepper,
+        accept_unpeppered,
+        key,
+        signature,
+    )? {
+        SignatureMatch::Current => Ok(true),
+        SignatureMatch::Previous => {
+            log!({
+                mod: log::Module::AccessKey,
+                ctx: "verifying an access key",
+                msg: "access key is signed with the previous signing key, its secret needs rotating",
+                tags: [
+                    "access_key", "rotation"
+                ],
+                attr: {
+                    vault: vault.to_owned(),
+                    access_key: access_key.to_owned()
+                }
+            });
 
-            continue;
-        } else {
-            break;
+            Ok(true)
         }
+        SignatureMatch::Mismatch => Ok(false),
     }
+}
 
-    for _ in 0..config_clone.access_keys.secret_access_key_length {
-        secret_access_key.push(
-            ALLOWED_CHARS
-                .chars()
-                .nth(random.gen::<usize>() % ALLOWED_CHARS.len())
-                .unwrap(),
-        );
-    }
+const ALLOWED_CHARS: &str = secrets::Charset::Alphanumeric.chars();
+
+fn sign_secret(secret: &str) -> String {
+    let siging_key = unsafe { ECDSA_SIGNING_KEY.clone().unwrap() };
+    let pepper = unsafe { &*std::ptr::addr_of!(PEPPER) };
 
+    sign_with(&siging_key, pepper, secret)
+}
+
+fn parse_sg(sg: Vec<String>) -> AppResult<Vec<db::access::AccessKeySgDocument>> {
     let mut parsed_sg = Vec::new();
 
     for v in sg {
-        if let Some((network, ip)) = v.split_once('/') {
+        if let Some((network, ip)) = sg::expand(&v).split_once('/') {
             let value = db::access::AccessKeySgDocument {
                 network: network.to_string(),
                 prefix: ip.parse::<i32>().map_app_err(|_| AppError {
@@ -202,6 +309,7 @@ pub fn create(
                     attr: Some(hashmap! {
                         "sg".to_owned() => v.to_owned()
                     }),
+                    code: Some(ErrorCode::InvalidArgument),
                 })?,
             };
 
@@ -213,11 +321,113 @@ pub fn create(
                 attr: Some(hashmap! {
                     "sg".to_owned() => v.to_owned()
                 }),
+                code: Some(ErrorCode::InvalidArgument),
             });
         }
     }
 
-    let time_now = chrono::Utc::now();
+    Ok(parsed_sg)
+}
+
+pub struct CreateAccessKeyResult {
+    pub access_key: String,
+    pub secret_access_key: String,
+}
+
+fn generate_secret_access_key() -> String {
+    let config_clone = config::get_clone();
+
+    secrets::random_string(
+        ALLOWED_CHARS,
+        config_clone.access_keys.secret_access_key_length,
+    )
+}
+
+pub fn create(
+    vault: &str,
+    sg: Option<Vec<String>>,
+    permission: Option<Vec<permission::VaultRoles>>,
+    expires_in_secs: Option<u64>,
+    name_prefixes: Vec<String>,
+    created_by: db::Actor,
+) -> AppResult<CreateAccessKeyResult> {
+    if !db::is_valid_name(vault) {
+        return Err(AppError {
+            message: "invalid vault name".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "vault".to_owned() => vault.to_owned()
+            }),
+            code: Some(ErrorCode::InvalidArgument),
+        });
+    }
+
+    let (sg, permission) = if sg.is_none() || permission.is_none() {
+        let (default_sg, default_permissions) = match db::vault::find(vault)? {
+            Some(document) => (document.default_sg, document.default_permissions),
+            None => (None, None),
+        };
+
+        (sg.or(default_sg), permission.or(default_permissions))
+    } else {
+        (sg, permission)
+    };
+
+    let sg = sg.context_app_err(|| AppError {
+        message: "missing security group and the vault has no default".to_owned(),
+        error: None,
+        attr: Some(hashmap! {
+            "vault".to_owned() => vault.to_owned()
+        }),
+        code: None,
+    })?;
+
+    let permission = permission.context_app_err(|| AppError {
+        message: "missing permission and the vault has no default".to_owned(),
+        error: None,
+        attr: Some(hashmap! {
+            "vault".to_owned() => vault.to_owned()
+        }),
+        code: None,
+    })?;
+
+    permission::validate_vault_roles(&permission)?;
+
+    let config_clone = config::get_clone();
+
+    let access_key = loop {
+        let access_key =
+            secrets::random_string(ALLOWED_CHARS, config_clone.access_keys.acces_key_length);
+
+        if db::access::find(vault, &access_key)?.is_none() {
+            break access_key;
+        }
+    };
+
+    let secret_access_key = generate_secret_access_key();
+
+    let parsed_sg = parse_sg(sg)?;
+
+    let time_now = clock::now();
+
+    let expires = match expires_in_secs {
+        Some(secs) => Some(
+            i64::try_from(secs)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .and_then(|v| time_now.checked_add_signed(v))
+                .context_app_err(|| AppError {
+                    message: "invalid expiry".to_owned(),
+                    error: None,
+                    attr: Some(hashmap! {
+                        "expires_in_secs".to_owned() => secs.to_string()
+                    }),
+                    code: Some(ErrorCode::InvalidArgument),
+                })?
+                .to_rfc3339(),
+        ),
+        None => None,
+    };
 
     db::access::insert(
         vault,
@@ -228,6 +438,10 @@ pub fn create(
             sg: parsed_sg,
             created: time_now.to_rfc3339(),
             last_used: None,
+            enabled: true,
+            created_by: Some(created_by),
+            expires,
+            name_prefixes,
         },
     )?;
 
@@ -244,6 +458,13 @@ pub fn delete_access_key(
     Ok(db::access::delete(vault, access_key)?)
 }
 
+pub fn delete_access_keys_where(
+    vault: &str,
+    filter: &db::access::AccessKeyFilter,
+) -> AppResult<Vec<String>> {
+    db::access::delete_where(vault, filter)
+}
+
 pub enum ChangePermissionForAccessKeyResult {
     Updated,
     NotFound,
@@ -254,6 +475,8 @@ pub fn change_permission(
     access_key: &str,
     permission: Vec<permission::VaultRoles>,
 ) -> AppResult<ChangePermissionForAccessKeyResult> {
+    permission::validate_vault_roles(&permission)?;
+
     match db::access::change_permission(vault, access_key, permission)? {
         db::access::ChangePermissionForAccessKeyResult::Updated => {
             Ok(ChangePermissionForAccessKeyResult::Updated)
@@ -264,42 +487,91 @@ pub fn change_permission(
     }
 }
 
-pub enum ChangeSgForAccessKeyResult {
+pub enum SetEnabledForAccessKeyResult {
     Updated,
     NotFound,
 }
 
-pub fn change_sg(
+pub fn set_enabled(
     vault: &str,
     access_key: &str,
-    sg: Vec<String>,
-) -> AppResult<ChangeSgForAccessKeyResult> {
-    let mut parsed_sg = Vec::new();
+    enabled: bool,
+) -> AppResult<SetEnabledForAccessKeyResult> {
+    match db::access::set_enabled(vault, access_key, enabled)? {
+        db::access::SetEnabledForAccessKeyResult::Updated => {
+            Ok(SetEnabledForAccessKeyResult::Updated)
+        }
+        db::access::SetEnabledForAccessKeyResult::NotFound => {
+            Ok(SetEnabledForAccessKeyResult::NotFound)
+        }
+    }
+}
 
-    for v in sg {
-        if let Some((network, ip)) = v.split_once('/') {
-            let value = db::access::AccessKeySgDocument {
-                network: network.to_string(),
-                prefix: ip.parse::<i32>().map_app_err(|_| AppError {
-                    message: "invalid network prefix".to_owned(),
-                    error: None,
-                    attr: Some(hashmap! {
-                        "sg".to_owned() => v.to_owned()
-                    }),
-                })?,
-            };
+pub enum RotateSecretForAccessKeyResult {
+    Rotated(String),
+    NotFound,
+}
 
-            parsed_sg.push(value);
-        } else {
-            return Err(AppError {
-                message: "invalid security group".to_owned(),
-                error: None,
-                attr: Some(hashmap! {
-                    "sg".to_owned() => v.to_owned()
-                }),
-            });
+pub fn rotate_secret(vault: &str, access_key: &str) -> AppResult<RotateSecretForAccessKeyResult> {
+    let secret_access_key = generate_secret_access_key();
+
+    match db::access::rotate_secret(vault, access_key, sign_secret(&secret_access_key))? {
+        db::access::RotateSecretForAccessKeyResult::Updated => {
+            Ok(RotateSecretForAccessKeyResult::Rotated(secret_access_key))
         }
+        db::access::RotateSecretForAccessKeyResult::NotFound => {
+            Ok(RotateSecretForAccessKeyResult::NotFound)
+        }
+    }
+}
+
+pub enum VerifySecretResult {
+    Matches,
+    Mismatch,
+    NotFound,
+}
+
+/// Checks `secret_access_key` against the key's stored signature, as a request
+/// would, without touching the key's last use.
+pub fn verify_secret(
+    vault: &str,
+    access_key: &str,
+    secret_access_key: &str,
+) -> AppResult<VerifySecretResult> {
+    let Some(document) = db::access::find(vault, access_key)? else {
+        return Ok(VerifySecretResult::NotFound);
+    };
+
+    let signature = base64_simd::STANDARD
+        .decode_to_vec(&document.secret_access_key)
+        .map_app_err(|e| AppError {
+            message: "failed to decode the secret access key".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "access_key".to_owned() => access_key.to_owned(),
+                "vault".to_owned() => vault.to_owned()
+            }),
+            code: None,
+        })?;
+
+    if verify_access_key(vault, access_key, secret_access_key, &signature)? {
+        Ok(VerifySecretResult::Matches)
+    } else {
+        Ok(VerifySecretResult::Mismatch)
     }
+}
+
+pub enum ChangeSgForAccessKeyResult {
+    Updated,
+    NotFound,
+}
+
+pub fn change_sg(
+    vault: &str,
+    access_key: &str,
+    sg: Vec<String>,
+) -> AppResult<ChangeSgForAccessKeyResult> {
+    let parsed_sg = parse_sg(sg)?;
 
     match db::access::change_sg(vault, access_key, parsed_sg)? {
         db::access::ChangeSgForAccessKeyResult::Updated => Ok(ChangeSgForAccessKeyResult::Updated),
@@ -308,3 +580,157 @@ pub fn change_sg(
         }
     }
 }
+
+pub enum CreateVaultResult {
+    Created,
+    AlreadyExists,
+    InvalidName,
+}
+
+pub fn create_vault(
+    vault: &str,
+    sg: Option<Vec<String>>,
+    permission: Option<Vec<permission::VaultRoles>>,
+) -> AppResult<CreateVaultResult> {
+    if let Some(sg) = &sg {
+        parse_sg(sg.clone())?;
+    }
+
+    match db::vault::create(vault, sg, permission)? {
+        db::vault::CreateVaultResult::Created => Ok(CreateVaultResult::Created),
+        db::vault::CreateVaultResult::AlreadyExists => Ok(CreateVaultResult::AlreadyExists),
+        db::vault::CreateVaultResult::InvalidName => Ok(CreateVaultResult::InvalidName),
+    }
+}
+
+pub enum SetVaultDefaultsResult {
+    Updated,
+    NotFound,
+}
+
+pub fn set_vault_defaults(
+    vault: &str,
+    sg: Option<Vec<String>>,
+    permission: Option<Vec<permission::VaultRoles>>,
+) -> AppResult<SetVaultDefaultsResult> {
+    if let Some(sg) = &sg {
+        parse_sg(sg.clone())?;
+    }
+
+    match db::vault::set_defaults(vault, sg, permission)? {
+        db::vault::SetVaultDefaultsResult::Updated => Ok(SetVaultDefaultsResult::Updated),
+        db::vault::SetVaultDefaultsResult::NotFound => Ok(SetVaultDefaultsResult::NotFound),
+    }
+}
+
+/// An access key that hasn't been used, or created if it never was, since the
+/// cutoff it was checked against.
+pub struct StaleAccessKey {
+    pub vault: String,
+    pub access_key: String,
+    pub last_used: String,
+    pub disabled: bool,
+}
+
+/// Keys idle since before the returned time are stale, `None` when the check
+/// is turned off.
+pub fn stale_cutoff() -> Option<chrono::DateTime<chrono::Utc>> {
+    config::get_clone()
+        .access_keys
+        .stale_after_days
+        .map(|v| clock::now() - chrono::Duration::days(v as i64))
+}
+
+/// Warns about the usable access keys idle since before `cutoff`, disabling
+/// them as well when `disable` is set.
+pub fn check_stale(
+    cutoff: chrono::DateTime<chrono::Utc>,
+    disable: bool,
+) -> AppResult<Vec<StaleAccessKey>> {
+    let mut result = Vec::new();
+    let time_now = clock::now();
+
+    for (vault, access_key, document) in db::access::list_all()? {
+        /* a disabled or expired key can't be used anyway */
+        if document.status(time_now) != db::access::AccessKeyStatus::Active {
+            continue;
+        }
+
+        let last_used = document.last_used.unwrap_or(document.created);
+
+        let stale = chrono::DateTime::parse_from_rfc3339(&last_used)
+            .map(|v| v < cutoff)
+            .unwrap_or(false);
+
+        if !stale {
+            continue;
+        }
+
+        let disabled = disable
+            && matches!(
+                db::access::set_enabled(&vault, &access_key, false)?,
+                db::access::SetEnabledForAccessKeyResult::Updated
+            );
+
+        log!({
+            mod: log::Module::AccessKey,
+            ctx: "checking for stale access keys",
+            msg: if disabled { "stale access key was disabled" } else { "access key is stale" },
+            tags: [
+                "accesskey", "warning"
+            ],
+            attr: {
+                vault: vault.clone(),
+                access_key: access_key.clone(),
+                last_used: last_used.clone(),
+            }
+        });
+
+        result.push(StaleAccessKey {
+            vault,
+            access_key,
+            last_used,
+            disabled,
+        });
+    }
+
+    Ok(result)
+}
+
+pub fn watch_stale() {
+    let config = config::get_clone().access_keys;
+
+    if config.stale_after_days.is_none() {
+        return;
+    }
+
+    let disable = config.disable_stale.unwrap_or(false);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            config
+                .stale_check_interval_secs
+                .unwrap_or(DEFAULT_STALE_CHECK_INTERVAL_SECS),
+        ));
+
+        loop {
+            interval.tick().await;
+
+            let Some(cutoff) = stale_cutoff() else {
+                continue;
+            };
+
+            if let Err(e) = check_stale(cutoff, disable) {
+                log!({
+                    mod: log::Module::AccessKey,
+                    ctx: "checking for stale access keys",
+                    msg: "failed to check the access keys",
+                    err: e,
+                    tags: [
+                        "accesskey", "db", "error"
+                    ]
+                });
+            }
+        }
+    });
+}
@@ -19,12 +19,101 @@ use futures::StreamExt;
 use maplit::hashmap;
 
 use crate::{
-    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
-    cmd, config, log, user,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult, ErrorCode},
+    cmd, config, db, log, permission, stats, user,
 };
 
 const MAXIMUM_FRAME_SIZE: usize = 128 * 1042 * 1024;
 
+/// Registered as app data by a server that refuses admins over plaintext.
+pub struct AdminRequiresTls(pub bool);
+
+fn min_client_version() -> u32 {
+    config::get_clone()
+        .server
+        .min_client_version
+        .unwrap_or(vaulty_protocol::MIN_CLIENT_VERSION)
+}
+
+/// Registered as app data with the server's cap on sessions and how many are open.
+#[derive(Clone)]
+pub struct SessionLimit {
+    max_sessions: Option<usize>,
+    active: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl SessionLimit {
+    pub fn new(max_sessions: Option<usize>) -> SessionLimit {
+        SessionLimit {
+            max_sessions,
+            active: std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        }
+    }
+
+    fn acquire(&self) -> Option<SessionSlot> {
+        self.active
+            .fetch_update(
+                std::sync::atomic::Ordering::SeqCst,
+                std::sync::atomic::Ordering::SeqCst,
+                |v| match self.max_sessions {
+                    Some(max_sessions) if v >= max_sessions => None,
+                    _ => Some(v + 1),
+                },
+            )
+            .ok()
+            .map(|_| SessionSlot(self.active.clone()))
+    }
+}
+
+pub struct SessionSlot(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/* responses waiting to be written; a client that lets this many pile up is disconnected */
+const OUTBOUND_QUEUE_SIZE: usize = 64;
+
+enum Outbound {
+    Text(String),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(actix_ws::CloseReason),
+}
+
+pub struct Outbox {
+    queue: tokio::sync::mpsc::Sender<Outbound>,
+    writer: tokio::task::JoinHandle<()>,
+}
+
+impl Outbox {
+    fn spawn(mut ws_session: actix_ws::Session) -> Outbox {
+        let (queue, mut receiver) = tokio::sync::mpsc::channel(OUTBOUND_QUEUE_SIZE);
+
+        let writer = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                let result = match message {
+                    Outbound::Text(data) => ws_session.text(data).await,
+                    Outbound::Ping(data) => ws_session.ping(&data).await,
+                    Outbound::Pong(data) => ws_session.pong(&data).await,
+                    Outbound::Close(close_reason) => {
+                        let _ = ws_session.close(Some(close_reason)).await;
+                        return;
+                    }
+                };
+
+                if result.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Outbox { queue, writer }
+    }
+}
+
 pub enum SessionState {
     Login,
     Command,
@@ -33,60 +122,103 @@ pub enum SessionState {
 pub struct Session {
     pub friendly_ip: String,
     pub ip: std::net::IpAddr,
+    pub tls: bool,
+    admin_requires_tls: bool,
+    session_limit: SessionLimit,
     pub state: SessionState,
     pub username: Option<String>,
-    pub ws_session: Option<actix_ws::Session>,
-    pub lock: tokio::sync::Mutex<()>,
+    pub outbox: Option<Outbox>,
+    pub slot: Option<SessionSlot>,
+    pub stats: stats::SessionEntry,
 }
 
 impl Session {
-    fn new(ip: &std::net::IpAddr, ws_session: actix_ws::Session) -> Session {
+    fn new(
+        ip: &std::net::IpAddr,
+        tls: bool,
+        admin_requires_tls: bool,
+        session_limit: SessionLimit,
+        ws_session: actix_ws::Session,
+    ) -> Session {
         Session {
             ip: ip.clone(),
             friendly_ip: ip.to_string(),
+            tls,
+            admin_requires_tls,
+            session_limit,
             state: SessionState::Login,
             username: None,
-            ws_session: Some(ws_session),
-            lock: tokio::sync::Mutex::new(()),
+            outbox: Some(Outbox::spawn(ws_session)),
+            slot: None,
+            stats: stats::SessionEntry::register(ip),
         }
     }
 
+    pub fn is_closed(&self) -> bool {
+        self.outbox.is_none()
+    }
+
     pub async fn close(&mut self) {
-        let stream = self
-            .ws_session
-            .take()
-            .expect("close was used before Session was initialized");
+        self.close_with(actix_ws::CloseReason::from(actix_ws::CloseCode::Error))
+            .await;
+    }
 
-        let close_reason = actix_ws::CloseReason::from(actix_ws::CloseCode::Error);
+    /* whatever is still queued goes out before the close frame */
+    pub async fn close_with(&mut self, close_reason: actix_ws::CloseReason) {
+        if let Some(outbox) = self.outbox.take() {
+            if outbox
+                .queue
+                .try_send(Outbound::Close(close_reason))
+                .is_err()
+            {
+                outbox.writer.abort();
+            }
+        }
+    }
+
+    fn enqueue(&mut self, message: Outbound) -> AppResult<()> {
+        let error = match self.outbox.as_ref().map(|v| v.queue.try_send(message)) {
+            Some(Ok(())) => return Ok(()),
+            Some(Err(tokio::sync::mpsc::error::TrySendError::Full(_))) => {
+                /* the client isn't reading and the writer is stuck on the socket */
+                if let Some(outbox) = self.outbox.take() {
+                    outbox.writer.abort();
+                }
+
+                "outbound queue is full"
+            }
+            Some(Err(tokio::sync::mpsc::error::TrySendError::Closed(_))) => {
+                self.outbox = None;
 
-        let _ = stream.close(Some(close_reason)).await;
+                "stream was closed"
+            }
+            None => "stream was closed",
+        };
+
+        Err(AppError {
+            message: "failed send the response".to_owned(),
+            error: Some(error.to_owned()),
+            attr: Some(hashmap![
+                "ip".to_owned() => self.friendly_ip.clone()
+            ]),
+            code: None,
+        })
     }
 
     pub async fn pong(&mut self, data: &[u8]) {
-        let stream = self
-            .ws_session
-            .as_mut()
-            .expect("send_response was used before Session was initialized");
-
-        let _ = self.lock.lock().await;
-        let _ = stream.pong(data).await;
+        let _ = self.enqueue(Outbound::Pong(data.to_vec()));
     }
 
     async fn ping(&mut self) -> AppResult<()> {
         let ping_data: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
 
-        let stream = self
-            .ws_session
-            .as_mut()
-            .expect("send_response was used before Session was initialized");
-
-        let _ = self.lock.lock().await;
-
-        Ok(stream.ping(&ping_data).await.map_app_err(|e| AppError {
-            message: "failed to ping".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?)
+        self.enqueue(Outbound::Ping(ping_data))
+            .map_app_err(|e| AppError {
+                message: "failed to ping".to_owned(),
+                error: e.error,
+                attr: e.attr,
+                code: None,
+            })
     }
 
     pub async fn send_response<T>(&mut self, data: T) -> AppResult<()>
@@ -98,11 +230,6 @@ impl Session {
             SessionState::Command => "command",
         };
 
-        let stream = self
-            .ws_session
-            .as_mut()
-            .expect("send_response was used before Session was initialized");
-
         let response = simd_json::to_string(&data).map_app_err(|e| AppError {
             message: "failed to serialize the responsee".to_owned(),
             error: Some(e.to_string()),
@@ -110,19 +237,12 @@ impl Session {
                 "state".to_owned() => state.to_owned(),
                 "ip".to_owned() => self.friendly_ip.clone()
             ]),
+            code: None,
         })?;
 
-        let _ = self.lock.lock().await;
-        let _ = stream.text(response).await.map_app_err(|_| AppError {
-            message: "failed send the response".to_owned(),
-            error: Some("stream was closed".to_owned()),
-            attr: Some(hashmap![
-                "state".to_owned() => state.to_owned(),
-                "ip".to_owned() => self.friendly_ip.clone()
-            ]),
-        })?;
+        self.stats.counters().sent(response.len());
 
-        Ok(())
+        self.enqueue(Outbound::Text(response))
     }
 
     async fn login_handle(&mut self, data: &str) -> AppResult<user::LoginResult> {
@@ -130,20 +250,32 @@ impl Session {
         struct Request {
             username: String,
             password: String,
+            /* clients from before the version was exchanged don't send it */
+            #[serde(default)]
+            protocol_version: u32,
         }
 
         #[derive(Debug, Clone, serde::Serialize)]
         enum ResponseResult {
             Granted,
             Denied,
+            SessionLimitReached,
+            UnsupportedVersion,
+            TlsRequired,
         }
 
         #[derive(Debug, Clone, serde::Serialize)]
         struct Response {
             result: ResponseResult,
+            protocol_version: u32,
+            min_client_version: Option<u32>,
             node_name: Option<String>,
+            role: Option<permission::UserRole>,
+            sg: Option<Vec<String>>,
         }
 
+        self.stats.counters().received(data.len());
+
         let mut data = data.to_string();
 
         let request: Request =
@@ -154,10 +286,99 @@ impl Session {
                     "state".to_owned() => "login".to_owned(),
                     "ip".to_owned() => self.friendly_ip.clone()
                 ]),
+                code: None,
             })?;
 
-        match user::login(self.ip, &request.username, &request.password)? {
-            user::LoginResult::Successful => {
+        let min_client_version = min_client_version();
+
+        if request.protocol_version < min_client_version {
+            log!({
+                mod: log::Module::Api,
+                ctx: "api login",
+                msg: "client protocol version is not supported",
+                tags: [
+                    "api", "access", "login"
+                ],
+                attr: {
+                    ip: self.friendly_ip.clone(),
+                    user: request.username.clone(),
+                    protocol_version: request.protocol_version,
+                    min_client_version: min_client_version,
+                }
+            });
+
+            self.send_response(&Response {
+                result: ResponseResult::UnsupportedVersion,
+                protocol_version: vaulty_protocol::VERSION,
+                min_client_version: Some(min_client_version),
+                node_name: None,
+                role: None,
+                sg: None,
+            })
+            .await?;
+
+            self.close_with(actix_ws::CloseReason {
+                code: actix_ws::CloseCode::Policy,
+                description: Some(format!(
+                    "protocol version {min_client_version} or newer is required"
+                )),
+            })
+            .await;
+
+            return Ok(user::LoginResult::Failed);
+        }
+
+        let plaintext_admins_refused = !self.tls && self.admin_requires_tls;
+        let refused = |role: &permission::UserRole| {
+            plaintext_admins_refused && matches!(role, permission::UserRole::Admin)
+        };
+
+        /* a known admin is refused before its password is verified, one that
+        is provisioned as an admin by this login only after */
+        let result = match db::user::find(&request.username)? {
+            Some(user) if refused(&user.role) => None,
+            _ => Some(user::login(self.ip, &request.username, &request.password)?),
+        };
+
+        match result {
+            Some(user::LoginResult::Successful(user)) if !refused(&user.role) => {
+                user::delay_granted().await;
+
+                self.slot = self.session_limit.acquire();
+
+                if self.slot.is_none() {
+                    log!({
+                        mod: log::Module::Api,
+                        ctx: "api login",
+                        msg: "maximum number of sessions reached",
+                        tags: [
+                            "api", "access", "login", "error"
+                        ],
+                        attr: {
+                            ip: self.friendly_ip.clone(),
+                            user: request.username.clone(),
+                        }
+                    });
+
+                    self.send_response(&Response {
+                        result: ResponseResult::SessionLimitReached,
+                        protocol_version: vaulty_protocol::VERSION,
+                        min_client_version: None,
+                        node_name: None,
+                        role: None,
+                        sg: None,
+                    })
+                    .await?;
+
+                    self.close_with(actix_ws::CloseReason {
+                        code: actix_ws::CloseCode::Again,
+                        description: Some("maximum number of sessions reached".to_owned()),
+                    })
+                    .await;
+
+                    return Ok(user::LoginResult::Failed);
+                }
+
                 log!({
                     mod: log::Module::Api,
                     ctx: "api login",
@@ -173,16 +394,58 @@ impl Session {
 
                 self.send_response(&Response {
                     result: ResponseResult::Granted,
+                    protocol_version: vaulty_protocol::VERSION,
+                    min_client_version: None,
                     node_name: Some(config::get_clone().node_name),
+                    role: Some(user.role),
+                    sg: Some(
+                        user.sg
+                            .iter()
+                            .map(|v| format!("{}/{}", v.network, v.prefix))
+                            .collect(),
+                    ),
                 })
                 .await?;
 
+                self.stats.set_username(&request.username);
                 self.username = Some(request.username);
                 self.state = SessionState::Command;
 
-                Ok(user::LoginResult::Successful)
+                Ok(user::LoginResult::Successful(user))
             }
-            user::LoginResult::Failed => {
+            None | Some(user::LoginResult::Successful(_)) => {
+                log!({
+                    mod: log::Module::Api,
+                    ctx: "api login",
+                    msg: "admin login over a plaintext connection was refused",
+                    tags: [
+                        "api", "access", "login", "error"
+                    ],
+                    attr: {
+                        ip: self.friendly_ip.clone(),
+                        user: request.username.clone(),
+                    }
+                });
+
+                self.send_response(&Response {
+                    result: ResponseResult::TlsRequired,
+                    protocol_version: vaulty_protocol::VERSION,
+                    min_client_version: None,
+                    node_name: None,
+                    role: None,
+                    sg: None,
+                })
+                .await?;
+
+                self.close_with(actix_ws::CloseReason {
+                    code: actix_ws::CloseCode::Policy,
+                    description: Some("admin logins require TLS".to_owned()),
+                })
+                .await;
+
+                Ok(user::LoginResult::Failed)
+            }
+            Some(user::LoginResult::Failed) => {
                 log!({
                     mod: log::Module::Api,
                     ctx: "api login",
@@ -200,7 +463,11 @@ impl Session {
 
                 self.send_response(&Response {
                     result: ResponseResult::Denied,
+                    protocol_version: vaulty_protocol::VERSION,
+                    min_client_version: None,
                     node_name: None,
+                    role: None,
+                    sg: None,
                 })
                 .await?;
 
@@ -210,18 +477,38 @@ impl Session {
     }
 
     async fn step(&mut self, data: String, command: &mut String) -> AppResult<()> {
-        /* simd_json corrupts the input data */
-        let mut data = data.trim().to_string();
+        self.stats.command_executed();
+        self.stats.counters().request();
+        self.stats.counters().received(data.len());
 
-        let request: cmd::Request =
-            unsafe { simd_json::from_str(&mut data) }.map_app_err(|e| AppError {
-                message: "invalid data received".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap![
-                    "state".to_owned() => "command".to_owned(),
-                    "ip".to_owned() => self.friendly_ip.clone()
-                ]),
-            })?;
+        let request = match cmd::Request::parse(data.trim()) {
+            Ok(request) => request,
+            Err(e) => {
+                log!({
+                    mod: log::Module::Api,
+                    ctx: "websockets handle",
+                    msg: "invalid data received",
+                    err: AppError {
+                        message: e.error.clone(),
+                        error: None,
+                        attr: None,
+                        code: None
+                    },
+                    tags: [
+                        "api", "execution", "error"
+                    ],
+                    attr: {
+                        ip: self.friendly_ip.clone(),
+                        user: self.username.clone(),
+                        command: e.command.clone(),
+                        field: e.field.clone()
+                    }
+                });
+
+                /* answered like a failed command, with where it went wrong */
+                return self.send_response(&e).await;
+            }
+        };
 
         *command = request.to_command_string();
 
@@ -241,11 +528,14 @@ impl Session {
 
         match request {
             cmd::Request::CreateUser(data) => cmd::user::insert(self, data).await?,
-            cmd::Request::ListUsers() => cmd::user::list(self).await?,
+            cmd::Request::ListUsers(data) => cmd::user::list(self, data).await?,
             cmd::Request::FindUser(data) => cmd::user::find(self, data).await?,
             cmd::Request::DeleteUser(data) => cmd::user::delete(self, data).await?,
             cmd::Request::PromoteUser(data) => cmd::user::promote(self, data).await?,
             cmd::Request::DemoteUser(data) => cmd::user::demote(self, data).await?,
+            cmd::Request::ChangeUserRole(data) => cmd::user::change_role(self, data).await?,
+            cmd::Request::LockUser(data) => cmd::user::lock(self, data).await?,
+            cmd::Request::UnlockUser(data) => cmd::user::unlock(self, data).await?,
             cmd::Request::ChangePasswordForUser(data) => {
                 cmd::user::change_password(self, data).await?
             }
@@ -254,17 +544,44 @@ impl Session {
             cmd::Request::ListAccessKeys(data) => cmd::access::list(self, data).await?,
             cmd::Request::FindAccessKey(data) => cmd::access::find(self, data).await?,
             cmd::Request::DeleteAccessKey(data) => cmd::access::delete(self, data).await?,
+            cmd::Request::DeleteAccessKeysWhere(data) => {
+                cmd::access::delete_where(self, data).await?
+            }
             cmd::Request::ChangePermissionForAccessKey(data) => {
                 cmd::access::change_permission(self, data).await?
             }
             cmd::Request::ChangeSgForAccessKey(data) => cmd::access::change_sg(self, data).await?,
-            cmd::Request::ListVaults() => cmd::vault::list(self).await?,
+            cmd::Request::SetAccessKeyEnabled(data) => cmd::access::set_enabled(self, data).await?,
+            cmd::Request::RotateAccessKeySecret(data) => {
+                cmd::access::rotate_secret(self, data).await?
+            }
+            cmd::Request::SimulateAccess(data) => cmd::access::simulate(self, data).await?,
+            cmd::Request::VerifyAccessKeySecret(data) => {
+                cmd::access::verify_secret(self, data).await?
+            }
+            cmd::Request::ListVaults(data) => cmd::vault::list(self, data).await?,
+            cmd::Request::CreateVault(data) => cmd::vault::create(self, data).await?,
             cmd::Request::FindVault(data) => cmd::vault::find(self, data).await?,
             cmd::Request::DeleteVault(data) => cmd::vault::delete(self, data).await?,
+            cmd::Request::RenameVault(data) => cmd::vault::rename(self, data).await?,
+            cmd::Request::SetVaultDefaults(data) => cmd::vault::set_defaults(self, data).await?,
+            cmd::Request::SetVaultValueSchema(data) => {
+                cmd::vault::set_value_schema(self, data).await?
+            }
+            cmd::Request::VerifyIntegrity(data) => cmd::vault::verify_integrity(self, data).await?,
             cmd::Request::InsertSecret(data) => cmd::secret::insert(self, data).await?,
+            cmd::Request::GenerateSecret(data) => cmd::secret::generate(self, data).await?,
             cmd::Request::ListSecrets(data) => cmd::secret::list(self, data).await?,
             cmd::Request::FindSecret(data) => cmd::secret::find(self, data).await?,
+            cmd::Request::GetSecrets(data) => cmd::secret::get(self, data).await?,
             cmd::Request::DeleteSecret(data) => cmd::secret::delete(self, data).await?,
+            cmd::Request::RestoreSecret(data) => cmd::secret::restore(self, data).await?,
+            cmd::Request::ExistsSecret(data) => cmd::secret::exists(self, data).await?,
+            cmd::Request::SecretHistory(data) => cmd::secret::history(self, data).await?,
+            cmd::Request::SessionStats() => cmd::stats::list(self).await?,
+            cmd::Request::ListSessions() => cmd::session::list(self).await?,
+            cmd::Request::KillSession(data) => cmd::session::kill(self, data).await?,
+            cmd::Request::ListRoles() => cmd::role::list(self).await?,
         }
 
         Ok(())
@@ -272,12 +589,21 @@ impl Session {
 }
 
 #[inline]
-fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
-    let host = host.context_app_err(|| AppError {
-        message: "missing IP in the request".to_owned(),
-        error: None,
-        attr: None,
-    })?;
+fn process_host_ip(req: &actix_web::HttpRequest) -> AppResult<std::net::IpAddr> {
+    /* peers on the Unix socket have no address, they're on the same machine */
+    if req.peer_addr().is_none() {
+        return Ok(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+    }
+
+    let connection_info = req.connection_info();
+    let host = connection_info
+        .realip_remote_addr()
+        .context_app_err(|| AppError {
+            message: "missing IP in the request".to_owned(),
+            error: None,
+            attr: None,
+            code: None,
+        })?;
 
     if let Some((host, _port)) = host.split_once(':') {
         Ok(host.parse().map_app_err(|_| AppError {
@@ -286,6 +612,7 @@ fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
             attr: Some(hashmap! {
                 "ip".to_owned() => host.to_string(),
             }),
+            code: None,
         })?)
     } else {
         Ok(host.parse().map_app_err(|_| AppError {
@@ -294,6 +621,7 @@ fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
             attr: Some(hashmap! {
                 "ip".to_owned() => host.to_string(),
             }),
+            code: None,
         })?)
     }
 }
@@ -303,7 +631,17 @@ async fn web_socket(
     req: actix_web::HttpRequest,
     stream: actix_web::web::Payload,
 ) -> impl actix_web::Responder {
-    let (ip, friendly_ip) = match process_host_ip(req.connection_info().realip_remote_addr()) {
+    /* from the listener, a forwarded scheme is up to the client to claim */
+    let tls = req.app_config().secure();
+    let admin_requires_tls = req
+        .app_data::<AdminRequiresTls>()
+        .is_some_and(|value| value.0);
+    let session_limit = req
+        .app_data::<SessionLimit>()
+        .cloned()
+        .unwrap_or_else(|| SessionLimit::new(None));
+
+    let (ip, friendly_ip) = match process_host_ip(&req) {
         Ok(value) => (value, value.to_string()),
         Err(e) => {
             log!({
@@ -324,9 +662,11 @@ async fn web_socket(
         message: "failed to begin handling websocket".to_owned(),
         error: Some(e.to_string()),
         attr: None,
+        code: None,
     }) {
         Ok((res, ws_session, stream)) => {
-            let mut user_session = Session::new(&ip, ws_session);
+            let mut user_session =
+                Session::new(&ip, tls, admin_requires_tls, session_limit, ws_session);
 
             let mut stream = stream
                 .max_frame_size(MAXIMUM_FRAME_SIZE)
@@ -351,7 +691,7 @@ async fn web_socket(
                     match msg {
                         Ok(actix_ws::AggregatedMessage::Text(data)) => {
                             match user_session.login_handle(&data).await {
-                                Ok(user::LoginResult::Successful) => user_authorized = true,
+                                Ok(user::LoginResult::Successful(_)) => user_authorized = true,
                                 Ok(user::LoginResult::Failed) => user_authorized = false,
                                 Err(e) => {
                                     log!({
@@ -379,7 +719,8 @@ async fn web_socket(
                         std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
 
                     {
-                        let user_session = user_session.clone();
+                        /* the session, and its slot, are dropped once the handler below returns */
+                        let user_session = std::sync::Arc::downgrade(&user_session);
                         let stream_closing = stream_closing.clone();
                         let friendly_ip = friendly_ip.clone();
 
@@ -387,6 +728,10 @@ async fn web_socket(
                             loop {
                                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
+                                let Some(user_session) = user_session.upgrade() else {
+                                    break;
+                                };
+
                                 let mut user_session = user_session.lock().await;
 
                                 if let Err(e) = user_session.ping().await {
@@ -414,6 +759,7 @@ async fn web_socket(
                     }
 
                     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+                    let kill_signal = user_session.lock().await.stats.kill_signal();
 
                     loop {
                         tokio::select! {
@@ -443,16 +789,41 @@ async fn web_socket(
 
                                 return;
                             }
+                            _ = kill_signal.notified() => {
+                                let mut user_session = user_session.lock().await;
+
+                                log!({
+                                    mod: log::Module::Api,
+                                    ctx: "websockets handle",
+                                    msg: "session was killed by an admin",
+                                    tags: [
+                                        "api", "access"
+                                    ],
+                                    attr: {
+                                        ip: friendly_ip.clone(),
+                                        user: user_session.username.clone()
+                                    }
+                                });
+
+                                user_session
+                                    .close_with(actix_ws::CloseReason {
+                                        code: actix_ws::CloseCode::Policy,
+                                        description: Some("session was killed".to_owned()),
+                                    })
+                                    .await;
+
+                                return;
+                            }
                             msg = stream.next() => {
                                 if let Some(msg) = msg {
                                     match msg {
                                         Ok(actix_ws::AggregatedMessage::Text(data)) => {
-                                            let (username, command, step_result) = {
+                                            let (username, command, step_result, closed) = {
                                                 let mut user_session = user_session.lock().await;
                                                 let mut command = String::new();
                                                 let result = user_session.step(data.to_string(), &mut command).await;
 
-                                                (user_session.username.clone().expect("command executed without user being logged in"), command, result)
+                                                (user_session.username.clone().expect("command executed without user being logged in"), command, result, user_session.is_closed())
                                             };
 
                                             if let Err(e) = step_result {
@@ -474,14 +845,20 @@ async fn web_socket(
                                                 #[derive(serde::Serialize)]
                                                 struct Response {
                                                     error: String,
+                                                    code: ErrorCode,
+                                                    /* the same command may succeed when sent again */
+                                                    #[serde(skip_serializing_if = "std::ops::Not::not")]
+                                                    transient: bool,
                                                 }
 
-                                                {
+                                                if !closed {
                                                     let mut user_session = user_session.lock().await;
 
                                                     if let Err(_) = user_session
                                                         .send_response(&Response {
                                                             error: e.message.clone(),
+                                                            code: e.response_code(),
+                                                            transient: db::is_transient_error(&e),
                                                         })
                                                         .await
                                                     {
@@ -489,6 +866,25 @@ async fn web_socket(
                                                     }
                                                 }
                                             }
+
+                                            let user_session = user_session.lock().await;
+
+                                            if user_session.is_closed() {
+                                                log!({
+                                                    mod: log::Module::Api,
+                                                    ctx: "websockets handle",
+                                                    msg: "session was closed while handling a command",
+                                                    tags: [
+                                                        "api", "error"
+                                                    ],
+                                                    attr: {
+                                                        ip: friendly_ip.clone(),
+                                                        user: user_session.username.clone()
+                                                    }
+                                                });
+
+                                                return;
+                                            }
                                         }
                                         Err(e) => {
                                             let user_session = user_session.lock().await;
@@ -500,7 +896,8 @@ async fn web_socket(
                                                 err: AppError {
                                                     message: e.to_string(),
                                                     error: None,
-                                                    attr: None
+                                                    attr: None,
+                                                    code: None
                                                 },
                                                 tags: [
                                                     "api", "error"
@@ -515,6 +912,23 @@ async fn web_socket(
                                         }
                                         _ => {}
                                     }
+                                } else {
+                                    let user_session = user_session.lock().await;
+
+                                    log!({
+                                        mod: log::Module::Api,
+                                        ctx: "websockets handle",
+                                        msg: "stream was closed",
+                                        tags: [
+                                            "api"
+                                        ],
+                                        attr: {
+                                            ip: friendly_ip.clone(),
+                                            user: user_session.username.clone()
+                                        }
+                                    });
+
+                                    return;
                                 }
                             }
                         }
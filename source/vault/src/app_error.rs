@@ -17,6 +17,20 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Sent along with the message of a failed websocket command, unlike the
+/// message it doesn't change between releases.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ErrorCode {
+    /// The command isn't valid JSON or doesn't match any request.
+    InvalidRequest,
+    UnknownCommand,
+    /// The request parsed, but one of its values was rejected.
+    InvalidArgument,
+    /// The database was busy, the same command may succeed when sent again.
+    Busy,
+    Internal,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AppError {
     pub message: String,
@@ -24,6 +38,20 @@ pub struct AppError {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attr: Option<std::collections::HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+}
+
+impl AppError {
+    /// The code a client gets for this error, errors without one are
+    /// internal unless the database was only busy.
+    pub fn response_code(&self) -> ErrorCode {
+        match self.code {
+            Some(code) => code,
+            None if crate::db::is_transient_error(self) => ErrorCode::Busy,
+            None => ErrorCode::Internal,
+        }
+    }
 }
 
 impl Into<json::JsonValue> for AppError {
@@ -32,6 +60,7 @@ impl Into<json::JsonValue> for AppError {
             message: self.message,
             error: self.error,
             attr: self.attr,
+            code: self.code.map(|v| format!("{:?}", v)),
         }
     }
 }
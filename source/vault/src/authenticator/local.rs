@@ -0,0 +1,33 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{app_error::AppResult, user};
+
+use super::{Authentication, Authenticator};
+
+/// Users and their Argon2 password hashes kept in the DB, the default.
+pub struct Local;
+
+impl Authenticator for Local {
+    fn authenticate(&self, username: &str, password: &str) -> AppResult<Authentication> {
+        if user::is_current_password(username, password)? {
+            Ok(Authentication::Granted { groups: Vec::new() })
+        } else {
+            Ok(Authentication::Denied)
+        }
+    }
+}
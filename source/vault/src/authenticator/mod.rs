@@ -0,0 +1,127 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::HashMap;
+
+#[cfg(not(feature = "oidc"))]
+use crate::app_error::AppError;
+use crate::{app_error::AppResult, config, permission};
+
+mod local;
+#[cfg(feature = "oidc")]
+mod oidc;
+
+pub use local::Local;
+#[cfg(feature = "oidc")]
+pub use oidc::Oidc;
+
+pub enum Authentication {
+    /// The password is right, `groups` are what the source says the user
+    /// belongs to, empty when it has no groups.
+    Granted {
+        groups: Vec<String>,
+    },
+    Denied,
+}
+
+/// How users granted by an external source are created in the DB and which
+/// role they get.
+#[derive(Debug, Clone)]
+pub struct Provisioning {
+    pub role_mapping: HashMap<String, permission::UserRole>,
+    pub default_role: Option<permission::UserRole>,
+    pub sg: Vec<String>,
+}
+
+impl Provisioning {
+    /// Admin when any of the groups maps to it, otherwise the role of the
+    /// first mapped group, falling back to `default_role`. `None` denies the
+    /// login.
+    pub fn role(&self, groups: &[String]) -> Option<permission::UserRole> {
+        let mut role = None;
+
+        for group in groups {
+            match self.role_mapping.get(group) {
+                Some(permission::UserRole::Admin) => return Some(permission::UserRole::Admin),
+                Some(mapped) if role.is_none() => role = Some(*mapped),
+                _ => {}
+            }
+        }
+
+        role.or(self.default_role)
+    }
+}
+
+/// Checks a username and password at login. The security groups and the
+/// lock are checked by `user::login` whatever the source.
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, username: &str, password: &str) -> AppResult<Authentication>;
+
+    /// `None` for users managed in the DB, otherwise granted users are created
+    /// on their first login and their role follows their groups.
+    fn provisioning(&self) -> Option<&Provisioning> {
+        None
+    }
+}
+
+static mut AUTHENTICATOR: Option<Box<dyn Authenticator>> = None;
+
+pub fn get() -> &'static dyn Authenticator {
+    match unsafe { AUTHENTICATOR.as_deref() } {
+        Some(authenticator) => authenticator,
+        None => &Local,
+    }
+}
+
+pub fn initialize() -> AppResult<()> {
+    let Some(authenticator) = config::get_clone().users.authenticator else {
+        return Ok(());
+    };
+
+    let provisioning = Provisioning {
+        role_mapping: authenticator.role_mapping.unwrap_or_default(),
+        default_role: authenticator.default_role,
+        sg: authenticator
+            .sg
+            .unwrap_or_else(|| vec!["127.0.0.1/32".to_owned()]),
+    };
+
+    let instance: Box<dyn Authenticator> = match authenticator.kind {
+        None | Some(config::AuthenticatorKind::Local) => Box::new(Local),
+        #[cfg(feature = "oidc")]
+        Some(config::AuthenticatorKind::Oidc) => {
+            Box::new(Oidc::load(authenticator.oidc, provisioning)?)
+        }
+        #[cfg(not(feature = "oidc"))]
+        Some(config::AuthenticatorKind::Oidc) => {
+            let _ = provisioning;
+
+            return Err(AppError {
+                message: "unsupported authenticator".to_owned(),
+                error: Some("vaulty was built without the oidc feature".to_owned()),
+                attr: None,
+                code: None,
+            });
+        }
+    };
+
+    unsafe {
+        AUTHENTICATOR = Some(instance);
+    }
+
+    Ok(())
+}
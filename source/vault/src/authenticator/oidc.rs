@@ -0,0 +1,113 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    access_keys,
+    app_error::{AppError, AppErrorOption, AppResult},
+    config, jwt, log,
+};
+
+use super::{Authentication, Authenticator, Provisioning};
+
+const DEFAULT_USERNAME_CLAIM: &str = "preferred_username";
+const DEFAULT_GROUPS_CLAIM: &str = "groups";
+
+/// Takes an ES256 signed ID token from the identity provider in place of the
+/// password. The token must be valid and name the user logging in.
+pub struct Oidc {
+    verifying_key: p256::ecdsa::VerifyingKey,
+    issuer: Option<String>,
+    audience: Option<String>,
+    username_claim: String,
+    groups_claim: String,
+    provisioning: Provisioning,
+}
+
+impl Oidc {
+    pub fn load(
+        oidc: Option<config::ConfigUsersOidc>,
+        provisioning: Provisioning,
+    ) -> AppResult<Oidc> {
+        let oidc = oidc.context_app_err(|| AppError {
+            message: "missing the OIDC settings".to_owned(),
+            error: None,
+            attr: None,
+            code: None,
+        })?;
+
+        Ok(Oidc {
+            verifying_key: access_keys::load_verifying_key(&oidc.public_key)?,
+            issuer: oidc.issuer,
+            audience: oidc.audience,
+            username_claim: oidc
+                .username_claim
+                .unwrap_or_else(|| DEFAULT_USERNAME_CLAIM.to_owned()),
+            groups_claim: oidc
+                .groups_claim
+                .unwrap_or_else(|| DEFAULT_GROUPS_CLAIM.to_owned()),
+            provisioning,
+        })
+    }
+}
+
+impl Authenticator for Oidc {
+    fn authenticate(&self, username: &str, password: &str) -> AppResult<Authentication> {
+        let claims: serde_json::Value = match jwt::verify_with(
+            &self.verifying_key,
+            self.issuer.as_deref(),
+            self.audience.as_deref(),
+            password,
+        ) {
+            Ok(claims) => claims,
+            Err(e) => {
+                log!({
+                    mod: log::Module::User,
+                    ctx: "authenticating with OIDC",
+                    msg: "ID token was refused",
+                    err: e,
+                    tags: [
+                        "user", "access"
+                    ],
+                    attr: {
+                        user: username
+                    }
+                });
+
+                return Ok(Authentication::Denied);
+            }
+        };
+
+        if claims[&self.username_claim].as_str() != Some(username) {
+            return Ok(Authentication::Denied);
+        }
+
+        let groups = match &claims[&self.groups_claim] {
+            serde_json::Value::Array(groups) => groups
+                .iter()
+                .filter_map(|v| v.as_str().map(|v| v.to_owned()))
+                .collect(),
+            serde_json::Value::String(group) => vec![group.clone()],
+            _ => Vec::new(),
+        };
+
+        Ok(Authentication::Granted { groups })
+    }
+
+    fn provisioning(&self) -> Option<&Provisioning> {
+        Some(&self.provisioning)
+    }
+}
@@ -0,0 +1,356 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+
+use maplit::hashmap;
+use rand::Rng;
+
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    config, db, permission, user,
+};
+
+const CONFIG_FILENAME: &str = "config.yml";
+const RSA_PRIVATE_KEY_FILENAME: &str = "secret-rsa-private.pem";
+const RSA_PUBLIC_KEY_FILENAME: &str = "secret-rsa-public.pem";
+const AES_KEY_FILENAME: &str = "secret-aes.key";
+const AES_IV_FILENAME: &str = "secret-iv.key";
+const SIGNING_KEY_FILENAME: &str = "access-private.pem";
+const VERIFYING_KEY_FILENAME: &str = "access-public.pem";
+const INITIAL_PASSWORD_CHARS: &str =
+    "1234567890qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM";
+const INITIAL_PASSWORD_LEN: usize = 20;
+const INITIAL_USERNAME: &str = "root";
+const INITIAL_SECURITY_GROUP: &str = "127.0.0.1/32";
+
+/// Where the first admin's password came from, and so whether it may be shown.
+pub enum InitialPassword {
+    /// Generated and kept nowhere else, it has to be shown once.
+    Generated(String),
+    /// Generated and written to the file.
+    Written(String),
+    /// Read from the environment or a file, the operator has it already.
+    Provided,
+}
+
+pub enum InitialAdmin {
+    Created {
+        username: String,
+        sg: Vec<String>,
+        password: InitialPassword,
+    },
+    Exists,
+    /// The admin is provisioned some other way.
+    Skipped,
+}
+
+fn write_file(path: &std::path::Path, content: &[u8], private: bool) -> AppResult<String> {
+    let filename = path.to_str().unwrap_or("N/A").to_owned();
+    let mut options = std::fs::OpenOptions::new();
+
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut file = options.open(path).map_app_err(|e| AppError {
+        message: "failed to create".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.clone()
+        }),
+        code: None,
+    })?;
+
+    file.write_all(content).map_app_err(|e| AppError {
+        message: "failed to write".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.clone()
+        }),
+        code: None,
+    })?;
+
+    Ok(filename)
+}
+
+#[inline]
+fn openssl_error(message: &str, e: openssl::error::ErrorStack) -> AppError {
+    AppError {
+        message: message.to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    }
+}
+
+fn yaml_string(value: &str) -> AppResult<String> {
+    serde_yaml::to_string(value)
+        .map(|v| v.trim_end().to_owned())
+        .map_app_err(|e| AppError {
+            message: "failed to serialize the config".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })
+}
+
+/* creates the key files and a config pointing at them, nothing in the
+directory is overwritten */
+pub fn generate_config(directory: &std::path::Path) -> AppResult<String> {
+    std::fs::create_dir_all(directory).map_app_err(|e| AppError {
+        message: "failed to create the directory".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "directory".to_owned() => directory.to_str().unwrap_or("N/A").to_owned()
+        }),
+        code: None,
+    })?;
+
+    let directory = directory.canonicalize().map_app_err(|e| AppError {
+        message: "failed to resolve the directory".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "directory".to_owned() => directory.to_str().unwrap_or("N/A").to_owned()
+        }),
+        code: None,
+    })?;
+
+    for filename in [
+        CONFIG_FILENAME,
+        RSA_PRIVATE_KEY_FILENAME,
+        RSA_PUBLIC_KEY_FILENAME,
+        AES_KEY_FILENAME,
+        AES_IV_FILENAME,
+        SIGNING_KEY_FILENAME,
+        VERIFYING_KEY_FILENAME,
+    ] {
+        let path = directory.join(filename);
+
+        if path.exists() {
+            return Err(AppError {
+                message: "refusing to overwrite an existing file".to_owned(),
+                error: None,
+                attr: Some(hashmap! {
+                    "filename".to_owned() => path.to_str().unwrap_or("N/A").to_owned()
+                }),
+                code: None,
+            });
+        }
+    }
+
+    let rsa = openssl::rsa::Rsa::generate(4096)
+        .and_then(openssl::pkey::PKey::from_rsa)
+        .map_err(|e| openssl_error("failed to generate the RSA key", e))?;
+    let ec = openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1)
+        .and_then(|group| openssl::ec::EcKey::generate(&group))
+        .and_then(openssl::pkey::PKey::from_ec_key)
+        .map_err(|e| openssl_error("failed to generate the ECDSA key", e))?;
+
+    let mut aes_key = [0u8; 32];
+    let mut aes_iv = [0u8; 12];
+
+    openssl::rand::rand_bytes(&mut aes_key)
+        .and_then(|_| openssl::rand::rand_bytes(&mut aes_iv))
+        .map_err(|e| openssl_error("failed to generate the AES key", e))?;
+
+    let rsa_private_key = write_file(
+        &directory.join(RSA_PRIVATE_KEY_FILENAME),
+        &rsa.private_key_to_pem_pkcs8()
+            .map_err(|e| openssl_error("failed to encode the RSA key", e))?,
+        true,
+    )?;
+    let rsa_public_key = write_file(
+        &directory.join(RSA_PUBLIC_KEY_FILENAME),
+        &rsa.public_key_to_pem()
+            .map_err(|e| openssl_error("failed to encode the RSA key", e))?,
+        true,
+    )?;
+    let aes_key = write_file(
+        &directory.join(AES_KEY_FILENAME),
+        base64_simd::STANDARD.encode_to_string(aes_key).as_bytes(),
+        true,
+    )?;
+    let aes_iv = write_file(
+        &directory.join(AES_IV_FILENAME),
+        base64_simd::STANDARD.encode_to_string(aes_iv).as_bytes(),
+        true,
+    )?;
+    let signing_key = write_file(
+        &directory.join(SIGNING_KEY_FILENAME),
+        &ec.private_key_to_pem_pkcs8()
+            .map_err(|e| openssl_error("failed to encode the ECDSA key", e))?,
+        true,
+    )?;
+    let verifying_key = write_file(
+        &directory.join(VERIFYING_KEY_FILENAME),
+        &ec.public_key_to_pem()
+            .map_err(|e| openssl_error("failed to encode the ECDSA key", e))?,
+        true,
+    )?;
+
+    let config = format!(
+        "node_name: Vaulty
+log:
+  filename: {log}
+db:
+  location: {db}
+  repair: true
+secrets:
+  scheme: Rsa
+  rsa_private_key: {rsa_private_key}
+  rsa_public_key: {rsa_public_key}
+  aes_key: {aes_key}
+  aes_iv: {aes_iv}
+access_keys:
+  signing_key: {signing_key}
+  verifying_key: {verifying_key}
+  delay_unsuccessful_attempts_millis: 5000
+  acces_key_length: 20
+  secret_access_key_length: 40
+users:
+  delay_unsuccessful_attempts_millis: 5000
+server:
+  listen_address: 127.0.0.1
+  listen_port: 8080
+",
+        log = yaml_string(directory.join("vault.log").to_str().unwrap_or("vault.log"))?,
+        db = yaml_string(
+            directory
+                .join("database.bin")
+                .to_str()
+                .unwrap_or("database.bin")
+        )?,
+        rsa_private_key = yaml_string(&rsa_private_key)?,
+        rsa_public_key = yaml_string(&rsa_public_key)?,
+        aes_key = yaml_string(&aes_key)?,
+        aes_iv = yaml_string(&aes_iv)?,
+        signing_key = yaml_string(&signing_key)?,
+        verifying_key = yaml_string(&verifying_key)?,
+    );
+
+    write_file(&directory.join(CONFIG_FILENAME), config.as_bytes(), false)
+}
+
+fn generate_password() -> String {
+    let mut random = rand::thread_rng();
+
+    (0..INITIAL_PASSWORD_LEN)
+        .map(|_| {
+            INITIAL_PASSWORD_CHARS
+                .chars()
+                .nth(random.gen::<usize>() % INITIAL_PASSWORD_CHARS.len())
+                .unwrap()
+        })
+        .collect()
+}
+
+/* the environment wins over the file, neither is logged */
+fn provided_password(bootstrap: &config::ConfigUsersBootstrap) -> AppResult<Option<String>> {
+    let password = if let Some(name) = &bootstrap.password_env {
+        std::env::var(name).map_app_err(|e| AppError {
+            message: "failed to read the initial password from the environment".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "variable".to_owned() => name.to_owned()
+            }),
+            code: None,
+        })?
+    } else if let Some(filename) = &bootstrap.password_file {
+        /* a trailing newline from the editor isn't part of the password */
+        std::fs::read_to_string(filename)
+            .map_app_err(|e| AppError {
+                message: "failed to read the initial password".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "filename".to_owned() => filename.to_owned()
+                }),
+                code: None,
+            })?
+            .trim_end()
+            .to_owned()
+    } else {
+        return Ok(None);
+    };
+
+    if password.is_empty() {
+        return Err(AppError {
+            message: "the initial password is empty".to_owned(),
+            error: None,
+            attr: None,
+            code: None,
+        });
+    }
+
+    Ok(Some(password))
+}
+
+/// Creates the first admin of a new database as `bootstrap` describes, a
+/// `root` allowed from `127.0.0.1/32` with a generated password by default.
+pub fn create_initial_admin(bootstrap: &config::ConfigUsersBootstrap) -> AppResult<InitialAdmin> {
+    if bootstrap.externally_provisioned.unwrap_or(false) {
+        return Ok(InitialAdmin::Skipped);
+    }
+
+    let username = bootstrap
+        .username
+        .clone()
+        .unwrap_or_else(|| INITIAL_USERNAME.to_owned());
+    let sg = bootstrap
+        .sg
+        .clone()
+        .unwrap_or_else(|| vec![INITIAL_SECURITY_GROUP.to_owned()]);
+
+    let (password, initial_password) = match provided_password(bootstrap)? {
+        Some(password) => (password, InitialPassword::Provided),
+        None => {
+            let password = generate_password();
+
+            match &bootstrap.write_password_to {
+                Some(filename) => {
+                    let filename =
+                        write_file(std::path::Path::new(filename), password.as_bytes(), true)?;
+
+                    (password, InitialPassword::Written(filename))
+                }
+                None => (password.clone(), InitialPassword::Generated(password)),
+            }
+        }
+    };
+
+    match user::create_user(
+        &username,
+        &password,
+        &permission::UserRole::Admin,
+        sg.clone(),
+    )? {
+        db::user::InsertUserResult::Created => Ok(InitialAdmin::Created {
+            username,
+            sg,
+            password: initial_password,
+        }),
+        db::user::InsertUserResult::Exists => Ok(InitialAdmin::Exists),
+    }
+}
@@ -0,0 +1,61 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/* timestamps stored in the DB are taken from here, so tests can move the
+time forward instead of waiting for it */
+static OFFSET_MILLIS: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+#[inline]
+fn offset() -> chrono::Duration {
+    chrono::Duration::milliseconds(OFFSET_MILLIS.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+pub fn now() -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now() + offset()
+}
+
+/* older releases stored some timestamps with the server's local offset,
+returns the UTC form of a timestamp that is not already in UTC */
+pub fn to_utc(timestamp: &str) -> Option<String> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+
+    if parsed.offset().local_minus_utc() == 0 {
+        return None;
+    }
+
+    Some(parsed.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+/* a fixed delay is itself a signal, so it's stretched by a random part of
+the jitter, never less than the base */
+pub fn jittered(base_millis: u64, jitter_millis: u64) -> std::time::Duration {
+    let jitter = if jitter_millis > 0 {
+        rand::Rng::gen_range(&mut rand::thread_rng(), 0..=jitter_millis)
+    } else {
+        0
+    };
+
+    std::time::Duration::from_millis(base_millis.saturating_add(jitter))
+}
+
+#[cfg(test)]
+pub fn advance(duration: chrono::Duration) {
+    OFFSET_MILLIS.fetch_add(
+        duration.num_milliseconds(),
+        std::sync::atomic::Ordering::Relaxed,
+    );
+}
@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, api, app_error::AppResult, cmd, db, log};
+use crate::{access_keys, api, app_error::AppResult, cmd, db, log, webhook};
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum ResponseState {
@@ -42,6 +42,14 @@ pub async fn delete(
         db::access::DeleteAccessKeyResult::NotFound => ResponseState::NotFound,
     };
 
+    if matches!(result, ResponseState::Deleted) {
+        webhook::notify(
+            &data.vault,
+            webhook::Action::DeleteAccessKey,
+            &data.access_key,
+        );
+    }
+
     log!({
         mod: log::Module::Vault,
         ctx: "request to delete access key",
@@ -0,0 +1,120 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    access_keys, api,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult, ErrorCode},
+    cmd, db, log, permission, webhook,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum Response {
+    Deleted { access_keys: Vec<String> },
+    MissingFilter,
+    Denied,
+}
+
+pub async fn delete_where(
+    session: &mut api::Session,
+    data: cmd::RequestDeleteAccessKeysWhere,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to delete access keys by filter",
+            msg: "insufficient permission",
+            tags: [
+                "api", "access_key", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username,
+                vault: data.vault
+            }
+        });
+
+        session.send_response(&Response::Denied).await?;
+
+        return Ok(());
+    }
+
+    let unused_since = match &data.unused_since {
+        Some(unused_since) => Some(
+            chrono::DateTime::parse_from_rfc3339(unused_since).map_app_err(|e| AppError {
+                message: "invalid unused_since timestamp".to_owned(),
+                error: Some(e.to_string()),
+                attr: None,
+                code: Some(ErrorCode::InvalidArgument),
+            })?,
+        ),
+        None => None,
+    };
+
+    let filter = db::access::AccessKeyFilter {
+        unused_since,
+        disabled: data.disabled,
+        permission: data.permission,
+    };
+
+    /* an empty filter matches every key of the vault */
+    if filter.is_empty() {
+        session.send_response(&Response::MissingFilter).await?;
+
+        return Ok(());
+    }
+
+    let deleted = access_keys::delete_access_keys_where(&data.vault, &filter)?;
+
+    for access_key in &deleted {
+        webhook::notify(&data.vault, webhook::Action::DeleteAccessKey, access_key);
+    }
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to delete access keys by filter",
+        msg: "access keys deleted",
+        tags: [
+            "api", "access_key", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username,
+            vault: data.vault,
+            count: deleted.len()
+        }
+    });
+
+    session
+        .send_response(&Response::Deleted {
+            access_keys: deleted,
+        })
+        .await?;
+
+    Ok(())
+}
@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, cmd, db, log, permission};
+use crate::{api, app_error::AppResult, clock, cmd, db, log, permission};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
@@ -24,11 +24,16 @@ struct ResponseEntry {
     sg: Vec<String>,
     created: String,
     last_used: Option<String>,
+    enabled: bool,
+    created_by: Option<db::Actor>,
+    expires: Option<String>,
+    name_prefixes: Vec<String>,
+    effective_status: db::access::AccessKeyStatus,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum Response {
-    Found(ResponseEntry),
+    Found(Box<ResponseEntry>),
     NotFound,
 }
 
@@ -39,6 +44,8 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindAccessKey) -
         .expect("state is command while user hasn't logged in");
 
     if let Some(document) = db::access::find(&data.vault, &data.access_key)? {
+        let effective_status = document.status(clock::now());
+
         let entry = ResponseEntry {
             access_key: data.access_key.clone(),
             permission: document.permission,
@@ -49,6 +56,11 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindAccessKey) -
                 .collect(),
             created: document.created,
             last_used: document.last_used,
+            enabled: document.enabled,
+            effective_status,
+            created_by: document.created_by,
+            expires: document.expires,
+            name_prefixes: document.name_prefixes,
         };
 
         log!({
@@ -66,7 +78,9 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindAccessKey) -
             }
         });
 
-        session.send_response(&Response::Found(entry)).await?;
+        session
+            .send_response(&Response::Found(Box::new(entry)))
+            .await?;
     } else {
         log!({
             mod: log::Module::Vault,
@@ -15,12 +15,19 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, api, app_error::AppResult, cmd, log};
+use crate::{
+    access_keys, api,
+    app_error::{AppError, AppResult, ErrorCode},
+    cmd, config, db, log, server, vault, webhook,
+};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct Response {
     access_key: String,
     secret_access_key: String,
+    /* both carry the secret, they're only ever part of this response */
+    authorization: String,
+    curl: String,
 }
 
 pub async fn insert(
@@ -32,7 +39,33 @@ pub async fn insert(
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    let document = access_keys::create(&data.vault, data.sg, data.permission)?;
+    let permission = match (data.permission, data.preset) {
+        (Some(_), Some(_)) => {
+            return Err(AppError {
+                message: "permission and preset can't be used together".to_owned(),
+                error: None,
+                attr: None,
+                code: Some(ErrorCode::InvalidArgument),
+            })
+        }
+        (None, Some(preset)) => Some(preset.roles()),
+        (permission, None) => permission,
+    };
+
+    let document = access_keys::create(
+        &data.vault,
+        data.sg,
+        permission,
+        data.expires_in_secs,
+        data.name_prefixes.unwrap_or_default(),
+        db::Actor::User(executer_username.clone()),
+    )?;
+
+    webhook::notify(
+        &data.vault,
+        webhook::Action::InsertAccessKey,
+        &document.access_key,
+    );
 
     log!({
         mod: log::Module::Vault,
@@ -44,15 +77,25 @@ pub async fn insert(
         attr: {
             ip: session.friendly_ip.clone(),
             user: executer_username.clone(),
-            vault: data.vault,
+            vault: data.vault.clone(),
             access_key: document.access_key.clone()
         }
     });
 
+    let authorization = vault::format_signature(&document.access_key, &document.secret_access_key);
+    let curl = format!(
+        "curl -H 'Authorization: {}' {}/{}/[SECRET NAME]",
+        authorization,
+        server::curl_target(&config::get_clone().server),
+        data.vault
+    );
+
     session
         .send_response(&Response {
             access_key: document.access_key,
             secret_access_key: document.secret_access_key,
+            authorization,
+            curl,
         })
         .await?;
 
@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, cmd, db, log, permission};
+use crate::{api, app_error::AppResult, clock, cmd, db, log, permission};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
@@ -24,6 +24,11 @@ struct ResponseEntry {
     sg: Vec<String>,
     created: String,
     last_used: Option<String>,
+    enabled: bool,
+    created_by: Option<db::Actor>,
+    expires: Option<String>,
+    name_prefixes: Vec<String>,
+    effective_status: db::access::AccessKeyStatus,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -38,9 +43,18 @@ pub async fn list(session: &mut api::Session, data: cmd::RequestListAccessKeys)
         .expect("state is command while user hasn't logged in");
 
     let mut result = Vec::new();
-    let documents = db::access::list(&data.vault)?;
+    let documents = db::access::list(
+        &data.vault,
+        data.name_prefix.as_deref().unwrap_or_default(),
+        data.sort_by.unwrap_or_default(),
+        data.order.unwrap_or_default(),
+    )?;
+
+    let time_now = clock::now();
 
     for (access_key, document) in documents {
+        let effective_status = document.status(time_now);
+
         result.push(ResponseEntry {
             access_key,
             permission: document.permission,
@@ -51,6 +65,11 @@ pub async fn list(session: &mut api::Session, data: cmd::RequestListAccessKeys)
                 .collect(),
             created: document.created,
             last_used: document.last_used,
+            enabled: document.enabled,
+            effective_status,
+            created_by: document.created_by,
+            expires: document.expires,
+            name_prefixes: document.name_prefixes,
         });
     }
 
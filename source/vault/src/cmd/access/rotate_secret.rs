@@ -0,0 +1,66 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, api, app_error::AppResult, cmd, log};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum Response {
+    Rotated {
+        access_key: String,
+        secret_access_key: String,
+    },
+    NotFound,
+}
+
+pub async fn rotate_secret(
+    session: &mut api::Session,
+    data: cmd::RequestRotateAccessKeySecret,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let response = match access_keys::rotate_secret(&data.vault, &data.access_key)? {
+        access_keys::RotateSecretForAccessKeyResult::Rotated(secret_access_key) => {
+            log!({
+                mod: log::Module::Vault,
+                ctx: "request to rotate the secret of an access key",
+                msg: "secret access key was rotated",
+                tags: [
+                    "api", "access_key", "request"
+                ],
+                attr: {
+                    ip: session.friendly_ip.clone(),
+                    user: executer_username.clone(),
+                    vault: data.vault,
+                    access_key: data.access_key.clone()
+                }
+            });
+
+            Response::Rotated {
+                access_key: data.access_key,
+                secret_access_key,
+            }
+        }
+        access_keys::RotateSecretForAccessKeyResult::NotFound => Response::NotFound,
+    };
+
+    session.send_response(response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppResult},
+    cmd, db, log, permission, vault,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseResult {
+    Authorized,
+    Unauthorized,
+    Forbidden,
+    InvalidIp,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseReason {
+    UnknownAccessKey,
+    NotInSecurityGroup,
+    Disabled,
+    Expired,
+    MissingPermission,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<ResponseReason>,
+    /// The access key's security groups when the IP is outside all of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sg: Option<Vec<String>>,
+}
+
+impl Response {
+    fn result(result: ResponseResult) -> Response {
+        Response {
+            result,
+            reason: None,
+            sg: None,
+        }
+    }
+
+    fn denied(result: ResponseResult, reason: ResponseReason) -> Response {
+        Response {
+            result,
+            reason: Some(reason),
+            sg: None,
+        }
+    }
+}
+
+pub async fn simulate(
+    session: &mut api::Session,
+    data: cmd::RequestSimulateAccess,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to simulate an access",
+            msg: "insufficient permission",
+            tags: [
+                "api", "access_key", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault,
+                access_key: data.access_key
+            }
+        });
+
+        return session
+            .send_response(Response::result(ResponseResult::Denied))
+            .await;
+    }
+
+    let Ok(requester_ip) = data.ip.parse::<std::net::IpAddr>() else {
+        return session
+            .send_response(Response::result(ResponseResult::InvalidIp))
+            .await;
+    };
+
+    let response =
+        match vault::simulate_access(requester_ip, &data.access_key, data.permission, &data.vault)?
+        {
+            vault::AccessCheckResult::Authorized => Response::result(ResponseResult::Authorized),
+            vault::AccessCheckResult::Forbidden => {
+                Response::denied(ResponseResult::Forbidden, ResponseReason::MissingPermission)
+            }
+            vault::AccessCheckResult::UnknownAccessKey
+            | vault::AccessCheckResult::InvalidSecretAccessKey => Response::denied(
+                ResponseResult::Unauthorized,
+                ResponseReason::UnknownAccessKey,
+            ),
+            vault::AccessCheckResult::NotInSecurityGroup(sg) => Response {
+                result: ResponseResult::Unauthorized,
+                reason: Some(ResponseReason::NotInSecurityGroup),
+                sg: Some(sg),
+            },
+            vault::AccessCheckResult::Disabled => {
+                Response::denied(ResponseResult::Unauthorized, ResponseReason::Disabled)
+            }
+            vault::AccessCheckResult::Expired => {
+                Response::denied(ResponseResult::Unauthorized, ResponseReason::Expired)
+            }
+        };
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to simulate an access",
+        msg: "access simulated",
+        tags: [
+            "api", "access_key", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username.clone(),
+            vault: data.vault,
+            access_key: data.access_key,
+            requester_ip: data.ip,
+            permission: format!("{:?}", data.permission),
+            result: format!("{:?}", response.result)
+        }
+    });
+
+    session.send_response(response).await?;
+
+    Ok(())
+}
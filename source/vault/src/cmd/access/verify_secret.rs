@@ -0,0 +1,119 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    access_keys, api,
+    app_error::{AppError, AppErrorOption, AppResult},
+    cmd, db, log, permission,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseResult {
+    Verified,
+    NotFound,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<bool>,
+}
+
+pub async fn verify_secret(
+    session: &mut api::Session,
+    data: cmd::RequestVerifyAccessKeySecret,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to verify an access key's secret",
+            msg: "insufficient permission",
+            tags: [
+                "api", "access_key", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault,
+                access_key: data.access_key
+            }
+        });
+
+        return session
+            .send_response(Response {
+                result: ResponseResult::Denied,
+                matches: None,
+            })
+            .await;
+    }
+
+    let response =
+        match access_keys::verify_secret(&data.vault, &data.access_key, &data.secret_access_key)? {
+            access_keys::VerifySecretResult::Matches => Response {
+                result: ResponseResult::Verified,
+                matches: Some(true),
+            },
+            access_keys::VerifySecretResult::Mismatch => Response {
+                result: ResponseResult::Verified,
+                matches: Some(false),
+            },
+            access_keys::VerifySecretResult::NotFound => Response {
+                result: ResponseResult::NotFound,
+                matches: None,
+            },
+        };
+
+    /* a guess is as slow here as it is over the REST API */
+    if response.matches != Some(true) {
+        access_keys::delay().await;
+    }
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to verify an access key's secret",
+        msg: "secret access key verified",
+        tags: [
+            "api", "access_key", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username.clone(),
+            vault: data.vault,
+            access_key: data.access_key,
+            result: format!("{:?}", response.result),
+            matches: response.matches
+        }
+    });
+
+    session.send_response(response).await?;
+
+    Ok(())
+}
@@ -15,14 +15,20 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::permission;
+use crate::{compression, db, permission, secrets};
 
 pub mod access;
+mod parse;
+pub mod role;
 pub mod secret;
+pub mod session;
+pub mod stats;
 pub mod user;
 pub mod vault;
 
-#[derive(Debug, Clone, serde::Deserialize)]
+pub use parse::ParseError;
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestCreateUser {
     pub username: String,
     pub password: String,
@@ -30,159 +36,361 @@ pub struct RequestCreateUser {
     pub sg: Vec<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct RequestListUsers {
+    pub after: Option<String>,
+    pub limit: Option<usize>,
+    pub role: Option<permission::UserRole>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestFindUser {
     pub username: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestDeleteUser {
     pub username: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestPromoteUser {
     pub username: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestDemoteUser {
     pub username: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestChangeUserRole {
+    pub username: String,
+    pub role: permission::UserRole,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestLockUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestUnlockUser {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestChangePasswordForUser {
     pub username: String,
     pub password: String,
+    #[serde(default)]
+    pub current_password: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestChangeSgForUser {
     pub username: String,
     pub sg: Vec<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestCreateAccessKey {
     pub vault: String,
-    pub permission: Vec<permission::VaultRoles>,
-    pub sg: Vec<String>,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub preset: Option<permission::VaultRolesPreset>,
+    pub sg: Option<Vec<String>>,
+    pub expires_in_secs: Option<u64>,
+    pub name_prefixes: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestListAccessKeys {
     pub vault: String,
+    pub sort_by: Option<db::access::AccessKeySortBy>,
+    pub order: Option<db::SortOrder>,
+    pub name_prefix: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct RequestListVaults {
+    pub sort_by: Option<db::vault::VaultSortBy>,
+    pub order: Option<db::SortOrder>,
+    pub name_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestFindAccessKey {
     pub vault: String,
     pub access_key: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestDeleteAccessKey {
     pub vault: String,
     pub access_key: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestDeleteAccessKeysWhere {
+    pub vault: String,
+    pub unused_since: Option<String>,
+    pub disabled: Option<bool>,
+    pub permission: Option<permission::VaultRoles>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestChangePermissionsForAccessKey {
     pub vault: String,
     pub access_key: String,
     pub permission: Vec<permission::VaultRoles>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestSetAccessKeyEnabled {
+    pub vault: String,
+    pub access_key: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestSimulateAccess {
+    pub vault: String,
+    pub access_key: String,
+    pub ip: String,
+    pub permission: permission::VaultRoles,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestVerifyAccessKeySecret {
+    pub vault: String,
+    pub access_key: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestRotateAccessKeySecret {
+    pub vault: String,
+    pub access_key: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestChangeSgForAccessKey {
     pub vault: String,
     pub access_key: String,
     pub sg: Vec<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestFindVault {
     pub vault: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestDeleteVault {
     pub vault: String,
+    pub confirm: Option<String>,
+    #[serde(default)]
+    pub force: bool,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestRenameVault {
+    pub vault: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestCreateVault {
+    pub vault: String,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub sg: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestSetVaultDefaults {
+    pub vault: String,
+    pub permission: Option<Vec<permission::VaultRoles>>,
+    pub sg: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestSetVaultValueSchema {
+    pub vault: String,
+    pub value_schema: db::vault::ValueSchema,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestVerifyIntegrity {
+    pub vault: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestInsertSecret {
     pub secret_name: String,
     pub vault: String,
+    /// Base64 encoded, left out for an alias.
+    #[serde(default)]
     pub data: String,
+    pub alias: Option<db::secret::SecretAlias>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestGenerateSecret {
+    pub vault: String,
+    pub secret_name: String,
+    pub length: Option<usize>,
+    pub charset: Option<secrets::Charset>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestListSecrets {
     pub vault: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestFindSecret {
     pub vault: String,
     pub secret_name: String,
+    pub expected_sha256: Option<String>,
+    pub compression: Option<compression::Compression>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestGetSecrets {
+    pub vault: String,
+    pub names: Vec<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub struct RequestDeleteSecret {
     pub vault: String,
     pub secret_name: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestRestoreSecret {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestSecretHistory {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestExistsSecret {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct RequestKillSession {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
 pub enum Request {
     CreateUser(RequestCreateUser),
-    ListUsers(),
+    ListUsers(RequestListUsers),
     FindUser(RequestFindUser),
     DeleteUser(RequestDeleteUser),
     PromoteUser(RequestPromoteUser),
     DemoteUser(RequestDemoteUser),
+    ChangeUserRole(RequestChangeUserRole),
+    LockUser(RequestLockUser),
+    UnlockUser(RequestUnlockUser),
     ChangePasswordForUser(RequestChangePasswordForUser),
     ChangeSgForUser(RequestChangeSgForUser),
     CreateAccessKey(RequestCreateAccessKey),
     ListAccessKeys(RequestListAccessKeys),
     FindAccessKey(RequestFindAccessKey),
     DeleteAccessKey(RequestDeleteAccessKey),
+    DeleteAccessKeysWhere(RequestDeleteAccessKeysWhere),
     ChangePermissionForAccessKey(RequestChangePermissionsForAccessKey),
     ChangeSgForAccessKey(RequestChangeSgForAccessKey),
-    ListVaults(),
+    SetAccessKeyEnabled(RequestSetAccessKeyEnabled),
+    RotateAccessKeySecret(RequestRotateAccessKeySecret),
+    SimulateAccess(RequestSimulateAccess),
+    VerifyAccessKeySecret(RequestVerifyAccessKeySecret),
+    ListVaults(RequestListVaults),
+    CreateVault(RequestCreateVault),
     FindVault(RequestFindVault),
     DeleteVault(RequestDeleteVault),
+    RenameVault(RequestRenameVault),
+    SetVaultDefaults(RequestSetVaultDefaults),
+    SetVaultValueSchema(RequestSetVaultValueSchema),
+    VerifyIntegrity(RequestVerifyIntegrity),
     InsertSecret(RequestInsertSecret),
+    GenerateSecret(RequestGenerateSecret),
     ListSecrets(RequestListSecrets),
     FindSecret(RequestFindSecret),
+    GetSecrets(RequestGetSecrets),
     DeleteSecret(RequestDeleteSecret),
+    RestoreSecret(RequestRestoreSecret),
+    ExistsSecret(RequestExistsSecret),
+    SecretHistory(RequestSecretHistory),
+    SessionStats(),
+    ListSessions(),
+    KillSession(RequestKillSession),
+    ListRoles(),
+}
+
+/// JSON Schema of every request a logged in session may send, for clients to
+/// check their payloads against.
+pub fn schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(Request)).expect("a schema is always valid JSON")
 }
 
 impl Request {
     pub fn to_command_string(&self) -> String {
         match self {
             Request::CreateUser(_) => "CreateUser".to_string(),
-            Request::ListUsers() => "ListUsers".to_string(),
+            Request::ListUsers(_) => "ListUsers".to_string(),
             Request::FindUser(_) => "FindUser".to_string(),
             Request::DeleteUser(_) => "DeleteUser".to_string(),
             Request::PromoteUser(_) => "PromoteUser".to_string(),
             Request::DemoteUser(_) => "DemoteUser".to_string(),
+            Request::ChangeUserRole(_) => "ChangeUserRole".to_string(),
+            Request::LockUser(_) => "LockUser".to_string(),
+            Request::UnlockUser(_) => "UnlockUser".to_string(),
             Request::ChangePasswordForUser(_) => "ChangePasswordForUser".to_string(),
             Request::ChangeSgForUser(_) => "ChangeSgForUser".to_string(),
             Request::CreateAccessKey(_) => "CreateAccessKey".to_string(),
             Request::ListAccessKeys(_) => "ListAccessKeys".to_string(),
             Request::FindAccessKey(_) => "FindAccessKey".to_string(),
             Request::DeleteAccessKey(_) => "DeleteAccessKey".to_string(),
+            Request::DeleteAccessKeysWhere(_) => "DeleteAccessKeysWhere".to_string(),
             Request::ChangePermissionForAccessKey(_) => "ChangePermissionForAccessKey".to_string(),
             Request::ChangeSgForAccessKey(_) => "ChangeSgForAccessKey".to_string(),
-            Request::ListVaults() => "ListVaults".to_string(),
+            Request::SetAccessKeyEnabled(_) => "SetAccessKeyEnabled".to_string(),
+            Request::RotateAccessKeySecret(_) => "RotateAccessKeySecret".to_string(),
+            Request::SimulateAccess(_) => "SimulateAccess".to_string(),
+            Request::VerifyAccessKeySecret(_) => "VerifyAccessKeySecret".to_string(),
+            Request::ListVaults(_) => "ListVaults".to_string(),
             Request::FindVault(_) => "FindVault".to_string(),
             Request::DeleteVault(_) => "DeleteVault".to_string(),
+            Request::RenameVault(_) => "RenameVault".to_string(),
+            Request::SetVaultDefaults(_) => "SetVaultDefaults".to_string(),
+            Request::SetVaultValueSchema(_) => "SetVaultValueSchema".to_string(),
+            Request::CreateVault(_) => "CreateVault".to_string(),
+            Request::VerifyIntegrity(_) => "VerifyIntegrity".to_string(),
             Request::InsertSecret(_) => "InsertSecret".to_string(),
+            Request::GenerateSecret(_) => "GenerateSecret".to_string(),
             Request::ListSecrets(_) => "ListSecrets".to_string(),
             Request::FindSecret(_) => "FindSecret".to_string(),
+            Request::GetSecrets(_) => "GetSecrets".to_string(),
             Request::DeleteSecret(_) => "DeleteSecret".to_string(),
+            Request::RestoreSecret(_) => "RestoreSecret".to_string(),
+            Request::ExistsSecret(_) => "ExistsSecret".to_string(),
+            Request::SecretHistory(_) => "SecretHistory".to_string(),
+            Request::SessionStats() => "SessionStats".to_string(),
+            Request::ListSessions() => "ListSessions".to_string(),
+            Request::KillSession(_) => "KillSession".to_string(),
+            Request::ListRoles() => "ListRoles".to_string(),
         }
     }
 }
@@ -0,0 +1,152 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::Request;
+use crate::app_error::ErrorCode;
+
+/// Why a command couldn't be parsed, precise enough for a client to tell
+/// which part of its request was wrong.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParseError {
+    pub error: String,
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<String>,
+}
+
+/* serde names what it wanted as "expected one of `A`, `B`" */
+fn expected_variants(message: &str) -> Vec<String> {
+    match message.split_once("expected one of ") {
+        Some((_, variants)) => variants
+            .split(", ")
+            .map(|v| v.trim_matches('`').to_owned())
+            .collect(),
+        None => match message.split_once("expected `") {
+            Some((_, variant)) => vec![variant.trim_end_matches('`').to_owned()],
+            None => Vec::new(),
+        },
+    }
+}
+
+/* the line and column are of no use in a single line command */
+fn strip_position(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    let suffix = format!(" at line {} column {}", error.line(), error.column());
+
+    message
+        .strip_suffix(&suffix)
+        .map(|v| v.to_owned())
+        .unwrap_or(message)
+}
+
+fn quoted(message: &str, prefix: &str) -> Option<String> {
+    message
+        .strip_prefix(prefix)
+        .and_then(|v| v.strip_prefix('`'))
+        .and_then(|v| v.split_once('`'))
+        .map(|(v, _)| v.to_owned())
+}
+
+/* serde doesn't say which field a bad value was in, so each one is left out
+in turn, the one whose absence makes the error go away or turn into its own
+missing field is it */
+fn failed_field(
+    command: &str,
+    body: &serde_json::Map<String, serde_json::Value>,
+) -> Option<String> {
+    body.keys()
+        .find(|key| {
+            let mut without = body.clone();
+            without.remove(*key);
+
+            let mut request = serde_json::Map::new();
+            request.insert(command.to_owned(), serde_json::Value::Object(without));
+
+            match serde_json::from_str::<Request>(&serde_json::Value::Object(request).to_string()) {
+                Ok(_) => true,
+                Err(e) => {
+                    quoted(&strip_position(&e), "missing field ").as_deref() == Some(key.as_str())
+                }
+            }
+        })
+        .cloned()
+}
+
+impl Request {
+    /// Unlike simd_json, leaves `data` untouched, and on failure tells which
+    /// command and field didn't match and what was expected there.
+    pub fn parse(data: &str) -> Result<Request, ParseError> {
+        let value: serde_json::Value = serde_json::from_str(data).map_err(|e| ParseError {
+            error: format!("invalid JSON: {}", e),
+            code: ErrorCode::InvalidRequest,
+            command: None,
+            field: None,
+            expected: Vec::new(),
+        })?;
+
+        let command = match &value {
+            serde_json::Value::Object(object) if object.len() == 1 => object.keys().next().cloned(),
+            _ => None,
+        };
+
+        let Some(command) = command else {
+            return Err(ParseError {
+                error: "expected an object with the command as its only key".to_owned(),
+                code: ErrorCode::InvalidRequest,
+                command: None,
+                field: None,
+                expected: Vec::new(),
+            });
+        };
+
+        let body = value.get(&command).and_then(|v| v.as_object()).cloned();
+
+        /* from a Value an empty tuple variant, `{"ListRoles": []}`, doesn't parse */
+        serde_json::from_str::<Request>(data).map_err(|e| {
+            let message = strip_position(&e);
+
+            if quoted(&message, "unknown variant ").as_deref() == Some(command.as_str()) {
+                return ParseError {
+                    error: format!("unknown command {}", command),
+                    code: ErrorCode::UnknownCommand,
+                    expected: expected_variants(&message),
+                    command: Some(command),
+                    field: None,
+                };
+            }
+
+            let field = quoted(&message, "missing field ")
+                .or_else(|| quoted(&message, "unknown field "))
+                .or_else(|| body.as_ref().and_then(|v| failed_field(&command, v)));
+
+            ParseError {
+                error: match &field {
+                    Some(field) => format!("invalid {} in {}: {}", field, command, message),
+                    None => format!("invalid {}: {}", command, message),
+                },
+                code: ErrorCode::InvalidRequest,
+                expected: expected_variants(&message),
+                command: Some(command),
+                field,
+            }
+        })
+    }
+}
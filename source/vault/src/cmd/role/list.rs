@@ -0,0 +1,87 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{api, app_error::AppResult, log, permission};
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct RoleEntry {
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct PresetEntry {
+    name: permission::VaultRolesPreset,
+    description: String,
+    roles: Vec<permission::VaultRoles>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    vault_roles: Vec<RoleEntry>,
+    user_roles: Vec<RoleEntry>,
+    presets: Vec<PresetEntry>,
+}
+
+pub async fn list(session: &mut api::Session) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let response = Response {
+        vault_roles: permission::VaultRoles::ALL
+            .iter()
+            .map(|v| RoleEntry {
+                name: v.to_string(),
+                description: v.description().to_owned(),
+            })
+            .collect(),
+        user_roles: permission::UserRole::ALL
+            .iter()
+            .map(|v| RoleEntry {
+                name: v.to_string(),
+                description: v.description().to_owned(),
+            })
+            .collect(),
+        presets: permission::VaultRolesPreset::ALL
+            .iter()
+            .map(|v| PresetEntry {
+                name: *v,
+                description: v.description().to_owned(),
+                roles: v.roles(),
+            })
+            .collect(),
+    };
+
+    log!({
+        mod: log::Module::Api,
+        ctx: "request to list roles",
+        msg: "roles listed",
+        tags: [
+            "api", "role", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username,
+        }
+    });
+
+    session.send_response(&response).await?;
+
+    Ok(())
+}
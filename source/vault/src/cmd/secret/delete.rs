@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, cmd, db, log};
+use crate::{api, app_error::AppResult, cmd, db, log, vault, webhook};
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum ResponseState {
@@ -34,11 +34,24 @@ pub async fn delete(session: &mut api::Session, data: cmd::RequestDeleteSecret)
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    let result = match db::secret::delete(&data.vault, &data.secret_name)? {
+    let result = match db::secret::delete(
+        &data.vault,
+        &data.secret_name,
+        vault::soft_delete(),
+        db::Actor::User(executer_username.clone()),
+    )? {
         db::secret::DeleteSecretResult::Deleted => ResponseState::Deleted,
         db::secret::DeleteSecretResult::NotFound => ResponseState::NotFound,
     };
 
+    if matches!(result, ResponseState::Deleted) {
+        webhook::notify(
+            &data.vault,
+            webhook::Action::DeleteSecret,
+            &data.secret_name,
+        );
+    }
+
     log!({
         mod: log::Module::Vault,
         ctx: "request to delete secret",
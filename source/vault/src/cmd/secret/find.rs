@@ -20,18 +20,24 @@ use maplit::hashmap;
 use crate::{
     api,
     app_error::{AppError, AppErrorResult, AppResult},
-    cmd, db, log, secrets,
+    cmd,
+    compression::Compression,
+    db, log, secrets,
 };
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
     created: String,
+    created_by: Option<db::Actor>,
     secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<Compression>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum Response {
     Found(ResponseEntry),
+    Unchanged,
     NotFound,
 }
 
@@ -41,7 +47,9 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindSecret) -> A
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    if let Some(document) = db::secret::find(&data.vault, &data.secret_name)? {
+    if let Some((target_vault, target_name, document)) =
+        super::resolve(&data.vault, &data.secret_name)?
+    {
         let secret = base64_simd::STANDARD
             .decode_to_vec(document.secret)
             .map_app_err(|e| AppError {
@@ -51,14 +59,44 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindSecret) -> A
                     "secret".to_owned() => data.secret_name.clone(),
                     "vault".to_owned() => data.vault.clone(),
                 }),
+                code: None,
             })?;
 
-        let secret = secrets::decrypt(&secret)?;
+        let secret = secrets::decrypt(&target_vault, &target_name, &secret)?;
+
+        if let Some(expected_sha256) = &data.expected_sha256 {
+            if secrets::matches_sha256(&secret, expected_sha256) {
+                log!({
+                    mod: log::Module::Vault,
+                    ctx: "request to find a secret",
+                    msg: "secret matches the expected checksum",
+                    tags: [
+                        "api", "secret", "request"
+                    ],
+                    attr: {
+                        ip: session.friendly_ip.clone(),
+                        user: executer_username.clone(),
+                        vault: data.vault.clone(),
+                        secret_name: data.secret_name.clone()
+                    }
+                });
+
+                return session.send_response(&Response::Unchanged).await;
+            }
+        }
+
+        let secret = match data.compression {
+            Some(compression) => compression.compress(&secret)?,
+            None => secret,
+        };
+
         let secret = base64_simd::STANDARD.encode_to_string(secret);
 
         let entry = ResponseEntry {
             created: document.created.clone(),
+            created_by: document.created_by.clone(),
             secret,
+            compression: data.compression,
         };
 
         log!({
@@ -0,0 +1,131 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{api, app_error::AppResult, clock, cmd, db, log, secrets, vault, webhook};
+
+const DEFAULT_LENGTH: usize = 32;
+const MAX_LENGTH: usize = 4096;
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseResult {
+    Inserted,
+    Updated,
+    InvalidName,
+    NameTaken,
+    InvalidLength,
+    InvalidValue,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+/* the generated value is only ever answered by a later read */
+pub async fn generate(
+    session: &mut api::Session,
+    data: cmd::RequestGenerateSecret,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let length = data.length.unwrap_or(DEFAULT_LENGTH);
+
+    if length == 0 || length > MAX_LENGTH {
+        return session
+            .send_response(&Response {
+                result: ResponseResult::InvalidLength,
+            })
+            .await;
+    }
+
+    let charset = data.charset.unwrap_or_default();
+    let secret = secrets::random_string(charset.chars(), length);
+
+    /* a generated value is still a value, a JSON vault only takes a number */
+    if !vault::accepts_value(&data.vault, secret.as_bytes())? {
+        return session
+            .send_response(&Response {
+                result: ResponseResult::InvalidValue,
+            })
+            .await;
+    }
+
+    let secret = secrets::encrypt(&data.vault, &data.secret_name, secret.as_bytes())?;
+    let secret = base64_simd::STANDARD.encode_to_string(secret);
+
+    let result = match db::secret::insert(
+        &data.vault,
+        &data.secret_name,
+        db::secret::SecretDocument {
+            created: clock::now().to_rfc3339(),
+            etag: Some(secrets::etag(&secret)),
+            last_accessed: None,
+            created_by: Some(db::Actor::User(executer_username.clone())),
+            secret,
+            alias: None,
+            bound: true,
+        },
+        vault::unique_names(),
+    )? {
+        db::secret::InsertSecretResult::Inserted => ResponseResult::Inserted,
+        db::secret::InsertSecretResult::Updated => ResponseResult::Updated,
+        db::secret::InsertSecretResult::InvalidName => {
+            return session
+                .send_response(&Response {
+                    result: ResponseResult::InvalidName,
+                })
+                .await;
+        }
+        db::secret::InsertSecretResult::NameTaken => {
+            return session
+                .send_response(&Response {
+                    result: ResponseResult::NameTaken,
+                })
+                .await;
+        }
+    };
+
+    webhook::notify(
+        &data.vault,
+        webhook::Action::InsertSecret,
+        &data.secret_name,
+    );
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to generate a secret",
+        msg: "secret generated",
+        tags: [
+            "api", "secret", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username.clone(),
+            vault: data.vault,
+            secret_name: data.secret_name.clone(),
+            length: length,
+            charset: format!("{:?}", charset)
+        }
+    });
+
+    session.send_response(&Response { result }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
+    cmd, db, log, permission, secrets,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct FoundEntry {
+    created: String,
+    secret: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum EntryResult {
+    Found(FoundEntry),
+    NotFound,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ResponseEntry {
+    secret_name: String,
+    result: EntryResult,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum Response {
+    Secrets(Vec<ResponseEntry>),
+    TooLarge { max_secret_bytes: usize },
+    Denied,
+}
+
+/* `*` matches any run of characters, everything else matches itself */
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+
+            name.char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(name.len()))
+                .any(|i| glob_match(rest, &name[i..]))
+        }
+    }
+}
+
+fn expand_names(vault: &str, names: Vec<String>) -> AppResult<Vec<String>> {
+    let mut result: Vec<String> = Vec::new();
+    let mut secret_names: Option<Vec<String>> = None;
+
+    for name in names {
+        if !name.contains('*') {
+            if !result.contains(&name) {
+                result.push(name);
+            }
+
+            continue;
+        }
+
+        if secret_names.is_none() {
+            secret_names = Some(
+                db::secret::list(vault)?
+                    .into_iter()
+                    .map(|v| v.secret_name)
+                    .collect(),
+            );
+        }
+
+        let matched: Vec<&String> = secret_names
+            .iter()
+            .flatten()
+            .filter(|v| glob_match(&name, v))
+            .collect();
+
+        /* a pattern without matches is reported back as not found */
+        if matched.is_empty() && !result.contains(&name) {
+            result.push(name);
+        }
+
+        for secret_name in matched {
+            if !result.contains(secret_name) {
+                result.push(secret_name.clone());
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub async fn get(session: &mut api::Session, data: cmd::RequestGetSecrets) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to get secrets",
+            msg: "insufficient permission",
+            tags: [
+                "api", "secret", "request"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault,
+            }
+        });
+
+        session.send_response(&Response::Denied).await?;
+
+        return Ok(());
+    }
+
+    let max_secret_bytes = secrets::max_secret_bytes();
+
+    let mut result = Vec::new();
+    let mut total_bytes = 0;
+
+    for secret_name in expand_names(&data.vault, data.names)? {
+        let Some((target_vault, target_name, document)) =
+            super::resolve(&data.vault, &secret_name)?
+        else {
+            result.push(ResponseEntry {
+                secret_name,
+                result: EntryResult::NotFound,
+            });
+
+            continue;
+        };
+
+        let secret = base64_simd::STANDARD
+            .decode_to_vec(document.secret)
+            .map_app_err(|e| AppError {
+                message: "failed to decode the secret".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "secret".to_owned() => secret_name.clone(),
+                    "vault".to_owned() => data.vault.clone(),
+                }),
+                code: None,
+            })?;
+
+        let secret = secrets::decrypt(&target_vault, &target_name, &secret)?;
+
+        total_bytes += secret.len();
+
+        if total_bytes > max_secret_bytes {
+            log!({
+                mod: log::Module::Vault,
+                ctx: "request to get secrets",
+                msg: "secrets exceed the maximum size",
+                tags: [
+                    "api", "secret", "request"
+                ],
+                attr: {
+                    ip: session.friendly_ip.clone(),
+                    user: executer_username.clone(),
+                    vault: data.vault,
+                    max_secret_bytes: max_secret_bytes
+                }
+            });
+
+            session
+                .send_response(&Response::TooLarge { max_secret_bytes })
+                .await?;
+
+            return Ok(());
+        }
+
+        result.push(ResponseEntry {
+            secret_name,
+            result: EntryResult::Found(FoundEntry {
+                created: document.created,
+                secret: base64_simd::STANDARD.encode_to_string(secret),
+            }),
+        });
+    }
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to get secrets",
+        msg: "secrets retrieved",
+        tags: [
+            "api", "secret", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username.clone(),
+            vault: data.vault,
+            found: result
+                .iter()
+                .filter(|v| matches!(v.result, EntryResult::Found(_)))
+                .count(),
+            not_found: result
+                .iter()
+                .filter(|v| matches!(v.result, EntryResult::NotFound))
+                .count()
+        }
+    });
+
+    session.send_response(&Response::Secrets(result)).await?;
+
+    Ok(())
+}
@@ -17,14 +17,19 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
     api,
-    app_error::{AppError, AppErrorResult, AppResult},
-    cmd, db, log, secrets,
+    app_error::{AppError, AppErrorResult, AppResult, ErrorCode},
+    clock, cmd, db, log, secrets, vault, webhook,
 };
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum ResponseResult {
     Inserted,
     Updated,
+    InvalidName,
+    NameTaken,
+    InvalidValue,
+    AliasTargetMissing,
+    CircularAlias,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -38,31 +43,99 @@ pub async fn insert(session: &mut api::Session, data: cmd::RequestInsertSecret)
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    let secret = base64_simd::STANDARD
-        .decode_to_vec(data.data)
-        .map_app_err(|e| AppError {
-            message: "failed to decode the data".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let actor = db::Actor::User(executer_username.clone());
 
-    let secret = secrets::encrypt(&secret)?;
-    let secret = base64_simd::STANDARD.encode_to_string(secret);
+    let result = if let Some(target) = data.alias {
+        if !data.data.is_empty() {
+            return Err(AppError {
+                message: "an alias can't hold a value".to_owned(),
+                error: None,
+                attr: None,
+                code: Some(ErrorCode::InvalidArgument),
+            });
+        }
 
-    let time_now = chrono::Utc::now();
+        match vault::store_alias(&data.vault, &data.secret_name, target, actor)? {
+            vault::StoreAliasResult::Stored(value) => value,
+            vault::StoreAliasResult::TargetMissing => {
+                return session
+                    .send_response(&Response {
+                        result: ResponseResult::AliasTargetMissing,
+                    })
+                    .await;
+            }
+            vault::StoreAliasResult::Circular => {
+                return session
+                    .send_response(&Response {
+                        result: ResponseResult::CircularAlias,
+                    })
+                    .await;
+            }
+        }
+    } else {
+        let secret = base64_simd::STANDARD
+            .decode_to_vec(data.data)
+            .map_app_err(|e| AppError {
+                message: "failed to decode the data".to_owned(),
+                error: Some(e.to_string()),
+                attr: None,
+                code: Some(ErrorCode::InvalidArgument),
+            })?;
 
-    let result = match db::secret::insert(
-        &data.vault,
-        &data.secret_name,
-        db::secret::SecretDocument {
-            created: time_now.to_rfc3339(),
-            secret: secret,
-        },
-    )? {
+        if !vault::accepts_value(&data.vault, &secret)? {
+            return session
+                .send_response(&Response {
+                    result: ResponseResult::InvalidValue,
+                })
+                .await;
+        }
+
+        let secret = secrets::encrypt(&data.vault, &data.secret_name, &secret)?;
+        let secret = base64_simd::STANDARD.encode_to_string(secret);
+
+        let time_now = clock::now();
+
+        db::secret::insert(
+            &data.vault,
+            &data.secret_name,
+            db::secret::SecretDocument {
+                created: time_now.to_rfc3339(),
+                etag: Some(secrets::etag(&secret)),
+                last_accessed: None,
+                created_by: Some(actor),
+                secret,
+                alias: None,
+                bound: true,
+            },
+            vault::unique_names(),
+        )?
+    };
+
+    let result = match result {
         db::secret::InsertSecretResult::Inserted => ResponseResult::Inserted,
         db::secret::InsertSecretResult::Updated => ResponseResult::Updated,
+        db::secret::InsertSecretResult::InvalidName => {
+            return session
+                .send_response(&Response {
+                    result: ResponseResult::InvalidName,
+                })
+                .await;
+        }
+        db::secret::InsertSecretResult::NameTaken => {
+            return session
+                .send_response(&Response {
+                    result: ResponseResult::NameTaken,
+                })
+                .await;
+        }
     };
 
+    webhook::notify(
+        &data.vault,
+        webhook::Action::InsertSecret,
+        &data.secret_name,
+    );
+
     log!({
         mod: log::Module::Vault,
         ctx: "request to insert a secret",
@@ -21,6 +21,8 @@ use crate::{api, app_error::AppResult, cmd, db, log};
 struct ResponseEntry {
     created: String,
     secret_name: String,
+    last_accessed: Option<String>,
+    created_by: Option<db::Actor>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -41,6 +43,8 @@ pub async fn list(session: &mut api::Session, data: cmd::RequestListSecrets) ->
         result.push(ResponseEntry {
             created: document.created,
             secret_name: document.secret_name,
+            last_accessed: document.last_accessed,
+            created_by: document.created_by,
         });
     }
 
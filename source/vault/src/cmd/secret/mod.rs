@@ -16,11 +16,53 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 mod delete;
+mod exists;
 mod find;
+mod generate;
+mod get;
+mod history;
 mod insert;
 mod list;
+mod restore;
 
 pub use delete::delete;
+pub use exists::exists;
 pub use find::find;
+pub use generate::generate;
+pub use get::get;
+pub use history::history;
 pub use insert::insert;
 pub use list::list;
+pub use restore::restore;
+
+use maplit::hashmap;
+
+use crate::{
+    app_error::{AppError, AppResult},
+    db,
+};
+
+/* the vault, name and document holding the value once aliases are followed,
+a broken alias reads as a missing secret */
+fn resolve(
+    vault: &str,
+    secret_name: &str,
+) -> AppResult<Option<(String, String, db::secret::SecretDocument)>> {
+    match db::secret::resolve(vault, secret_name)? {
+        db::secret::ResolveSecretResult::Found(vault, secret_name, document) => {
+            Ok(Some((vault, secret_name, *document)))
+        }
+        db::secret::ResolveSecretResult::NotFound | db::secret::ResolveSecretResult::Broken => {
+            Ok(None)
+        }
+        db::secret::ResolveSecretResult::TooDeep => Err(AppError {
+            message: "the secret's aliases loop or go too deep".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned(),
+            }),
+            code: None,
+        }),
+    }
+}
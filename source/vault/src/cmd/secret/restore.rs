@@ -0,0 +1,80 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{api, app_error::AppResult, cmd, db, log, vault, webhook};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseState {
+    Restored,
+    NotFound,
+    Exists,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseState,
+}
+
+pub async fn restore(session: &mut api::Session, data: cmd::RequestRestoreSecret) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    /* with soft delete turned off nothing is restorable */
+    let result = match vault::deleted_secrets_cutoff() {
+        Some(cutoff) => match db::secret::restore(
+            &data.vault,
+            &data.secret_name,
+            cutoff,
+            vault::unique_names(),
+            db::Actor::User(executer_username.clone()),
+        )? {
+            db::secret::RestoreSecretResult::Restored => ResponseState::Restored,
+            db::secret::RestoreSecretResult::NotFound => ResponseState::NotFound,
+            db::secret::RestoreSecretResult::Exists => ResponseState::Exists,
+        },
+        None => ResponseState::NotFound,
+    };
+
+    if matches!(result, ResponseState::Restored) {
+        webhook::notify(
+            &data.vault,
+            webhook::Action::RestoreSecret,
+            &data.secret_name,
+        );
+
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to restore secret",
+            msg: "secret restored",
+            tags: [
+                "api", "secret", "request"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault,
+                secret_name: data.secret_name.clone()
+            }
+        });
+    }
+
+    session.send_response(&Response { result }).await?;
+
+    Ok(())
+}
@@ -46,10 +46,37 @@ pub async fn change_password(
         message: "command executing user is not in the DB".to_owned(),
         error: None,
         attr: None,
+        code: None,
     })?;
 
     if matches!(executer.role, permission::UserRole::Admin) || &data.username == &executer_username
     {
+        if let Some(current_password) = &data.current_password {
+            if !user::is_current_password(&data.username, current_password)? {
+                log!({
+                    mod: log::Module::Vault,
+                    ctx: "request to update user's password",
+                    msg: "current password doesn't match",
+                    tags: [
+                        "api", "user", "request", "error"
+                    ],
+                    attr: {
+                        ip: session.friendly_ip.clone(),
+                        user: executer_username.clone(),
+                        target_user: data.username
+                    }
+                });
+
+                session
+                    .send_response(&Response {
+                        result: ResponseResult::Denied,
+                    })
+                    .await?;
+
+                return Ok(());
+            }
+        }
+
         let result = match user::change_password(&data.username, &data.password)? {
             db::user::ChangeUserPasswordResult::Changed => ResponseResult::Changed,
             db::user::ChangeUserPasswordResult::NotFound => ResponseResult::NotFound,
@@ -0,0 +1,142 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppResult},
+    cmd, db, log, permission, user,
+};
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub enum ResponseResult {
+    Promoted,
+    Demoted,
+    NoChange,
+    NotFound,
+    LastAdmin,
+    Protected,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+pub async fn change_role(
+    session: &mut api::Session,
+    data: cmd::RequestChangeUserRole,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if matches!(executer.role, permission::UserRole::Admin) {
+        let result = match user::change_role(&data.username, &data.role)? {
+            user::ChangeRoleResult::Protected => ResponseResult::Protected,
+            user::ChangeRoleResult::Changed(result) => match result {
+                db::user::ChangeUserRoleResult::Promoted => ResponseResult::Promoted,
+                db::user::ChangeUserRoleResult::Demoted => ResponseResult::Demoted,
+                db::user::ChangeUserRoleResult::NoChange => ResponseResult::NoChange,
+                db::user::ChangeUserRoleResult::NotFound => ResponseResult::NotFound,
+                db::user::ChangeUserRoleResult::LastAdmin => ResponseResult::LastAdmin,
+            },
+        };
+
+        match result {
+            ResponseResult::Promoted | ResponseResult::Demoted => {
+                log!({
+                    mod: log::Module::Vault,
+                    ctx: "request to change a user's role",
+                    msg: "user role changed",
+                    tags: [
+                        "api", "user", "request"
+                    ],
+                    attr: {
+                        ip: session.friendly_ip.clone(),
+                        user: executer_username.clone(),
+                        target_user: data.username,
+                        role: data.role
+                    }
+                });
+            }
+            ResponseResult::LastAdmin => {
+                log!({
+                    mod: log::Module::Vault,
+                    ctx: "request to change a user's role",
+                    msg: "refused to demote the last admin",
+                    tags: [
+                        "api", "user", "request", "error"
+                    ],
+                    attr: {
+                        ip: session.friendly_ip.clone(),
+                        user: executer_username.clone(),
+                        target_user: data.username
+                    }
+                });
+            }
+            ResponseResult::Protected => {
+                log!({
+                    mod: log::Module::Vault,
+                    ctx: "request to change a user's role",
+                    msg: "refused to demote a protected user",
+                    tags: [
+                        "api", "user", "request", "error"
+                    ],
+                    attr: {
+                        ip: session.friendly_ip.clone(),
+                        user: executer_username.clone(),
+                        target_user: data.username
+                    }
+                });
+            }
+            _ => {}
+        }
+
+        session.send_response(&Response { result }).await?;
+    } else {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to change a user's role",
+            msg: "insufficient permission",
+            tags: [
+                "api", "user", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                target_user: data.username
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Denied,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
@@ -46,6 +46,7 @@ pub async fn change_sg(
         message: "command executing user is not in the DB".to_owned(),
         error: None,
         attr: None,
+        code: None,
     })?;
 
     if matches!(executer.role, permission::UserRole::Admin) || &data.username == &executer_username
@@ -18,13 +18,14 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 use crate::{
     api,
     app_error::{AppError, AppErrorOption, AppResult},
-    cmd, db, log, permission,
+    cmd, db, log, permission, user,
 };
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum ResponseResult {
     Deleted,
     NotFound,
+    Protected,
     Denied,
 }
 
@@ -43,9 +44,50 @@ pub async fn delete(session: &mut api::Session, data: cmd::RequestDeleteUser) ->
         message: "command executing user is not in the DB".to_owned(),
         error: None,
         attr: None,
+        code: None,
     })?;
 
-    if matches!(executer.role, permission::UserRole::Admin) {
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to delete a user",
+            msg: "insufficient permission",
+            tags: [
+                "api", "user", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                target_user: data.username
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Denied,
+            })
+            .await?;
+    } else if user::is_protected(&data.username) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to delete a user",
+            msg: "refused to delete a protected user",
+            tags: [
+                "api", "user", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                target_user: data.username
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Protected,
+            })
+            .await?;
+    } else {
         match db::user::delete(&data.username)? {
             db::user::DeleteUserResult::Deleted => {
                 log!({
@@ -90,26 +132,6 @@ pub async fn delete(session: &mut api::Session, data: cmd::RequestDeleteUser) ->
                     .await?;
             }
         }
-    } else {
-        log!({
-            mod: log::Module::Vault,
-            ctx: "request to delete a user",
-            msg: "insufficient permission",
-            tags: [
-                "api", "user", "request", "error"
-            ],
-            attr: {
-                ip: session.friendly_ip.clone(),
-                user: executer_username.clone(),
-                target_user: data.username
-            }
-        });
-
-        session
-            .send_response(&Response {
-                result: ResponseResult::Denied,
-            })
-            .await?;
     }
 
     Ok(())
@@ -24,6 +24,7 @@ struct ResponseEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     last_login: Option<String>,
     sg: Vec<String>,
+    locked: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -63,6 +64,7 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindUser) -> App
                     .iter()
                     .map(|v| format!("{}/{}", v.network, v.prefix))
                     .collect(),
+                locked: user.manually_locked,
             }))
             .await?;
     } else {
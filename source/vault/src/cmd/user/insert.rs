@@ -43,6 +43,7 @@ pub async fn insert(session: &mut api::Session, data: cmd::RequestCreateUser) ->
         message: "command executing user is not in the DB".to_owned(),
         error: None,
         attr: None,
+        code: None,
     })?;
 
     if matches!(executer.role, permission::UserRole::Admin) {
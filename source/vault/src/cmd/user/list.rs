@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, db, log, permission};
+use crate::{api, app_error::AppResult, cmd, db, log, permission};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
@@ -24,20 +24,23 @@ struct ResponseEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     last_login: Option<String>,
     sg: Vec<String>,
+    locked: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct Response {
     users: Vec<ResponseEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
 }
 
-pub async fn list(session: &mut api::Session) -> AppResult<()> {
+pub async fn list(session: &mut api::Session, data: cmd::RequestListUsers) -> AppResult<()> {
     let executer_username = session
         .username
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    let users_list = db::user::list()?;
+    let users_page = db::user::list_paged(data.after.as_deref(), data.limit, data.role)?;
 
     log!({
         mod: log::Module::Vault,
@@ -54,15 +57,18 @@ pub async fn list(session: &mut api::Session) -> AppResult<()> {
 
     session
         .send_response(&Response {
-            users: users_list
+            users: users_page
+                .users
                 .iter()
                 .map(|v| ResponseEntry {
                     username: v.username.clone(),
                     role: v.role,
                     last_login: v.last_login.clone(),
                     sg: v.sg.clone(),
+                    locked: v.manually_locked,
                 })
                 .collect(),
+            next: users_page.next,
         })
         .await?;
 
@@ -16,19 +16,25 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 mod change_password;
+mod change_role;
 mod change_sg;
 mod delete;
 mod demote;
 mod find;
 mod insert;
 mod list;
+mod lock;
 mod promote;
+mod unlock;
 
 pub use change_password::change_password;
+pub use change_role::change_role;
 pub use change_sg::change_sg;
 pub use delete::delete;
 pub use demote::demote;
 pub use find::find;
 pub use insert::insert;
 pub use list::list;
+pub use lock::lock;
 pub use promote::promote;
+pub use unlock::unlock;
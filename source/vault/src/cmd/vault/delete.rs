@@ -15,12 +15,18 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, cmd, db, log};
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppResult},
+    cmd, db, log, permission,
+};
 
 #[derive(Debug, Clone, serde::Serialize)]
 enum ResponseResult {
     Deleted,
     NotFound,
+    ConfirmationMismatch,
+    Denied,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -34,6 +40,61 @@ pub async fn delete(session: &mut api::Session, data: cmd::RequestDeleteVault) -
         .clone()
         .expect("state is command while user hasn't logged in");
 
+    if data.force {
+        let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+            message: "command executing user is not in the DB".to_owned(),
+            error: None,
+            attr: None,
+            code: None,
+        })?;
+
+        if !matches!(executer.role, permission::UserRole::Admin) {
+            log!({
+                mod: log::Module::Vault,
+                ctx: "request to delete a vault",
+                msg: "insufficient permission to force the deletion",
+                tags: [
+                    "api", "vault", "request", "error"
+                ],
+                attr: {
+                    ip: session.friendly_ip.clone(),
+                    user: executer_username.clone(),
+                    vault: data.vault.clone(),
+                }
+            });
+
+            session
+                .send_response(&Response {
+                    result: ResponseResult::Denied,
+                })
+                .await?;
+
+            return Ok(());
+        }
+    } else if data.confirm.as_deref() != Some(data.vault.as_str()) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to delete a vault",
+            msg: "confirmation doesn't match the vault's name",
+            tags: [
+                "api", "vault", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault.clone(),
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::ConfirmationMismatch,
+            })
+            .await?;
+
+        return Ok(());
+    }
+
     let result = match db::vault::delete(&data.vault)? {
         db::vault::DeleteVaultResult::Deleted => ResponseResult::Deleted,
         db::vault::DeleteVaultResult::NotFound => ResponseResult::NotFound,
@@ -50,6 +111,7 @@ pub async fn delete(session: &mut api::Session, data: cmd::RequestDeleteVault) -
             ip: session.friendly_ip.clone(),
             user: executer_username.clone(),
             vault: data.vault.clone(),
+            forced: data.force,
         }
     });
 
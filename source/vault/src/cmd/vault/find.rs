@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, cmd, db, log};
+use crate::{api, app_error::AppResult, cmd, db, log, permission};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
@@ -23,6 +23,9 @@ struct ResponseEntry {
     created: String,
     secrets_count: i64,
     access_keys_count: i64,
+    default_permissions: Option<Vec<permission::VaultRoles>>,
+    default_sg: Option<Vec<String>>,
+    value_schema: db::vault::ValueSchema,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -58,6 +61,9 @@ pub async fn find(session: &mut api::Session, data: cmd::RequestFindVault) -> Ap
                 created: document.created,
                 secrets_count: document.secrets_count,
                 access_keys_count: document.access_keys_count,
+                default_permissions: document.default_permissions,
+                default_sg: document.default_sg,
+                value_schema: document.value_schema,
             }))
             .await?;
     } else {
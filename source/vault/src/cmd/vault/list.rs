@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{api, app_error::AppResult, db, log};
+use crate::{api, app_error::AppResult, cmd, db, log};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct ResponseEntry {
@@ -30,13 +30,17 @@ struct Response {
     vaults: Vec<ResponseEntry>,
 }
 
-pub async fn list(session: &mut api::Session) -> AppResult<()> {
+pub async fn list(session: &mut api::Session, data: cmd::RequestListVaults) -> AppResult<()> {
     let executer_username = session
         .username
         .clone()
         .expect("state is command while user hasn't logged in");
 
-    let result = db::vault::list()?;
+    let result = db::vault::list(
+        data.name_prefix.as_deref().unwrap_or_default(),
+        data.sort_by.unwrap_or_default(),
+        data.order.unwrap_or_default(),
+    )?;
 
     log!({
         mod: log::Module::Vault,
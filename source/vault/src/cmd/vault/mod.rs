@@ -15,10 +15,20 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+mod create;
 mod delete;
 mod find;
 mod list;
+mod rename;
+mod set_defaults;
+mod set_value_schema;
+mod verify_integrity;
 
+pub use create::create;
 pub use delete::delete;
 pub use find::find;
 pub use list::list;
+pub use rename::rename;
+pub use set_defaults::set_defaults;
+pub use set_value_schema::set_value_schema;
+pub use verify_integrity::verify_integrity;
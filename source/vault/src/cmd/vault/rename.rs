@@ -0,0 +1,113 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppResult},
+    cmd, db, log, permission, secrets,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseResult {
+    Renamed,
+    NotFound,
+    AlreadyExists,
+    InvalidName,
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+pub async fn rename(session: &mut api::Session, data: cmd::RequestRenameVault) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if matches!(executer.role, permission::UserRole::Admin) {
+        /* the secrets are sealed with their vault's name, see secrets::context */
+        let rebind = |secret_name: &str, document: &mut db::secret::SecretDocument| {
+            /* aliases hold no value, their targets are rewritten by the rename */
+            if document.alias.is_some() {
+                return Ok(());
+            }
+
+            document.secret =
+                secrets::rebind(&data.vault, &data.name, secret_name, &document.secret)?;
+            document.etag = Some(secrets::etag(&document.secret));
+
+            Ok(())
+        };
+
+        let result = match db::vault::rename(&data.vault, &data.name, &rebind)? {
+            db::vault::RenameVaultResult::Renamed => ResponseResult::Renamed,
+            db::vault::RenameVaultResult::NotFound => ResponseResult::NotFound,
+            db::vault::RenameVaultResult::AlreadyExists => ResponseResult::AlreadyExists,
+            db::vault::RenameVaultResult::InvalidName => ResponseResult::InvalidName,
+        };
+
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to rename a vault",
+            msg: "vault rename executed",
+            tags: [
+                "api", "vault", "request"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault.clone(),
+                name: data.name.clone(),
+                result: format!("{:?}", result),
+            }
+        });
+
+        session.send_response(&Response { result }).await?;
+    } else {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to rename a vault",
+            msg: "insufficient permission",
+            tags: [
+                "api", "vault", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault.clone(),
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Denied,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    api,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
+    cmd, db, log, permission, secrets,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct FailedEntry {
+    vault: String,
+    secret_name: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DriftEntry {
+    vault: String,
+    secrets_count: Option<i64>,
+    actual_secrets_count: i64,
+    access_keys_count: Option<i64>,
+    actual_access_keys_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum ResponseResult {
+    Failed(FailedEntry),
+    Drift(DriftEntry),
+    Done {
+        secrets: i64,
+        failed: i64,
+        drifted: i64,
+    },
+    Denied,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Response {
+    result: ResponseResult,
+}
+
+#[inline]
+fn verify_secret(
+    vault: &str,
+    secret_name: &str,
+    document: &db::secret::SecretDocument,
+) -> AppResult<()> {
+    /* an alias holds no value, its target is checked on its own */
+    if document.alias.is_some() {
+        return Ok(());
+    }
+
+    let secret = base64_simd::STANDARD
+        .decode_to_vec(&document.secret)
+        .map_app_err(|e| AppError {
+            message: "invalid base64 encoding".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    secrets::decrypt(vault, secret_name, &secret)?;
+
+    Ok(())
+}
+
+pub async fn verify_integrity(
+    session: &mut api::Session,
+    data: cmd::RequestVerifyIntegrity,
+) -> AppResult<()> {
+    let executer_username = session
+        .username
+        .clone()
+        .expect("state is command while user hasn't logged in");
+
+    let executer = db::user::find(&executer_username)?.context_app_err(|| AppError {
+        message: "command executing user is not in the DB".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    })?;
+
+    if !matches!(executer.role, permission::UserRole::Admin) {
+        log!({
+            mod: log::Module::Vault,
+            ctx: "request to verify integrity",
+            msg: "insufficient permission",
+            tags: [
+                "api", "vault", "request", "error"
+            ],
+            attr: {
+                ip: session.friendly_ip.clone(),
+                user: executer_username.clone(),
+                vault: data.vault.clone(),
+            }
+        });
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Denied,
+            })
+            .await?;
+
+        return Ok(());
+    }
+
+    /* everything is read from one snapshot, so writes made while the scan
+    is running can't show up as drift */
+    let txn = db::begin_read()?;
+
+    let mut secrets_count = 0;
+    let mut failed_count = 0;
+    let mut drifted_count = 0;
+    let mut actual_secrets: BTreeMap<String, i64> = BTreeMap::new();
+
+    for entry in db::secret::iter(&txn)? {
+        let (vault, secret_name, document) = entry?;
+
+        if data.vault.as_ref().is_some_and(|v| *v != vault) {
+            continue;
+        }
+
+        secrets_count += 1;
+        *actual_secrets.entry(vault.clone()).or_insert(0) += 1;
+
+        if let Err(e) = verify_secret(&vault, &secret_name, &document) {
+            failed_count += 1;
+
+            session
+                .send_response(&Response {
+                    result: ResponseResult::Failed(FailedEntry {
+                        vault,
+                        secret_name,
+                        error: e.message,
+                    }),
+                })
+                .await?;
+        }
+    }
+
+    let mut actual_access_keys = db::access::count_by_vault(&txn)?;
+    let mut vaults: BTreeMap<String, Option<(i64, i64)>> = BTreeMap::new();
+
+    for vault in db::vault::list_in(&txn)? {
+        vaults.insert(
+            vault.vault,
+            Some((vault.secrets_count, vault.access_keys_count)),
+        );
+    }
+
+    /* secrets or access keys left behind in a vault that isn't in the DB */
+    for vault in actual_secrets.keys().chain(actual_access_keys.keys()) {
+        vaults.entry(vault.clone()).or_insert(None);
+    }
+
+    for (vault, counts) in vaults {
+        if data.vault.as_ref().is_some_and(|v| *v != vault) {
+            continue;
+        }
+
+        let actual_secrets_count = actual_secrets.get(&vault).copied().unwrap_or(0);
+        let actual_access_keys_count = actual_access_keys.remove(&vault).unwrap_or(0);
+
+        if counts == Some((actual_secrets_count, actual_access_keys_count)) {
+            continue;
+        }
+
+        drifted_count += 1;
+
+        session
+            .send_response(&Response {
+                result: ResponseResult::Drift(DriftEntry {
+                    vault,
+                    secrets_count: counts.map(|v| v.0),
+                    actual_secrets_count,
+                    access_keys_count: counts.map(|v| v.1),
+                    actual_access_keys_count,
+                }),
+            })
+            .await?;
+    }
+
+    log!({
+        mod: log::Module::Vault,
+        ctx: "request to verify integrity",
+        msg: "integrity verification executed",
+        tags: [
+            "api", "vault", "request"
+        ],
+        attr: {
+            ip: session.friendly_ip.clone(),
+            user: executer_username.clone(),
+            vault: data.vault.clone(),
+            secrets: secrets_count,
+            failed: failed_count,
+            drifted: drifted_count,
+        }
+    });
+
+    session
+        .send_response(&Response {
+            result: ResponseResult::Done {
+                secrets: secrets_count,
+                failed: failed_count,
+                drifted: drifted_count,
+            },
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+
+use crate::app_error::{AppError, AppErrorResult, AppResult};
+
+/// How a secret's value is compressed on the way out, never at rest.
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The `Content-Encoding` token.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> AppResult<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .map_app_err(|e| AppError {
+                        message: "failed to gzip a value".to_owned(),
+                        error: Some(e.to_string()),
+                        attr: None,
+                        code: None,
+                    })
+            }
+            Compression::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_app_err(|e| AppError {
+                    message: "failed to zstd compress a value".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None,
+                }),
+        }
+    }
+
+    /// The supported encoding `Accept-Encoding` rates highest, zstd on a tie.
+    /// None leaves the value as it is.
+    pub fn negotiate(accept_encoding: &str) -> Option<Compression> {
+        let mut result: Option<(Compression, f32)> = None;
+
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+
+            let quality = parts
+                .find_map(|v| v.strip_prefix("q="))
+                .map(|v| v.parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+
+            let compression = if name.eq_ignore_ascii_case("zstd") {
+                Compression::Zstd
+            } else if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+                Compression::Gzip
+            } else {
+                continue;
+            };
+
+            if quality <= 0.0 {
+                continue;
+            }
+
+            match result {
+                Some((_, best)) if best > quality => {}
+                Some((Compression::Zstd, best)) if best == quality => {}
+                _ => result = Some((compression, quality)),
+            }
+        }
+
+        result.map(|(compression, _)| compression)
+    }
+}
@@ -1,65 +1,291 @@
 use maplit::hashmap;
 
-use crate::app_error::{AppError, AppErrorResult, AppResult};
+use crate::{
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
+    log, permission,
+};
 
 const CONFIG_OBJECT_FILENAME: &str = "config.yml";
 
+const DEFAULT_NODE_NAME: &str = "Vaulty";
+const DEFAULT_DELAY_UNSUCCESSFUL_ATTEMPTS_MILLIS: u64 = 5000;
+const DEFAULT_ACCESS_KEY_LENGTH: usize = 20;
+const DEFAULT_SECRET_ACCESS_KEY_LENGTH: usize = 40;
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1";
+const DEFAULT_LISTEN_PORT: u16 = 8080;
+
+fn default_node_name() -> String {
+    DEFAULT_NODE_NAME.to_owned()
+}
+
+fn default_delay_unsuccessful_attempts_millis() -> u64 {
+    DEFAULT_DELAY_UNSUCCESSFUL_ATTEMPTS_MILLIS
+}
+
+fn default_access_key_length() -> usize {
+    DEFAULT_ACCESS_KEY_LENGTH
+}
+
+fn default_secret_access_key_length() -> usize {
+    DEFAULT_SECRET_ACCESS_KEY_LENGTH
+}
+
+fn default_listen_address() -> String {
+    DEFAULT_LISTEN_ADDRESS.to_owned()
+}
+
+fn default_listen_port() -> u16 {
+    DEFAULT_LISTEN_PORT
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConfigDbCompaction {
+    pub interval_hours: Option<u64>,
+    pub free_ratio: Option<f64>,
+    pub idle_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
 pub struct ConfigDb {
+    #[serde(default)]
     pub location: String,
+    pub repair: Option<bool>,
+    pub compaction: Option<ConfigDbCompaction>,
     #[cfg(debug_assertions)]
     pub debug_populate: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
+pub enum SecretsScheme {
+    Rsa,
+    Ec,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
 pub struct ConfigSecrets {
-    pub rsa_private_key: String,
-    pub rsa_public_key: String,
+    pub scheme: Option<SecretsScheme>,
+    pub rsa_private_key: Option<String>,
+    pub rsa_public_key: Option<String>,
+    pub ec_private_key: Option<String>,
+    pub ec_public_key: Option<String>,
+    #[serde(default)]
     pub aes_key: String,
+    #[serde(default)]
     pub aes_iv: String,
+    pub max_secret_bytes: Option<usize>,
+    pub last_accessed_interval_secs: Option<u64>,
+    pub pad_not_found: Option<bool>,
+    pub soft_delete_retention_secs: Option<u64>,
+    pub upload_ttl_secs: Option<u64>,
+    pub max_upload_bytes: Option<usize>,
+    pub unique_names: Option<bool>,
+    pub self_test: Option<bool>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ConfigAccessKeys {
+    #[serde(default)]
     pub signing_key: String,
+    #[serde(default)]
     pub verifying_key: String,
+    #[serde(default = "default_delay_unsuccessful_attempts_millis")]
     pub delay_unsuccessful_attempts_millis: u64,
+    pub delay_jitter_millis: Option<u64>,
+    #[serde(default = "default_access_key_length")]
     pub acces_key_length: usize,
+    #[serde(default = "default_secret_access_key_length")]
     pub secret_access_key_length: usize,
+    pub pepper: Option<String>,
+    pub accept_unpeppered: Option<bool>,
+    pub previous_verifying_key: Option<String>,
+    pub stale_after_days: Option<u64>,
+    pub stale_check_interval_secs: Option<u64>,
+    pub disable_stale: Option<bool>,
+    pub self_test: Option<bool>,
+}
+
+impl Default for ConfigAccessKeys {
+    fn default() -> Self {
+        ConfigAccessKeys {
+            signing_key: String::new(),
+            verifying_key: String::new(),
+            delay_unsuccessful_attempts_millis: DEFAULT_DELAY_UNSUCCESSFUL_ATTEMPTS_MILLIS,
+            delay_jitter_millis: None,
+            acces_key_length: DEFAULT_ACCESS_KEY_LENGTH,
+            secret_access_key_length: DEFAULT_SECRET_ACCESS_KEY_LENGTH,
+            pepper: None,
+            accept_unpeppered: None,
+            previous_verifying_key: None,
+            stale_after_days: None,
+            stale_check_interval_secs: None,
+            disable_stale: None,
+            self_test: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ConfigUsersBootstrap {
+    pub username: Option<String>,
+    pub sg: Option<Vec<String>>,
+    pub password_env: Option<String>,
+    pub password_file: Option<String>,
+    pub write_password_to: Option<String>,
+    pub externally_provisioned: Option<bool>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub enum AuthenticatorKind {
+    Local,
+    Oidc,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConfigUsersOidc {
+    #[serde(default)]
+    pub public_key: String,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub username_claim: Option<String>,
+    pub groups_claim: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConfigUsersAuthenticator {
+    pub kind: Option<AuthenticatorKind>,
+    pub oidc: Option<ConfigUsersOidc>,
+    pub role_mapping: Option<std::collections::HashMap<String, permission::UserRole>>,
+    pub default_role: Option<permission::UserRole>,
+    pub sg: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ConfigUsers {
+    #[serde(default = "default_delay_unsuccessful_attempts_millis")]
     pub delay_unsuccessful_attempts_millis: u64,
+    pub delay_jitter_millis: Option<u64>,
+    pub bootstrap: Option<ConfigUsersBootstrap>,
+    pub authenticator: Option<ConfigUsersAuthenticator>,
+    pub protected: Option<Vec<String>>,
+}
+
+impl Default for ConfigUsers {
+    fn default() -> Self {
+        ConfigUsers {
+            delay_unsuccessful_attempts_millis: DEFAULT_DELAY_UNSUCCESSFUL_ATTEMPTS_MILLIS,
+            delay_jitter_millis: None,
+            bootstrap: None,
+            authenticator: None,
+            protected: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ConfigServerTls {
+    #[serde(default)]
     pub certificate: String,
+    #[serde(default)]
     pub key: String,
+    pub ocsp: Option<String>,
+    pub reload_interval_secs: Option<u64>,
+    pub hsts_max_age_secs: Option<u64>,
+    pub hsts_include_subdomains: Option<bool>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ConfigServer {
+    #[serde(default = "default_listen_address")]
     pub listen_address: String,
+    #[serde(default = "default_listen_port")]
     pub listen_port: u16,
+    pub unix_socket: Option<String>,
+    pub max_sessions: Option<usize>,
+    pub min_client_version: Option<u32>,
+    pub path_prefix: Option<String>,
+    pub security_headers: Option<bool>,
+    pub admin_requires_tls: Option<bool>,
+    pub dual_stack_sg: Option<bool>,
     pub tls: Option<ConfigServerTls>,
 }
 
+impl Default for ConfigServer {
+    fn default() -> Self {
+        ConfigServer {
+            listen_address: DEFAULT_LISTEN_ADDRESS.to_owned(),
+            listen_port: DEFAULT_LISTEN_PORT,
+            unix_socket: None,
+            max_sessions: None,
+            min_client_version: None,
+            path_prefix: None,
+            security_headers: None,
+            admin_requires_tls: None,
+            dual_stack_sg: None,
+            tls: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+pub enum AccessLogFormat {
+    Common,
+    Combined,
+}
+
+/// What happens once writing to the log file keeps failing.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum LogWriteFailure {
+    Ignore,
+    Maintenance,
+    Exit,
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ConfigLog {
+    #[serde(default)]
     pub filename: String,
+    pub stdout: Option<bool>,
+    pub access_log: Option<String>,
+    pub access_log_format: Option<AccessLogFormat>,
+    pub modules: Option<Vec<log::Module>>,
+    pub on_write_failure: Option<LogWriteFailure>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConfigWebhook {
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub secret: String,
+    pub retries: Option<u32>,
+    pub ca_certificate: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConfigJwt {
+    #[serde(default)]
+    pub public_key: String,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Config {
+    #[serde(default = "default_node_name")]
     pub node_name: String,
     pub log: Option<ConfigLog>,
+    #[serde(default)]
     pub db: ConfigDb,
+    #[serde(default)]
     pub secrets: ConfigSecrets,
+    #[serde(default)]
     pub access_keys: ConfigAccessKeys,
+    #[serde(default)]
     pub users: ConfigUsers,
+    #[serde(default)]
     pub server: ConfigServer,
+    pub webhook: Option<ConfigWebhook>,
+    pub jwt: Option<ConfigJwt>,
 }
 
 pub static mut CONFIG_OBJECT: Option<Config> = None;
@@ -68,29 +294,259 @@ pub fn get_clone() -> Config {
     unsafe { CONFIG_OBJECT.clone().unwrap() }
 }
 
-pub fn initialize(filename: Option<String>) -> AppResult<()> {
-    let filename = if let Some(filename) = filename {
-        filename
-    } else {
-        CONFIG_OBJECT_FILENAME.to_string()
+/// Expands `${NAME}` and `${NAME:-default}` with what `lookup` gives for the
+/// name, `$$` stands for a literal `$`. A name without a value or a default is
+/// an error.
+pub fn interpolate(content: &str, lookup: impl Fn(&str) -> Option<String>) -> AppResult<String> {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(idx) = rest.find('$') {
+        result.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').context_app_err(|| AppError {
+                message: "unterminated variable".to_owned(),
+                error: None,
+                attr: None,
+                code: None,
+            })?;
+
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+
+            let valid_name = name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            if !valid_name {
+                return Err(AppError {
+                    message: "invalid variable name".to_owned(),
+                    error: None,
+                    attr: Some(hashmap! {
+                        "variable".to_owned() => name.to_owned()
+                    }),
+                    code: None,
+                });
+            }
+
+            match (lookup(name), default) {
+                (Some(value), _) => result.push_str(&value),
+                (None, Some(default)) => result.push_str(default),
+                (None, None) => {
+                    return Err(AppError {
+                        message: "undefined variable".to_owned(),
+                        error: None,
+                        attr: Some(hashmap! {
+                            "variable".to_owned() => name.to_owned()
+                        }),
+                        code: None,
+                    })
+                }
+            }
+
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+fn require(problems: &mut Vec<String>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        problems.push(format!("{field} is missing"));
+    }
+}
+
+/// Checks the whole config and lists every missing or invalid field, so a
+/// first run doesn't have to be fixed one field at a time.
+pub fn validate(config: &Config) -> AppResult<()> {
+    let mut problems = Vec::new();
+
+    require(&mut problems, "node_name", &config.node_name);
+
+    if let Some(log) = &config.log {
+        require(&mut problems, "log.filename", &log.filename);
+    }
+
+    require(&mut problems, "db.location", &config.db.location);
+
+    if let Some(free_ratio) = config.db.compaction.as_ref().and_then(|v| v.free_ratio) {
+        if !(0.0..=1.0).contains(&free_ratio) {
+            problems.push("db.compaction.free_ratio must be between 0 and 1".to_owned());
+        }
+    }
+
+    let secrets = &config.secrets;
+
+    require(&mut problems, "secrets.aes_key", &secrets.aes_key);
+    require(&mut problems, "secrets.aes_iv", &secrets.aes_iv);
+
+    let (private_key, public_key) = match secrets.scheme {
+        Some(SecretsScheme::Ec) => (
+            ("secrets.ec_private_key", &secrets.ec_private_key),
+            ("secrets.ec_public_key", &secrets.ec_public_key),
+        ),
+        _ => (
+            ("secrets.rsa_private_key", &secrets.rsa_private_key),
+            ("secrets.rsa_public_key", &secrets.rsa_public_key),
+        ),
     };
 
-    let file_content = std::fs::read_to_string(&filename).map_app_err(|e| AppError {
+    for (field, value) in [private_key, public_key] {
+        require(&mut problems, field, value.as_deref().unwrap_or_default());
+    }
+
+    if secrets.max_secret_bytes == Some(0) {
+        problems.push("secrets.max_secret_bytes must be greater than 0".to_owned());
+    }
+
+    let access_keys = &config.access_keys;
+
+    require(
+        &mut problems,
+        "access_keys.signing_key",
+        &access_keys.signing_key,
+    );
+    require(
+        &mut problems,
+        "access_keys.verifying_key",
+        &access_keys.verifying_key,
+    );
+
+    if access_keys.acces_key_length == 0 {
+        problems.push("access_keys.acces_key_length must be greater than 0".to_owned());
+    }
+
+    if access_keys.secret_access_key_length == 0 {
+        problems.push("access_keys.secret_access_key_length must be greater than 0".to_owned());
+    }
+
+    if let Some(authenticator) = &config.users.authenticator {
+        if let Some(AuthenticatorKind::Oidc) = authenticator.kind {
+            require(
+                &mut problems,
+                "users.authenticator.oidc.public_key",
+                authenticator
+                    .oidc
+                    .as_ref()
+                    .map(|v| v.public_key.as_str())
+                    .unwrap_or_default(),
+            );
+        }
+    }
+
+    if let Some(protected) = &config.users.protected {
+        if protected.iter().any(|v| v.trim().is_empty()) {
+            problems.push("users.protected can't have an empty username".to_owned());
+        }
+    }
+
+    let server = &config.server;
+
+    if server.unix_socket.is_none() {
+        require(
+            &mut problems,
+            "server.listen_address",
+            &server.listen_address,
+        );
+
+        if server.listen_port == 0 {
+            problems.push("server.listen_port must be greater than 0".to_owned());
+        }
+    }
+
+    if server.admin_requires_tls == Some(true) && server.unix_socket.is_some() {
+        problems.push(
+            "server.admin_requires_tls can't be used together with server.unix_socket".to_owned(),
+        );
+    }
+
+    if let Some(tls) = &server.tls {
+        require(&mut problems, "server.tls.certificate", &tls.certificate);
+        require(&mut problems, "server.tls.key", &tls.key);
+
+        if server.unix_socket.is_some() {
+            problems.push("server.tls can't be used together with server.unix_socket".to_owned());
+        }
+    }
+
+    if let Some(webhook) = &config.webhook {
+        require(&mut problems, "webhook.url", &webhook.url);
+        require(&mut problems, "webhook.secret", &webhook.secret);
+    }
+
+    if let Some(jwt) = &config.jwt {
+        require(&mut problems, "jwt.public_key", &jwt.public_key);
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError {
+            message: "invalid config".to_owned(),
+            error: Some(problems.join(", ")),
+            attr: None,
+            code: None,
+        })
+    }
+}
+
+pub fn load(filename: &str) -> AppResult<Config> {
+    let file_content = std::fs::read_to_string(filename).map_app_err(|e| AppError {
         message: "failed to read the content".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
-            "filename".to_owned() => filename.clone()
+            "filename".to_owned() => filename.to_owned()
         }),
+        code: None,
     })?;
 
-    let object: Config = serde_yaml::from_str(&file_content).map_app_err(|e| AppError {
+    let file_content = interpolate(&file_content, |name| std::env::var(name).ok())?;
+
+    let config = serde_yaml::from_str(&file_content).map_app_err(|e| AppError {
         message: "failed to deserialize the content".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
-            "filename".to_owned() => filename
+            "filename".to_owned() => filename.to_owned()
+        }),
+        code: None,
+    })?;
+
+    validate(&config).map_app_err(|e| AppError {
+        message: e.message,
+        error: e.error,
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned()
         }),
+        code: None,
     })?;
 
+    Ok(config)
+}
+
+pub fn initialize(filename: Option<String>) -> AppResult<()> {
+    let filename = if let Some(filename) = filename {
+        filename
+    } else {
+        CONFIG_OBJECT_FILENAME.to_string()
+    };
+
+    let object = load(&filename)?;
+
     unsafe {
         CONFIG_OBJECT = Some(object);
     }
@@ -20,10 +20,10 @@ use redb::{ReadableTable, TableHandle};
 
 use crate::{
     app_error::{AppError, AppErrorResult, AppResult},
-    permission,
+    clock, permission,
 };
 
-use super::{vault, ACCESS_KEY_TABLE, DATABASE};
+use super::{begin_write, codec, database, vault, SortOrder, ACCESS_KEY_TABLE};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AccessKeySgDocument {
@@ -38,24 +38,62 @@ pub struct AccessKeyDocument {
     pub sg: Vec<AccessKeySgDocument>,
     pub created: String,
     pub last_used: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub created_by: Option<super::Actor>,
+    #[serde(default)]
+    pub expires: Option<String>,
+    #[serde(default)]
+    pub name_prefixes: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub enum AccessKeyStatus {
+    Active,
+    Expired,
+    Disabled,
+}
+
+impl AccessKeyDocument {
+    pub fn is_expired(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        match &self.expires {
+            Some(expires) => match chrono::DateTime::parse_from_rfc3339(expires) {
+                Ok(expires) => expires <= now,
+                /* an unreadable expiry is treated as passed rather than never */
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
+
+    /* an expired key stays expired when enabled again, so that wins */
+    pub fn status(&self, now: chrono::DateTime<chrono::Utc>) -> AccessKeyStatus {
+        if self.is_expired(now) {
+            AccessKeyStatus::Expired
+        } else if !self.enabled {
+            AccessKeyStatus::Disabled
+        } else {
+            AccessKeyStatus::Active
+        }
+    }
 }
 
 pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> AppResult<()> {
-    let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+    let document = codec::encode(&document).map_app_err(|e| AppError {
         message: "failed to serialize document to JSON".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "document".to_owned() => "AccessKeyDocument".to_owned()
         }),
+        code: None,
     })?;
 
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     {
         let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
@@ -64,6 +102,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -76,6 +115,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
                 "vault".to_owned() => vault.to_owned(),
                 "access_key".to_owned() => access_key.to_owned()
             }),
+            code: None,
         })?;
 
         if query.is_some() {
@@ -88,6 +128,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             });
         }
     };
@@ -99,6 +140,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         table
@@ -111,6 +153,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?;
     }
 
@@ -124,6 +167,7 @@ pub fn insert(vault: &str, access_key: &str, document: AccessKeyDocument) -> App
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(())
@@ -135,13 +179,7 @@ pub enum DeleteAccessKeyResult {
 }
 
 pub fn delete(vault: &str, access_key: &str) -> AppResult<DeleteAccessKeyResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let result = {
         let mut table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
@@ -150,6 +188,7 @@ pub fn delete(vault: &str, access_key: &str) -> AppResult<DeleteAccessKeyResult>
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         if table
@@ -162,6 +201,7 @@ pub fn delete(vault: &str, access_key: &str) -> AppResult<DeleteAccessKeyResult>
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?
             .is_some()
         {
@@ -183,26 +223,167 @@ pub fn delete(vault: &str, access_key: &str) -> AppResult<DeleteAccessKeyResult>
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
-pub fn find(vault: &str, access_key: &str) -> AppResult<Option<AccessKeyDocument>> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
+/// Conditions an access key has to meet, all of the set ones, to be deleted
+/// by `delete_where`.
+#[derive(Debug, Clone, Default)]
+pub struct AccessKeyFilter {
+    /// Not used since, keys that were never used count from their creation.
+    pub unused_since: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub disabled: Option<bool>,
+    pub permission: Option<permission::VaultRoles>,
+}
+
+impl AccessKeyFilter {
+    pub fn is_empty(&self) -> bool {
+        self.unused_since.is_none() && self.disabled.is_none() && self.permission.is_none()
+    }
+
+    fn matches(&self, document: &AccessKeyDocument) -> bool {
+        if let Some(unused_since) = self.unused_since {
+            let used = document.last_used.as_deref().unwrap_or(&document.created);
+
+            match chrono::DateTime::parse_from_rfc3339(used) {
+                Ok(used) if used < unused_since => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(disabled) = self.disabled {
+            if document.enabled == disabled {
+                return false;
+            }
+        }
+
+        if let Some(permission) = &self.permission {
+            if !document.permission.contains(permission) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+pub fn delete_where(vault: &str, filter: &AccessKeyFilter) -> AppResult<Vec<String>> {
+    let txn = begin_write()?;
+
+    let mut to_delete = Vec::new();
+
+    {
+        let mut table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.range((vault, "")..).map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
             error: Some(e.to_string()),
-            attr: None,
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
         })?;
 
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                }),
+                code: None,
+            })?;
+
+            let (access_key_ns, access_key) = key.value();
+
+            if access_key_ns != vault {
+                break;
+            }
+
+            let mut value = value.value().to_owned();
+
+            let document: AccessKeyDocument =
+                codec::decode(&mut value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                        "document".to_owned() => "AccessKeyDocument".to_owned(),
+                        "vault".to_owned() => vault.to_owned(),
+                        "access_key".to_owned() => access_key.to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            if filter.matches(&document) {
+                to_delete.push(access_key.to_owned());
+            }
+        }
+
+        for access_key in &to_delete {
+            table
+                .remove((vault, access_key.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                        "vault".to_owned() => vault.to_owned(),
+                        "access_key".to_owned() => access_key.to_owned()
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    if !to_delete.is_empty() {
+        vault::update(
+            vault,
+            vault::UpdateVault::DecreaseAccessKeys(to_delete.len() as i64),
+            &txn,
+        )?;
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(to_delete)
+}
+
+pub fn find(vault: &str, access_key: &str) -> AppResult<Option<AccessKeyDocument>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
     let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
         message: "failed to open table".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
     if let Some(value) = table.get((vault, access_key)).map_app_err(|e| AppError {
@@ -213,21 +394,21 @@ pub fn find(vault: &str, access_key: &str) -> AppResult<Option<AccessKeyDocument
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })? {
         let mut value = value.value().to_owned();
 
-        Ok(
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
-                    "document".to_owned() => "AccessKeyDocument".to_owned(),
-                    "vault".to_owned() => vault.to_owned(),
-                    "access_key".to_owned() => access_key.to_owned()
-                }),
-            })?,
-        )
+        Ok(codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "document".to_owned() => "AccessKeyDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "access_key".to_owned() => access_key.to_owned()
+            }),
+            code: None,
+        })?)
     } else {
         Ok(None)
     }
@@ -243,14 +424,118 @@ pub fn change_permission(
     access_key: &str,
     permission: Vec<permission::VaultRoles>,
 ) -> AppResult<ChangePermissionForAccessKeyResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
+    let txn = begin_write()?;
+
+    let document = {
+        let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let query = table.get((vault, access_key)).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "document".to_owned() => "AccessKeyDocument".to_owned()
+            }),
+            code: None,
+        })?;
+
+        if let Some(document) = query {
+            let mut document_value = document.value().to_string();
+
+            let document: AccessKeyDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "AccessKeyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            Some(document)
+        } else {
+            None
+        }
+    };
+
+    let result = if let Some(mut document) = document {
+        let mut table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        document.permission = permission;
+
+        let document = codec::encode(&document).map_app_err(|e| AppError {
+            message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
-            attr: None,
+            attr: Some(hashmap! {
+                "document".to_owned() => "AccessKeyDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "access_key".to_owned() => access_key.to_owned()
+            }),
+            code: None,
         })?;
 
+        /* borrow checker */
+        let _ = table
+            .insert((vault, access_key), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to update a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                    "document".to_owned() => "AccessKeyDocument".to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "access_key".to_owned() => access_key.to_owned()
+                }),
+                code: None,
+            })?;
+
+        ChangePermissionForAccessKeyResult::Updated
+    } else {
+        ChangePermissionForAccessKeyResult::NotFound
+    };
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+            "document".to_owned() => "AccessKeyDocument".to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "access_key".to_owned() => access_key.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(result)
+}
+
+pub enum SetEnabledForAccessKeyResult {
+    Updated,
+    NotFound,
+}
+
+pub fn set_enabled(
+    vault: &str,
+    access_key: &str,
+    enabled: bool,
+) -> AppResult<SetEnabledForAccessKeyResult> {
+    let txn = begin_write()?;
+
     let document = {
         let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
             message: "failed to open table".to_owned(),
@@ -258,6 +543,7 @@ pub fn change_permission(
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -268,20 +554,132 @@ pub fn change_permission(
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
                 "document".to_owned() => "AccessKeyDocument".to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: AccessKeyDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
+            let document: AccessKeyDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "AccessKeyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            Some(document)
+        } else {
+            None
+        }
+    };
+
+    let result = if let Some(mut document) = document {
+        let mut table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        document.enabled = enabled;
+
+        let document = codec::encode(&document).map_app_err(|e| AppError {
+            message: "failed to serialize document to JSON".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "document".to_owned() => "AccessKeyDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "access_key".to_owned() => access_key.to_owned()
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let _ = table
+            .insert((vault, access_key), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to update a document".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
-                    "document".to_owned() => "AccessKeyDocument".to_owned()
+                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                    "document".to_owned() => "AccessKeyDocument".to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?;
 
+        SetEnabledForAccessKeyResult::Updated
+    } else {
+        SetEnabledForAccessKeyResult::NotFound
+    };
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+            "document".to_owned() => "AccessKeyDocument".to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "access_key".to_owned() => access_key.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(result)
+}
+
+pub enum RotateSecretForAccessKeyResult {
+    Updated,
+    NotFound,
+}
+
+pub fn rotate_secret(
+    vault: &str,
+    access_key: &str,
+    secret_access_key: String,
+) -> AppResult<RotateSecretForAccessKeyResult> {
+    let txn = begin_write()?;
+
+    let document = {
+        let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let query = table.get((vault, access_key)).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "document".to_owned() => "AccessKeyDocument".to_owned()
+            }),
+            code: None,
+        })?;
+
+        if let Some(document) = query {
+            let mut document_value = document.value().to_string();
+
+            let document: AccessKeyDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "AccessKeyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
             Some(document)
         } else {
             None
@@ -295,11 +693,12 @@ pub fn change_permission(
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
-        document.permission = permission;
+        document.secret_access_key = secret_access_key;
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
@@ -307,6 +706,7 @@ pub fn change_permission(
                 "vault".to_owned() => vault.to_owned(),
                 "access_key".to_owned() => access_key.to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -321,11 +721,12 @@ pub fn change_permission(
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?;
 
-        ChangePermissionForAccessKeyResult::Updated
+        RotateSecretForAccessKeyResult::Updated
     } else {
-        ChangePermissionForAccessKeyResult::NotFound
+        RotateSecretForAccessKeyResult::NotFound
     };
 
     txn.commit().map_app_err(|e| AppError {
@@ -337,6 +738,7 @@ pub fn change_permission(
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(result)
@@ -352,13 +754,7 @@ pub fn change_sg(
     access_key: &str,
     sg: Vec<AccessKeySgDocument>,
 ) -> AppResult<ChangeSgForAccessKeyResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let document = {
         let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
@@ -367,6 +763,7 @@ pub fn change_sg(
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -377,19 +774,21 @@ pub fn change_sg(
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
                 "document".to_owned() => "AccessKeyDocument".to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: AccessKeyDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "document".to_owned() => "AccessKeyDocument".to_owned()
-                }),
-            })?;
+            let document: AccessKeyDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "AccessKeyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
 
             Some(document)
         } else {
@@ -404,11 +803,12 @@ pub fn change_sg(
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         document.sg = sg;
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
@@ -416,6 +816,7 @@ pub fn change_sg(
                 "vault".to_owned() => vault.to_owned(),
                 "access_key".to_owned() => access_key.to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -430,6 +831,7 @@ pub fn change_sg(
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?;
 
         ChangeSgForAccessKeyResult::Updated
@@ -446,67 +848,196 @@ pub fn change_sg(
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
-pub fn list(vault: &str) -> AppResult<Vec<(String, AccessKeyDocument)>> {
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+pub enum AccessKeySortBy {
+    #[default]
+    Name,
+    Created,
+}
+
+pub fn list(
+    vault: &str,
+    name_prefix: &str,
+    sort_by: AccessKeySortBy,
+    order: SortOrder,
+) -> AppResult<Vec<(String, AccessKeyDocument)>> {
     let mut result = Vec::new();
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let table_iter = table
+        .range((vault, name_prefix)..)
         .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
+            message: "failed to iter over table".to_owned(),
             error: Some(e.to_string()),
-            attr: None,
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
         })?;
 
+    for entry in table_iter {
+        let (key, value) = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        let (access_key_ns, access_key) = key.value();
+
+        if access_key_ns != vault || !access_key.starts_with(name_prefix) {
+            break;
+        }
+
+        let mut value = value.value().to_owned();
+
+        let value: AccessKeyDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "document".to_owned() => "AccessKeyDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "access_key".to_owned() => access_key.to_owned()
+            }),
+            code: None,
+        })?;
+
+        result.push((access_key.to_owned(), value));
+    }
+
+    /* the scan is already in name order */
+    if let AccessKeySortBy::Created = sort_by {
+        result.sort_by_cached_key(|(_, v)| chrono::DateTime::parse_from_rfc3339(&v.created).ok());
+    }
+
+    if matches!(order, SortOrder::Descending) {
+        result.reverse();
+    }
+
+    Ok(result)
+}
+
+/// Every access key of every vault, as `(vault, access key, document)`.
+pub fn list_all() -> AppResult<Vec<(String, String, AccessKeyDocument)>> {
+    let mut result = Vec::new();
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
     let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
         message: "failed to open table".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
-    let mut table_iter = table.iter().map_app_err(|e| AppError {
+    let table_iter = table.iter().map_app_err(|e| AppError {
         message: "failed to iter over table".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
-            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
-            "vault".to_owned() => vault.to_owned(),
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
-    while let Some(entry) = table_iter.next() {
+    for entry in table_iter {
         let (key, value) = entry.map_app_err(|e| AppError {
             message: "failed to iter next value".to_owned(),
             error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let (vault, access_key) = key.value();
+
+        let mut value = value.value().to_owned();
+
+        let value: AccessKeyDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "document".to_owned() => "AccessKeyDocument".to_owned(),
                 "vault".to_owned() => vault.to_owned(),
+                "access_key".to_owned() => access_key.to_owned()
             }),
+            code: None,
         })?;
 
-        let (access_key_ns, access_key) = key.value();
+        result.push((vault.to_owned(), access_key.to_owned(), value));
+    }
 
-        if access_key_ns == vault {
-            let mut value = value.value().to_owned();
+    Ok(result)
+}
 
-            let value: AccessKeyDocument =
-                unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                    message: "failed to deserialize JSON document".to_owned(),
-                    error: Some(e.to_string()),
-                    attr: Some(hashmap! {
-                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
-                        "document".to_owned() => "AccessKeyDocument".to_owned(),
-                        "vault".to_owned() => vault.to_owned(),
-                        "access_key".to_owned() => access_key.to_owned()
-                    }),
-                })?;
+pub fn count_by_vault(
+    txn: &redb::ReadTransaction,
+) -> AppResult<std::collections::HashMap<String, i64>> {
+    let mut result = std::collections::HashMap::new();
 
-            result.push((access_key.to_owned(), value));
-        }
+    let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let table_iter = table.iter().map_app_err(|e| AppError {
+        message: "failed to iter over table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    for entry in table_iter {
+        let (key, _) = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let (vault, _) = key.value();
+
+        *result.entry(vault.to_owned()).or_insert(0) += 1;
     }
 
     Ok(result)
@@ -522,6 +1053,7 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         let mut table_iter = table.iter().map_app_err(|e| AppError {
@@ -530,6 +1062,7 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         while let Some(entry) = table_iter.next() {
@@ -540,6 +1073,7 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
                     "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
                     "vault".to_owned() => vault.to_owned(),
                 }),
+                code: None,
             })?;
 
             let (access_key_ns, access_key) = key.value();
@@ -557,6 +1091,7 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         for key in to_delete {
@@ -569,6 +1104,7 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
                         "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
                         "vault".to_owned() => vault.to_owned(),
                     }),
+                    code: None,
                 })?;
         }
     }
@@ -576,15 +1112,92 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
     Ok(())
 }
 
-pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
+pub fn rename_vault(vault: &str, new_vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
+    let mut to_move = Vec::new();
+
+    {
+        let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
             error: Some(e.to_string()),
-            attr: None,
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
         })?;
 
+        let table_iter = table.range((vault, "")..).map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let (key_ns, key_name) = key.value();
+
+            if key_ns != vault {
+                break;
+            }
+
+            to_move.push((key_name.to_string(), value.value().to_string()));
+        }
+    }
+
+    if !to_move.is_empty() {
+        let mut table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (key, value) in to_move {
+            table
+                .remove((vault, key.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a key".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                        "vault".to_owned() => vault.to_owned(),
+                    }),
+                    code: None,
+                })?;
+
+            table
+                .insert((new_vault, key.as_str()), value.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                        "vault".to_owned() => new_vault.to_owned(),
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
+    let txn = begin_write()?;
+
     let document = {
         let table = txn.open_table(ACCESS_KEY_TABLE).map_app_err(|e| AppError {
             message: "failed to open table".to_owned(),
@@ -592,6 +1205,7 @@ pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         let query = table.get((vault, access_key)).map_app_err(|e| AppError {
@@ -600,20 +1214,22 @@ pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_owned();
 
-            let document: AccessKeyDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
-                    "document".to_owned() => "AccessKeyDocument".to_owned()
-                }),
-            })?;
+            let document: AccessKeyDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned(),
+                        "document".to_owned() => "AccessKeyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
 
             Some(document)
         } else {
@@ -628,18 +1244,20 @@ pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => ACCESS_KEY_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
-        let time_now = chrono::Utc::now();
+        let time_now = clock::now();
 
         document.last_used = Some(time_now.to_rfc3339());
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "AccessKeyDocument".to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -653,6 +1271,7 @@ pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
                     "vault".to_owned() => vault.to_owned(),
                     "access_key".to_owned() => access_key.to_owned()
                 }),
+                code: None,
             })?;
     };
 
@@ -664,6 +1283,7 @@ pub fn refresh_access_time(vault: &str, access_key: &str) -> AppResult<()> {
             "vault".to_owned() => vault.to_owned(),
             "access_key".to_owned() => access_key.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(())
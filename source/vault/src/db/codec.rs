@@ -0,0 +1,70 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/* how documents are turned into table rows. A row written as JSON is the
+document itself, as every row was before the codecs, so an existing database
+reads unchanged. Any other first character tags the codec the rest of the row
+was written with, which lets rows of different codecs share a table and a new
+codec be added without rewriting the old rows */
+
+#[derive(Debug, Clone)]
+pub struct CodecError(String);
+
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Codec {
+    Json,
+}
+
+impl Codec {
+    /// The codec of a row, `None` when its tag is unknown.
+    pub fn of(row: &str) -> Option<Codec> {
+        match row.chars().next() {
+            Some('{') => Some(Codec::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn encode<T>(document: &T) -> Result<String, CodecError>
+where
+    T: serde::Serialize,
+{
+    simd_json::to_string(document).map_err(|e| CodecError(e.to_string()))
+}
+
+/// Reads a row written with any of the codecs, the row is used as scratch
+/// space and left in an unspecified state.
+pub fn decode<T>(row: &mut str) -> Result<T, CodecError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match Codec::of(row) {
+        Some(Codec::Json) => {
+            unsafe { simd_json::from_str(row) }.map_err(|e| CodecError(e.to_string()))
+        }
+        None => Err(CodecError(format!(
+            "unknown document codec {:?}",
+            row.chars().next().unwrap_or_default()
+        ))),
+    }
+}
@@ -0,0 +1,282 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use maplit::hashmap;
+
+use super::{begin_write, DATABASE};
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock, config, log, stats,
+};
+
+const CHECK_INTERVAL_SECS: u64 = 60;
+const DEFAULT_IDLE_SECS: u64 = 300;
+/* what's left free right after a compaction isn't a reason to run another */
+const FREE_RATIO_COOLDOWN_SECS: i64 = 3600;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub enum CompactResult {
+    Compacted { reclaimed: u64 },
+    AlreadyRunning,
+    /* redb won't compact while a read transaction is open */
+    Busy,
+}
+
+/* clears the flag however the compaction ends */
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+fn file_size() -> AppResult<u64> {
+    let location = config::get_clone().db.location;
+
+    std::fs::metadata(&location)
+        .map(|v| v.len())
+        .map_app_err(|e| AppError {
+            message: "failed to read the DB file size".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "filename".to_owned() => location.clone()
+            }),
+            code: None,
+        })
+}
+
+/// The part of the DB file that holds no data, pages left unused and the
+/// unused space within pages.
+pub fn free_ratio() -> AppResult<f64> {
+    let txn = begin_write()?;
+
+    let stats = txn.stats().map_app_err(|e| AppError {
+        message: "failed to read the DB stats".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    txn.abort().map_app_err(|e| AppError {
+        message: "failed to abort the transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let size = file_size()?;
+
+    if size == 0 {
+        return Ok(0.0);
+    }
+
+    let used = stats.stored_bytes() + stats.metadata_bytes();
+
+    Ok(size.saturating_sub(used) as f64 / size as f64)
+}
+
+pub fn compact() -> AppResult<CompactResult> {
+    if RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return Ok(CompactResult::AlreadyRunning);
+    }
+
+    let _guard = RunningGuard;
+    let before = file_size()?;
+
+    /* waits for the transactions being begun, the ones already open make
+    redb refuse to compact */
+    let result = DATABASE
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_mut()
+        .expect("db.rs hasn't been initialized")
+        .compact();
+
+    match result {
+        Ok(_) => Ok(CompactResult::Compacted {
+            reclaimed: before.saturating_sub(file_size()?),
+        }),
+        Err(redb::CompactionError::TransactionInProgress) => Ok(CompactResult::Busy),
+        Err(e) => Err(AppError {
+            message: "failed to compact the DB".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        }),
+    }
+}
+
+/// Whether the schedule calls for a compaction at `now`. Nothing runs until
+/// the server has been idle for `idle_secs`, then either `interval_hours`
+/// passed since the last compaction or the free ratio reached `free_ratio`.
+pub fn is_due(
+    schedule: &config::ConfigDbCompaction,
+    now: chrono::DateTime<chrono::Utc>,
+    last_compaction: chrono::DateTime<chrono::Utc>,
+    last_activity: Option<chrono::DateTime<chrono::Utc>>,
+    free_ratio: Option<f64>,
+) -> bool {
+    let idle = chrono::Duration::seconds(schedule.idle_secs.unwrap_or(DEFAULT_IDLE_SECS) as i64);
+
+    if last_activity.is_some_and(|v| now - v < idle) {
+        return false;
+    }
+
+    let elapsed = now - last_compaction;
+
+    if let Some(hours) = schedule.interval_hours {
+        if elapsed >= chrono::Duration::hours(hours as i64) {
+            return true;
+        }
+    }
+
+    match (schedule.free_ratio, free_ratio) {
+        (Some(threshold), Some(ratio)) => {
+            ratio >= threshold && elapsed >= chrono::Duration::seconds(FREE_RATIO_COOLDOWN_SECS)
+        }
+        _ => false,
+    }
+}
+
+pub fn watch() {
+    let schedule = match config::get_clone().db.compaction {
+        Some(value) => value,
+        None => return,
+    };
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        /* the first scheduled run is an interval after the start */
+        let mut last_compaction = clock::now();
+
+        loop {
+            interval.tick().await;
+
+            let free_ratio = match schedule.free_ratio {
+                Some(_) => match free_ratio() {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        log!({
+                            mod: log::Module::Db,
+                            ctx: "compacting",
+                            msg: "failed to measure the free space",
+                            err: e,
+                            tags: [
+                                "db", "error"
+                            ]
+                        });
+
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            if !is_due(
+                &schedule,
+                clock::now(),
+                last_compaction,
+                stats::last_activity(),
+                free_ratio,
+            ) {
+                continue;
+            }
+
+            log!({
+                mod: log::Module::Db,
+                ctx: "compacting",
+                msg: "compaction started",
+                tags: ["db"],
+                attr: {
+                    free_ratio: free_ratio
+                }
+            });
+
+            /* redb works synchronously and a compaction can take a while */
+            match tokio::task::spawn_blocking(compact).await {
+                Ok(Ok(CompactResult::Compacted { reclaimed })) => {
+                    last_compaction = clock::now();
+
+                    log!({
+                        mod: log::Module::Db,
+                        ctx: "compacting",
+                        msg: "compaction finished",
+                        tags: ["db"],
+                        attr: {
+                            reclaimed_bytes: reclaimed
+                        }
+                    });
+                }
+                Ok(Ok(CompactResult::AlreadyRunning)) => {
+                    log!({
+                        mod: log::Module::Db,
+                        ctx: "compacting",
+                        msg: "a compaction is already running, skipped",
+                        tags: ["db"]
+                    });
+                }
+                Ok(Ok(CompactResult::Busy)) => {
+                    log!({
+                        mod: log::Module::Db,
+                        ctx: "compacting",
+                        msg: "a transaction is open, retrying on the next check",
+                        tags: ["db"]
+                    });
+                }
+                Ok(Err(e)) => {
+                    last_compaction = clock::now();
+
+                    log!({
+                        mod: log::Module::Db,
+                        ctx: "compacting",
+                        msg: "failed to compact the DB",
+                        err: e,
+                        tags: [
+                            "db", "error"
+                        ]
+                    });
+                }
+                Err(e) => {
+                    last_compaction = clock::now();
+
+                    log!({
+                        mod: log::Module::Db,
+                        ctx: "compacting",
+                        msg: "the compaction task failed",
+                        err: AppError {
+                            message: e.to_string(),
+                            error: None,
+                            attr: None,
+                            code: None
+                        },
+                        tags: [
+                            "db", "error"
+                        ]
+                    });
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,178 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+use redb::{ReadableTable, TableHandle};
+
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock,
+};
+
+use super::{codec, database, SECRET_HISTORY_TABLE};
+
+/// The oldest entries of a secret are dropped past this many.
+pub const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SecretAction {
+    Inserted,
+    Updated,
+    Deleted,
+    Restored,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SecretHistoryEntry {
+    pub timestamp: String,
+    pub actor: Option<super::Actor>,
+    pub action: SecretAction,
+}
+
+/// The changes of one secret, oldest first.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SecretHistoryDocument {
+    pub entries: Vec<SecretHistoryEntry>,
+}
+
+/* appended in the transaction of the change itself, so a change that isn't
+committed leaves no entry */
+pub(super) fn record(
+    vault: &str,
+    secret_name: &str,
+    action: SecretAction,
+    actor: Option<super::Actor>,
+    txn: &redb::WriteTransaction,
+) -> AppResult<()> {
+    let mut table = txn
+        .open_table(SECRET_HISTORY_TABLE)
+        .map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    let existing = table
+        .get((vault, secret_name))
+        .map_app_err(|e| AppError {
+            message: "failed to retrive a document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?
+        .map(|v| v.value().to_owned());
+
+    /* an unreadable history is started over rather than failing the change */
+    let mut document: SecretHistoryDocument = existing
+        .and_then(|mut v| codec::decode(&mut v).ok())
+        .unwrap_or_default();
+
+    document.entries.push(SecretHistoryEntry {
+        timestamp: clock::now().to_rfc3339(),
+        actor,
+        action,
+    });
+
+    if document.entries.len() > MAX_ENTRIES {
+        let excess = document.entries.len() - MAX_ENTRIES;
+        document.entries.drain(..excess);
+    }
+
+    let document = codec::encode(&document).map_app_err(|e| AppError {
+        message: "failed to serialize document to JSON".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "document".to_owned() => "SecretHistoryDocument".to_owned()
+        }),
+        code: None,
+    })?;
+
+    table
+        .insert((vault, secret_name), document.as_str())
+        .map_app_err(|e| AppError {
+            message: "failed to insert/update a document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    Ok(())
+}
+
+/// The recorded changes of a secret, oldest first. Empty for a secret that
+/// was never changed.
+pub fn find(vault: &str, secret_name: &str) -> AppResult<Vec<SecretHistoryEntry>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn
+        .open_table(SECRET_HISTORY_TABLE)
+        .map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    let Some(document) = table.get((vault, secret_name)).map_app_err(|e| AppError {
+        message: "failed to retrive a document".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut document = document.value().to_owned();
+
+    let document: SecretHistoryDocument =
+        codec::decode(&mut document).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_HISTORY_TABLE.name().to_owned(),
+                "document".to_owned() => "SecretHistoryDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    Ok(document.entries)
+}
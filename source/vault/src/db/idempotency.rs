@@ -0,0 +1,249 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+use redb::{ReadableTable, TableHandle};
+
+use crate::app_error::{AppError, AppErrorResult, AppResult};
+
+use super::{begin_write, codec, database, IDEMPOTENCY_TABLE};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IdempotencyDocument {
+    pub created: String,
+    pub status: u16,
+}
+
+pub fn insert(
+    vault: &str,
+    secret_name: &str,
+    idempotency_key: &str,
+    document: IdempotencyDocument,
+) -> AppResult<()> {
+    let document = codec::encode(&document).map_app_err(|e| AppError {
+        message: "failed to serialize document to JSON".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "document".to_owned() => "IdempotencyDocument".to_owned()
+        }),
+        code: None,
+    })?;
+
+    let txn = begin_write()?;
+
+    {
+        let mut table = txn
+            .open_table(IDEMPOTENCY_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        table
+            .insert((vault, secret_name, idempotency_key), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to insert a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(())
+}
+
+pub fn find(
+    vault: &str,
+    secret_name: &str,
+    idempotency_key: &str,
+) -> AppResult<Option<IdempotencyDocument>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn
+        .open_table(IDEMPOTENCY_TABLE)
+        .map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    let query = table
+        .get((vault, secret_name, idempotency_key))
+        .map_app_err(|e| AppError {
+            message: "failed to retrive a document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    if let Some(value) = query {
+        let mut value = value.value().to_owned();
+
+        Ok(codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+                "document".to_owned() => "IdempotencyDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?)
+    } else {
+        Ok(None)
+    }
+}
+
+/* removes the records created before `before`, returns how many */
+pub fn sweep(before: chrono::DateTime<chrono::Utc>) -> AppResult<usize> {
+    let txn = begin_write()?;
+
+    let mut to_delete = Vec::new();
+
+    {
+        let table = txn
+            .open_table(IDEMPOTENCY_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let mut value = value.value().to_owned();
+
+            let document: IdempotencyDocument =
+                codec::decode(&mut value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+                        "document".to_owned() => "IdempotencyDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            /* unreadable timestamps are swept as well, they could never match */
+            let expired = chrono::DateTime::parse_from_rfc3339(&document.created)
+                .map(|v| v < before)
+                .unwrap_or(true);
+
+            if expired {
+                let (vault, secret_name, idempotency_key) = key.value();
+
+                to_delete.push((
+                    vault.to_owned(),
+                    secret_name.to_owned(),
+                    idempotency_key.to_owned(),
+                ));
+            }
+        }
+    }
+
+    let deleted = to_delete.len();
+
+    if !to_delete.is_empty() {
+        let mut table = txn
+            .open_table(IDEMPOTENCY_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        for key in to_delete {
+            table
+                .remove((key.0.as_str(), key.1.as_str(), key.2.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a key".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned(),
+                        "vault".to_owned() => key.0.clone(),
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => IDEMPOTENCY_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(deleted)
+}
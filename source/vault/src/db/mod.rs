@@ -16,32 +16,112 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use maplit::hashmap;
+use redb::{MultimapTableHandle, TableHandle};
 
 use crate::{
     app_error::{AppError, AppErrorResult, AppResult},
-    config,
+    config, fs, log,
 };
 
 pub mod access;
+pub mod codec;
+pub mod compaction;
+pub mod history;
+pub mod idempotency;
 pub mod secret;
+pub mod upload;
 pub mod user;
 pub mod vault;
 
-static mut DATABASE: once_cell::sync::Lazy<Option<redb::Database>> =
-    once_cell::sync::Lazy::new(|| None);
+/* transactions share it, the compaction takes it exclusively as redb needs
+the only reference to the database for it */
+static DATABASE: std::sync::RwLock<Option<redb::Database>> = std::sync::RwLock::new(None);
+
+struct DatabaseRef(std::sync::RwLockReadGuard<'static, Option<redb::Database>>);
+
+impl std::ops::Deref for DatabaseRef {
+    type Target = redb::Database;
+
+    fn deref(&self) -> &redb::Database {
+        self.0.as_ref().expect("db.rs hasn't been initialized")
+    }
+}
+
+/* hold it only to begin a transaction, the transaction doesn't borrow it */
+fn database() -> DatabaseRef {
+    /* a panic elsewhere while the lock was held leaves the database intact */
+    DatabaseRef(
+        DATABASE
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+    )
+}
+
+fn set_database(database: redb::Database) {
+    *DATABASE
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(database);
+}
 
 const ACCESS_KEY_TABLE: redb::TableDefinition<(&str, &str), &str> =
     redb::TableDefinition::new("access-key");
 const SECRETS_TABLE: redb::TableDefinition<(&str, &str), &str> =
     redb::TableDefinition::new("secrets");
+const DELETED_SECRETS_TABLE: redb::TableDefinition<(&str, &str), &str> =
+    redb::TableDefinition::new("deleted-secrets");
+const SECRET_HISTORY_TABLE: redb::TableDefinition<(&str, &str), &str> =
+    redb::TableDefinition::new("secret-history");
+/* secret name -> the vaults holding it, keeps the unique names policy off a full scan */
+const SECRET_NAMES_TABLE: redb::MultimapTableDefinition<&str, &str> =
+    redb::MultimapTableDefinition::new("secret-names");
 const USERS_TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("users");
 const VAULT_TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("vault");
+const IDEMPOTENCY_TABLE: redb::TableDefinition<(&str, &str, &str), &str> =
+    redb::TableDefinition::new("idempotency");
+const UPLOADS_TABLE: redb::TableDefinition<(&str, &str, &str), &str> =
+    redb::TableDefinition::new("uploads");
+
+pub const MAX_NAME_LENGTH: usize = 128;
+
+pub const BEGIN_WRITE_ATTEMPTS: u32 = 4;
+const BEGIN_WRITE_BACKOFF_MILLIS: u64 = 10;
+const TRANSIENT_ERROR_MESSAGE: &str = "the database is busy, try again";
+
+const REDB_MAGIC_NUMBER: [u8; 9] = [b'r', b'e', b'd', b'b', 0x1A, 0x0A, 0xA9, 0x0D, 0x0A];
 
 pub enum InitializeState {
     Ok,
     Created,
 }
 
+/// Who wrote a document, a user over the websocket or an access key over HTTP.
+/// A bearer token is recorded by its subject.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Actor {
+    User(String),
+    AccessKey(String),
+    Token(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Vault and secret names become table keys and REST path segments, so
+/// they are kept short and limited to characters that need no escaping.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | ':'))
+}
+
 #[cfg(debug_assertions)]
 fn populate_db(filename: &str) -> anyhow::Result<()> {
     use crate::app_error::AppErrorToAnyhowResult;
@@ -77,7 +157,7 @@ fn populate_db(filename: &str) -> anyhow::Result<()> {
 
                     let value: InsertSecret = serde_yaml::from_value(value.clone())?;
 
-                    secret::insert(&value.vault, &value.secret_name, value.document)
+                    secret::insert(&value.vault, &value.secret_name, value.document, false)
                         .to_anyhow_error()?;
                 }
                 "insert_user" => {
@@ -99,44 +179,329 @@ fn populate_db(filename: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn corrupted_error(database_path: &std::path::Path, error: &str) -> AppError {
+    AppError {
+        message: "the DB file is corrupted or truncated".to_owned(),
+        error: Some(error.to_owned()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => database_path.to_str().unwrap_or("N/A").to_owned()
+        }),
+        code: None,
+    }
+}
+
+fn check_header(database_path: &std::path::Path) -> AppResult<()> {
+    use std::io::Read;
+
+    let mut magic_number = [0u8; REDB_MAGIC_NUMBER.len()];
+
+    std::fs::File::open(database_path)
+        .and_then(|mut file| file.read_exact(&mut magic_number))
+        .map_app_err(|e| corrupted_error(database_path, &e.to_string()))?;
+
+    if magic_number != REDB_MAGIC_NUMBER {
+        return Err(corrupted_error(database_path, "invalid magic number"));
+    }
+
+    Ok(())
+}
+
+pub fn open(database_path: &std::path::Path, repair: bool) -> AppResult<redb::Database> {
+    /* redb reinitializes a file without its magic number, wiping whatever was in it */
+    check_header(database_path)?;
+
+    let filename = database_path.to_str().unwrap_or("N/A").to_owned();
+    let mut builder = redb::Builder::new();
+
+    builder.set_repair_callback(move |session| {
+        if !repair {
+            session.abort();
+            return;
+        }
+
+        log!({
+            mod: log::Module::Db,
+            ctx: "initializing",
+            msg: "repairing the DB",
+            tags: [
+                "init", "db"
+            ],
+            attr: {
+                filename: filename.clone(),
+                progress: session.progress()
+            }
+        });
+    });
+
+    /* redb panics on some truncated files instead of returning an error */
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| builder.open(database_path)));
+    std::panic::set_hook(default_hook);
+
+    match result {
+        Ok(Ok(database)) => Ok(database),
+        Ok(Err(redb::DatabaseError::RepairAborted)) => Err(AppError {
+            message: "the DB needs a repair and db.repair is disabled".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "filename".to_owned() => database_path.to_str().unwrap_or("N/A").to_owned()
+            }),
+            code: None,
+        }),
+        Ok(Err(redb::DatabaseError::Storage(redb::StorageError::Corrupted(e)))) => {
+            Err(corrupted_error(database_path, &e))
+        }
+        Ok(Err(e)) => Err(AppError {
+            message: "failed to open".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "filename".to_owned() => database_path.to_str().unwrap_or("N/A").to_owned()
+            }),
+            code: None,
+        }),
+        Err(_) => Err(corrupted_error(database_path, "failed to read the file")),
+    }
+}
+
+pub fn begin_read() -> AppResult<redb::ReadTransaction> {
+    database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })
+}
+
+/// Whether a failure to begin a write transaction may pass on its own, such
+/// as a filesystem that's briefly unable to take the write.
+fn is_transient(e: &redb::TransactionError) -> bool {
+    match e {
+        redb::TransactionError::Storage(redb::StorageError::Io(e)) => matches!(
+            e.kind(),
+            std::io::ErrorKind::Interrupted
+                | std::io::ErrorKind::WouldBlock
+                | std::io::ErrorKind::TimedOut
+        ),
+        _ => false,
+    }
+}
+
+/// Whether the error is a write that gave up after the retries, callers may
+/// answer it as unavailable rather than failed.
+pub fn is_transient_error(e: &AppError) -> bool {
+    e.message == TRANSIENT_ERROR_MESSAGE
+}
+
+#[cfg(test)]
+thread_local! {
+    static INJECTED_WRITE_FAILURES: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+}
+
+/// The next `count` write transactions begun on this thread fail as if the
+/// storage was busy.
+#[cfg(test)]
+pub fn inject_write_failures(count: u32) {
+    INJECTED_WRITE_FAILURES.with(|v| v.set(count));
+}
+
+#[cfg(test)]
+pub fn injected_write_failures() -> u32 {
+    INJECTED_WRITE_FAILURES.with(|v| v.get())
+}
+
+/* boxed, redb's error is much larger than the transaction */
+#[inline]
+fn try_begin_write(
+    database: &redb::Database,
+) -> Result<redb::WriteTransaction, Box<redb::TransactionError>> {
+    #[cfg(test)]
+    if INJECTED_WRITE_FAILURES.with(|v| v.replace(v.get().saturating_sub(1))) > 0 {
+        return Err(Box::new(redb::TransactionError::Storage(
+            redb::StorageError::Io(std::io::ErrorKind::WouldBlock.into()),
+        )));
+    }
+
+    database.begin_write().map_err(Box::new)
+}
+
+pub fn begin_write() -> AppResult<redb::WriteTransaction> {
+    let mut attempt = 1;
+
+    loop {
+        /* not in the match, the backoff mustn't hold back a compaction */
+        let result = try_begin_write(&database());
+
+        match result {
+            Ok(txn) => return Ok(txn),
+            Err(e) if is_transient(&e) && attempt < BEGIN_WRITE_ATTEMPTS => {
+                /* a blocking wait, the callers are synchronous and it is kept short */
+                std::thread::sleep(std::time::Duration::from_millis(
+                    BEGIN_WRITE_BACKOFF_MILLIS << (attempt - 1),
+                ));
+
+                attempt += 1;
+            }
+            Err(e) if is_transient(&e) => {
+                return Err(AppError {
+                    message: TRANSIENT_ERROR_MESSAGE.to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "attempts".to_owned() => attempt.to_string()
+                    }),
+                    code: None,
+                })
+            }
+            Err(e) => {
+                return Err(AppError {
+                    message: "failed to begin write transaction".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None,
+                })
+            }
+        }
+    }
+}
+
+fn create_tables() -> AppResult<()> {
+    let txn = begin_write()?;
+
+    for table in [
+        ACCESS_KEY_TABLE,
+        SECRETS_TABLE,
+        DELETED_SECRETS_TABLE,
+        SECRET_HISTORY_TABLE,
+    ] {
+        txn.open_table(table).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table.name().to_owned()
+            }),
+            code: None,
+        })?;
+    }
+
+    for table in [USERS_TABLE, VAULT_TABLE] {
+        txn.open_table(table).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table.name().to_owned()
+            }),
+            code: None,
+        })?;
+    }
+
+    for table in [IDEMPOTENCY_TABLE, UPLOADS_TABLE] {
+        txn.open_table(table).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table.name().to_owned()
+            }),
+            code: None,
+        })?;
+    }
+
+    txn.open_multimap_table(SECRET_NAMES_TABLE)
+        .map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    Ok(())
+}
+
+/* documents written by older releases may carry a local offset, rewrite
+them to UTC so every persisted timestamp shares the same timezone */
+pub fn migrate_timestamps() -> AppResult<()> {
+    let txn = begin_write()?;
+
+    let users = user::migrate_timestamps(&txn)?;
+    let vaults = vault::migrate_timestamps(&txn)?;
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    if users + vaults > 0 {
+        log!({
+            mod: log::Module::Db,
+            ctx: "initialize",
+            msg: "migrated stored timestamps to UTC",
+            tags: ["db"],
+            attr: {
+                "users": users,
+                "vaults": vaults
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Creates a new database file readable by the owner only, along with the
+/// directories leading to it.
+pub fn create(database_path: &std::path::Path) -> AppResult<redb::Database> {
+    fs::create_parent_directories(database_path)?;
+
+    let database = redb::Database::create(database_path).map_app_err(|e| {
+        let datbase_path = database_path.to_str().unwrap_or("N/A").to_owned();
+
+        AppError {
+            message: "failed to create".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "filename".to_owned() => datbase_path
+            }),
+            code: None,
+        }
+    })?;
+
+    fs::set_private(database_path)?;
+
+    Ok(database)
+}
+
 pub fn initialize() -> AppResult<InitializeState> {
     let config_clone = config::get_clone();
     let database_path = std::path::Path::new(&config_clone.db.location);
 
     if database_path.exists() {
-        let database = redb::Database::open(database_path).map_app_err(|e| {
-            let datbase_path = database_path.to_str().unwrap_or("N/A").to_owned();
+        fs::warn_if_exposed(database_path, log::Module::Db);
 
-            AppError {
-                message: "failed to open".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "filename".to_owned() => datbase_path
-                }),
-            }
-        })?;
+        let database = open(database_path, config_clone.db.repair.unwrap_or(true))?;
 
-        unsafe {
-            *DATABASE = Some(database);
-        }
+        set_database(database);
+
+        create_tables()?;
+        migrate_timestamps()?;
+        secret::index_names()?;
 
         Ok(InitializeState::Ok)
     } else {
-        let database = redb::Database::create(database_path).map_app_err(|e| {
-            let datbase_path = database_path.to_str().unwrap_or("N/A").to_owned();
+        let database = create(database_path)?;
 
-            AppError {
-                message: "failed to create".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "filename".to_owned() => datbase_path
-                }),
-            }
-        })?;
+        set_database(database);
 
-        unsafe {
-            *DATABASE = Some(database);
-        }
+        create_tables()?;
 
         #[cfg(debug_assertions)]
         if let Some(filename) = config_clone.db.debug_populate {
@@ -146,6 +511,7 @@ pub fn initialize() -> AppResult<InitializeState> {
                 attr: Some(hashmap! {
                     "filename".to_owned() => filename
                 }),
+                code: None,
             })?;
         }
 
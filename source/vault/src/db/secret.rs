@@ -16,43 +16,238 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use maplit::hashmap;
-use redb::{ReadableTable, TableHandle};
+use redb::{
+    MultimapTableHandle, ReadableMultimapTable, ReadableTable, ReadableTableMetadata, TableHandle,
+};
 
-use crate::app_error::{AppError, AppErrorResult, AppResult};
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock, log,
+};
 
-use super::{vault, DATABASE, SECRETS_TABLE};
+use super::{
+    begin_write, codec, database, history, vault, DELETED_SECRETS_TABLE, SECRETS_TABLE,
+    SECRET_HISTORY_TABLE, SECRET_NAMES_TABLE,
+};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SecretDocument {
     pub created: String,
     pub secret: String,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_accessed: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<super::Actor>,
+    /// Set on an alias, which holds no value of its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<SecretAlias>,
+    /// Whether the value is sealed with its vault and name, see
+    /// `secrets::context`. Those stored before aren't, `bind_legacy` seals them.
+    #[serde(default)]
+    pub bound: bool,
 }
 
+/// The secret an alias is read in place of.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SecretAlias {
+    pub vault: String,
+    pub secret_name: String,
+}
+
+/// How many aliases are followed before a chain is taken for a loop.
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
 pub enum InsertSecretResult {
     Inserted,
     Updated,
+    InvalidName,
+    /// Another vault already holds a secret under this name.
+    NameTaken,
+}
+
+fn open_names_table<'txn>(
+    txn: &'txn redb::WriteTransaction,
+) -> AppResult<redb::MultimapTable<'txn, &'static str, &'static str>> {
+    txn.open_multimap_table(SECRET_NAMES_TABLE)
+        .map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned()
+            }),
+            code: None,
+        })
+}
+
+/* whether a vault other than `vault` holds `secret_name` */
+fn is_name_taken(vault: &str, secret_name: &str, txn: &redb::WriteTransaction) -> AppResult<bool> {
+    let table = open_names_table(txn)?;
+
+    let vaults = table.get(secret_name).map_app_err(|e| AppError {
+        message: "failed to retrive a document".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    for entry in vaults {
+        let entry = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        if entry.value() != vault {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn claim_name(vault: &str, secret_name: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
+    open_names_table(txn)?
+        .insert(secret_name, vault)
+        .map_app_err(|e| AppError {
+            message: "failed to insert/update a document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    Ok(())
+}
+
+fn release_name(vault: &str, secret_name: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
+    open_names_table(txn)?
+        .remove(secret_name, vault)
+        .map_app_err(|e| AppError {
+            message: "failed to delete a key".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    Ok(())
+}
+
+/// Fills the name index from the stored secrets when it's empty, databases created
+/// before the index existed are indexed on their first start.
+pub fn index_names() -> AppResult<()> {
+    let txn = begin_write()?;
+
+    if !open_names_table(&txn)?
+        .is_empty()
+        .map_app_err(|e| AppError {
+            message: "failed to read table length".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?
+    {
+        return Ok(());
+    }
+
+    let mut names = Vec::new();
+
+    {
+        let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, _) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let (vault, secret_name) = key.value();
+
+            names.push((vault.to_owned(), secret_name.to_owned()));
+        }
+    }
+
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    for (vault, secret_name) in &names {
+        claim_name(vault, secret_name, &txn)?;
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRET_NAMES_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(())
 }
 
+/// With `unique_names` a new name held by another vault is answered with
+/// [`InsertSecretResult::NameTaken`] instead of being inserted, updates are
+/// always let through.
 pub fn insert(
     vault: &str,
     secret_name: &str,
     document: SecretDocument,
+    unique_names: bool,
 ) -> AppResult<InsertSecretResult> {
-    let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+    if !super::is_valid_name(vault) || !super::is_valid_name(secret_name) {
+        return Ok(InsertSecretResult::InvalidName);
+    }
+
+    let actor = document.created_by.clone();
+
+    let document = codec::encode(&document).map_app_err(|e| AppError {
         message: "failed to serialize document to JSON".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "document".to_owned() => "SecretDocument".to_owned()
         }),
+        code: None,
     })?;
 
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let result = {
         let mut table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
@@ -61,6 +256,7 @@ pub fn insert(
             attr: Some(hashmap! {
                 "table".to_owned() => SECRETS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         if table
@@ -73,6 +269,7 @@ pub fn insert(
                     "vault".to_owned() => vault.to_owned(),
                     "secret_name".to_owned() => secret_name.to_owned()
                 }),
+                code: None,
             })?
             .is_some()
         {
@@ -83,9 +280,22 @@ pub fn insert(
     };
 
     if matches!(result, InsertSecretResult::Inserted) {
+        /* dropping the transaction discards the insert */
+        if unique_names && is_name_taken(vault, secret_name, &txn)? {
+            return Ok(InsertSecretResult::NameTaken);
+        }
+
+        claim_name(vault, secret_name, &txn)?;
         vault::update(vault, vault::UpdateVault::IncreaseSecrets, &txn)?;
     }
 
+    let action = match result {
+        InsertSecretResult::Updated => history::SecretAction::Updated,
+        _ => history::SecretAction::Inserted,
+    };
+
+    history::record(vault, secret_name, action, actor, &txn)?;
+
     txn.commit().map_app_err(|e| AppError {
         message: "failed to commit to the DB".to_owned(),
         error: Some(e.to_string()),
@@ -94,55 +304,76 @@ pub fn insert(
             "vault".to_owned() => vault.to_owned(),
             "secret_name".to_owned() => secret_name.to_owned()
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
-pub enum DeleteSecretResult {
-    Deleted,
-    NotFound,
-}
-
-pub fn delete(vault: &str, secret_name: &str) -> AppResult<DeleteSecretResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+pub fn set_last_accessed(vault: &str, secret_name: &str, last_accessed: String) -> AppResult<()> {
+    let txn = begin_write()?;
 
-    let result = {
+    {
         let mut table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
             message: "failed to open table".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "table".to_owned() => SECRETS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
-        if table
-            .remove((vault, secret_name))
+        /* borrow checker */
+        let document = table
+            .get((vault, secret_name))
             .map_app_err(|e| AppError {
-                message: "failed to delete a document".to_owned(),
+                message: "failed to retrive a document".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
                     "table".to_owned() => SECRETS_TABLE.name().to_owned(),
                     "vault".to_owned() => vault.to_owned(),
                     "secret_name".to_owned() => secret_name.to_owned()
                 }),
+                code: None,
             })?
-            .is_some()
-        {
-            DeleteSecretResult::Deleted
-        } else {
-            DeleteSecretResult::NotFound
-        }
-    };
+            .map(|v| v.value().to_string());
+
+        /* deleted since it was read, nothing to record */
+        if let Some(mut document) = document {
+            let mut document: SecretDocument =
+                codec::decode(&mut document).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "SecretDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            document.last_accessed = Some(last_accessed);
 
-    if matches!(result, DeleteSecretResult::Deleted) {
-        vault::update(vault, vault::UpdateVault::DecreaseSecrets, &txn)?;
+            let document = codec::encode(&document).map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "SecretDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert((vault, secret_name), document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                        "vault".to_owned() => vault.to_owned(),
+                        "secret_name".to_owned() => secret_name.to_owned()
+                    }),
+                    code: None,
+                })?;
+        }
     }
 
     txn.commit().map_app_err(|e| AppError {
@@ -153,150 +384,701 @@ pub fn delete(vault: &str, secret_name: &str) -> AppResult<DeleteSecretResult> {
             "vault".to_owned() => vault.to_owned(),
             "secret_name".to_owned() => secret_name.to_owned()
         }),
+        code: None,
     })?;
 
-    Ok(result)
+    Ok(())
 }
 
-pub fn find(vault: &str, secret_name: &str) -> AppResult<Option<SecretDocument>> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
-
-    let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
-        message: "failed to open table".to_owned(),
-        error: Some(e.to_string()),
-        attr: Some(hashmap! {
-            "table".to_owned() => SECRETS_TABLE.name().to_owned()
-        }),
-    })?;
-
-    if let Some(value) = table.get((vault, secret_name)).map_app_err(|e| AppError {
-        message: "failed to retrive a document".to_owned(),
-        error: Some(e.to_string()),
-        attr: Some(hashmap! {
-            "table".to_owned() => SECRETS_TABLE.name().to_owned(),
-            "vault".to_owned() => vault.to_owned(),
-            "secret_name".to_owned() => secret_name.to_owned()
-        }),
-    })? {
-        let mut value = value.value().to_owned();
-
-        Ok(
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => SECRETS_TABLE.name().to_owned(),
-                    "document".to_owned() => "SecretDocument".to_owned(),
-                    "vault".to_owned() => vault.to_owned(),
-                    "secret_name".to_owned() => secret_name.to_owned()
-                }),
-            })?,
-        )
-    } else {
-        Ok(None)
-    }
+pub enum DeleteSecretResult {
+    Deleted,
+    NotFound,
 }
 
-pub struct SecretListEntry {
-    pub created: String,
-    pub secret_name: String,
+/// A soft-deleted secret waiting in the recycle bin for a restore or the sweeper.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeletedSecretDocument {
+    pub deleted: String,
+    pub document: SecretDocument,
 }
 
-pub fn list(vault: &str) -> AppResult<Vec<SecretListEntry>> {
-    let mut result = Vec::new();
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
-
-    let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
-        message: "failed to open table".to_owned(),
-        error: Some(e.to_string()),
-        attr: Some(hashmap! {
-            "table".to_owned() => SECRETS_TABLE.name().to_owned()
-        }),
-    })?;
-
-    let mut table_iter = table.iter().map_app_err(|e| AppError {
-        message: "failed to iter over table".to_owned(),
-        error: Some(e.to_string()),
-        attr: Some(hashmap! {
-            "table".to_owned() => SECRETS_TABLE.name().to_owned()
-        }),
-    })?;
+/// With `soft` the document is moved to the recycle bin instead of being dropped.
+pub fn delete(
+    vault: &str,
+    secret_name: &str,
+    soft: bool,
+    actor: super::Actor,
+) -> AppResult<DeleteSecretResult> {
+    let txn = begin_write()?;
 
-    while let Some(entry) = table_iter.next() {
-        let (key, value) = entry.map_app_err(|e| AppError {
-            message: "failed to iter next value".to_owned(),
+    let removed = {
+        let mut table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
-                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
-                "vault".to_owned() => vault.to_owned(),
+                "table".to_owned() => SECRETS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
-        let mut value = value.value().to_string();
-
-        let document: SecretDocument =
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
+        let removed = table
+            .remove((vault, secret_name))
+            .map_app_err(|e| AppError {
+                message: "failed to delete a document".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
                     "table".to_owned() => SECRETS_TABLE.name().to_owned(),
-                    "document".to_owned() => "SecretDocument".to_owned(),
                     "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
                 }),
-            })?;
-
-        let (secret_ns, secret_name) = key.value();
-
-        if secret_ns == vault {
-            result.push(SecretListEntry {
-                created: document.created,
-                secret_name: secret_name.to_owned(),
-            });
-        }
-    }
+                code: None,
+            })?
+            .map(|v| v.value().to_owned());
 
-    Ok(result)
-}
+        removed
+    };
 
-pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
-    let mut to_delete = Vec::new();
+    let Some(mut removed) = removed else {
+        return Ok(DeleteSecretResult::NotFound);
+    };
 
-    {
-        let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
-            message: "failed to open table".to_owned(),
+    if soft {
+        let document: SecretDocument = codec::decode(&mut removed).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
-                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                "document".to_owned() => "SecretDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
             }),
+            code: None,
         })?;
 
-        let mut table_iter = table.iter().map_app_err(|e| AppError {
-            message: "failed to iter over table".to_owned(),
+        let document = codec::encode(&DeletedSecretDocument {
+            deleted: clock::now().to_rfc3339(),
+            document,
+        })
+        .map_app_err(|e| AppError {
+            message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
-                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+                "document".to_owned() => "DeletedSecretDocument".to_owned()
             }),
+            code: None,
         })?;
 
-        while let Some(entry) = table_iter.next() {
-            let (key, _) = entry.map_app_err(|e| AppError {
-                message: "failed to iter next value".to_owned(),
+        let mut table = txn
+            .open_table(DELETED_SECRETS_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        /* deleting a name again replaces the older copy in the bin */
+        table
+            .insert((vault, secret_name), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to insert/update a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+    }
+
+    release_name(vault, secret_name, &txn)?;
+    vault::update(vault, vault::UpdateVault::DecreaseSecrets, &txn)?;
+    history::record(
+        vault,
+        secret_name,
+        history::SecretAction::Deleted,
+        Some(actor),
+        &txn,
+    )?;
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(DeleteSecretResult::Deleted)
+}
+
+pub enum RestoreSecretResult {
+    Restored,
+    NotFound,
+    /// A secret was inserted under the same name after the delete, in this vault
+    /// or, with `unique_names`, in another one.
+    Exists,
+}
+
+/// Copies deleted before `deleted_after` are treated as already purged.
+pub fn restore(
+    vault: &str,
+    secret_name: &str,
+    deleted_after: chrono::DateTime<chrono::Utc>,
+    unique_names: bool,
+    actor: super::Actor,
+) -> AppResult<RestoreSecretResult> {
+    let txn = begin_write()?;
+
+    let document = {
+        let mut table = txn
+            .open_table(DELETED_SECRETS_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        let removed = table
+            .remove((vault, secret_name))
+            .map_app_err(|e| AppError {
+                message: "failed to delete a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?
+            .map(|v| v.value().to_owned());
+
+        removed
+    };
+
+    let Some(mut document) = document else {
+        return Ok(RestoreSecretResult::NotFound);
+    };
+
+    let document: DeletedSecretDocument =
+        codec::decode(&mut document).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned(),
+                "document".to_owned() => "DeletedSecretDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    /* expired copies may still be around until the next sweep, the transaction is
+    dropped so the sweeper is the only one removing them */
+    let expired = chrono::DateTime::parse_from_rfc3339(&document.deleted)
+        .map(|v| v < deleted_after)
+        .unwrap_or(true);
+
+    if expired {
+        return Ok(RestoreSecretResult::NotFound);
+    }
+
+    {
+        let mut table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let exists = table
+            .get((vault, secret_name))
+            .map_app_err(|e| AppError {
+                message: "failed to retrive a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?
+            .is_some();
+
+        if exists || unique_names && is_name_taken(vault, secret_name, &txn)? {
+            return Ok(RestoreSecretResult::Exists);
+        }
+
+        let document = codec::encode(&document.document).map_app_err(|e| AppError {
+            message: "failed to serialize document to JSON".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "document".to_owned() => "SecretDocument".to_owned()
+            }),
+            code: None,
+        })?;
+
+        table
+            .insert((vault, secret_name), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to insert/update a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+    }
+
+    claim_name(vault, secret_name, &txn)?;
+    vault::update(vault, vault::UpdateVault::IncreaseSecrets, &txn)?;
+    history::record(
+        vault,
+        secret_name,
+        history::SecretAction::Restored,
+        Some(actor),
+        &txn,
+    )?;
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(RestoreSecretResult::Restored)
+}
+
+/// Permanently removes the copies deleted before `before`, returns how many went.
+pub fn sweep_deleted(before: chrono::DateTime<chrono::Utc>) -> AppResult<usize> {
+    let txn = begin_write()?;
+
+    let mut to_delete = Vec::new();
+
+    {
+        let table = txn
+            .open_table(DELETED_SECRETS_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let mut value = value.value().to_owned();
+
+            /* unreadable documents could never be restored, they are swept as well */
+            let expired = codec::decode::<DeletedSecretDocument>(&mut value)
+                .ok()
+                .and_then(|v| chrono::DateTime::parse_from_rfc3339(&v.deleted).ok())
+                .map(|v| v < before)
+                .unwrap_or(true);
+
+            if expired {
+                let (vault, secret_name) = key.value();
+
+                to_delete.push((vault.to_owned(), secret_name.to_owned()));
+            }
+        }
+    }
+
+    let deleted = to_delete.len();
+
+    if !to_delete.is_empty() {
+        let mut table = txn
+            .open_table(DELETED_SECRETS_TABLE)
+            .map_app_err(|e| AppError {
+                message: "failed to open table".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+        for (vault, secret_name) in to_delete {
+            table
+                .remove((vault.as_str(), secret_name.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned(),
+                        "vault".to_owned() => vault.clone(),
+                        "secret_name".to_owned() => secret_name.clone()
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => DELETED_SECRETS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(deleted)
+}
+
+pub fn find(vault: &str, secret_name: &str) -> AppResult<Option<SecretDocument>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    if let Some(value) = table.get((vault, secret_name)).map_app_err(|e| AppError {
+        message: "failed to retrive a document".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })? {
+        let mut value = value.value().to_owned();
+
+        Ok(codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                "document".to_owned() => "SecretDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?)
+    } else {
+        Ok(None)
+    }
+}
+
+pub enum ResolveSecretResult {
+    /// The vault, name and document of the secret that holds the value, the
+    /// requested one itself unless it's an alias.
+    Found(String, String, Box<SecretDocument>),
+    NotFound,
+    /// An alias on the way points at a secret that doesn't exist.
+    Broken,
+    /// The aliases loop or go deeper than `MAX_ALIAS_DEPTH`.
+    TooDeep,
+}
+
+/// Finds a secret, following aliases to the one holding the value.
+pub fn resolve(vault: &str, secret_name: &str) -> AppResult<ResolveSecretResult> {
+    let mut visited: Vec<(String, String)> = Vec::new();
+    let mut vault = vault.to_owned();
+    let mut secret_name = secret_name.to_owned();
+
+    loop {
+        let document = match find(&vault, &secret_name)? {
+            Some(value) => value,
+            None if visited.is_empty() => return Ok(ResolveSecretResult::NotFound),
+            None => return Ok(ResolveSecretResult::Broken),
+        };
+
+        let Some(alias) = document.alias.clone() else {
+            return Ok(ResolveSecretResult::Found(
+                vault,
+                secret_name,
+                Box::new(document),
+            ));
+        };
+
+        visited.push((vault, secret_name));
+
+        if visited.len() > MAX_ALIAS_DEPTH
+            || visited
+                .iter()
+                .any(|(v, n)| *v == alias.vault && *n == alias.secret_name)
+        {
+            return Ok(ResolveSecretResult::TooDeep);
+        }
+
+        vault = alias.vault;
+        secret_name = alias.secret_name;
+    }
+}
+
+pub enum CheckAliasResult {
+    Valid,
+    /// The target, or a secret further down its aliases, doesn't exist.
+    Missing,
+    /// Following the target leads back to the alias, or too deep.
+    Circular,
+}
+
+/// Whether `vault`/`secret_name` may be made an alias of `target`.
+pub fn check_alias(
+    vault: &str,
+    secret_name: &str,
+    target: &SecretAlias,
+) -> AppResult<CheckAliasResult> {
+    let mut hops = 1;
+    let mut next = target.clone();
+
+    loop {
+        if (next.vault == vault && next.secret_name == secret_name) || hops > MAX_ALIAS_DEPTH {
+            return Ok(CheckAliasResult::Circular);
+        }
+
+        match find(&next.vault, &next.secret_name)? {
+            None => return Ok(CheckAliasResult::Missing),
+            Some(SecretDocument {
+                alias: Some(alias), ..
+            }) => {
+                hops += 1;
+                next = alias;
+            }
+            Some(_) => return Ok(CheckAliasResult::Valid),
+        }
+    }
+}
+
+pub struct SecretListEntry {
+    pub created: String,
+    pub secret_name: String,
+    pub last_accessed: Option<String>,
+    pub created_by: Option<super::Actor>,
+}
+
+pub fn list(vault: &str) -> AppResult<Vec<SecretListEntry>> {
+    let mut result = Vec::new();
+
+    for_each_in(vault, |entry| {
+        result.push(entry);
+        true
+    })?;
+
+    Ok(result)
+}
+
+/// Hands the vault's secrets to `f` one at a time from a single read
+/// transaction, in name order, until it returns false.
+pub fn for_each_in(vault: &str, mut f: impl FnMut(SecretListEntry) -> bool) -> AppResult<()> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let mut table_iter = table
+        .range::<(&str, &str)>((vault, "")..)
+        .map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    while let Some(entry) = table_iter.next() {
+        let (key, value) = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        let (secret_ns, secret_name) = key.value();
+
+        if secret_ns != vault {
+            break;
+        }
+
+        let mut value = value.value().to_string();
+
+        let document: SecretDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                "document".to_owned() => "SecretDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        let keep_going = f(SecretListEntry {
+            created: document.created,
+            secret_name: secret_name.to_owned(),
+            last_accessed: document.last_accessed,
+            created_by: document.created_by,
+        });
+
+        if !keep_going {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn iter(
+    txn: &redb::ReadTransaction,
+) -> AppResult<impl Iterator<Item = AppResult<(String, String, SecretDocument)>>> {
+    let table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let table_iter = table.range::<(&str, &str)>(..).map_app_err(|e| AppError {
+        message: "failed to iter over table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => SECRETS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(table_iter.map(|entry| {
+        let (key, value) = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+            }),
+            code: None,
+        })?;
+
+        let (vault, secret_name) = key.value();
+        let mut value = value.value().to_string();
+
+        let document: SecretDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                "document".to_owned() => "SecretDocument".to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret".to_owned() => secret_name.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        Ok((vault.to_owned(), secret_name.to_owned(), document))
+    }))
+}
+
+pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
+    for secret_name in purge_table(vault, SECRETS_TABLE, txn)? {
+        release_name(vault, &secret_name, txn)?;
+    }
+
+    purge_table(vault, DELETED_SECRETS_TABLE, txn)?;
+    purge_table(vault, SECRET_HISTORY_TABLE, txn)?;
+
+    Ok(())
+}
+
+/* returns the names that were removed */
+fn purge_table(
+    vault: &str,
+    table_definition: redb::TableDefinition<(&str, &str), &str>,
+    txn: &redb::WriteTransaction,
+) -> AppResult<Vec<String>> {
+    let mut to_delete = Vec::new();
+
+    {
+        let table = txn.open_table(table_definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table_definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let mut table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table_definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        while let Some(entry) = table_iter.next() {
+            let (key, _) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
-                    "table".to_owned() => SECRETS_TABLE.name().to_owned()
+                    "table".to_owned() => table_definition.name().to_owned()
                 }),
+                code: None,
             })?;
 
             let (access_key_ns, access_key) = key.value();
@@ -308,27 +1090,465 @@ pub fn purge(vault: &str, txn: &redb::WriteTransaction) -> AppResult<()> {
     }
 
     if !to_delete.is_empty() {
-        let mut table = txn.open_table(SECRETS_TABLE).map_app_err(|e| AppError {
+        let mut table = txn.open_table(table_definition).map_app_err(|e| AppError {
             message: "failed to open table".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
-                "table".to_owned() => SECRETS_TABLE.name().to_owned()
+                "table".to_owned() => table_definition.name().to_owned()
             }),
+            code: None,
         })?;
 
-        for key in to_delete {
+        for key in &to_delete {
             table
                 .remove((key.0.as_str(), key.1.as_str()))
                 .map_app_err(|e| AppError {
                     message: "failed to delete a key".to_owned(),
                     error: Some(e.to_string()),
                     attr: Some(hashmap! {
-                        "table".to_owned() => SECRETS_TABLE.name().to_owned(),
+                        "table".to_owned() => table_definition.name().to_owned(),
+                        "vault".to_owned() => vault.to_owned(),
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(to_delete.into_iter().map(|(_, name)| name).collect())
+}
+
+fn rename_vault_in_table(
+    vault: &str,
+    new_vault: &str,
+    table_definition: redb::TableDefinition<(&str, &str), &str>,
+    rewrite: &dyn Fn(&str, String) -> AppResult<String>,
+    txn: &redb::WriteTransaction,
+) -> AppResult<Vec<String>> {
+    let mut to_move = Vec::new();
+
+    {
+        let table = txn.open_table(table_definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table_definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.range((vault, "")..).map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table_definition.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => table_definition.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let (key_ns, key_name) = key.value();
+
+            if key_ns != vault {
+                break;
+            }
+
+            to_move.push((
+                key_name.to_string(),
+                rewrite(key_name, value.value().to_string())?,
+            ));
+        }
+    }
+
+    if !to_move.is_empty() {
+        let mut table = txn.open_table(table_definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => table_definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (key, value) in &to_move {
+            table
+                .remove((vault, key.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a key".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => table_definition.name().to_owned(),
                         "vault".to_owned() => vault.to_owned(),
                     }),
+                    code: None,
+                })?;
+
+            table
+                .insert((new_vault, key.as_str()), value.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => table_definition.name().to_owned(),
+                        "vault".to_owned() => new_vault.to_owned(),
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(to_move.into_iter().map(|(name, _)| name).collect())
+}
+
+fn decode_row<T: serde::de::DeserializeOwned>(row: &mut str, name: &str) -> AppResult<T> {
+    codec::decode(row).map_app_err(|e| AppError {
+        message: "failed to deserialize JSON document".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "document".to_owned() => name.to_owned()
+        }),
+        code: None,
+    })
+}
+
+fn encode_row<T: serde::Serialize>(document: &T, name: &str) -> AppResult<String> {
+    codec::encode(document).map_app_err(|e| AppError {
+        message: "failed to serialize document to JSON".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "document".to_owned() => name.to_owned()
+        }),
+        code: None,
+    })
+}
+
+/* aliases in any vault that point into the renamed one, rewritten so they
+keep resolving */
+fn retarget_aliases_in<T>(
+    vault: &str,
+    new_vault: &str,
+    definition: redb::TableDefinition<(&str, &str), &str>,
+    document_name: &str,
+    secret: fn(&mut T) -> &mut SecretDocument,
+    txn: &redb::WriteTransaction,
+) -> AppResult<()>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let mut to_update = Vec::new();
+
+    {
+        let table = txn.open_table(definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => definition.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let (key_vault, secret_name) = key.value();
+            let mut row = value.value().to_string();
+            let mut document: T = decode_row(&mut row, document_name)?;
+
+            match &mut secret(&mut document).alias {
+                Some(alias) if alias.vault == vault => alias.vault = new_vault.to_owned(),
+                _ => continue,
+            }
+
+            to_update.push((
+                key_vault.to_owned(),
+                secret_name.to_owned(),
+                encode_row(&document, document_name)?,
+            ));
+        }
+    }
+
+    if !to_update.is_empty() {
+        let mut table = txn.open_table(definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (key_vault, secret_name, document) in to_update {
+            table
+                .insert(
+                    (key_vault.as_str(), secret_name.as_str()),
+                    document.as_str(),
+                )
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => definition.name().to_owned(),
+                        "vault".to_owned() => key_vault.clone(),
+                        "secret_name".to_owned() => secret_name.clone()
+                    }),
+                    code: None,
                 })?;
         }
     }
 
     Ok(())
 }
+
+/* the recycle bin and the history follow the vault, so restores keep working
+after a rename */
+pub fn rename_vault(
+    vault: &str,
+    new_vault: &str,
+    rebind: &dyn Fn(&str, &mut SecretDocument) -> AppResult<()>,
+    txn: &redb::WriteTransaction,
+) -> AppResult<()> {
+    let rebind_secret = |secret_name: &str, mut row: String| -> AppResult<String> {
+        let mut document: SecretDocument = decode_row(&mut row, "SecretDocument")?;
+
+        rebind(secret_name, &mut document)?;
+
+        encode_row(&document, "SecretDocument")
+    };
+
+    let rebind_deleted = |secret_name: &str, mut row: String| -> AppResult<String> {
+        let mut document: DeletedSecretDocument = decode_row(&mut row, "DeletedSecretDocument")?;
+
+        rebind(secret_name, &mut document.document)?;
+
+        encode_row(&document, "DeletedSecretDocument")
+    };
+
+    for secret_name in rename_vault_in_table(vault, new_vault, SECRETS_TABLE, &rebind_secret, txn)?
+    {
+        release_name(vault, &secret_name, txn)?;
+        claim_name(new_vault, &secret_name, txn)?;
+    }
+
+    rename_vault_in_table(
+        vault,
+        new_vault,
+        DELETED_SECRETS_TABLE,
+        &rebind_deleted,
+        txn,
+    )?;
+    rename_vault_in_table(
+        vault,
+        new_vault,
+        SECRET_HISTORY_TABLE,
+        &|_, row| Ok(row),
+        txn,
+    )?;
+
+    /* the history holds no aliases, only the changes */
+    retarget_aliases_in::<SecretDocument>(
+        vault,
+        new_vault,
+        SECRETS_TABLE,
+        "SecretDocument",
+        |v| v,
+        txn,
+    )?;
+    retarget_aliases_in::<DeletedSecretDocument>(
+        vault,
+        new_vault,
+        DELETED_SECRETS_TABLE,
+        "DeletedSecretDocument",
+        |v| &mut v.document,
+        txn,
+    )?;
+
+    Ok(())
+}
+
+/* the documents of one table that aren't bound, sealed and written back */
+fn bind_legacy_in<T>(
+    txn: &redb::WriteTransaction,
+    definition: redb::TableDefinition<(&str, &str), &str>,
+    document_name: &str,
+    secret: fn(&mut T) -> &mut SecretDocument,
+    seal: &dyn Fn(&str, &str, &str) -> AppResult<String>,
+) -> AppResult<usize>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let mut to_update = Vec::new();
+
+    {
+        let table = txn.open_table(definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => definition.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let (vault, secret_name) = key.value();
+            let mut document_value = value.value().to_string();
+
+            let mut document: T = codec::decode(&mut document_value).map_app_err(|e| AppError {
+                message: "failed to deserialize JSON document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => document_name.to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+
+            let secret_document = secret(&mut document);
+
+            if secret_document.bound {
+                continue;
+            }
+
+            /* an alias holds no value to seal */
+            if secret_document.alias.is_none() {
+                /* one that can't be sealed stays unbound for VerifyIntegrity to
+                report, the others are still sealed */
+                match seal(vault, secret_name, &secret_document.secret) {
+                    Ok(sealed) => secret_document.secret = sealed,
+                    Err(e) => {
+                        log!({
+                            mod: log::Module::Db,
+                            ctx: "sealing legacy secrets",
+                            msg: "failed to seal a legacy secret",
+                            err: e,
+                            tags: [
+                                "db", "secrets", "error"
+                            ],
+                            attr: {
+                                table: definition.name().to_owned(),
+                                vault: vault.to_owned(),
+                                secret_name: secret_name.to_owned()
+                            }
+                        });
+
+                        continue;
+                    }
+                }
+
+                secret_document.etag = Some(crate::secrets::etag(&secret_document.secret));
+            }
+
+            secret_document.bound = true;
+            to_update.push((vault.to_owned(), secret_name.to_owned(), document));
+        }
+    }
+
+    let updated = to_update.len();
+
+    if !to_update.is_empty() {
+        let mut table = txn.open_table(definition).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => definition.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (vault, secret_name, document) in to_update {
+            let document = codec::encode(&document).map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => document_name.to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert((vault.as_str(), secret_name.as_str()), document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => definition.name().to_owned(),
+                        "vault".to_owned() => vault.clone(),
+                        "secret_name".to_owned() => secret_name.clone()
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Seals the secrets stored before values were bound to their vault and name,
+/// the live ones and those in the recycle bin. `seal` gets the vault, the name
+/// and the stored value, and returns it sealed with the two. A secret `seal`
+/// fails on is logged and left unbound.
+pub fn bind_legacy(seal: &dyn Fn(&str, &str, &str) -> AppResult<String>) -> AppResult<usize> {
+    let txn = begin_write()?;
+
+    let updated =
+        bind_legacy_in::<SecretDocument>(&txn, SECRETS_TABLE, "SecretDocument", |v| v, seal)?
+            + bind_legacy_in::<DeletedSecretDocument>(
+                &txn,
+                DELETED_SECRETS_TABLE,
+                "DeletedSecretDocument",
+                |v| &mut v.document,
+                seal,
+            )?;
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    Ok(updated)
+}
@@ -0,0 +1,380 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+use redb::{ReadableTable, TableHandle};
+
+use crate::app_error::{AppError, AppErrorResult, AppResult};
+
+use super::{begin_write, codec, database, UPLOADS_TABLE};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UploadDocument {
+    pub created: String,
+    /// Bytes received so far, the offset the next chunk must start at.
+    pub length: usize,
+    /// The received bytes in base64, they're only encrypted on completion.
+    pub data: String,
+}
+
+pub enum AppendUploadResult {
+    Appended(usize),
+    NotFound,
+    OffsetMismatch(usize),
+    TooLarge,
+}
+
+fn serialize(document: &UploadDocument) -> AppResult<String> {
+    codec::encode(document).map_app_err(|e| AppError {
+        message: "failed to serialize document to JSON".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "document".to_owned() => "UploadDocument".to_owned()
+        }),
+        code: None,
+    })
+}
+
+fn deserialize(value: &str) -> AppResult<UploadDocument> {
+    let mut value = value.to_owned();
+
+    codec::decode(&mut value).map_app_err(|e| AppError {
+        message: "failed to deserialize JSON document".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+            "document".to_owned() => "UploadDocument".to_owned()
+        }),
+        code: None,
+    })
+}
+
+pub fn insert(
+    vault: &str,
+    secret_name: &str,
+    upload_id: &str,
+    document: UploadDocument,
+) -> AppResult<()> {
+    let document = serialize(&document)?;
+
+    let txn = begin_write()?;
+
+    {
+        let mut table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        table
+            .insert((vault, secret_name, upload_id), document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to insert a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(())
+}
+
+pub fn find(vault: &str, secret_name: &str, upload_id: &str) -> AppResult<Option<UploadDocument>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let query = table
+        .get((vault, secret_name, upload_id))
+        .map_app_err(|e| AppError {
+            message: "failed to retrive a document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "secret_name".to_owned() => secret_name.to_owned()
+            }),
+            code: None,
+        })?;
+
+    match query {
+        Some(value) => Ok(Some(deserialize(value.value())?)),
+        None => Ok(None),
+    }
+}
+
+/* the offset is checked in the same transaction as the write, so two
+clients sending the same chunk can't both append it */
+pub fn append(
+    vault: &str,
+    secret_name: &str,
+    upload_id: &str,
+    offset: usize,
+    chunk: &[u8],
+    max_bytes: usize,
+) -> AppResult<AppendUploadResult> {
+    let txn = begin_write()?;
+
+    let result = {
+        let mut table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let document = table
+            .get((vault, secret_name, upload_id))
+            .map_app_err(|e| AppError {
+                message: "failed to retrive a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?
+            .map(|v| deserialize(v.value()))
+            .transpose()?;
+
+        match document {
+            None => AppendUploadResult::NotFound,
+            Some(document) if document.length != offset => {
+                AppendUploadResult::OffsetMismatch(document.length)
+            }
+            Some(document) if document.length + chunk.len() > max_bytes => {
+                AppendUploadResult::TooLarge
+            }
+            Some(mut document) => {
+                let mut data = base64_simd::STANDARD
+                    .decode_to_vec(&document.data)
+                    .map_app_err(|e| AppError {
+                        message: "failed to decode the upload".to_owned(),
+                        error: Some(e.to_string()),
+                        attr: Some(hashmap! {
+                            "vault".to_owned() => vault.to_owned(),
+                            "secret_name".to_owned() => secret_name.to_owned()
+                        }),
+                        code: None,
+                    })?;
+
+                data.extend_from_slice(chunk);
+
+                document.length = data.len();
+                document.data = base64_simd::STANDARD.encode_to_string(&data);
+
+                table
+                    .insert(
+                        (vault, secret_name, upload_id),
+                        serialize(&document)?.as_str(),
+                    )
+                    .map_app_err(|e| AppError {
+                        message: "failed to insert/update a document".to_owned(),
+                        error: Some(e.to_string()),
+                        attr: Some(hashmap! {
+                            "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                            "vault".to_owned() => vault.to_owned(),
+                            "secret_name".to_owned() => secret_name.to_owned()
+                        }),
+                        code: None,
+                    })?;
+
+                AppendUploadResult::Appended(document.length)
+            }
+        }
+    };
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(result)
+}
+
+pub fn delete(vault: &str, secret_name: &str, upload_id: &str) -> AppResult<()> {
+    let txn = begin_write()?;
+
+    {
+        let mut table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        table
+            .remove((vault, secret_name, upload_id))
+            .map_app_err(|e| AppError {
+                message: "failed to delete a key".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "secret_name".to_owned() => secret_name.to_owned()
+                }),
+                code: None,
+            })?;
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+            "vault".to_owned() => vault.to_owned(),
+            "secret_name".to_owned() => secret_name.to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(())
+}
+
+/* removes the uploads started before `before`, returns how many */
+pub fn sweep(before: chrono::DateTime<chrono::Utc>) -> AppResult<usize> {
+    let txn = begin_write()?;
+
+    let mut to_delete = Vec::new();
+
+    {
+        let table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let document = deserialize(value.value())?;
+
+            /* unreadable timestamps are swept as well, they could never complete */
+            let expired = chrono::DateTime::parse_from_rfc3339(&document.created)
+                .map(|v| v < before)
+                .unwrap_or(true);
+
+            if expired {
+                let (vault, secret_name, upload_id) = key.value();
+
+                to_delete.push((
+                    vault.to_owned(),
+                    secret_name.to_owned(),
+                    upload_id.to_owned(),
+                ));
+            }
+        }
+    }
+
+    let deleted = to_delete.len();
+
+    if !to_delete.is_empty() {
+        let mut table = txn.open_table(UPLOADS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for key in to_delete {
+            table
+                .remove((key.0.as_str(), key.1.as_str(), key.2.as_str()))
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a key".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => UPLOADS_TABLE.name().to_owned(),
+                        "vault".to_owned() => key.0.clone(),
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => UPLOADS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    Ok(deleted)
+}
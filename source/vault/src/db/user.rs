@@ -20,10 +20,10 @@ use redb::{ReadableTable, TableHandle};
 
 use crate::{
     app_error::{AppError, AppErrorResult, AppResult},
-    permission,
+    clock, permission,
 };
 
-use super::{DATABASE, USERS_TABLE};
+use super::{begin_write, codec, database, USERS_TABLE};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UserSgDocument {
@@ -37,6 +37,12 @@ pub struct UserDocument {
     pub role: permission::UserRole,
     pub last_login: Option<String>,
     pub sg: Vec<UserSgDocument>,
+    #[serde(default)]
+    pub manually_locked: bool,
+    /// Created by an external source on its first login, only such a user's
+    /// role follows its groups.
+    #[serde(default)]
+    pub provisioned: bool,
 }
 
 pub enum InsertUserResult {
@@ -45,21 +51,16 @@ pub enum InsertUserResult {
 }
 
 pub fn insert(username: &str, document: UserDocument) -> AppResult<InsertUserResult> {
-    let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+    let document = codec::encode(&document).map_app_err(|e| AppError {
         message: "failed to serialize document to JSON".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "document".to_owned() => "UserDocument".to_owned()
         }),
+        code: None,
     })?;
 
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     {
         let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
@@ -68,6 +69,7 @@ pub fn insert(username: &str, document: UserDocument) -> AppResult<InsertUserRes
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         if table
@@ -79,6 +81,7 @@ pub fn insert(username: &str, document: UserDocument) -> AppResult<InsertUserRes
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?
             .is_some()
         {
@@ -94,6 +97,7 @@ pub fn insert(username: &str, document: UserDocument) -> AppResult<InsertUserRes
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?;
     }
 
@@ -104,6 +108,7 @@ pub fn insert(username: &str, document: UserDocument) -> AppResult<InsertUserRes
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(InsertUserResult::Created)
@@ -115,13 +120,7 @@ pub enum DeleteUserResult {
 }
 
 pub fn delete(username: &str) -> AppResult<DeleteUserResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let result = {
         let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
@@ -130,6 +129,7 @@ pub fn delete(username: &str) -> AppResult<DeleteUserResult> {
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         if table
@@ -141,6 +141,7 @@ pub fn delete(username: &str) -> AppResult<DeleteUserResult> {
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?
             .is_some()
         {
@@ -157,19 +158,19 @@ pub fn delete(username: &str) -> AppResult<DeleteUserResult> {
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
 pub fn find(username: &str) -> AppResult<Option<UserDocument>> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
 
     let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
         message: "failed to open table".to_owned(),
@@ -177,6 +178,7 @@ pub fn find(username: &str) -> AppResult<Option<UserDocument>> {
         attr: Some(hashmap! {
             "table".to_owned() => USERS_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
     if let Some(value) = table.get(&username).map_app_err(|e| AppError {
@@ -186,20 +188,20 @@ pub fn find(username: &str) -> AppResult<Option<UserDocument>> {
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })? {
         let mut value = value.value().to_owned();
 
-        Ok(
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => USERS_TABLE.name().to_owned(),
-                    "document".to_owned() => "UserDocument".to_owned(),
-                    "username".to_owned() => username.to_owned(),
-                }),
-            })?,
-        )
+        Ok(codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned(),
+                "document".to_owned() => "UserDocument".to_owned(),
+                "username".to_owned() => username.to_owned(),
+            }),
+            code: None,
+        })?)
     } else {
         Ok(None)
     }
@@ -210,59 +212,96 @@ pub struct ListUsersResult {
     pub role: permission::UserRole,
     pub last_login: Option<String>,
     pub sg: Vec<String>,
+    pub manually_locked: bool,
 }
 
-pub fn list() -> AppResult<Vec<ListUsersResult>> {
-    let mut result = Vec::new();
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+pub struct ListUsersPage {
+    pub users: Vec<ListUsersResult>,
+    /// The username to pass as `after` for the next page, `None` on the last one.
+    pub next: Option<String>,
+}
 
-    let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
-        message: "failed to open table".to_owned(),
+/// Lists users in username order, starting right after `after`. A missing
+/// `limit` returns every user left.
+pub fn list_paged(
+    after: Option<&str>,
+    limit: Option<usize>,
+    role_filter: Option<permission::UserRole>,
+) -> AppResult<ListUsersPage> {
+    let mut users: Vec<ListUsersResult> = Vec::new();
+    let mut next = None;
+
+    /* an empty page could never point at the next one */
+    let limit = limit.map(|v| v.max(1));
+
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
         error: Some(e.to_string()),
-        attr: Some(hashmap! {
-            "table".to_owned() => USERS_TABLE.name().to_owned()
-        }),
+        attr: None,
+        code: None,
     })?;
 
-    let mut table_iter = table.iter().map_app_err(|e| AppError {
-        message: "failed to iter over table".to_owned(),
+    let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "table".to_owned() => USERS_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
-    while let Some(entry) = table_iter.next() {
+    let start = match after {
+        Some(after) => std::ops::Bound::Excluded(after),
+        None => std::ops::Bound::Unbounded,
+    };
+
+    let table_iter = table
+        .range::<&str>((start, std::ops::Bound::Unbounded))
+        .map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+    for entry in table_iter {
         let (key, value) = entry.map_app_err(|e| AppError {
             message: "failed to iter next value".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         let username = key.value().to_owned();
         let mut value = value.value().to_owned();
 
-        let user: UserDocument =
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => USERS_TABLE.name().to_owned(),
-                    "document".to_owned() => "UserDocument".to_owned(),
-                    "username".to_owned() => username.clone(),
-                }),
-            })?;
+        let user: UserDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned(),
+                "document".to_owned() => "UserDocument".to_owned(),
+                "username".to_owned() => username.clone(),
+            }),
+            code: None,
+        })?;
+
+        if role_filter.is_some_and(|v| v != user.role) {
+            continue;
+        }
+
+        /* one more match past the limit is all it takes to know there's a next page */
+        if limit.is_some_and(|v| users.len() >= v) {
+            next = users.last().map(|v| v.username.clone());
+            break;
+        }
 
-        result.push(ListUsersResult {
-            username: key.value().to_owned(),
+        users.push(ListUsersResult {
+            username,
             role: user.role,
             last_login: user.last_login,
             sg: user
@@ -270,10 +309,11 @@ pub fn list() -> AppResult<Vec<ListUsersResult>> {
                 .iter()
                 .map(|v| format!("{}/{}", v.network, v.prefix))
                 .collect(),
+            manually_locked: user.manually_locked,
         });
     }
 
-    Ok(result)
+    Ok(ListUsersPage { users, next })
 }
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -282,17 +322,67 @@ pub enum ChangeUserRoleResult {
     Demoted,
     NoChange,
     NotFound,
+    /// Demoting the user would leave no unlocked admin.
+    LastAdmin,
 }
 
-pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<ChangeUserRoleResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
+/* locked admins don't count, none of them could unlock the others */
+fn has_other_admin(username: &str, txn: &redb::WriteTransaction) -> AppResult<bool> {
+    let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+        message: "failed to open table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => USERS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    let table_iter = table.iter().map_app_err(|e| AppError {
+        message: "failed to iter over table".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => USERS_TABLE.name().to_owned()
+        }),
+        code: None,
+    })?;
+
+    for entry in table_iter {
+        let (key, value) = entry.map_app_err(|e| AppError {
+            message: "failed to iter next value".to_owned(),
             error: Some(e.to_string()),
-            attr: None,
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
         })?;
 
+        if key.value() == username {
+            continue;
+        }
+
+        let mut document_value = value.value().to_string();
+
+        let document: UserDocument =
+            codec::decode(&mut document_value).map_app_err(|e| AppError {
+                message: "failed to deserialize JSON document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "UserDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+        if matches!(document.role, permission::UserRole::Admin) && !document.manually_locked {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<ChangeUserRoleResult> {
+    let txn = begin_write()?;
+
     let document = {
         let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
             message: "failed to open table".to_owned(),
@@ -300,6 +390,7 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -310,18 +401,20 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
                 "table".to_owned() => USERS_TABLE.name().to_owned(),
                 "username".to_owned() => username.to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: UserDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
+            let document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
                     message: "failed to deserialize JSON document".to_owned(),
                     error: Some(e.to_string()),
                     attr: Some(hashmap! {
                         "document".to_owned() => "UserDocument".to_owned()
                     }),
+                    code: None,
                 })?;
 
             Some(document)
@@ -331,14 +424,6 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
     };
 
     let result = if let Some(mut document) = document {
-        let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
-            message: "failed to open table".to_owned(),
-            error: Some(e.to_string()),
-            attr: Some(hashmap! {
-                "table".to_owned() => USERS_TABLE.name().to_owned()
-            }),
-        })?;
-
         let result = match document.role {
             permission::UserRole::Admin => match &role {
                 permission::UserRole::Admin => ChangeUserRoleResult::NoChange,
@@ -350,14 +435,28 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
             },
         };
 
+        if matches!(result, ChangeUserRoleResult::Demoted) && !has_other_admin(username, &txn)? {
+            return Ok(ChangeUserRoleResult::LastAdmin);
+        }
+
+        let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
         document.role = role.clone();
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "UserDocument".to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -370,6 +469,7 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?;
 
         result
@@ -384,6 +484,116 @@ pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<Cha
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
+    })?;
+
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub enum SetUserLockedResult {
+    Locked,
+    Unlocked,
+    NoChange,
+    NotFound,
+}
+
+pub fn set_locked(username: &str, locked: bool) -> AppResult<SetUserLockedResult> {
+    let txn = begin_write()?;
+
+    let document = {
+        let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let query = table.get(&username).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned(),
+                "username".to_owned() => username.to_owned()
+            }),
+            code: None,
+        })?;
+
+        if let Some(document) = query {
+            let mut document_value = document.value().to_string();
+
+            let document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "UserDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            Some(document)
+        } else {
+            None
+        }
+    };
+
+    let result = if let Some(mut document) = document {
+        let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let result = match (document.manually_locked, locked) {
+            (false, true) => SetUserLockedResult::Locked,
+            (true, false) => SetUserLockedResult::Unlocked,
+            _ => SetUserLockedResult::NoChange,
+        };
+
+        document.manually_locked = locked;
+
+        let document = codec::encode(&document).map_app_err(|e| AppError {
+            message: "failed to serialize document to JSON".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "document".to_owned() => "UserDocument".to_owned()
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let _ = table
+            .insert(&username, document.as_str())
+            .map_app_err(|e| AppError {
+                message: "failed to update a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => USERS_TABLE.name().to_owned(),
+                    "username".to_owned() => username.to_owned()
+                }),
+                code: None,
+            })?;
+
+        result
+    } else {
+        SetUserLockedResult::NotFound
+    };
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "table".to_owned() => USERS_TABLE.name().to_owned(),
+            "username".to_owned() => username.to_owned(),
+        }),
+        code: None,
     })?;
 
     Ok(result)
@@ -396,13 +606,7 @@ pub enum ChangeUserPasswordResult {
 }
 
 pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPasswordResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let document = {
         let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
@@ -411,6 +615,7 @@ pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPa
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -421,18 +626,20 @@ pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPa
                 "table".to_owned() => USERS_TABLE.name().to_owned(),
                 "username".to_owned() => username.to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: UserDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
+            let document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
                     message: "failed to deserialize JSON document".to_owned(),
                     error: Some(e.to_string()),
                     attr: Some(hashmap! {
                         "document".to_owned() => "UserDocument".to_owned()
                     }),
+                    code: None,
                 })?;
 
             Some(document)
@@ -448,16 +655,18 @@ pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPa
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         document.password = password.to_string();
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "UserDocument".to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -470,6 +679,7 @@ pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPa
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?;
 
         ChangeUserPasswordResult::Changed
@@ -484,6 +694,7 @@ pub fn change_password(username: &str, password: &str) -> AppResult<ChangeUserPa
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(result)
@@ -496,13 +707,7 @@ pub enum ChangeUserSgResult {
 }
 
 pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUserSgResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let document = {
         let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
@@ -511,6 +716,7 @@ pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUse
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -521,18 +727,20 @@ pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUse
                 "table".to_owned() => USERS_TABLE.name().to_owned(),
                 "username".to_owned() => username.to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: UserDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
+            let document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
                     message: "failed to deserialize JSON document".to_owned(),
                     error: Some(e.to_string()),
                     attr: Some(hashmap! {
                         "document".to_owned() => "UserDocument".to_owned()
                     }),
+                    code: None,
                 })?;
 
             Some(document)
@@ -548,16 +756,18 @@ pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUse
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         document.sg = sg;
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "UserDocument".to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -570,6 +780,7 @@ pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUse
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?;
 
         ChangeUserSgResult::Changed
@@ -584,19 +795,14 @@ pub fn change_sg(username: &str, sg: Vec<UserSgDocument>) -> AppResult<ChangeUse
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
 pub fn refresh_last_active(username: &str) -> AppResult<()> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin write transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let document = {
         let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
@@ -605,6 +811,7 @@ pub fn refresh_last_active(username: &str) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -615,18 +822,20 @@ pub fn refresh_last_active(username: &str) -> AppResult<()> {
                 "table".to_owned() => USERS_TABLE.name().to_owned(),
                 "username".to_owned() => username.to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let document: UserDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
+            let document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
                     message: "failed to deserialize JSON document".to_owned(),
                     error: Some(e.to_string()),
                     attr: Some(hashmap! {
                         "document".to_owned() => "UserDocument".to_owned()
                     }),
+                    code: None,
                 })?;
 
             Some(document)
@@ -642,18 +851,20 @@ pub fn refresh_last_active(username: &str) -> AppResult<()> {
             attr: Some(hashmap! {
                 "table".to_owned() => USERS_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
-        let timenow: chrono::DateTime<chrono::Local> = chrono::Local::now();
+        let timenow = clock::now();
 
         document.last_login = Some(timenow.to_rfc3339());
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "UserDocument".to_owned()
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -666,6 +877,7 @@ pub fn refresh_last_active(username: &str) -> AppResult<()> {
                     "table".to_owned() => USERS_TABLE.name().to_owned(),
                     "username".to_owned() => username.to_owned()
                 }),
+                code: None,
             })?;
     }
 
@@ -676,7 +888,98 @@ pub fn refresh_last_active(username: &str) -> AppResult<()> {
             "table".to_owned() => USERS_TABLE.name().to_owned(),
             "username".to_owned() => username.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(())
 }
+
+pub fn migrate_timestamps(txn: &redb::WriteTransaction) -> AppResult<usize> {
+    let mut to_update = Vec::new();
+
+    {
+        let table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => USERS_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let mut document_value = value.value().to_string();
+
+            let mut document: UserDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "UserDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            if let Some(last_login) = document.last_login.as_deref().and_then(clock::to_utc) {
+                document.last_login = Some(last_login);
+                to_update.push((key.value().to_string(), document));
+            }
+        }
+    }
+
+    let updated = to_update.len();
+
+    if !to_update.is_empty() {
+        let mut table = txn.open_table(USERS_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => USERS_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (key, document) in to_update {
+            let document = codec::encode(&document).map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "UserDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert(key.as_str(), document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => USERS_TABLE.name().to_owned(),
+                        "document".to_owned() => "UserDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(updated)
+}
@@ -18,15 +18,53 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 use maplit::hashmap;
 use redb::{ReadableTable, TableHandle};
 
-use crate::app_error::{AppError, AppErrorResult, AppResult};
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock, permission,
+};
 
-use super::{access, secret, DATABASE, VAULT_TABLE};
+use super::{access, begin_write, codec, database, secret, SortOrder, VAULT_TABLE};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VaultDocument {
     pub created: String,
     pub secrets_count: i64,
     pub access_keys_count: i64,
+    #[serde(default)]
+    pub default_permissions: Option<Vec<permission::VaultRoles>>,
+    #[serde(default)]
+    pub default_sg: Option<Vec<String>>,
+    #[serde(default)]
+    pub value_schema: ValueSchema,
+}
+
+/// What the values of a vault's secrets must be, checked before they're
+/// encrypted since the server can't look at them afterwards.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+pub enum ValueSchema {
+    #[default]
+    None,
+    Utf8,
+    Json,
+}
+
+impl ValueSchema {
+    pub fn accepts(&self, data: &[u8]) -> bool {
+        match self {
+            ValueSchema::None => true,
+            ValueSchema::Utf8 => std::str::from_utf8(data).is_ok(),
+            ValueSchema::Json => serde_json::from_slice::<serde::de::IgnoredAny>(data).is_ok(),
+        }
+    }
 }
 
 pub enum UpdateVault {
@@ -34,6 +72,7 @@ pub enum UpdateVault {
     IncreaseAccessKey,
     DecreaseSecrets,
     DecreaseAccessKey,
+    DecreaseAccessKeys(i64),
 }
 
 pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) -> AppResult<()> {
@@ -44,6 +83,7 @@ pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) ->
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
         /* borrow checker */
@@ -54,30 +94,33 @@ pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) ->
                 "table".to_owned() => VAULT_TABLE.name().to_owned(),
                 "document".to_owned() => "VaultDocument".to_owned()
             }),
+            code: None,
         })?;
 
         if let Some(document) = query {
             let mut document_value = document.value().to_string();
 
-            let mut document: VaultDocument = unsafe { simd_json::from_str(&mut document_value) }
-                .map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "document".to_owned() => "VaultDocument".to_owned()
-                }),
-            })?;
+            let mut document: VaultDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
 
             match update {
                 UpdateVault::IncreaseSecrets => document.secrets_count += 1,
                 UpdateVault::IncreaseAccessKey => document.access_keys_count += 1,
                 UpdateVault::DecreaseSecrets => document.secrets_count -= 1,
                 UpdateVault::DecreaseAccessKey => document.access_keys_count -= 1,
+                UpdateVault::DecreaseAccessKeys(count) => document.access_keys_count -= count,
             }
 
             document
         } else {
-            let now: chrono::DateTime<chrono::Local> = chrono::Local::now();
+            let now = clock::now();
 
             VaultDocument {
                 created: now.to_rfc3339(),
@@ -91,6 +134,9 @@ pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) ->
                 } else {
                     0
                 },
+                default_permissions: None,
+                default_sg: None,
+                value_schema: ValueSchema::None,
             }
         }
     };
@@ -102,14 +148,16 @@ pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) ->
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned(),
             }),
+            code: None,
         })?;
 
-        let document = simd_json::to_string(&document).map_app_err(|e| AppError {
+        let document = codec::encode(&document).map_app_err(|e| AppError {
             message: "failed to serialize document to JSON".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "document".to_owned() => "VaultDocument".to_owned()
             }),
+            code: None,
         })?;
 
         let _ = table
@@ -121,12 +169,21 @@ pub fn update(vault: &str, update: UpdateVault, txn: &redb::WriteTransaction) ->
                     "table".to_owned() => VAULT_TABLE.name().to_owned(),
                     "document".to_owned() => "VaultDocument".to_owned()
                 }),
+                code: None,
             })?;
     }
 
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+pub enum VaultSortBy {
+    #[default]
+    Name,
+    Created,
+    SecretsCount,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ListVaultDocument {
     pub vault: String,
@@ -135,15 +192,45 @@ pub struct ListVaultDocument {
     pub access_keys_count: i64,
 }
 
-pub fn list() -> AppResult<Vec<ListVaultDocument>> {
+pub fn list(
+    name_prefix: &str,
+    sort_by: VaultSortBy,
+    order: SortOrder,
+) -> AppResult<Vec<ListVaultDocument>> {
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let mut result = list_prefixed_in(&txn, name_prefix)?;
+
+    /* the scan is already in name order */
+    match sort_by {
+        VaultSortBy::Name => {}
+        VaultSortBy::Created => {
+            result.sort_by_cached_key(|v| chrono::DateTime::parse_from_rfc3339(&v.created).ok())
+        }
+        VaultSortBy::SecretsCount => result.sort_by_key(|v| v.secrets_count),
+    }
+
+    if matches!(order, SortOrder::Descending) {
+        result.reverse();
+    }
+
+    Ok(result)
+}
+
+pub fn list_in(txn: &redb::ReadTransaction) -> AppResult<Vec<ListVaultDocument>> {
+    list_prefixed_in(txn, "")
+}
+
+fn list_prefixed_in(
+    txn: &redb::ReadTransaction,
+    name_prefix: &str,
+) -> AppResult<Vec<ListVaultDocument>> {
     let mut result = Vec::new();
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
 
     let table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
         message: "failed to open table".to_owned(),
@@ -151,37 +238,44 @@ pub fn list() -> AppResult<Vec<ListVaultDocument>> {
         attr: Some(hashmap! {
             "table".to_owned() => VAULT_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
-    let mut table_iter = table.iter().map_app_err(|e| AppError {
+    let table_iter = table.range(name_prefix..).map_app_err(|e| AppError {
         message: "failed to iter over table".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "table".to_owned() => VAULT_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
-    while let Some(entry) = table_iter.next() {
+    for entry in table_iter {
         let (key, value) = entry.map_app_err(|e| AppError {
             message: "failed to iter next value".to_owned(),
             error: Some(e.to_string()),
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         let vault = key.value().to_string();
+
+        if !vault.starts_with(name_prefix) {
+            break;
+        }
         let mut value = value.value().to_owned();
 
-        let value: VaultDocument =
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => VAULT_TABLE.name().to_owned(),
-                    "document".to_owned() => "VaultDocument".to_owned()
-                }),
-            })?;
+        let value: VaultDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                "document".to_owned() => "VaultDocument".to_owned()
+            }),
+            code: None,
+        })?;
 
         result.push(ListVaultDocument {
             vault,
@@ -200,16 +294,18 @@ pub struct FindVaultDocument {
     pub created: String,
     pub secrets_count: i64,
     pub access_keys_count: i64,
+    pub default_permissions: Option<Vec<permission::VaultRoles>>,
+    pub default_sg: Option<Vec<String>>,
+    pub value_schema: ValueSchema,
 }
 
 pub fn find(vault: &str) -> AppResult<Option<FindVaultDocument>> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_read()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = database().begin_read().map_app_err(|e| AppError {
+        message: "failed to begin read transaction".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
 
     let table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
         message: "failed to open table".to_owned(),
@@ -217,6 +313,7 @@ pub fn find(vault: &str) -> AppResult<Option<FindVaultDocument>> {
         attr: Some(hashmap! {
             "table".to_owned() => VAULT_TABLE.name().to_owned()
         }),
+        code: None,
     })?;
 
     if let Some(value) = table.get(vault).map_app_err(|e| AppError {
@@ -225,42 +322,129 @@ pub fn find(vault: &str) -> AppResult<Option<FindVaultDocument>> {
         attr: Some(hashmap! {
             "table".to_owned() => VAULT_TABLE.name().to_owned()
         }),
+        code: None,
     })? {
         let mut value = value.value().to_owned();
-        let document: VaultDocument =
-            unsafe { simd_json::from_str(&mut value) }.map_app_err(|e| AppError {
-                message: "failed to deserialize JSON document".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "table".to_owned() => VAULT_TABLE.name().to_owned(),
-                    "document".to_owned() => "VaultDocument".to_owned()
-                }),
-            })?;
+        let document: VaultDocument = codec::decode(&mut value).map_app_err(|e| AppError {
+            message: "failed to deserialize JSON document".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                "document".to_owned() => "VaultDocument".to_owned()
+            }),
+            code: None,
+        })?;
 
         Ok(Some(FindVaultDocument {
             vault: vault.to_string(),
             created: document.created,
             secrets_count: document.secrets_count,
             access_keys_count: document.access_keys_count,
+            default_permissions: document.default_permissions,
+            default_sg: document.default_sg,
+            value_schema: document.value_schema,
         }))
     } else {
         Ok(None)
     }
 }
 
+pub enum CreateVaultResult {
+    Created,
+    AlreadyExists,
+    InvalidName,
+}
+
+/// Writes an empty vault, the way the first secret or access key would, but
+/// with its defaults already set.
+pub fn create(
+    vault: &str,
+    default_sg: Option<Vec<String>>,
+    default_permissions: Option<Vec<permission::VaultRoles>>,
+) -> AppResult<CreateVaultResult> {
+    if !super::is_valid_name(vault) {
+        return Ok(CreateVaultResult::InvalidName);
+    }
+
+    let txn = begin_write()?;
+
+    let result = {
+        let mut table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let found = table
+            .get(vault)
+            .map_app_err(|e| AppError {
+                message: "failed to retrive a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => VAULT_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?
+            .is_some();
+
+        if found {
+            CreateVaultResult::AlreadyExists
+        } else {
+            let document = codec::encode(&VaultDocument {
+                created: clock::now().to_rfc3339(),
+                secrets_count: 0,
+                access_keys_count: 0,
+                default_permissions,
+                default_sg,
+                value_schema: ValueSchema::None,
+            })
+            .map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "VaultDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert(vault, document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            CreateVaultResult::Created
+        }
+    };
+
+    if matches!(result, CreateVaultResult::Created) {
+        txn.commit().map_app_err(|e| AppError {
+            message: "failed to commit to the DB".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+    }
+
+    Ok(result)
+}
+
 pub enum DeleteVaultResult {
     Deleted,
     NotFound,
 }
 
 pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
-    let txn = unsafe { DATABASE.as_ref().expect("db.rs hasn't been initialized") }
-        .begin_write()
-        .map_app_err(|e| AppError {
-            message: "failed to begin read transaction".to_owned(),
-            error: Some(e.to_string()),
-            attr: None,
-        })?;
+    let txn = begin_write()?;
 
     let table_found = {
         let table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
@@ -269,6 +453,7 @@ pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         let result = table.get(vault).map_app_err(|e| AppError {
@@ -277,6 +462,7 @@ pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         result.is_some()
@@ -289,6 +475,7 @@ pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         table.remove(vault).map_app_err(|e| AppError {
@@ -297,6 +484,7 @@ pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
             attr: Some(hashmap! {
                 "table".to_owned() => VAULT_TABLE.name().to_owned()
             }),
+            code: None,
         })?;
 
         access::purge(vault, &txn)?;
@@ -311,7 +499,306 @@ pub fn delete(vault: &str) -> AppResult<DeleteVaultResult> {
         message: "failed to commit to the DB".to_owned(),
         error: Some(e.to_string()),
         attr: None,
+        code: None,
+    })?;
+
+    Ok(result)
+}
+
+pub enum RenameVaultResult {
+    Renamed,
+    NotFound,
+    AlreadyExists,
+    InvalidName,
+}
+
+/// `rebind` re-encrypts each of the vault's secrets, live or in the recycle bin,
+/// for the new name.
+pub fn rename(
+    vault: &str,
+    new_vault: &str,
+    rebind: &dyn Fn(&str, &mut secret::SecretDocument) -> AppResult<()>,
+) -> AppResult<RenameVaultResult> {
+    if !super::is_valid_name(new_vault) {
+        return Ok(RenameVaultResult::InvalidName);
+    }
+
+    let txn = begin_write()?;
+
+    let result = {
+        let mut table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let target_found = table
+            .get(new_vault)
+            .map_app_err(|e| AppError {
+                message: "failed to retrive a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => VAULT_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?
+            .is_some();
+
+        if target_found {
+            RenameVaultResult::AlreadyExists
+        } else {
+            /* borrow checker */
+            let document = table
+                .remove(vault)
+                .map_app_err(|e| AppError {
+                    message: "failed to delete a key".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => VAULT_TABLE.name().to_owned()
+                    }),
+                    code: None,
+                })?
+                .map(|v| v.value().to_string());
+
+            if let Some(document) = document {
+                table
+                    .insert(new_vault, document.as_str())
+                    .map_app_err(|e| AppError {
+                        message: "failed to insert/update a document".to_owned(),
+                        error: Some(e.to_string()),
+                        attr: Some(hashmap! {
+                            "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                            "document".to_owned() => "VaultDocument".to_owned()
+                        }),
+                        code: None,
+                    })?;
+
+                RenameVaultResult::Renamed
+            } else {
+                RenameVaultResult::NotFound
+            }
+        }
+    };
+
+    if matches!(result, RenameVaultResult::Renamed) {
+        access::rename_vault(vault, new_vault, &txn)?;
+        secret::rename_vault(vault, new_vault, rebind, &txn)?;
+
+        txn.commit().map_app_err(|e| AppError {
+            message: "failed to commit to the DB".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+    }
+
+    Ok(result)
+}
+
+pub enum SetVaultDefaultsResult {
+    Updated,
+    NotFound,
+}
+
+/* rewrites an existing vault's document, false when there's no such vault */
+fn modify(vault: &str, change: impl FnOnce(&mut VaultDocument)) -> AppResult<bool> {
+    let txn = begin_write()?;
+
+    let result = {
+        let mut table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        /* borrow checker */
+        let document = table
+            .get(vault)
+            .map_app_err(|e| AppError {
+                message: "failed to retrive a document".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => VAULT_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?
+            .map(|v| v.value().to_string());
+
+        if let Some(mut document) = document {
+            let mut document: VaultDocument =
+                codec::decode(&mut document).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            change(&mut document);
+
+            let document = codec::encode(&document).map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "VaultDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert(vault, document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to insert/update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            true
+        } else {
+            false
+        }
+    };
+
+    txn.commit().map_app_err(|e| AppError {
+        message: "failed to commit to the DB".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
     })?;
 
     Ok(result)
 }
+
+pub fn set_defaults(
+    vault: &str,
+    default_sg: Option<Vec<String>>,
+    default_permissions: Option<Vec<permission::VaultRoles>>,
+) -> AppResult<SetVaultDefaultsResult> {
+    let updated = modify(vault, |document| {
+        document.default_permissions = default_permissions;
+        document.default_sg = default_sg;
+    })?;
+
+    Ok(if updated {
+        SetVaultDefaultsResult::Updated
+    } else {
+        SetVaultDefaultsResult::NotFound
+    })
+}
+
+pub enum SetValueSchemaResult {
+    Updated,
+    NotFound,
+}
+
+pub fn set_value_schema(vault: &str, value_schema: ValueSchema) -> AppResult<SetValueSchemaResult> {
+    let updated = modify(vault, |document| document.value_schema = value_schema)?;
+
+    Ok(if updated {
+        SetValueSchemaResult::Updated
+    } else {
+        SetValueSchemaResult::NotFound
+    })
+}
+
+pub fn migrate_timestamps(txn: &redb::WriteTransaction) -> AppResult<usize> {
+    let mut to_update = Vec::new();
+
+    {
+        let table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        let table_iter = table.iter().map_app_err(|e| AppError {
+            message: "failed to iter over table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for entry in table_iter {
+            let (key, value) = entry.map_app_err(|e| AppError {
+                message: "failed to iter next value".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "table".to_owned() => VAULT_TABLE.name().to_owned()
+                }),
+                code: None,
+            })?;
+
+            let mut document_value = value.value().to_string();
+
+            let mut document: VaultDocument =
+                codec::decode(&mut document_value).map_app_err(|e| AppError {
+                    message: "failed to deserialize JSON document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+
+            if let Some(created) = clock::to_utc(&document.created) {
+                document.created = created;
+                to_update.push((key.value().to_string(), document));
+            }
+        }
+    }
+
+    let updated = to_update.len();
+
+    if !to_update.is_empty() {
+        let mut table = txn.open_table(VAULT_TABLE).map_app_err(|e| AppError {
+            message: "failed to open table".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "table".to_owned() => VAULT_TABLE.name().to_owned()
+            }),
+            code: None,
+        })?;
+
+        for (key, document) in to_update {
+            let document = codec::encode(&document).map_app_err(|e| AppError {
+                message: "failed to serialize document to JSON".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "document".to_owned() => "VaultDocument".to_owned()
+                }),
+                code: None,
+            })?;
+
+            table
+                .insert(key.as_str(), document.as_str())
+                .map_app_err(|e| AppError {
+                    message: "failed to update a document".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: Some(hashmap! {
+                        "table".to_owned() => VAULT_TABLE.name().to_owned(),
+                        "document".to_owned() => "VaultDocument".to_owned()
+                    }),
+                    code: None,
+                })?;
+        }
+    }
+
+    Ok(updated)
+}
@@ -24,6 +24,7 @@ pub static SECRETS: Exit = Exit(3);
 pub static IAM: Exit = Exit(4);
 pub static SERVER: Exit = Exit(5);
 pub static DB: Exit = Exit(5);
+pub static WEBHOOK: Exit = Exit(6);
 
 impl Exit {
     pub fn exit(self) {
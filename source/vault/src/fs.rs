@@ -0,0 +1,111 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    log,
+};
+
+/// Creates the missing directories leading to `path`, readable by the owner only
+/// on Unix. Directories that already exist are left as they are.
+pub fn create_parent_directories(path: &std::path::Path) -> AppResult<()> {
+    let Some(parent) = path.parent().filter(|v| !v.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+
+    let mut builder = std::fs::DirBuilder::new();
+
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+
+        builder.mode(0o700);
+    }
+
+    builder.create(parent).map_app_err(|e| AppError {
+        message: "failed to create the directory".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "directory".to_owned() => parent.to_str().unwrap_or("N/A").to_owned()
+        }),
+        code: None,
+    })
+}
+
+/// Makes `path` readable and writable by the owner only, does nothing outside Unix.
+pub fn set_private(path: &std::path::Path) -> AppResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).map_app_err(
+            |e| AppError {
+                message: "failed to set the permissions".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "filename".to_owned() => path.to_str().unwrap_or("N/A").to_owned()
+                }),
+                code: None,
+            },
+        )?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+/// Whether the group or others may access `path`, always false outside Unix.
+pub fn is_exposed(path: &std::path::Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::metadata(path)
+            .map(|v| v.permissions().mode() & 0o077 != 0)
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+
+        false
+    }
+}
+
+/* files created before the permissions were enforced are left alone, only reported */
+pub fn warn_if_exposed(path: &std::path::Path, module: log::Module) {
+    if is_exposed(path) {
+        log!({
+            mod: module,
+            ctx: "initializing",
+            msg: "file is readable by the group or others, consider chmod 600",
+            tags: [
+                "init", "warning"
+            ],
+            attr: {
+                filename: path.to_str().unwrap_or("N/A").to_owned()
+            }
+        });
+    }
+}
@@ -0,0 +1,220 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+use p256::ecdsa::signature::Verifier;
+
+use crate::{
+    access_keys,
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock, config, permission,
+};
+
+struct Jwt {
+    verifying_key: p256::ecdsa::VerifyingKey,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+static mut JWT: Option<Jwt> = None;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Header {
+    alg: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// What a bearer token grants, in place of an access key document.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub vault: String,
+    pub permission: Vec<permission::VaultRoles>,
+    /// Falls back to the vault's default security groups when missing.
+    #[serde(default)]
+    pub sg: Option<Vec<String>>,
+    pub exp: i64,
+}
+
+/* the claims every token is checked against, whatever else it carries */
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Registered {
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+    #[serde(default)]
+    iss: Option<String>,
+    #[serde(default)]
+    aud: Option<Audience>,
+}
+
+pub fn initialize() -> AppResult<()> {
+    let config_clone = config::get_clone();
+
+    if let Some(jwt) = config_clone.jwt {
+        let verifying_key = access_keys::load_verifying_key(&jwt.public_key)?;
+
+        unsafe {
+            JWT = Some(Jwt {
+                verifying_key,
+                issuer: jwt.issuer,
+                audience: jwt.audience,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    unsafe { JWT.is_some() }
+}
+
+#[inline]
+fn invalid(message: &str) -> AppError {
+    AppError {
+        message: message.to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    }
+}
+
+fn decode_part(part: &str) -> AppResult<Vec<u8>> {
+    base64_simd::URL_SAFE_NO_PAD
+        .decode_to_vec(part)
+        .map_app_err(|e| AppError {
+            message: "failed to decode the token".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })
+}
+
+/// Checks the ES256 signature, the validity window and, when configured, the
+/// issuer and audience. The claims are only returned for a token that passes.
+pub fn verify(token: &str) -> AppResult<Claims> {
+    let jwt = unsafe { JWT.as_ref() }.ok_or_else(|| invalid("bearer tokens are not enabled"))?;
+
+    verify_with(
+        &jwt.verifying_key,
+        jwt.issuer.as_deref(),
+        jwt.audience.as_deref(),
+        token,
+    )
+}
+
+/// Like [`verify`], with the key, issuer and audience given rather than the
+/// configured ones, for tokens that aren't bearer tokens.
+pub fn verify_with<T: serde::de::DeserializeOwned>(
+    verifying_key: &p256::ecdsa::VerifyingKey,
+    issuer: Option<&str>,
+    audience: Option<&str>,
+    token: &str,
+) -> AppResult<T> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("malformed token"));
+    };
+
+    let mut header_json = decode_part(header)?;
+    let header_document: Header =
+        simd_json::from_slice(&mut header_json).map_app_err(|e| AppError {
+            message: "failed to deserialize the token header".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    /* the algorithm comes from the key, never from the token, so "none"
+    or a swap to an HMAC can't slip through */
+    if header_document.alg != "ES256" {
+        return Err(AppError {
+            message: "unsupported token algorithm".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "alg".to_owned() => header_document.alg
+            }),
+            code: None,
+        });
+    }
+
+    let signature =
+        p256::ecdsa::Signature::from_slice(&decode_part(signature)?).map_app_err(|e| AppError {
+            message: "invalid token signature".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    verifying_key
+        .verify(format!("{header}.{payload}").as_bytes(), &signature)
+        .map_app_err(|_| invalid("invalid token signature"))?;
+
+    let mut payload = decode_part(payload)?;
+    let registered: Registered =
+        simd_json::from_slice(&mut payload.clone()).map_app_err(|e| AppError {
+            message: "failed to deserialize the token claims".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    let now = clock::now().timestamp();
+
+    if registered.exp <= now {
+        return Err(invalid("token has expired"));
+    }
+
+    if registered.nbf.is_some_and(|v| v > now) {
+        return Err(invalid("token is not valid yet"));
+    }
+
+    if let Some(issuer) = issuer {
+        if registered.iss.as_deref() != Some(issuer) {
+            return Err(invalid("token issuer doesn't match"));
+        }
+    }
+
+    if let Some(audience) = audience {
+        let matches = match &registered.aud {
+            Some(Audience::One(aud)) => aud == audience,
+            Some(Audience::Many(aud)) => aud.iter().any(|v| v == audience),
+            None => false,
+        };
+
+        if !matches {
+            return Err(invalid("token audience doesn't match"));
+        }
+    }
+
+    simd_json::from_slice(&mut payload).map_app_err(|e| AppError {
+        message: "failed to deserialize the token claims".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })
+}
@@ -19,10 +19,20 @@ use maplit::hashmap;
 
 use crate::{
     app_error::{AppError, AppErrorResult, AppResult},
-    config,
+    config, exit, fs, server,
 };
 
 pub static mut STREAM_OUTPUT: Option<std::fs::File> = None;
+pub static STDOUT_OUTPUT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+pub static mut ACCESS_STREAM_OUTPUT: Option<(std::fs::File, config::AccessLogFormat)> = None;
+/* names of the modules whose events are written, all of them when None */
+pub static mut ENABLED_MODULES: Option<Vec<&'static str>> = None;
+pub static mut ON_WRITE_FAILURE: config::LogWriteFailure = config::LogWriteFailure::Ignore;
+/* consecutive failed writes, a successful one starts the count over */
+static WRITE_FAILURES: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+/* one failed write may be a hiccup, this many in a row are not */
+const PERSISTENT_WRITE_FAILURES: u32 = 3;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Module {
@@ -36,22 +46,39 @@ pub enum Module {
     Vault,
     Api,
     User,
+    Webhook,
+}
+
+impl Module {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Module::App => "app",
+            Module::Log => "log",
+            Module::Cfg => "cfg",
+            Module::Db => "db",
+            Module::Secrets => "secrets",
+            Module::AccessKey => "accesskey",
+            Module::Server => "server",
+            Module::Vault => "vault",
+            Module::Api => "api",
+            Module::User => "user",
+            Module::Webhook => "webhook",
+        }
+    }
 }
 
 impl Into<json::JsonValue> for Module {
     fn into(self) -> json::JsonValue {
-        match &self {
-            Module::App => json::JsonValue::String("app".to_owned()),
-            Module::Log => json::JsonValue::String("log".to_owned()),
-            Module::Cfg => json::JsonValue::String("cfg".to_owned()),
-            Module::Db => json::JsonValue::String("db".to_owned()),
-            Module::Secrets => json::JsonValue::String("secrets".to_owned()),
-            Module::AccessKey => json::JsonValue::String("accesskey".to_owned()),
-            Module::Server => json::JsonValue::String("server".to_owned()),
-            Module::Vault => json::JsonValue::String("vault".to_owned()),
-            Module::Api => json::JsonValue::String("api".to_owned()),
-            Module::User => json::JsonValue::String("user".to_owned()),
-        }
+        json::JsonValue::String(self.name().to_owned())
+    }
+}
+
+/// Whether an event's module is one of `log.modules`, an event without a
+/// module is always written.
+pub fn enabled(data: &json::JsonValue) -> bool {
+    match (unsafe { ENABLED_MODULES.as_ref() }, data["mod"].as_str()) {
+        (Some(modules), Some(module)) => modules.contains(&module),
+        _ => true,
     }
 }
 
@@ -59,7 +86,7 @@ impl Into<json::JsonValue> for Module {
 macro_rules! log {
     ({ $($json:tt)+ }) => {
         {
-            use crate::log::STREAM_OUTPUT;
+            use crate::log::{STDOUT_OUTPUT, STREAM_OUTPUT};
             use std::io::Write;
 
             let stream = unsafe { STREAM_OUTPUT.as_ref() };
@@ -74,39 +101,152 @@ macro_rules! log {
                 $($json)+
             };
 
-            let message = data.dump();
+            if crate::log::enabled(&data) {
+                let message = data.dump();
 
-            if let Some(mut stream) = stream {
-                let _ = write!(stream, "{message}\n");
+                if let Some(mut stream) = stream {
+                    crate::log::written(write!(stream, "{message}\n"));
+                }
+
+                if STDOUT_OUTPUT.load(std::sync::atomic::Ordering::Relaxed) {
+                    let mut stdout = std::io::stdout();
+                    let _ = write!(stdout, "{message}\n");
+                }
             }
+        }
+    }
+}
 
-            let mut stdout = std::io::stdout();
-            let _ = write!(stdout, "{message}\n");
+/// Counts the log file writes that failed in a row and, once they look
+/// persistent, reacts as `log.on_write_failure` says. From then on the events
+/// go to STDOUT as well, so they aren't all lost.
+pub fn written(result: std::io::Result<()>) {
+    let e = match result {
+        Ok(()) => {
+            WRITE_FAILURES.store(0, std::sync::atomic::Ordering::Relaxed);
+            return;
         }
+        Err(e) => e,
+    };
+
+    let failures = WRITE_FAILURES.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+
+    if failures != PERSISTENT_WRITE_FAILURES {
+        return;
+    }
+
+    STDOUT_OUTPUT.store(true, std::sync::atomic::Ordering::Relaxed);
+
+    let reaction = unsafe { ON_WRITE_FAILURE };
+
+    crate::log!({
+        mod: Module::Log,
+        ctx: "writing the log",
+        msg: "the log file can't be written",
+        err: e.to_string(),
+        tags: [
+            "log", "error"
+        ],
+        attr: {
+            failures: failures,
+            reaction: format!("{:?}", reaction)
+        }
+    });
+
+    match reaction {
+        config::LogWriteFailure::Ignore => {}
+        config::LogWriteFailure::Maintenance => server::enter_maintenance(),
+        config::LogWriteFailure::Exit => exit::LOG.exit(),
     }
 }
 
+/// Opens a log file for appending, creating it and the directories leading to it.
+/// A new file is readable by the owner only.
+pub fn open(filename: &str) -> AppResult<std::fs::File> {
+    let path = std::path::Path::new(filename);
+
+    fs::create_parent_directories(path)?;
+
+    let created = !path.exists();
+
+    let stream = std::fs::OpenOptions::new()
+        .read(false)
+        .append(true)
+        .create(true)
+        .open(filename)
+        .map_app_err(|e| AppError {
+            message: "failed to open/create".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "filename".to_owned() => filename.to_owned()
+            }),
+            code: None,
+        })?;
+
+    if created {
+        fs::set_private(path)?;
+    }
+
+    Ok(stream)
+}
+
 pub fn initialize() -> AppResult<()> {
     let config = config::get_clone();
 
     if let Some(log_config) = config.log {
-        let stream = std::fs::OpenOptions::new()
-            .read(false)
-            .append(true)
-            .create(true)
-            .open(&log_config.filename)
-            .map_app_err(|e| AppError {
-                message: "failed to open/create".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "filename".to_owned() => log_config.filename
-                }),
-            })?;
+        let stream = open(&log_config.filename)?;
 
         unsafe {
             STREAM_OUTPUT = Some(stream);
         }
+
+        fs::warn_if_exposed(std::path::Path::new(&log_config.filename), Module::Log);
+
+        unsafe {
+            ON_WRITE_FAILURE = log_config
+                .on_write_failure
+                .unwrap_or(config::LogWriteFailure::Ignore);
+        }
+
+        if let Some(modules) = &log_config.modules {
+            unsafe {
+                ENABLED_MODULES = Some(modules.iter().map(Module::name).collect());
+            }
+        }
+
+        /* only with a file to write to, otherwise the events would be lost */
+        STDOUT_OUTPUT.store(
+            log_config.stdout.unwrap_or(true),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        if let Some(filename) = log_config.access_log {
+            let stream = open(&filename)?;
+
+            fs::warn_if_exposed(std::path::Path::new(&filename), Module::Log);
+
+            let format = log_config
+                .access_log_format
+                .unwrap_or(config::AccessLogFormat::Combined);
+
+            unsafe {
+                ACCESS_STREAM_OUTPUT = Some((stream, format));
+            }
+        }
     }
 
     Ok(())
 }
+
+pub fn access_format() -> Option<config::AccessLogFormat> {
+    unsafe { ACCESS_STREAM_OUTPUT.as_ref() }.map(|(_, format)| *format)
+}
+
+pub fn access(line: &str) {
+    use std::io::Write;
+
+    if let Some((stream, _)) = unsafe { ACCESS_STREAM_OUTPUT.as_ref() } {
+        let mut stream: &std::fs::File = stream;
+        written(stream.write_all(format!("{line}\n").as_bytes()));
+    }
+}
@@ -15,21 +15,91 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use rand::Rng;
+use crate::app_error::{AppError, AppErrorOption};
 
 pub mod access_keys;
 pub mod api;
 pub mod app_error;
+pub mod authenticator;
+pub mod bootstrap;
+pub mod clock;
 pub mod cmd;
+pub mod compression;
 pub mod config;
 pub mod db;
 pub mod exit;
+pub mod fs;
+pub mod jwt;
 pub mod log;
 pub mod permission;
 pub mod secrets;
 pub mod server;
+pub mod sg;
+pub mod stats;
 pub mod user;
 pub mod vault;
+pub mod webhook;
+
+#[cfg(test)]
+mod tests;
+
+fn generate_config() -> bool {
+    let mut directory: Option<Option<String>> = None;
+    let mut args = std::env::args();
+
+    while let Some(arg) = args.next() {
+        if arg == "--generate-config" {
+            directory = Some(args.next());
+        }
+    }
+
+    let Some(directory) = directory else {
+        return false;
+    };
+
+    let result = directory
+        .context_app_err(|| AppError {
+            message: "missing the directory".to_owned(),
+            error: Some("usage: vaulty --generate-config [directory]".to_owned()),
+            attr: None,
+            code: None,
+        })
+        .and_then(|directory| bootstrap::generate_config(std::path::Path::new(&directory)));
+
+    match result {
+        Ok(filename) => {
+            println!("Config generated, start vaulty with: vaulty --config {filename}");
+        }
+        Err(e) => {
+            log!({
+                mod: log::Module::Cfg,
+                ctx: "generating config",
+                msg: "failed to generate the config",
+                err: e,
+                tags: [
+                    "config", "error"
+                ],
+            });
+
+            exit::CONFIG.exit();
+        }
+    }
+
+    true
+}
+
+fn dump_schema() -> bool {
+    if !std::env::args().any(|arg| arg == "--dump-schema") {
+        return false;
+    }
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&cmd::schema()).expect("a schema is always valid JSON")
+    );
+
+    true
+}
 
 fn initialize_config() {
     let mut config_filename: Option<String> = None;
@@ -81,31 +151,14 @@ fn initialize_db() {
     match db::initialize() {
         Ok(init_state) => {
             if matches!(init_state, db::InitializeState::Created) {
-                const ALLOWED_CHARS: &str =
-                    "1234567890qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM";
-                const INITIAL_PASSWORD_LEN: usize = 20;
-                const INITIAL_USERNAME: &str = "root";
-                const INITIAL_SECURITY_GROUP: &str = "127.0.0.1/32";
-
-                let mut random = rand::thread_rng();
-                let mut initial_password = String::new();
-
-                for _ in 0..INITIAL_PASSWORD_LEN {
-                    initial_password.push(
-                        ALLOWED_CHARS
-                            .chars()
-                            .nth(random.gen::<usize>() % ALLOWED_CHARS.len())
-                            .unwrap(),
-                    );
-                }
+                let bootstrap = config::get_clone().users.bootstrap.unwrap_or_default();
 
-                match user::create_user(
-                    INITIAL_USERNAME,
-                    &initial_password,
-                    &permission::UserRole::Admin,
-                    vec![INITIAL_SECURITY_GROUP.to_string()],
-                ) {
-                    Ok(db::user::InsertUserResult::Created) => {
+                match bootstrap::create_initial_admin(&bootstrap) {
+                    Ok(bootstrap::InitialAdmin::Created {
+                        username,
+                        sg,
+                        password: bootstrap::InitialPassword::Generated(password),
+                    }) => {
                         log!({
                             mod: log::Module::Db,
                             ctx: "initializing",
@@ -114,13 +167,60 @@ fn initialize_db() {
                                 "init", "db"
                             ],
                             attr: {
-                                user: INITIAL_USERNAME,
-                                password: initial_password,
-                                sg: INITIAL_SECURITY_GROUP
+                                user: username,
+                                password: password,
+                                sg: sg
                             }
                         });
                     }
-                    Ok(db::user::InsertUserResult::Exists) => {}
+                    Ok(bootstrap::InitialAdmin::Created {
+                        username,
+                        sg,
+                        password: bootstrap::InitialPassword::Written(filename),
+                    }) => {
+                        log!({
+                            mod: log::Module::Db,
+                            ctx: "initializing",
+                            msg: "root user created, its password is written to the file",
+                            tags: [
+                                "init", "db"
+                            ],
+                            attr: {
+                                user: username,
+                                filename: filename,
+                                sg: sg
+                            }
+                        });
+                    }
+                    Ok(bootstrap::InitialAdmin::Created {
+                        username,
+                        sg,
+                        password: bootstrap::InitialPassword::Provided,
+                    }) => {
+                        log!({
+                            mod: log::Module::Db,
+                            ctx: "initializing",
+                            msg: "root user created with the provided password",
+                            tags: [
+                                "init", "db"
+                            ],
+                            attr: {
+                                user: username,
+                                sg: sg
+                            }
+                        });
+                    }
+                    Ok(bootstrap::InitialAdmin::Exists) => {}
+                    Ok(bootstrap::InitialAdmin::Skipped) => {
+                        log!({
+                            mod: log::Module::Db,
+                            ctx: "initializing",
+                            msg: "root user is externally provisioned, none created",
+                            tags: [
+                                "init", "db"
+                            ],
+                        });
+                    }
                     Err(e) => {
                         log!({
                             mod: log::Module::Db,
@@ -154,7 +254,7 @@ fn initialize_db() {
 }
 
 fn initialize_secrets() {
-    if let Err(e) = secrets::initialize() {
+    if let Err(e) = secrets::initialize().and_then(|_| secrets::bind_legacy()) {
         log!({
             mod: log::Module::Secrets,
             ctx: "initializing",
@@ -185,20 +285,78 @@ fn initialize_access_keys() {
     }
 }
 
+fn initialize_jwt() {
+    if let Err(e) = jwt::initialize() {
+        log!({
+            mod: log::Module::AccessKey,
+            ctx: "initializing",
+            msg: "failed to initialize the JWT module",
+            err: e,
+            tags: [
+                "init", "jwt", "error"
+            ],
+        });
+
+        exit::IAM.exit();
+    }
+}
+
 fn initialize_users() {
     user::initialize();
+
+    if let Err(e) = authenticator::initialize() {
+        log!({
+            mod: log::Module::User,
+            ctx: "initializing",
+            msg: "failed to initialize the authenticator",
+            err: e,
+            tags: [
+                "init", "user", "error"
+            ],
+        });
+
+        exit::IAM.exit();
+    }
+}
+
+fn initialize_webhook() {
+    if let Err(e) = webhook::initialize() {
+        log!({
+            mod: log::Module::Webhook,
+            ctx: "initializing",
+            msg: "failed to initialize the webhook module",
+            err: e,
+            tags: [
+                "init", "webhook", "error"
+            ],
+        });
+
+        exit::WEBHOOK.exit();
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    /* before the banner, so the output can be piped straight to a file */
+    if dump_schema() {
+        return;
+    }
+
     println!("Copyright (C) 2024  S. Ivanov\n");
 
+    if generate_config() {
+        return;
+    }
+
     initialize_config();
     initialize_log();
     initialize_db();
     initialize_secrets();
     initialize_access_keys();
+    initialize_jwt();
     initialize_users();
+    initialize_webhook();
+    sg::initialize();
 
     if let Err(e) = server::start().await {
         log!({
@@ -15,12 +15,27 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+use crate::app_error::{AppError, AppResult, ErrorCode};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub enum UserRole {
     Admin,
     User,
 }
 
+impl UserRole {
+    pub const ALL: [UserRole; 2] = [UserRole::Admin, UserRole::User];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "Can run every command",
+            UserRole::User => "Admin only commands are denied",
+        }
+    }
+}
+
 impl ToString for UserRole {
     fn to_string(&self) -> String {
         match self {
@@ -39,12 +54,37 @@ impl Into<json::JsonValue> for UserRole {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub enum VaultRoles {
     ListSecrets,
     DeleteSecrets,
     CreateSecrets,
     DecryptSecrets,
+    /* implies every other role, Owner is checked instead of being expanded so
+    a role added later is implied too */
+    Owner,
+}
+
+impl VaultRoles {
+    pub const ALL: [VaultRoles; 5] = [
+        VaultRoles::ListSecrets,
+        VaultRoles::DeleteSecrets,
+        VaultRoles::CreateSecrets,
+        VaultRoles::DecryptSecrets,
+        VaultRoles::Owner,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            VaultRoles::ListSecrets => "List the vault's secrets and check if a secret exists",
+            VaultRoles::DeleteSecrets => "Delete secrets",
+            VaultRoles::CreateSecrets => "Insert and update secrets",
+            VaultRoles::DecryptSecrets => "Retrieve the secrets' values",
+            VaultRoles::Owner => "Everything in the vault, including permissions added later",
+        }
+    }
 }
 
 impl ToString for VaultRoles {
@@ -54,6 +94,7 @@ impl ToString for VaultRoles {
             VaultRoles::DeleteSecrets => "DeleteSecrets".to_owned(),
             VaultRoles::CreateSecrets => "CreateSecrets".to_owned(),
             VaultRoles::DecryptSecrets => "DecryptSecrets".to_owned(),
+            VaultRoles::Owner => "Owner".to_owned(),
         }
     }
 }
@@ -65,6 +106,60 @@ impl Into<json::JsonValue> for VaultRoles {
             Self::DeleteSecrets => json::JsonValue::String("DeleteSecrets".to_string()),
             Self::CreateSecrets => json::JsonValue::String("CreateSecrets".to_string()),
             Self::DecryptSecrets => json::JsonValue::String("DecryptSecrets".to_string()),
+            Self::Owner => json::JsonValue::String("Owner".to_string()),
         }
     }
 }
+
+/// Whether the roles grant `permission`, an Owner holds every permission.
+pub fn contains_or_owner(roles: &[VaultRoles], permission: VaultRoles) -> bool {
+    roles.contains(&VaultRoles::Owner) || roles.contains(&permission)
+}
+
+/* shorthands for common permission sets, expanded when the access key is created */
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub enum VaultRolesPreset {
+    WriteOnly,
+}
+
+impl VaultRolesPreset {
+    pub const ALL: [VaultRolesPreset; 1] = [VaultRolesPreset::WriteOnly];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            VaultRolesPreset::WriteOnly => "Can insert secrets but never read, list or delete them",
+        }
+    }
+
+    pub fn roles(&self) -> Vec<VaultRoles> {
+        match self {
+            VaultRolesPreset::WriteOnly => vec![VaultRoles::CreateSecrets],
+        }
+    }
+}
+
+pub fn validate_vault_roles(roles: &[VaultRoles]) -> AppResult<()> {
+    if roles.is_empty() {
+        return Err(AppError {
+            message: "at least one permission is required".to_owned(),
+            error: None,
+            attr: None,
+            code: Some(ErrorCode::InvalidArgument),
+        });
+    }
+
+    /* deleting secrets the key can't even see is never intended */
+    if contains_or_owner(roles, VaultRoles::DeleteSecrets)
+        && !contains_or_owner(roles, VaultRoles::ListSecrets)
+        && !contains_or_owner(roles, VaultRoles::DecryptSecrets)
+    {
+        return Err(AppError {
+            message: "DeleteSecrets requires ListSecrets or DecryptSecrets".to_owned(),
+            error: None,
+            attr: None,
+            code: Some(ErrorCode::InvalidArgument),
+        });
+    }
+
+    Ok(())
+}
@@ -17,19 +17,81 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::io::Read;
 
-use aes_gcm::{aead::Aead, KeyInit};
+use aes_gcm::{
+    aead::{Aead, Payload},
+    KeyInit,
+};
 use maplit::hashmap;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::Rng;
 use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
 
 use crate::{
-    app_error::{AppError, AppErrorResult, AppResult},
-    config,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
+    config, db, log,
 };
 
-static mut RSA_PRIVATE_KEY: Option<rsa::RsaPrivateKey> = None;
-static mut RSA_PUBLIC_KEY: Option<rsa::RsaPublicKey> = None;
-static mut AES_KEY: Option<Vec<u8>> = None;
-static mut AES_IV: Option<Vec<u8>> = None;
+const RSA_BLOCK_SIZE: usize = 512;
+const RSA_SCHEME_TAG: u8 = 0x52;
+const EC_SCHEME_TAG: u8 = 0xEC;
+const EC_PUBLIC_KEY_SIZE: usize = 33;
+const EC_NONCE_SIZE: usize = 12;
+const EC_HKDF_INFO: &[u8] = b"vaulty secrets";
+const DEFAULT_MAX_SECRET_BYTES: usize = 1024 * 1024;
+const SELF_TEST_VALUE: &[u8] = b"vaulty self-test";
+
+static mut ENGINE: Option<SecretsEngine> = None;
+
+/// Character sets for server-side generated values.
+#[derive(
+    Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub enum Charset {
+    #[default]
+    Alphanumeric,
+    Numeric,
+    Hex,
+    /// Alphanumeric plus the printable ASCII symbols.
+    Symbols,
+}
+
+impl Charset {
+    pub const fn chars(&self) -> &'static str {
+        match self {
+            Charset::Alphanumeric => {
+                "1234567890qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM"
+            }
+            Charset::Numeric => "1234567890",
+            Charset::Hex => "0123456789abcdef",
+            Charset::Symbols => {
+                "1234567890qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM!#$%&()*+,-./:;<=>?@[]^_{|}~"
+            }
+        }
+    }
+}
+
+/// `length` characters picked uniformly from `charset`, taken from the thread's
+/// CSPRNG.
+pub fn random_string(charset: &str, length: usize) -> String {
+    let charset: Vec<char> = charset.chars().collect();
+    let mut random = rand::thread_rng();
+
+    (0..length)
+        .map(|_| charset[random.gen_range(0..charset.len())])
+        .collect()
+}
+
+pub struct SecretsKeys {
+    pub rsa: Option<(rsa::RsaPrivateKey, rsa::RsaPublicKey)>,
+    pub ec: Option<(p256::SecretKey, p256::PublicKey)>,
+    pub aes_key: Vec<u8>,
+    pub aes_iv: Vec<u8>,
+}
+
+pub struct SecretsEngine {
+    scheme: config::SecretsScheme,
+    keys: SecretsKeys,
+}
 
 fn load_pem(filename: &str) -> AppResult<String> {
     let mut result = String::new();
@@ -45,6 +107,7 @@ fn load_pem(filename: &str) -> AppResult<String> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })?;
 
     file.read_to_string(&mut result).map_app_err(|e| AppError {
@@ -53,50 +116,109 @@ fn load_pem(filename: &str) -> AppResult<String> {
         attr: Some(hashmap! {
             "filename".to_owned() => filename.to_owned(),
         }),
+        code: None,
     })?;
 
     Ok(result)
 }
 
-fn load_rsa_private_key(filename: String) -> AppResult<()> {
-    let file_content = load_pem(&filename)?;
+fn load_rsa_private_key(filename: &str) -> AppResult<rsa::RsaPrivateKey> {
+    let file_content = load_pem(filename)?;
 
-    let result = rsa::RsaPrivateKey::from_pkcs8_pem(&file_content).map_app_err(|e| AppError {
+    rsa::RsaPrivateKey::from_pkcs8_pem(&file_content).map_app_err(|e| AppError {
         message: "failed to load the PEM".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "filename".to_owned() => filename.to_owned(),
         }),
-    })?;
+        code: None,
+    })
+}
 
-    unsafe {
-        RSA_PRIVATE_KEY = Some(result);
-    }
+fn load_rsa_public_key(filename: &str) -> AppResult<rsa::RsaPublicKey> {
+    let file_content = load_pem(filename)?;
 
-    Ok(())
+    rsa::RsaPublicKey::from_public_key_pem(&file_content).map_app_err(|e| AppError {
+        message: "failed to load the PEM".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned(),
+        }),
+        code: None,
+    })
 }
 
-fn load_rsa_public_key(filename: String) -> AppResult<()> {
-    let file_content = load_pem(&filename)?;
+fn load_ec_private_key(filename: &str) -> AppResult<p256::SecretKey> {
+    let file_content = load_pem(filename)?;
 
-    let result =
-        rsa::RsaPublicKey::from_public_key_pem(&file_content).map_app_err(|e| AppError {
-            message: "failed to load the PEM".to_owned(),
-            error: Some(e.to_string()),
+    p256::SecretKey::from_pkcs8_pem(&file_content).map_app_err(|e| AppError {
+        message: "failed to load the PEM".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned(),
+        }),
+        code: None,
+    })
+}
+
+fn load_ec_public_key(filename: &str) -> AppResult<p256::PublicKey> {
+    let file_content = load_pem(filename)?;
+
+    p256::PublicKey::from_public_key_pem(&file_content).map_app_err(|e| AppError {
+        message: "failed to load the PEM".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned(),
+        }),
+        code: None,
+    })
+}
+
+fn load_key_pair<T, U>(
+    private_key: &Option<String>,
+    public_key: &Option<String>,
+    load_private_key: fn(&str) -> AppResult<T>,
+    load_public_key: fn(&str) -> AppResult<U>,
+) -> AppResult<Option<(T, U)>> {
+    match (private_key, public_key) {
+        (Some(private_key), Some(public_key)) => Ok(Some((
+            load_private_key(private_key)?,
+            load_public_key(public_key)?,
+        ))),
+        (None, None) => Ok(None),
+        _ => Err(AppError {
+            message: "both the private and the public key must be set".to_owned(),
+            error: None,
             attr: Some(hashmap! {
-                "filename".to_owned() => filename.to_owned(),
+                "filename".to_owned() => private_key.clone().or(public_key.clone()).unwrap_or_default(),
             }),
-        })?;
-
-    unsafe {
-        RSA_PUBLIC_KEY = Some(result);
+            code: None,
+        }),
     }
+}
 
-    Ok(())
+#[inline]
+fn ec_derive_key(
+    shared_secret: p256::ecdh::SharedSecret,
+    ephemeral_public_key: &[u8],
+) -> AppResult<Vec<u8>> {
+    let mut key = vec![0u8; 32];
+
+    shared_secret
+        .extract::<sha2::Sha256>(Some(ephemeral_public_key))
+        .expand(EC_HKDF_INFO, &mut key)
+        .map_app_err(|e| AppError {
+            message: "failed to perform a HKDF key derivation".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    Ok(key)
 }
 
-fn load_aes_key(filename: String) -> AppResult<()> {
-    let file_content = load_pem(&filename)?;
+fn load_aes_key(filename: &str) -> AppResult<Vec<u8>> {
+    let file_content = load_pem(filename)?;
 
     let key = base64_simd::STANDARD
         .decode_to_vec(file_content.trim())
@@ -106,6 +228,7 @@ fn load_aes_key(filename: String) -> AppResult<()> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })?;
 
     if key.len() != 32 {
@@ -115,16 +238,15 @@ fn load_aes_key(filename: String) -> AppResult<()> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })
     } else {
-        unsafe { AES_KEY = Some(key) }
-
-        Ok(())
+        Ok(key)
     }
 }
 
-fn load_aes_iv(filename: String) -> AppResult<()> {
-    let file_content = load_pem(&filename)?;
+fn load_aes_iv(filename: &str) -> AppResult<Vec<u8>> {
+    let file_content = load_pem(filename)?;
 
     let key = base64_simd::STANDARD
         .decode_to_vec(file_content.trim())
@@ -134,6 +256,7 @@ fn load_aes_iv(filename: String) -> AppResult<()> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })?;
 
     if key.len() != 12 {
@@ -143,190 +266,566 @@ fn load_aes_iv(filename: String) -> AppResult<()> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })
     } else {
-        unsafe { AES_IV = Some(key) }
-
-        Ok(())
+        Ok(key)
     }
 }
 
-pub fn initialize() -> AppResult<()> {
-    let config_clone = config::get_clone();
+impl SecretsEngine {
+    pub fn new(scheme: config::SecretsScheme, keys: SecretsKeys) -> AppResult<SecretsEngine> {
+        if keys.aes_key.len() != 32 {
+            return Err(AppError {
+                message: "invalid AES key".to_owned(),
+                error: Some("expected 32 bytes key".to_owned()),
+                attr: None,
+                code: None,
+            });
+        }
 
-    load_rsa_private_key(config_clone.secrets.rsa_private_key)?;
-    load_rsa_public_key(config_clone.secrets.rsa_public_key)?;
-    load_aes_key(config_clone.secrets.aes_key)?;
-    load_aes_iv(config_clone.secrets.aes_iv)?;
+        if keys.aes_iv.len() != 12 {
+            return Err(AppError {
+                message: "invalid AES key's IV".to_owned(),
+                error: Some("expected 12 bytes key".to_owned()),
+                attr: None,
+                code: None,
+            });
+        }
 
-    Ok(())
-}
+        match scheme {
+            config::SecretsScheme::Rsa if keys.rsa.is_none() => Err(AppError {
+                message: "the RSA scheme requires RSA keys".to_owned(),
+                error: None,
+                attr: None,
+                code: None,
+            }),
+            config::SecretsScheme::Ec if keys.ec.is_none() => Err(AppError {
+                message: "the EC scheme requires EC keys".to_owned(),
+                error: None,
+                attr: None,
+                code: None,
+            }),
+            _ => Ok(SecretsEngine { scheme, keys }),
+        }
+    }
 
-#[inline]
-fn rsa_decrypt(encrypted: &[u8]) -> AppResult<Vec<u8>> {
-    const BLOCK_SIZE: usize = 512;
+    pub fn load(secrets: &config::ConfigSecrets) -> AppResult<SecretsEngine> {
+        SecretsEngine::new(
+            secrets.scheme.clone().unwrap_or(config::SecretsScheme::Rsa),
+            SecretsKeys {
+                rsa: load_key_pair(
+                    &secrets.rsa_private_key,
+                    &secrets.rsa_public_key,
+                    load_rsa_private_key,
+                    load_rsa_public_key,
+                )?,
+                ec: load_key_pair(
+                    &secrets.ec_private_key,
+                    &secrets.ec_public_key,
+                    load_ec_private_key,
+                    load_ec_public_key,
+                )?,
+                aes_key: load_aes_key(&secrets.aes_key)?,
+                aes_iv: load_aes_iv(&secrets.aes_iv)?,
+            },
+        )
+    }
 
-    let private_key = unsafe {
-        RSA_PRIVATE_KEY
-            .clone()
-            .expect("secrets.rs hasn't been initialized")
-    };
+    #[inline]
+    fn rsa_decrypt(&self, encrypted: &[u8]) -> AppResult<Vec<u8>> {
+        let (private_key, _) = self.keys.rsa.as_ref().context_app_err(|| AppError {
+            message: "failed to perform a RSA 4096 decryption".to_owned(),
+            error: Some("RSA keys aren't configured".to_owned()),
+            attr: None,
+            code: None,
+        })?;
 
-    let encrypted_len = encrypted.len();
+        let encrypted_len = encrypted.len();
 
-    if encrypted_len % BLOCK_SIZE > 0 {
-        return Err(AppError {
-            message: "failed to perform a RSA 4096 decryption".to_owned(),
-            error: Some("invalid block size".to_owned()),
-            attr: Some(hashmap! {
-                "block_size".to_owned() => format!("{}", encrypted_len)
-            }),
-        });
-    }
+        if encrypted_len % RSA_BLOCK_SIZE > 0 {
+            return Err(AppError {
+                message: "failed to perform a RSA 4096 decryption".to_owned(),
+                error: Some("invalid block size".to_owned()),
+                attr: Some(hashmap! {
+                    "block_size".to_owned() => format!("{}", encrypted_len)
+                }),
+                code: None,
+            });
+        }
 
-    if encrypted_len > BLOCK_SIZE {
-        let mut idx = 0;
-        let mut result = Vec::new();
+        if encrypted_len > RSA_BLOCK_SIZE {
+            let mut idx = 0;
+            let mut result = Vec::new();
+
+            while idx + RSA_BLOCK_SIZE <= encrypted_len {
+                let block = encrypted[idx..(idx + RSA_BLOCK_SIZE)].to_vec();
+
+                result.append(
+                    &mut private_key
+                        .decrypt(rsa::Pkcs1v15Encrypt, &block)
+                        .map_app_err(|e| AppError {
+                            message: "failed to perform a RSA 4096 decryption".to_owned(),
+                            error: Some(e.to_string()),
+                            attr: None,
+                            code: None,
+                        })?,
+                );
+
+                idx += RSA_BLOCK_SIZE;
+            }
+
+            Ok(result)
+        } else {
+            Ok(private_key
+                .decrypt(rsa::Pkcs1v15Encrypt, encrypted)
+                .map_app_err(|e| AppError {
+                    message: "failed to perform a RSA 4096 decryption".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None,
+                })?)
+        }
+    }
 
-        while idx + BLOCK_SIZE <= encrypted_len {
-            let block = encrypted[idx..(idx + BLOCK_SIZE)].to_vec();
+    #[inline]
+    fn rsa_encrypt(&self, plain: &[u8]) -> AppResult<Vec<u8>> {
+        let (_, public_key) = self.keys.rsa.as_ref().context_app_err(|| AppError {
+            message: "failed to perform a RSA 4096 encryption".to_owned(),
+            error: Some("RSA keys aren't configured".to_owned()),
+            attr: None,
+            code: None,
+        })?;
 
+        let mut rng = rand::thread_rng();
+        let plain_len = plain.len();
+        let mut result = vec![RSA_SCHEME_TAG];
+
+        if plain_len > RSA_BLOCK_SIZE {
+            let mut idx = 0;
+
+            while idx + RSA_BLOCK_SIZE <= plain_len {
+                let block = plain[idx..(idx + RSA_BLOCK_SIZE)].to_vec();
+
+                result.append(
+                    &mut public_key
+                        .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &block)
+                        .map_app_err(|e| AppError {
+                            message: "failed to perform a RSA 4096 encryption".to_owned(),
+                            error: Some(e.to_string()),
+                            attr: None,
+                            code: None,
+                        })?,
+                );
+
+                idx += RSA_BLOCK_SIZE;
+            }
+        } else {
             result.append(
-                &mut private_key
-                    .decrypt(rsa::Pkcs1v15Encrypt, &block)
+                &mut public_key
+                    .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, plain)
                     .map_app_err(|e| AppError {
-                        message: "failed to perform a RSA 4096 decryption".to_owned(),
+                        message: "failed to perform a RSA 4096 encryption".to_owned(),
                         error: Some(e.to_string()),
                         attr: None,
+                        code: None,
                     })?,
             );
-
-            idx += BLOCK_SIZE;
         }
 
         Ok(result)
-    } else {
-        Ok(private_key
-            .decrypt(rsa::Pkcs1v15Encrypt, encrypted)
+    }
+
+    #[inline]
+    fn ec_decrypt(&self, encrypted: &[u8]) -> AppResult<Vec<u8>> {
+        let (private_key, _) = self.keys.ec.as_ref().context_app_err(|| AppError {
+            message: "failed to perform a ECIES decryption".to_owned(),
+            error: Some("EC keys aren't configured".to_owned()),
+            attr: None,
+            code: None,
+        })?;
+
+        if encrypted.len() < EC_PUBLIC_KEY_SIZE + EC_NONCE_SIZE {
+            return Err(AppError {
+                message: "failed to perform a ECIES decryption".to_owned(),
+                error: Some("invalid size".to_owned()),
+                attr: None,
+                code: None,
+            });
+        }
+
+        let (ephemeral_public_key, encrypted) = encrypted.split_at(EC_PUBLIC_KEY_SIZE);
+        let (nonce, encrypted) = encrypted.split_at(EC_NONCE_SIZE);
+
+        let shared_secret = p256::ecdh::diffie_hellman(
+            private_key.to_nonzero_scalar(),
+            p256::PublicKey::from_sec1_bytes(ephemeral_public_key)
+                .map_app_err(|e| AppError {
+                    message: "failed to perform a ECIES decryption".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None,
+                })?
+                .as_affine(),
+        );
+
+        let key = ec_derive_key(shared_secret, ephemeral_public_key)?;
+
+        let cipher = aes_gcm::Aes256Gcm::new_from_slice(&key).map_app_err(|e| AppError {
+            message: "failed to create a AES GCM object".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+        cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), encrypted)
             .map_app_err(|e| AppError {
-                message: "failed to perform a RSA 4096 decryption".to_owned(),
+                message: "failed to perform a ECIES decryption".to_owned(),
                 error: Some(e.to_string()),
                 attr: None,
-            })?)
+                code: None,
+            })
     }
-}
 
-#[inline]
-fn rsa_encrypt(plain: &[u8]) -> AppResult<Vec<u8>> {
-    const BLOCK_SIZE: usize = 512;
+    #[inline]
+    fn ec_encrypt(&self, plain: &[u8]) -> AppResult<Vec<u8>> {
+        let (_, public_key) = self.keys.ec.as_ref().context_app_err(|| AppError {
+            message: "failed to perform a ECIES encryption".to_owned(),
+            error: Some("EC keys aren't configured".to_owned()),
+            attr: None,
+            code: None,
+        })?;
 
-    let public_key = unsafe {
-        RSA_PUBLIC_KEY
-            .clone()
-            .expect("secrets.rs hasn't been initialized")
-    };
+        let ephemeral_secret = p256::ecdh::EphemeralSecret::random(&mut rand::rngs::OsRng);
+        let ephemeral_public_key = ephemeral_secret.public_key().to_encoded_point(true);
 
-    let mut rng = rand::thread_rng();
-    let plain_len = plain.len();
+        let key = ec_derive_key(
+            ephemeral_secret.diffie_hellman(public_key),
+            ephemeral_public_key.as_bytes(),
+        )?;
 
-    if plain_len > BLOCK_SIZE {
-        let mut idx = 0;
-        let mut result = Vec::new();
+        let mut nonce = [0u8; EC_NONCE_SIZE];
 
-        while idx + BLOCK_SIZE <= plain_len {
-            let block = plain[idx..(idx + BLOCK_SIZE)].to_vec();
+        rand::thread_rng().fill(&mut nonce);
 
-            result.append(
-                &mut public_key
-                    .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &block)
-                    .map_app_err(|e| AppError {
-                        message: "failed to perform a RSA 4096 encryption".to_owned(),
-                        error: Some(e.to_string()),
-                        attr: None,
-                    })?,
-            );
+        let cipher = aes_gcm::Aes256Gcm::new_from_slice(&key).map_app_err(|e| AppError {
+            message: "failed to create a AES GCM object".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
 
-            idx += BLOCK_SIZE;
-        }
+        let encrypted = cipher
+            .encrypt(aes_gcm::Nonce::from_slice(&nonce), plain)
+            .map_app_err(|e| AppError {
+                message: "failed to perform a ECIES encryption".to_owned(),
+                error: Some(e.to_string()),
+                attr: None,
+                code: None,
+            })?;
+
+        let mut result = vec![EC_SCHEME_TAG];
+
+        result.extend_from_slice(ephemeral_public_key.as_bytes());
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&encrypted);
 
         Ok(result)
-    } else {
-        Ok(public_key
-            .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, plain)
+    }
+
+    #[inline]
+    fn aes_decrypt(&self, encrypted: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+        let key = &self.keys.aes_key;
+        let iv = &self.keys.aes_iv;
+
+        let cipher = aes_gcm::Aes256Gcm::new_from_slice(key).map_app_err(|e| AppError {
+            message: "failed to create a AES GCM object".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+        let nonce = aes_gcm::Nonce::from_slice(&iv[0..12]);
+
+        Ok(cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: encrypted,
+                    aad,
+                },
+            )
+            .map_app_err(|e| AppError {
+                message: "failed to perform a AES GCM decryption".to_owned(),
+                error: Some(e.to_string()),
+                attr: None,
+                code: None,
+            })?)
+    }
+
+    #[inline]
+    fn aes_encrypt(&self, plain: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+        let key = &self.keys.aes_key;
+        let iv = &self.keys.aes_iv;
+
+        let cipher = aes_gcm::Aes256Gcm::new_from_slice(key).map_app_err(|e| AppError {
+            message: "failed to create a a AES GCM object".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+        let nonce = aes_gcm::Nonce::from_slice(&iv[0..12]);
+
+        Ok(cipher
+            .encrypt(nonce, Payload { msg: plain, aad })
             .map_app_err(|e| AppError {
-                message: "failed to perform a RSA 4096 encryption".to_owned(),
+                message: "failed to perform a AES GCM encryption".to_owned(),
                 error: Some(e.to_string()),
                 attr: None,
+                code: None,
             })?)
     }
+
+    /// Round-trips a known value through every configured key pair, so keys
+    /// from different pairs are caught before a secret is stored with them.
+    pub fn self_test(&self) -> AppResult<()> {
+        let mismatched = |pair: &str| AppError {
+            message: "secrets self-test failed".to_owned(),
+            error: Some(format!(
+                "the {pair} private key doesn't decrypt what its public key encrypts"
+            )),
+            attr: None,
+            code: None,
+        };
+
+        /* the blobs are decrypted without their tag, it's only there to tell
+        the schemes apart */
+        if self.keys.rsa.is_some() {
+            let encrypted = self.rsa_encrypt(SELF_TEST_VALUE)?;
+
+            if self.rsa_decrypt(&encrypted[1..]).ok().as_deref() != Some(SELF_TEST_VALUE) {
+                return Err(mismatched("RSA"));
+            }
+        }
+
+        if self.keys.ec.is_some() {
+            let encrypted = self.ec_encrypt(SELF_TEST_VALUE)?;
+
+            if self.ec_decrypt(&encrypted[1..]).ok().as_deref() != Some(SELF_TEST_VALUE) {
+                return Err(mismatched("EC"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `aad` must be the one the secret was encrypted with, see `context`.
+    pub fn decrypt(&self, encrypted: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+        if encrypted.is_empty() {
+            return Err(AppError {
+                message: "provided empty data for decryption".to_owned(),
+                error: None,
+                attr: None,
+                code: None,
+            });
+        }
+
+        /* the RSA blobs written before the schemes were tagged have no tag,
+        they're whole RSA blocks, while a tagged RSA blob is one byte over */
+        let legacy_rsa = encrypted.len().is_multiple_of(RSA_BLOCK_SIZE);
+
+        let data = match encrypted[0] {
+            RSA_SCHEME_TAG if !legacy_rsa => self.rsa_decrypt(&encrypted[1..])?,
+            /* a legacy RSA blob may start with the tag as well, AES GCM
+            authentication tells them apart */
+            EC_SCHEME_TAG => match self.ec_decrypt(&encrypted[1..]) {
+                Err(_) if legacy_rsa => self.rsa_decrypt(encrypted)?,
+                result => result?,
+            },
+            _ if legacy_rsa => self.rsa_decrypt(encrypted)?,
+            _ => {
+                return Err(AppError {
+                    message: "failed to decrypt".to_owned(),
+                    error: Some("unknown scheme".to_owned()),
+                    attr: Some(hashmap! {
+                        "tag".to_owned() => format!("{}", encrypted[0])
+                    }),
+                    code: None,
+                })
+            }
+        };
+
+        self.aes_decrypt(&data, aad)
+    }
+
+    pub fn encrypt(&self, plain: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+        if plain.is_empty() {
+            return Err(AppError {
+                message: "provided empty data for encryption".to_owned(),
+                error: None,
+                attr: None,
+                code: None,
+            });
+        }
+
+        let data = self.aes_encrypt(plain, aad)?;
+
+        match self.scheme {
+            config::SecretsScheme::Rsa => self.rsa_encrypt(&data),
+            config::SecretsScheme::Ec => self.ec_encrypt(&data),
+        }
+    }
 }
 
-#[inline]
-fn aes_decrypt(encrypted: &[u8]) -> AppResult<Vec<u8>> {
-    let key = unsafe { AES_KEY.clone().expect("secrets.rs hasn't been initialized") };
+/// The most secret bytes one request may carry in, or take out.
+pub fn max_secret_bytes() -> usize {
+    config::get_clone()
+        .secrets
+        .max_secret_bytes
+        .unwrap_or(DEFAULT_MAX_SECRET_BYTES)
+}
 
-    let iv = unsafe { AES_IV.clone().expect("secrets.rs hasn't been initialized") };
+pub fn initialize() -> AppResult<()> {
+    let config_clone = config::get_clone();
+    let engine = SecretsEngine::load(&config_clone.secrets)?;
 
-    let cipher = aes_gcm::Aes256Gcm::new_from_slice(&key).map_app_err(|e| AppError {
-        message: "failed to create a AES GCM object".to_owned(),
-        error: Some(e.to_string()),
-        attr: None,
-    })?;
+    if config_clone.secrets.self_test.unwrap_or(true) {
+        engine.self_test()?;
+    }
 
-    let nonce = aes_gcm::Nonce::from_slice(&iv[0..12]);
+    unsafe {
+        ENGINE = Some(engine);
+    }
 
-    Ok(cipher.decrypt(nonce, encrypted).map_app_err(|e| AppError {
-        message: "failed to perform a AES GCM decryption".to_owned(),
-        error: Some(e.to_string()),
-        attr: None,
-    })?)
+    Ok(())
 }
 
 #[inline]
-fn aes_encrypt(plain: &[u8]) -> AppResult<Vec<u8>> {
-    let key = unsafe { AES_KEY.clone().expect("secrets.rs hasn't been initialized") };
-
-    let iv = unsafe { AES_IV.clone().expect("secrets.rs hasn't been initialized") };
+fn engine() -> &'static SecretsEngine {
+    unsafe { ENGINE.as_ref().expect("secrets.rs hasn't been initialized") }
+}
 
-    let cipher = aes_gcm::Aes256Gcm::new_from_slice(&key).map_app_err(|e| AppError {
-        message: "failed to create a a AES GCM object".to_owned(),
-        error: Some(e.to_string()),
-        attr: None,
-    })?;
+/// The additional authenticated data a secret is sealed with, so its ciphertext
+/// can't be moved under another name. Names can't hold a '|'.
+pub fn context(vault: &str, secret_name: &str) -> Vec<u8> {
+    format!("{vault}||{secret_name}").into_bytes()
+}
 
-    let nonce = aes_gcm::Nonce::from_slice(&iv[0..12]);
+pub fn decrypt(vault: &str, secret_name: &str, encrypted: &[u8]) -> AppResult<Vec<u8>> {
+    engine().decrypt(encrypted, &context(vault, secret_name))
+}
 
-    Ok(cipher.encrypt(nonce, plain).map_app_err(|e| AppError {
-        message: "failed to perform a AES GCM encryption".to_owned(),
-        error: Some(e.to_string()),
-        attr: None,
-    })?)
+pub fn encrypt(vault: &str, secret_name: &str, plain: &[u8]) -> AppResult<Vec<u8>> {
+    engine().encrypt(plain, &context(vault, secret_name))
 }
 
-pub fn decrypt(encrypted: &[u8]) -> AppResult<Vec<u8>> {
-    if encrypted.is_empty() {
-        return Err(AppError {
-            message: "provided empty data for decryption".to_owned(),
-            error: None,
-            attr: None,
+/// Seals the secrets stored before they were bound to their vault and name,
+/// once the engine is initialized. Those were encrypted without AAD, which
+/// AES GCM takes as an empty one.
+pub fn bind_legacy() -> AppResult<()> {
+    let seal = |vault: &str, secret_name: &str, secret: &str| {
+        let secret = base64_simd::STANDARD
+            .decode_to_vec(secret)
+            .map_app_err(|e| AppError {
+                message: "failed to decode the secret".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "secret".to_owned() => secret_name.to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                }),
+                code: None,
+            })?;
+
+        let secret = encrypt(vault, secret_name, &engine().decrypt(&secret, b"")?)?;
+
+        Ok(base64_simd::STANDARD.encode_to_string(secret))
+    };
+
+    let sealed = db::secret::bind_legacy(&seal)?;
+
+    if sealed > 0 {
+        log!({
+            mod: log::Module::Secrets,
+            ctx: "initializing",
+            msg: "sealed the secrets stored before they were bound to their names",
+            tags: ["secrets"],
+            attr: {
+                "secrets": sealed
+            }
         });
     }
 
-    let data = rsa_decrypt(encrypted)?;
+    Ok(())
+}
+
+/// Re-encrypts a stored secret under its vault's new name.
+pub fn rebind(vault: &str, new_vault: &str, secret_name: &str, secret: &str) -> AppResult<String> {
+    let secret = base64_simd::STANDARD
+        .decode_to_vec(secret)
+        .map_app_err(|e| AppError {
+            message: "failed to decode the secret".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "secret".to_owned() => secret_name.to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+            }),
+            code: None,
+        })?;
+
+    let secret = encrypt(
+        new_vault,
+        secret_name,
+        &decrypt(vault, secret_name, &secret)?,
+    )?;
 
-    Ok(aes_decrypt(&data)?)
+    Ok(base64_simd::STANDARD.encode_to_string(secret))
 }
 
-pub fn encrypt(plain: &[u8]) -> AppResult<Vec<u8>> {
-    if plain.is_empty() {
-        return Err(AppError {
-            message: "provided empty data for encryption".to_owned(),
-            error: None,
+/// Decodes and decrypts a throwaway secret the way a stored one is, so a
+/// lookup that finds nothing can take as long as one that does.
+pub fn decrypt_decoy() -> AppResult<()> {
+    static DECOY: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+    let decoy = match DECOY.get() {
+        Some(value) => value,
+        None => {
+            let value =
+                base64_simd::STANDARD.encode_to_string(encrypt("", "", b"vaulty decoy secret")?);
+
+            DECOY.get_or_init(|| value)
+        }
+    };
+
+    let decoy = base64_simd::STANDARD
+        .decode_to_vec(decoy)
+        .map_app_err(|e| AppError {
+            message: "failed to decode the decoy secret".to_owned(),
+            error: Some(e.to_string()),
             attr: None,
-        });
-    }
+            code: None,
+        })?;
+
+    decrypt("", "", &decoy)?;
+
+    Ok(())
+}
+
+pub fn etag(encrypted: &str) -> String {
+    openssl::sha::sha256(encrypted.as_bytes())
+        .iter()
+        .map(|v| format!("{v:02x}"))
+        .collect()
+}
 
-    let data = aes_encrypt(plain)?;
+/* whether a decrypted value hashes to the hex digest a client already holds */
+pub fn matches_sha256(value: &[u8], expected: &str) -> bool {
+    let digest: String = openssl::sha::sha256(value)
+        .iter()
+        .map(|v| format!("{v:02x}"))
+        .collect();
+    let expected = expected.trim().to_ascii_lowercase();
 
-    Ok(rsa_encrypt(&data)?)
+    digest.len() == expected.len() && openssl::memcmp::eq(digest.as_bytes(), expected.as_bytes())
 }
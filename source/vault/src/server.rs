@@ -18,12 +18,12 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 use maplit::hashmap;
 
 use crate::{
-    api,
-    app_error::{AppError, AppErrorResult, AppResult},
-    config, log, vault,
+    access_keys, api,
+    app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
+    config, db, log, secrets, vault,
 };
 
-fn load_cert(filename: String) -> AppResult<Vec<rustls::Certificate>> {
+pub fn load_cert(filename: String) -> AppResult<Vec<rustls::Certificate>> {
     let mut result = Vec::new();
 
     let file = std::fs::OpenOptions::new()
@@ -36,6 +36,7 @@ fn load_cert(filename: String) -> AppResult<Vec<rustls::Certificate>> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })?;
 
     let reader = &mut std::io::BufReader::new(file);
@@ -45,6 +46,7 @@ fn load_cert(filename: String) -> AppResult<Vec<rustls::Certificate>> {
         attr: Some(hashmap! {
             "filename".to_owned() => filename.to_owned(),
         }),
+        code: None,
     })?;
 
     for cert in certs {
@@ -54,7 +56,7 @@ fn load_cert(filename: String) -> AppResult<Vec<rustls::Certificate>> {
     Ok(result)
 }
 
-fn load_key(filename: String) -> AppResult<rustls::PrivateKey> {
+pub fn load_key(filename: String) -> AppResult<rustls::PrivateKey> {
     let file = std::fs::OpenOptions::new()
         .read(true)
         .write(false)
@@ -65,103 +67,590 @@ fn load_key(filename: String) -> AppResult<rustls::PrivateKey> {
             attr: Some(hashmap! {
                 "filename".to_owned() => filename.to_owned(),
             }),
+            code: None,
         })?;
 
     let reader = &mut std::io::BufReader::new(file);
-    let key = rustls_pemfile::pkcs8_private_keys(reader).map_app_err(|e| AppError {
+    let items = rustls_pemfile::read_all(reader).map_app_err(|e| AppError {
         message: "failed to load the private key".to_owned(),
         error: Some(e.to_string()),
         attr: Some(hashmap! {
             "filename".to_owned() => filename.to_owned(),
         }),
+        code: None,
     })?;
 
-    if key.is_empty() {
-        Err(AppError {
-            message: "failed to load the private key".to_owned(),
-            error: Some("missing key".to_owned()),
+    for item in items {
+        let key = match item {
+            rustls_pemfile::Item::PKCS8Key(key) => rustls::PrivateKey(key),
+            rustls_pemfile::Item::RSAKey(key) => rustls::PrivateKey(key),
+            rustls_pemfile::Item::ECKey(key) => rustls::PrivateKey(key),
+            _ => continue,
+        };
+
+        if rustls::sign::any_supported_type(&key).is_ok() {
+            return Ok(key);
+        }
+    }
+
+    Err(AppError {
+        message: "failed to load the private key".to_owned(),
+        error: Some("no usable PKCS#8, PKCS#1 (RSA) or SEC1 (EC) private key".to_owned()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned(),
+        }),
+        code: None,
+    })
+}
+
+fn parse_cert(
+    certificate: &rustls::Certificate,
+    tls: &config::ConfigServerTls,
+) -> AppResult<openssl::x509::X509> {
+    openssl::x509::X509::from_der(&certificate.0).map_app_err(|e| AppError {
+        message: "failed to parse the certificate".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => tls.certificate.clone(),
+        }),
+        code: None,
+    })
+}
+
+pub fn verify_chain(
+    chain: &[rustls::Certificate],
+    key: &rustls::PrivateKey,
+    tls: &config::ConfigServerTls,
+) -> AppResult<()> {
+    let private_key =
+        openssl::pkey::PKey::private_key_from_der(&key.0).map_app_err(|e| AppError {
+            message: "failed to parse the private key".to_owned(),
+            error: Some(e.to_string()),
             attr: Some(hashmap! {
-                "filename".to_owned() => filename.to_owned(),
+                "filename".to_owned() => tls.key.clone(),
             }),
-        })
+            code: None,
+        })?;
+
+    let mut parsed_chain = Vec::new();
+
+    for certificate in chain {
+        parsed_chain.push(parse_cert(certificate, tls)?);
+    }
+
+    let matches_key = |certificate: &openssl::x509::X509| {
+        certificate
+            .public_key()
+            .map(|v| v.public_eq(&private_key))
+            .unwrap_or(false)
+    };
+
+    let leaf = parsed_chain.first().context_app_err(|| AppError {
+        message: "failed to load the certificate".to_owned(),
+        error: Some("missing certificate".to_owned()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => tls.certificate.clone(),
+        }),
+        code: None,
+    })?;
+
+    if !matches_key(leaf) {
+        return Err(AppError {
+            message: "the leaf certificate doesn't match the private key".to_owned(),
+            error: if parsed_chain.iter().skip(1).any(matches_key) {
+                Some(
+                    "the matching certificate is not first, the chain must be leaf-first"
+                        .to_owned(),
+                )
+            } else {
+                None
+            },
+            attr: Some(hashmap! {
+                "certificate".to_owned() => tls.certificate.clone(),
+                "key".to_owned() => tls.key.clone(),
+            }),
+            code: None,
+        });
+    }
+
+    for (position, pair) in parsed_chain.windows(2).enumerate() {
+        if pair[1].issued(&pair[0]) != openssl::x509::X509VerifyResult::OK {
+            return Err(AppError {
+                message: "the certificate chain is not in leaf-first order".to_owned(),
+                error: Some(format!(
+                    "certificate {} is not the issuer of certificate {}",
+                    position + 1,
+                    position
+                )),
+                attr: Some(hashmap! {
+                    "filename".to_owned() => tls.certificate.clone(),
+                }),
+                code: None,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn load_ocsp(filename: &str) -> AppResult<Vec<u8>> {
+    std::fs::read(filename).map_app_err(|e| AppError {
+        message: "failed to read the OCSP response".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned(),
+        }),
+        code: None,
+    })
+}
+
+fn load_certified_key(tls: &config::ConfigServerTls) -> AppResult<rustls::sign::CertifiedKey> {
+    let tls_cert = load_cert(tls.certificate.clone())?;
+    let tls_key = load_key(tls.key.clone())?;
+
+    verify_chain(&tls_cert, &tls_key, tls)?;
+
+    let signing_key = rustls::sign::any_supported_type(&tls_key).map_app_err(|e| AppError {
+        message: "failed to load the private key".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => tls.key.clone(),
+        }),
+        code: None,
+    })?;
+
+    let mut certified_key = rustls::sign::CertifiedKey::new(tls_cert, signing_key);
+
+    if let Some(ocsp) = &tls.ocsp {
+        certified_key.ocsp = Some(load_ocsp(ocsp)?);
+    }
+
+    Ok(certified_key)
+}
+
+struct CertificateResolver {
+    certified_key: std::sync::RwLock<std::sync::Arc<rustls::sign::CertifiedKey>>,
+}
+
+impl rustls::server::ResolvesServerCert for CertificateResolver {
+    fn resolve(
+        &self,
+        _client_hello: rustls::server::ClientHello,
+    ) -> Option<std::sync::Arc<rustls::sign::CertifiedKey>> {
+        self.certified_key.read().ok().map(|v| v.clone())
+    }
+}
+
+fn reload_certificate(resolver: &CertificateResolver, tls: &config::ConfigServerTls) {
+    match load_certified_key(tls) {
+        Ok(certified_key) => {
+            if let Ok(mut current) = resolver.certified_key.write() {
+                *current = std::sync::Arc::new(certified_key);
+            }
+
+            log!({
+                mod: log::Module::Server,
+                ctx: "server runtime",
+                msg: "TLS certificate reloaded",
+                tags: [
+                    "server", "tls"
+                ],
+                attr: {
+                    certificate: tls.certificate.clone(),
+                    key: tls.key.clone()
+                }
+            });
+        }
+        Err(e) => log!({
+            mod: log::Module::Server,
+            ctx: "server runtime",
+            msg: "failed to reload the TLS certificate, keeping the current one",
+            err: e,
+            tags: [
+                "server", "tls", "error"
+            ],
+            attr: {
+                certificate: tls.certificate.clone(),
+                key: tls.key.clone()
+            }
+        }),
+    }
+}
+
+fn files_modified(tls: &config::ConfigServerTls) -> Option<Vec<std::time::SystemTime>> {
+    let mut result = Vec::new();
+
+    for filename in [Some(&tls.certificate), Some(&tls.key), tls.ocsp.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        result.push(
+            std::fs::metadata(filename)
+                .and_then(|v| v.modified())
+                .ok()?,
+        );
+    }
+
+    Some(result)
+}
+
+fn watch_certificate(resolver: std::sync::Arc<CertificateResolver>, tls: config::ConfigServerTls) {
+    tokio::spawn(async move {
+        #[cfg(not(target_os = "windows"))]
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log!({
+                    mod: log::Module::Server,
+                    ctx: "server initializing",
+                    msg: "failed to listen for SIGHUP",
+                    err: AppError {
+                        message: e.to_string(),
+                        error: None,
+                        attr: None,
+                        code: None
+                    },
+                    tags: [
+                        "server", "tls", "error"
+                    ]
+                });
+
+                None
+            }
+        };
+
+        let mut interval = tls
+            .reload_interval_secs
+            .map(|v| tokio::time::interval(tokio::time::Duration::from_secs(v.max(1))));
+        let mut last_modified = files_modified(&tls);
+
+        if let Some(interval) = interval.as_mut() {
+            interval.tick().await;
+        }
+
+        loop {
+            #[cfg(not(target_os = "windows"))]
+            let hangup_received = async {
+                match hangup.as_mut() {
+                    Some(hangup) => {
+                        hangup.recv().await;
+                    }
+                    None => futures::future::pending().await,
+                }
+            };
+
+            #[cfg(target_os = "windows")]
+            let hangup_received = futures::future::pending::<()>();
+
+            let interval_elapsed = async {
+                match interval.as_mut() {
+                    Some(interval) => {
+                        interval.tick().await;
+                    }
+                    None => futures::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = hangup_received => {
+                    last_modified = files_modified(&tls);
+                    reload_certificate(&resolver, &tls);
+                }
+                _ = interval_elapsed => {
+                    let modified = files_modified(&tls);
+
+                    if modified.is_some() && modified != last_modified {
+                        last_modified = modified;
+                        reload_certificate(&resolver, &tls);
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(target_os = "windows"))]
+fn remove_stale_socket(path: &str) -> AppResult<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    /* a socket left behind by a previous run would fail the bind */
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            std::fs::remove_file(path).map_app_err(|e| AppError {
+                message: "failed to remove the stale Unix socket".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "bind_address".to_owned() => path.to_owned(),
+                }),
+                code: None,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+const DEFAULT_HSTS_MAX_AGE_SECS: u64 = 365 * 24 * 60 * 60;
+
+/* a year unless configured, a max age of 0 leaves HSTS out */
+fn hsts_header(tls: &config::ConfigServerTls) -> Option<String> {
+    let max_age = tls.hsts_max_age_secs.unwrap_or(DEFAULT_HSTS_MAX_AGE_SECS);
+
+    if max_age == 0 {
+        return None;
+    }
+
+    if tls.hsts_include_subdomains.unwrap_or(false) {
+        Some(format!("max-age={}; includeSubDomains", max_age))
     } else {
-        Ok(rustls::PrivateKey(key[0].clone()))
+        Some(format!("max-age={}", max_age))
+    }
+}
+
+/* set for good once the audit log can't be written, see log.on_write_failure */
+static MAINTENANCE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Answers every request with 503 Service Unavailable from now on.
+pub fn enter_maintenance() {
+    MAINTENANCE.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+pub fn in_maintenance() -> bool {
+    MAINTENANCE.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+async fn maintenance(
+    req: actix_web::dev::ServiceRequest,
+    next: actix_web::middleware::Next<impl actix_web::body::MessageBody>,
+) -> Result<
+    actix_web::dev::ServiceResponse<actix_web::body::EitherBody<impl actix_web::body::MessageBody>>,
+    actix_web::Error,
+> {
+    if !in_maintenance() {
+        return next.call(req).await.map(|v| v.map_into_left_body());
+    }
+
+    Ok(req
+        .into_response(
+            actix_web::HttpResponse::ServiceUnavailable()
+                .content_type("application/json")
+                .body(r#"{"status":"maintenance"}"#),
+        )
+        .map_into_right_body())
+}
+
+/* HSTS only means something over TLS, so a plaintext bind never sends it */
+fn security_headers(
+    security_headers: bool,
+    hsts: Option<&str>,
+) -> actix_web::middleware::DefaultHeaders {
+    let headers = actix_web::middleware::DefaultHeaders::new();
+
+    if !security_headers {
+        return headers;
+    }
+
+    let headers = headers
+        .add((actix_web::http::header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .add((actix_web::http::header::CACHE_CONTROL, "no-store"));
+
+    match hsts {
+        Some(value) => headers.add((
+            actix_web::http::header::STRICT_TRANSPORT_SECURITY,
+            value.to_owned(),
+        )),
+        None => headers,
     }
 }
 
+/* "/v1/" and "v1" both become "/v1", no prefix at all is an empty scope */
+fn path_prefix(server_config: &config::ConfigServer) -> AppResult<String> {
+    let prefix = match &server_config.path_prefix {
+        Some(value) => value.trim().trim_end_matches('/'),
+        None => return Ok(String::new()),
+    };
+
+    if prefix.is_empty() {
+        return Ok(String::new());
+    }
+
+    let prefix = if prefix.starts_with('/') {
+        prefix.to_owned()
+    } else {
+        format!("/{}", prefix)
+    };
+
+    if prefix.contains(['{', '}', '?', '#']) || prefix.contains(char::is_whitespace) {
+        return Err(AppError {
+            message: "invalid REST path prefix".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "path_prefix".to_owned() => prefix,
+            }),
+            code: None,
+        });
+    }
+
+    Ok(prefix)
+}
+
+/// How `curl` reaches the REST API of a server started with `server_config`.
+pub fn curl_target(server_config: &config::ConfigServer) -> String {
+    let prefix = path_prefix(server_config).unwrap_or_default();
+
+    if let Some(path) = &server_config.unix_socket {
+        return format!("--unix-socket {} http://localhost{}", path, prefix);
+    }
+
+    let scheme = match server_config.tls {
+        Some(_) => "https",
+        None => "http",
+    };
+
+    let host = match server_config.listen_address.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V6(ip)) => format!("[{}]", ip),
+        _ => server_config.listen_address.clone(),
+    };
+
+    format!(
+        "{}://{}:{}{}",
+        scheme, host, server_config.listen_port, prefix
+    )
+}
+
 pub async fn start() -> AppResult<()> {
-    let config_clone = config::get_clone();
-    let friendly_listen_address = format!(
-        "{}:{}",
-        config_clone.server.listen_address, config_clone.server.listen_port
-    );
+    serve(config::get_clone().server).await
+}
+
+pub async fn serve(server_config: config::ConfigServer) -> AppResult<()> {
+    let friendly_listen_address = match &server_config.unix_socket {
+        Some(path) => format!("unix:{}", path),
+        None => format!(
+            "{}:{}",
+            server_config.listen_address, server_config.listen_port
+        ),
+    };
+
+    if server_config.unix_socket.is_some() && server_config.tls.is_some() {
+        return Err(AppError {
+            message: "TLS isn't supported on a Unix socket".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "bind_address".to_owned() => friendly_listen_address,
+            }),
+            code: None,
+        });
+    }
+
+    let certificate_resolver = if let Some(tls) = &server_config.tls {
+        let resolver = std::sync::Arc::new(CertificateResolver {
+            certified_key: std::sync::RwLock::new(std::sync::Arc::new(load_certified_key(tls)?)),
+        });
+
+        watch_certificate(resolver.clone(), tls.clone());
+
+        Some(resolver)
+    } else {
+        None
+    };
+
+    let path_prefix = path_prefix(&server_config)?;
+    let send_security_headers = server_config.security_headers.unwrap_or(true);
+    let admin_requires_tls = server_config.admin_requires_tls.unwrap_or(false);
+    let session_limit = api::SessionLimit::new(server_config.max_sessions);
+    let max_body_bytes = secrets::max_secret_bytes();
+    let hsts = match (&server_config.tls, server_config.unix_socket.is_some()) {
+        (Some(tls), false) => hsts_header(tls),
+        _ => None,
+    };
+
+    vault::watch_idempotency_keys();
+    vault::watch_deleted_secrets();
+    vault::watch_uploads();
+    access_keys::watch_stale();
+    db::compaction::watch();
 
     loop {
-        let server = if let Some(tls) = &config_clone.server.tls {
-            let tls_cert = load_cert(tls.certificate.clone())?;
-            let tls_key = load_key(tls.key.clone())?;
+        let path_prefix = path_prefix.clone();
+        let hsts = hsts.clone();
+        let session_limit = session_limit.clone();
+
+        let http_server = actix_web::HttpServer::new(move || {
+            /* the websocket and the health check stay at the root, only the REST API moves */
+            actix_web::App::new()
+                /* a larger body is refused with 413 before it's buffered up for a handler */
+                .app_data(actix_web::web::PayloadConfig::new(max_body_bytes))
+                .app_data(api::AdminRequiresTls(admin_requires_tls))
+                .app_data(session_limit.clone())
+                .wrap(security_headers(send_security_headers, hsts.as_deref()))
+                .wrap(actix_web::middleware::from_fn(maintenance))
+                .wrap(actix_web::middleware::from_fn(vault::access_log))
+                .service(api::web_socket)
+                .service(vault::req_health)
+                .service(
+                    actix_web::web::scope(&path_prefix)
+                        .service(vault::req_list)
+                        .service(vault::req_get)
+                        .service(vault::req_head)
+                        .service(vault::req_post)
+                        .service(vault::req_put)
+                        .service(vault::req_delete)
+                        .service(vault::req_upload_start)
+                        .service(vault::req_upload_append)
+                        .service(vault::req_upload_complete),
+                )
+                .default_service(actix_web::web::to(vault::not_found))
+        })
+        .keep_alive(actix_web::http::KeepAlive::Disabled)
+        .backlog(u32::MAX);
+
+        let http_server = if let Some(path) = &server_config.unix_socket {
+            #[cfg(not(target_os = "windows"))]
+            {
+                remove_stale_socket(path)?;
+
+                http_server.bind_uds(path)
+            }
+
+            #[cfg(target_os = "windows")]
+            {
+                let _ = path;
 
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Unix sockets aren't supported on Windows",
+                ))
+            }
+        } else if let Some(resolver) = &certificate_resolver {
             let server_tls_config = rustls::ServerConfig::builder()
                 .with_safe_defaults()
                 .with_no_client_auth()
-                .with_single_cert(tls_cert, tls_key)
-                .map_app_err(|e| AppError {
-                    message: "failed to build the TLS config".to_owned(),
-                    error: Some(e.to_string()),
-                    attr: None,
-                })?;
-
-            actix_web::HttpServer::new(|| {
-                actix_web::App::new()
-                    .service(api::web_socket)
-                    .service(vault::req_list)
-                    .service(vault::req_get)
-                    .service(vault::req_post)
-                    .service(vault::req_put)
-                    .service(vault::req_delete)
-            })
-            .keep_alive(actix_web::http::KeepAlive::Disabled)
-            .backlog(u32::MAX)
-            .bind_rustls(
+                .with_cert_resolver(resolver.clone());
+
+            http_server.bind_rustls(
                 (
-                    config_clone.server.listen_address.clone(),
-                    config_clone.server.listen_port,
+                    server_config.listen_address.clone(),
+                    server_config.listen_port,
                 ),
                 server_tls_config,
             )
-            .map_app_err(|e| AppError {
-                message: "failed to run the HTTP server".to_owned(),
-                error: Some(e.to_string()),
-                attr: Some(hashmap! {
-                    "bind_address".to_owned() => friendly_listen_address.clone(),
-                }),
-            })?
-            .run()
         } else {
-            actix_web::HttpServer::new(|| {
-                actix_web::App::new()
-                    .service(api::web_socket)
-                    .service(vault::req_list)
-                    .service(vault::req_get)
-                    .service(vault::req_post)
-                    .service(vault::req_put)
-                    .service(vault::req_delete)
-            })
-            .keep_alive(actix_web::http::KeepAlive::Disabled)
-            .backlog(u32::MAX)
-            .bind((
-                config_clone.server.listen_address.clone(),
-                config_clone.server.listen_port,
+            http_server.bind((
+                server_config.listen_address.clone(),
+                server_config.listen_port,
             ))
+        };
+
+        let server = http_server
             .map_app_err(|e| AppError {
                 message: "failed to run the HTTP server".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
                     "bind_address".to_owned() => friendly_listen_address.clone(),
                 }),
+                code: None,
             })?
-            .run()
-        };
+            .run();
 
         log!({
             mod: log::Module::Server,
@@ -184,7 +673,8 @@ pub async fn start() -> AppResult<()> {
                 err: AppError {
                     message: e.to_string(),
                     error: None,
-                    attr: None
+                    attr: None,
+                    code: None
                 },
                 tags: [
                     "init", "db", "error"
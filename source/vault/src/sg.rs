@@ -0,0 +1,72 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config;
+
+/* whether a client is matched against the security groups of both IP families */
+static mut DUAL_STACK: bool = false;
+
+pub fn initialize() {
+    let config_clone = config::get_clone();
+
+    unsafe {
+        DUAL_STACK = config_clone.server.dual_stack_sg.unwrap_or(false);
+    }
+}
+
+/// Expands the `any4` and `any6` shorthands, any other security group is
+/// returned as it is.
+pub fn expand(sg: &str) -> &str {
+    match sg {
+        "any4" => "0.0.0.0/0",
+        "any6" => "::/0",
+        _ => sg,
+    }
+}
+
+/* the same client seen through the other family: an IPv4-mapped address
+and its IPv4 one, or either loopback */
+fn other_family(ip: std::net::IpAddr) -> Option<std::net::IpAddr> {
+    match ip {
+        std::net::IpAddr::V4(ip) if ip.is_loopback() => {
+            Some(std::net::IpAddr::V6(std::net::Ipv6Addr::LOCALHOST))
+        }
+        std::net::IpAddr::V4(ip) => Some(std::net::IpAddr::V6(ip.to_ipv6_mapped())),
+        std::net::IpAddr::V6(ip) if ip.is_loopback() => {
+            Some(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST))
+        }
+        std::net::IpAddr::V6(ip) => ip.to_ipv4_mapped().map(std::net::IpAddr::V4),
+    }
+}
+
+/// Whether the security group holds the client, also through the other IP
+/// family when `dual_stack` is set.
+pub fn contains_with(
+    network: &ipnetwork::IpNetwork,
+    ip: std::net::IpAddr,
+    dual_stack: bool,
+) -> bool {
+    if network.contains(ip) {
+        return true;
+    }
+
+    dual_stack && other_family(ip).is_some_and(|ip| network.contains(ip))
+}
+
+pub fn contains(network: &ipnetwork::IpNetwork, ip: std::net::IpAddr) -> bool {
+    contains_with(network, ip, unsafe { DUAL_STACK })
+}
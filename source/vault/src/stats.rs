@@ -0,0 +1,201 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use crate::clock;
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+static SESSIONS: Mutex<BTreeMap<u64, Arc<SessionStats>>> = Mutex::new(BTreeMap::new());
+static ACCESS_KEYS: Mutex<BTreeMap<(String, String), Arc<Counters>>> = Mutex::new(BTreeMap::new());
+/* milliseconds since the epoch of the last request or command, 0 when there was none */
+static LAST_ACTIVITY_MILLIS: AtomicI64 = AtomicI64::new(0);
+
+/* the counters stay usable even if a thread panicked while holding the lock */
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, Default)]
+pub struct Counters {
+    requests: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct CountersSnapshot {
+    pub requests: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+impl Counters {
+    pub fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        LAST_ACTIVITY_MILLIS.store(clock::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CountersSnapshot {
+        CountersSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SessionStats {
+    id: u64,
+    ip: String,
+    username: Mutex<Option<String>>,
+    connected: String,
+    last_command: Mutex<Option<String>>,
+    kill: Arc<tokio::sync::Notify>,
+    counters: Counters,
+}
+
+/// An open session as an admin sees it in `ListSessions`.
+#[derive(Debug, Clone)]
+pub struct SessionSnapshot {
+    pub id: u64,
+    pub ip: String,
+    pub username: Option<String>,
+    pub connected: String,
+    pub last_command: Option<String>,
+}
+
+/* keeps the session listed while it's open */
+pub struct SessionEntry(Arc<SessionStats>);
+
+impl SessionEntry {
+    pub fn register(ip: &std::net::IpAddr) -> SessionEntry {
+        let stats = Arc::new(SessionStats {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            ip: ip.to_string(),
+            username: Mutex::new(None),
+            connected: clock::now().to_rfc3339(),
+            last_command: Mutex::new(None),
+            kill: Arc::new(tokio::sync::Notify::new()),
+            counters: Counters::default(),
+        });
+
+        lock(&SESSIONS).insert(stats.id, stats.clone());
+
+        SessionEntry(stats)
+    }
+
+    pub fn set_username(&self, username: &str) {
+        *lock(&self.0.username) = Some(username.to_owned());
+    }
+
+    pub fn command_executed(&self) {
+        *lock(&self.0.last_command) = Some(clock::now().to_rfc3339());
+    }
+
+    /// Notified once an admin kills the session, it stays notified until
+    /// awaited.
+    pub fn kill_signal(&self) -> Arc<tokio::sync::Notify> {
+        self.0.kill.clone()
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.0.counters
+    }
+}
+
+impl Drop for SessionEntry {
+    fn drop(&mut self) {
+        lock(&SESSIONS).remove(&self.0.id);
+    }
+}
+
+pub fn access_key(vault: &str, access_key: &str) -> Arc<Counters> {
+    lock(&ACCESS_KEYS)
+        .entry((vault.to_owned(), access_key.to_owned()))
+        .or_default()
+        .clone()
+}
+
+pub fn sessions() -> Vec<(u64, String, Option<String>, CountersSnapshot)> {
+    lock(&SESSIONS)
+        .values()
+        .map(|v| {
+            (
+                v.id,
+                v.ip.clone(),
+                lock(&v.username).clone(),
+                v.counters.snapshot(),
+            )
+        })
+        .collect()
+}
+
+pub fn connected_sessions() -> Vec<SessionSnapshot> {
+    lock(&SESSIONS)
+        .values()
+        .map(|v| SessionSnapshot {
+            id: v.id,
+            ip: v.ip.clone(),
+            username: lock(&v.username).clone(),
+            connected: v.connected.clone(),
+            last_command: lock(&v.last_command).clone(),
+        })
+        .collect()
+}
+
+/* the session closes itself, its entry goes away when it does */
+pub fn kill_session(id: u64) -> bool {
+    match lock(&SESSIONS).get(&id) {
+        Some(v) => {
+            v.kill.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn access_keys() -> Vec<((String, String), CountersSnapshot)> {
+    lock(&ACCESS_KEYS)
+        .iter()
+        .map(|(k, v)| (k.clone(), v.snapshot()))
+        .collect()
+}
+
+/// When the last REST request or websocket command was counted.
+pub fn last_activity() -> Option<chrono::DateTime<chrono::Utc>> {
+    match LAST_ACTIVITY_MILLIS.load(Ordering::Relaxed) {
+        0 => None,
+        millis => chrono::DateTime::from_timestamp_millis(millis),
+    }
+}
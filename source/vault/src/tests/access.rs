@@ -0,0 +1,587 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, db, permission, vault};
+
+use super::{authorization as authorization_for, create_access_key, request, Client};
+
+#[tokio::test]
+async fn disabled_access_key() {
+    let authorization = create_access_key(
+        "disabled",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("POST", "/disabled/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "SetAccessKeyEnabled": {
+                "vault": "disabled",
+                "access_key": access_key,
+                "enabled": false
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Updated");
+
+    let response = request("GET", "/disabled/password", &headers, b"").await;
+    assert_eq!(response.status, 401);
+
+    let response = client
+        .send(serde_json::json!({
+            "FindAccessKey": {
+                "vault": "disabled",
+                "access_key": access_key
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["enabled"], false);
+    assert_eq!(response["Found"]["permission"][0], "CreateSecrets");
+
+    let response = client
+        .send(serde_json::json!({
+            "SetAccessKeyEnabled": {
+                "vault": "disabled",
+                "access_key": access_key,
+                "enabled": true
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Updated");
+
+    let response = request("GET", "/disabled/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
+
+#[test]
+fn existing_access_key_is_enabled() {
+    let mut document = r#"{
+        "secret_access_key": "",
+        "permission": ["ListSecrets"],
+        "sg": [],
+        "created": "2024-01-01T00:00:00+00:00",
+        "last_used": null
+    }"#
+    .to_owned();
+
+    let document: db::access::AccessKeyDocument =
+        unsafe { simd_json::from_str(&mut document) }.unwrap();
+
+    assert!(document.enabled);
+}
+
+#[tokio::test]
+async fn write_only_access_key() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": "drop-box",
+                "preset": "WriteOnly",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    let access_key = response["access_key"].as_str().unwrap();
+    let authorization = format!(
+        "VAULTY {}:{}",
+        access_key,
+        response["secret_access_key"].as_str().unwrap()
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/drop-box/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("PUT", "/drop-box/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+
+    for (method, path) in [
+        ("GET", "/drop-box/password"),
+        ("HEAD", "/drop-box/password"),
+        ("GET", "/drop-box"),
+        ("DELETE", "/drop-box/password"),
+    ] {
+        let response = request(method, path, &headers, b"").await;
+        assert_eq!(response.status, 403, "{method} {path}");
+    }
+
+    let document = db::access::find("drop-box", access_key).unwrap().unwrap();
+    assert_eq!(document.permission, [permission::VaultRoles::CreateSecrets]);
+}
+
+#[tokio::test]
+async fn forbidden_lists_granted_permissions() {
+    let authorization = create_access_key(
+        "forbidden-body",
+        vec![
+            permission::VaultRoles::ListSecrets,
+            permission::VaultRoles::CreateSecrets,
+        ],
+    );
+
+    let response = request(
+        "GET",
+        "/forbidden-body/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 403);
+
+    let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "granted": ["ListSecrets", "CreateSecrets"] })
+    );
+
+    /* nothing is disclosed before the secret access key checks out */
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+    let authorization = authorization_for(access_key, "wrong");
+
+    let response = request(
+        "GET",
+        "/forbidden-body/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 401);
+    assert!(response.body.is_empty());
+}
+
+#[tokio::test]
+async fn invalid_permission_combinations() {
+    let mut client = Client::login_root().await;
+
+    for request in [
+        serde_json::json!({
+            "vault": "combinations",
+            "permission": ["DeleteSecrets"],
+            "sg": ["127.0.0.1/32"]
+        }),
+        serde_json::json!({
+            "vault": "combinations",
+            "permission": ["DeleteSecrets", "CreateSecrets"],
+            "sg": ["127.0.0.1/32"]
+        }),
+        serde_json::json!({
+            "vault": "combinations",
+            "permission": [],
+            "sg": ["127.0.0.1/32"]
+        }),
+        serde_json::json!({
+            "vault": "combinations",
+            "permission": ["CreateSecrets"],
+            "preset": "WriteOnly",
+            "sg": ["127.0.0.1/32"]
+        }),
+    ] {
+        let response = client
+            .send(serde_json::json!({ "CreateAccessKey": request.clone() }))
+            .await;
+        assert!(response["error"].is_string(), "{request}");
+        assert_eq!(response["code"], "InvalidArgument", "{request}");
+    }
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": "combinations",
+                "permission": ["DeleteSecrets", "ListSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    let access_key = response["access_key"].as_str().unwrap().to_owned();
+
+    let response = client
+        .send(serde_json::json!({
+            "ChangePermissionForAccessKey": {
+                "vault": "combinations",
+                "access_key": access_key,
+                "permission": ["DeleteSecrets"]
+            }
+        }))
+        .await;
+    assert!(response["error"].is_string());
+
+    let document = db::access::find("combinations", &access_key)
+        .unwrap()
+        .unwrap();
+    assert_eq!(document.permission.len(), 2);
+}
+
+#[tokio::test]
+async fn rotated_secret_access_key() {
+    let authorization = create_access_key(
+        "rotated",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("POST", "/rotated/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "RotateAccessKeySecret": {
+                "vault": "rotated",
+                "access_key": access_key
+            }
+        }))
+        .await;
+    assert_eq!(response["Rotated"]["access_key"], access_key);
+
+    let rotated = authorization_for(
+        access_key,
+        response["Rotated"]["secret_access_key"].as_str().unwrap(),
+    );
+    assert_ne!(rotated, authorization);
+
+    let response = request("GET", "/rotated/password", &headers, b"").await;
+    assert_eq!(response.status, 401);
+
+    let headers = [("Authorization", rotated.as_str())];
+
+    let response = request("GET", "/rotated/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindAccessKey": {
+                "vault": "rotated",
+                "access_key": access_key
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["permission"][0], "CreateSecrets");
+    assert_eq!(response["Found"]["permission"][1], "DecryptSecrets");
+    assert_eq!(response["Found"]["sg"][0], "127.0.0.1/32");
+
+    let response = client
+        .send(serde_json::json!({
+            "RotateAccessKeySecret": {
+                "vault": "rotated",
+                "access_key": "missing"
+            }
+        }))
+        .await;
+    assert_eq!(response, "NotFound");
+}
+
+fn access_key_of(authorization: &str) -> String {
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    access_key.to_owned()
+}
+
+#[tokio::test]
+async fn delete_unused_access_keys() {
+    let used = create_access_key("bulk-delete", vec![permission::VaultRoles::CreateSecrets]);
+    let unused = create_access_key("bulk-delete", vec![permission::VaultRoles::CreateSecrets]);
+
+    let unused_since = clock::now().to_rfc3339();
+
+    let headers = [("Authorization", used.as_str())];
+    let response = request("POST", "/bulk-delete/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteAccessKeysWhere": {
+                "vault": "bulk-delete",
+                "unused_since": unused_since
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Deleted"]["access_keys"],
+        serde_json::json!([access_key_of(&unused)])
+    );
+
+    assert!(db::access::find("bulk-delete", &access_key_of(&unused))
+        .unwrap()
+        .is_none());
+    assert!(db::access::find("bulk-delete", &access_key_of(&used))
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        db::vault::find("bulk-delete")
+            .unwrap()
+            .unwrap()
+            .access_keys_count,
+        1
+    );
+}
+
+#[tokio::test]
+async fn delete_access_keys_without_filter() {
+    let authorization = create_access_key("bulk-guard", vec![permission::VaultRoles::ListSecrets]);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteAccessKeysWhere": {
+                "vault": "bulk-guard",
+                "unused_since": null,
+                "disabled": null,
+                "permission": null
+            }
+        }))
+        .await;
+    assert_eq!(response, "MissingFilter");
+
+    assert!(
+        db::access::find("bulk-guard", &access_key_of(&authorization))
+            .unwrap()
+            .is_some()
+    );
+}
+
+async fn create_with_expiry(client: &mut Client, vault: &str, expires_in_secs: u64) -> String {
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": vault,
+                "permission": ["CreateSecrets", "DecryptSecrets"],
+                "sg": ["127.0.0.1/32"],
+                "expires_in_secs": expires_in_secs
+            }
+        }))
+        .await;
+
+    authorization_for(
+        response["access_key"].as_str().unwrap(),
+        response["secret_access_key"].as_str().unwrap(),
+    )
+}
+
+async fn effective_status(client: &mut Client, vault: &str, access_key: &str) -> String {
+    let response = client
+        .send(serde_json::json!({
+            "FindAccessKey": {
+                "vault": vault,
+                "access_key": access_key
+            }
+        }))
+        .await;
+
+    response["Found"]["effective_status"]
+        .as_str()
+        .unwrap()
+        .to_owned()
+}
+
+async fn set_enabled(client: &mut Client, vault: &str, access_key: &str, enabled: bool) {
+    let response = client
+        .send(serde_json::json!({
+            "SetAccessKeyEnabled": {
+                "vault": vault,
+                "access_key": access_key,
+                "enabled": enabled
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Updated");
+}
+
+#[tokio::test]
+async fn effective_status_active_and_disabled() {
+    let mut client = Client::login_root().await;
+
+    /* far enough out that other tests moving the clock don't reach it */
+    let authorization =
+        create_with_expiry(&mut client, "status-disabled", 365 * 24 * 60 * 60).await;
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    assert_eq!(
+        effective_status(&mut client, "status-disabled", access_key).await,
+        "Active"
+    );
+
+    set_enabled(&mut client, "status-disabled", access_key, false).await;
+    assert_eq!(
+        effective_status(&mut client, "status-disabled", access_key).await,
+        "Disabled"
+    );
+
+    set_enabled(&mut client, "status-disabled", access_key, true).await;
+    assert_eq!(
+        effective_status(&mut client, "status-disabled", access_key).await,
+        "Active"
+    );
+
+    let response = client
+        .send(serde_json::json!({
+            "ListAccessKeys": {
+                "vault": "status-disabled"
+            }
+        }))
+        .await;
+    assert_eq!(response["access_keys"][0]["effective_status"], "Active");
+    assert!(response["access_keys"][0]["expires"].is_string());
+}
+
+#[tokio::test]
+async fn effective_status_expired() {
+    let mut client = Client::login_root().await;
+
+    let authorization = create_with_expiry(&mut client, "status-expired", 0).await;
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    assert_eq!(
+        effective_status(&mut client, "status-expired", access_key).await,
+        "Expired"
+    );
+
+    let response = request("POST", "/status-expired/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 401);
+
+    /* enabling it again doesn't bring it back */
+    set_enabled(&mut client, "status-expired", access_key, false).await;
+    assert_eq!(
+        effective_status(&mut client, "status-expired", access_key).await,
+        "Expired"
+    );
+
+    set_enabled(&mut client, "status-expired", access_key, true).await;
+    assert_eq!(
+        effective_status(&mut client, "status-expired", access_key).await,
+        "Expired"
+    );
+}
+
+#[test]
+fn effective_status_at_the_expiry() {
+    let expires = clock::now() + chrono::Duration::hours(1);
+    let document = db::access::AccessKeyDocument {
+        secret_access_key: String::new(),
+        permission: vec![permission::VaultRoles::ListSecrets],
+        sg: Vec::new(),
+        created: clock::now().to_rfc3339(),
+        last_used: None,
+        enabled: true,
+        created_by: None,
+        expires: Some(expires.to_rfc3339()),
+        name_prefixes: Vec::new(),
+    };
+
+    assert_eq!(
+        document.status(expires - chrono::Duration::seconds(1)),
+        db::access::AccessKeyStatus::Active
+    );
+    assert_eq!(
+        document.status(expires),
+        db::access::AccessKeyStatus::Expired
+    );
+
+    let document = db::access::AccessKeyDocument {
+        expires: None,
+        ..document
+    };
+
+    assert_eq!(
+        document.status(expires + chrono::Duration::days(365)),
+        db::access::AccessKeyStatus::Active
+    );
+}
+
+#[tokio::test]
+async fn ready_to_use_authorization_header() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": "ready-to-use",
+                "permission": ["CreateSecrets", "DecryptSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    let access_key = response["access_key"].as_str().unwrap();
+    let secret_access_key = response["secret_access_key"].as_str().unwrap();
+    let authorization = response["authorization"].as_str().unwrap();
+
+    assert_eq!(
+        vault::parse_signature(authorization),
+        Some((access_key.to_owned(), secret_access_key.to_owned()))
+    );
+    assert_eq!(
+        authorization,
+        authorization_for(access_key, secret_access_key)
+    );
+
+    let curl = response["curl"].as_str().unwrap();
+    assert!(curl.contains(&format!("'Authorization: {authorization}'")));
+    assert!(curl.ends_with("/ready-to-use/[SECRET NAME]"));
+
+    let document = db::access::find("ready-to-use", access_key)
+        .unwrap()
+        .unwrap();
+    assert_ne!(document.secret_access_key, secret_access_key);
+
+    let headers = [("Authorization", authorization)];
+
+    let response = request("POST", "/ready-to-use/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/ready-to-use/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
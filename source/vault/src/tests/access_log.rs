@@ -0,0 +1,58 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, permission};
+
+use super::{create_access_key, request};
+
+#[tokio::test]
+async fn combined_log_format_line() {
+    let authorization = create_access_key(
+        "clf",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, secret_access_key) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("POST", "/clf/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/clf/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let filename = config::get_clone().log.unwrap().access_log.unwrap();
+    let content = std::fs::read_to_string(filename).unwrap();
+    assert!(!content.contains(secret_access_key));
+
+    let line = content
+        .lines()
+        .find(|v| v.contains("\"GET /clf/password HTTP/1.1\""))
+        .expect("missing the access log line");
+
+    let (prefix, rest) = line.split_once(" [").unwrap();
+    assert_eq!(prefix, format!("127.0.0.1 - {access_key}"));
+
+    let (time, rest) = rest.split_once("] ").unwrap();
+    assert!(chrono::DateTime::parse_from_str(time, "%d/%b/%Y:%H:%M:%S %z").is_ok());
+    assert_eq!(rest, "\"GET /clf/password HTTP/1.1\" 200 7 \"-\" \"-\"");
+}
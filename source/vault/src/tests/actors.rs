@@ -0,0 +1,136 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{create_access_key, request, Client, ROOT_USERNAME};
+
+#[tokio::test]
+async fn rest_writes_record_the_access_key() {
+    let authorization = create_access_key(
+        "actors-rest",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("PUT", "/actors-rest/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    assert_eq!(
+        db::secret::find("actors-rest", "password")
+            .unwrap()
+            .unwrap()
+            .created_by,
+        Some(db::Actor::AccessKey(access_key.to_owned()))
+    );
+
+    let response = request("GET", "/actors-rest", &headers, b"").await;
+    let listing: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(
+        listing["secrets"][0]["created_by"],
+        serde_json::json!({ "AccessKey": access_key })
+    );
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "actors-rest",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Found"]["created_by"],
+        serde_json::json!({ "AccessKey": access_key })
+    );
+}
+
+#[tokio::test]
+async fn admin_writes_record_the_user() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "actors-admin",
+                "secret_name": "password",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "ListSecrets": {
+                "vault": "actors-admin"
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["secrets"][0]["created_by"],
+        serde_json::json!({ "User": ROOT_USERNAME })
+    );
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": "actors-admin",
+                "permission": ["ListSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    let access_key = response["access_key"].as_str().unwrap().to_owned();
+
+    let response = client
+        .send(serde_json::json!({
+            "FindAccessKey": {
+                "vault": "actors-admin",
+                "access_key": access_key
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Found"]["created_by"],
+        serde_json::json!({ "User": ROOT_USERNAME })
+    );
+
+    let response = client
+        .send(serde_json::json!({
+            "ListAccessKeys": {
+                "vault": "actors-admin",
+                "sort_by": null,
+                "order": null,
+                "name_prefix": null
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["access_keys"][0]["created_by"],
+        serde_json::json!({ "User": ROOT_USERNAME })
+    );
+}
@@ -0,0 +1,104 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+use crate::{config, permission, server, user};
+
+use super::{free_port, Client, ROOT_PASSWORD, ROOT_USERNAME};
+
+async fn login(
+    address: &str,
+    forwarded_proto: Option<&str>,
+    username: &str,
+    password: &str,
+) -> serde_json::Value {
+    let mut request = format!("ws://{address}/").into_client_request().unwrap();
+
+    if let Some(proto) = forwarded_proto {
+        request
+            .headers_mut()
+            .insert("X-Forwarded-Proto", proto.parse().unwrap());
+    }
+
+    let (stream, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("failed to connect to the websocket");
+
+    let mut client = Client {
+        stream,
+        bytes_sent: 0,
+        bytes_received: 0,
+    };
+
+    client
+        .send(serde_json::json!({
+            "username": username,
+            "password": password,
+            "protocol_version": vaulty_protocol::VERSION
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn admin_login_over_plaintext_refused() {
+    super::server();
+
+    user::create_user(
+        "plaintext-user",
+        "hunter2",
+        &permission::UserRole::User,
+        vec!["127.0.0.1/32".to_owned()],
+    )
+    .unwrap();
+
+    let port = free_port();
+    let address = format!("127.0.0.1:{port}");
+
+    let mut server_config = config::get_clone().server;
+    server_config.listen_port = port;
+    server_config.admin_requires_tls = Some(true);
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    while tokio::net::TcpStream::connect(&address).await.is_err() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let response = login(&address, None, ROOT_USERNAME, ROOT_PASSWORD).await;
+    assert_eq!(response["result"], "TlsRequired");
+
+    /* refused before the password is checked */
+    let response = login(&address, None, ROOT_USERNAME, "wrong password").await;
+    assert_eq!(response["result"], "TlsRequired");
+
+    /* users that aren't admins aren't affected */
+    let response = login(&address, None, "plaintext-user", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    /* a client claiming TLS over plaintext */
+    let response = login(&address, Some("https"), ROOT_USERNAME, ROOT_PASSWORD).await;
+    assert_eq!(response["result"], "TlsRequired");
+
+    /* the shared server doesn't require it */
+    Client::login_root().await;
+}
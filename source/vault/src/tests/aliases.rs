@@ -0,0 +1,414 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{create_access_key, create_scoped_access_key, request, Client};
+
+#[tokio::test]
+async fn alias_resolution() {
+    let authorization = create_access_key(
+        "aliases",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/aliases/canonical", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let alias_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Alias-Of", "aliases/canonical"),
+    ];
+
+    let response = request("PUT", "/aliases/first-alias", &alias_headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    /* an alias of an alias */
+    let alias_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Alias-Of", "aliases/first-alias"),
+    ];
+
+    let response = request("PUT", "/aliases/second-alias", &alias_headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    for path in ["/aliases/first-alias", "/aliases/second-alias"] {
+        let response = request("GET", path, &headers, b"").await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hunter2");
+    }
+
+    /* updating the canonical secret shows through */
+    let response = request("PUT", "/aliases/canonical", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/aliases/second-alias", &headers, b"").await;
+    assert_eq!(response.body, b"hunter3");
+
+    /* an alias holds no value */
+    let response = request("PUT", "/aliases/third-alias", &alias_headers, b"hunter2").await;
+    assert_eq!(response.status, 400);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "aliases-other",
+                "secret_name": "other-alias",
+                "alias": {
+                    "vault": "aliases",
+                    "secret_name": "canonical"
+                }
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "aliases-other",
+                "secret_name": "other-alias"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["secret"], "aHVudGVyMw==");
+}
+
+/* aliases made by an admin, which the access key may not read through */
+async fn admin_alias(vault: &str, secret_name: &str, target_vault: &str, target_name: &str) {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": vault,
+                "secret_name": secret_name,
+                "alias": {
+                    "vault": target_vault,
+                    "secret_name": target_name
+                }
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+}
+
+#[tokio::test]
+async fn alias_target_permission() {
+    let authorization = create_access_key(
+        "aliases-source",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let target_authorization = create_access_key(
+        "aliases-target",
+        vec![permission::VaultRoles::CreateSecrets],
+    );
+    let target_headers = [("Authorization", target_authorization.as_str())];
+
+    let response = request(
+        "PUT",
+        "/aliases-target/elsewhere",
+        &target_headers,
+        b"hunter2",
+    )
+    .await;
+    assert_eq!(response.status, 201);
+
+    /* without rights in the target's vault an existing target can't be told
+    from a missing one */
+    for target in ["aliases-target/elsewhere", "aliases-target/missing"] {
+        let alias_headers = [
+            ("Authorization", authorization.as_str()),
+            ("Alias-Of", target),
+        ];
+
+        let response = request("PUT", "/aliases-source/pointer", &alias_headers, b"").await;
+        assert_eq!(response.status, 403);
+        assert!(response.body.is_empty());
+    }
+
+    /* the access key can't decrypt the target's vault */
+    admin_alias("aliases-source", "pointer", "aliases-target", "elsewhere").await;
+
+    let response = request("GET", "/aliases-source/pointer", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    /* nor names outside its prefixes through an alias within its own */
+    let scoped = create_scoped_access_key(
+        "aliases-source",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+        vec!["app1-".to_owned()],
+    );
+    let scoped_headers = [("Authorization", scoped.as_str())];
+
+    let response = request("PUT", "/aliases-source/private", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    for target in ["aliases-source/private", "aliases-source/missing"] {
+        let alias_headers = [("Authorization", scoped.as_str()), ("Alias-Of", target)];
+
+        let response = request("PUT", "/aliases-source/app1-private", &alias_headers, b"").await;
+        assert_eq!(response.status, 403);
+    }
+
+    admin_alias(
+        "aliases-source",
+        "app1-private",
+        "aliases-source",
+        "private",
+    )
+    .await;
+
+    let response = request("GET", "/aliases-source/app1-private", &scoped_headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    /* within its prefixes it may */
+    let alias_headers = [
+        ("Authorization", scoped.as_str()),
+        ("Alias-Of", "aliases-source/app1-pointer"),
+    ];
+
+    let response = request("PUT", "/aliases-source/app1-second", &alias_headers, b"").await;
+    assert_eq!(response.status, 422);
+}
+
+#[tokio::test]
+async fn broken_alias() {
+    let authorization = create_access_key(
+        "aliases-broken",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::DeleteSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let alias_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Alias-Of", "aliases-broken/doomed"),
+    ];
+
+    /* the target has to exist when the alias is made */
+    let response = request("PUT", "/aliases-broken/dangling", &alias_headers, b"").await;
+    assert_eq!(response.status, 422);
+
+    let response = request("PUT", "/aliases-broken/doomed", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("PUT", "/aliases-broken/dangling", &alias_headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("DELETE", "/aliases-broken/doomed", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/aliases-broken/dangling", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "aliases-broken",
+                "secret_name": "dangling"
+            }
+        }))
+        .await;
+    assert_eq!(response, "NotFound");
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "aliases-broken",
+                "secret_name": "another",
+                "alias": {
+                    "vault": "aliases-broken",
+                    "secret_name": "doomed"
+                }
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "AliasTargetMissing");
+}
+
+#[tokio::test]
+async fn circular_alias() {
+    let authorization = create_access_key(
+        "aliases-loop",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/aliases-loop/loop-a", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let alias_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Alias-Of", "aliases-loop/loop-a"),
+    ];
+
+    let response = request("PUT", "/aliases-loop/loop-b", &alias_headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    /* turning the target into an alias of its alias */
+    let alias_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Alias-Of", "aliases-loop/loop-b"),
+    ];
+
+    let response = request("PUT", "/aliases-loop/loop-a", &alias_headers, b"").await;
+    assert_eq!(response.status, 422);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "aliases-loop",
+                "secret_name": "loop-c",
+                "alias": {
+                    "vault": "aliases-loop",
+                    "secret_name": "loop-c"
+                }
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "CircularAlias");
+
+    let response = request("GET", "/aliases-loop/loop-b", &headers, b"").await;
+    assert_eq!(response.body, b"hunter2");
+
+    /* a loop stored behind the checks' back is still caught when read */
+    for (name, target) in [("loop-d", "loop-e"), ("loop-e", "loop-d")] {
+        db::secret::insert(
+            "aliases-loop",
+            name,
+            db::secret::SecretDocument {
+                created: "2024-01-01T00:00:00+00:00".to_owned(),
+                secret: String::new(),
+                etag: None,
+                last_accessed: None,
+                created_by: None,
+                alias: Some(db::secret::SecretAlias {
+                    vault: "aliases-loop".to_owned(),
+                    secret_name: target.to_owned(),
+                }),
+                bound: true,
+            },
+            false,
+        )
+        .unwrap();
+    }
+
+    let response = request("GET", "/aliases-loop/loop-d", &headers, b"").await;
+    assert_eq!(response.status, 508);
+}
+
+#[tokio::test]
+async fn renamed_target_vault() {
+    let authorization = create_access_key(
+        "aliases-moving",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/aliases-moving/target", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    admin_alias("aliases-moving", "inside", "aliases-moving", "target").await;
+    admin_alias("aliases-outside", "outside", "aliases-moving", "target").await;
+    admin_alias("aliases-outside", "binned", "aliases-moving", "target").await;
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteSecret": {
+                "vault": "aliases-outside",
+                "secret_name": "binned"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+
+    let response = client
+        .send(serde_json::json!({
+            "RenameVault": {
+                "vault": "aliases-moving",
+                "name": "aliases-moved"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Renamed");
+
+    /* the aliases in the renamed vault and those pointing into it follow it */
+    for (vault, secret_name) in [("aliases-moved", "inside"), ("aliases-outside", "outside")] {
+        let response = client
+            .send(serde_json::json!({
+                "FindSecret": {
+                    "vault": vault,
+                    "secret_name": secret_name
+                }
+            }))
+            .await;
+        assert_eq!(
+            response["Found"]["secret"], "aHVudGVyMg==",
+            "{vault}/{secret_name}"
+        );
+    }
+
+    /* as does one in the recycle bin once it's restored */
+    let response = client
+        .send(serde_json::json!({
+            "RestoreSecret": {
+                "vault": "aliases-outside",
+                "secret_name": "binned"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Restored");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "aliases-outside",
+                "secret_name": "binned"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["secret"], "aHVudGVyMg==");
+}
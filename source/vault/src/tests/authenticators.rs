@@ -0,0 +1,256 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{app_error::AppResult, authenticator, db, permission, user};
+
+const LOCALHOST: std::net::IpAddr = std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+const OUTSIDE: std::net::IpAddr = std::net::IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+/* grants one password and reports fixed groups, as a directory would */
+struct Mock {
+    password: &'static str,
+    groups: Vec<String>,
+    provisioning: Option<authenticator::Provisioning>,
+}
+
+impl authenticator::Authenticator for Mock {
+    fn authenticate(
+        &self,
+        _username: &str,
+        password: &str,
+    ) -> AppResult<authenticator::Authentication> {
+        if password == self.password {
+            Ok(authenticator::Authentication::Granted {
+                groups: self.groups.clone(),
+            })
+        } else {
+            Ok(authenticator::Authentication::Denied)
+        }
+    }
+
+    fn provisioning(&self) -> Option<&authenticator::Provisioning> {
+        self.provisioning.as_ref()
+    }
+}
+
+fn external(groups: &[&str]) -> Mock {
+    Mock {
+        password: "directory-password",
+        groups: groups.iter().map(|v| v.to_string()).collect(),
+        provisioning: Some(authenticator::Provisioning {
+            role_mapping: [
+                ("vault-admins".to_owned(), permission::UserRole::Admin),
+                ("vault-users".to_owned(), permission::UserRole::User),
+            ]
+            .into(),
+            default_role: None,
+            sg: vec!["127.0.0.1/32".to_owned()],
+        }),
+    }
+}
+
+fn role(result: user::LoginResult) -> Option<permission::UserRole> {
+    match result {
+        user::LoginResult::Successful(user) => Some(user.role),
+        user::LoginResult::Failed => None,
+    }
+}
+
+#[test]
+fn mock_authenticator_grants_and_denies() {
+    super::server();
+
+    let mock = external(&["vault-users"]);
+
+    assert_eq!(
+        role(user::login_with(&mock, LOCALHOST, "external-grant", "wrong").unwrap()),
+        None
+    );
+    assert!(db::user::find("external-grant").unwrap().is_none());
+
+    assert_eq!(
+        role(user::login_with(&mock, LOCALHOST, "external-grant", "directory-password").unwrap()),
+        Some(permission::UserRole::User)
+    );
+    assert!(db::user::find("external-grant").unwrap().is_some());
+
+    /* the security groups still apply to a provisioned user */
+    assert_eq!(
+        role(user::login_with(&mock, OUTSIDE, "external-grant", "directory-password").unwrap()),
+        None
+    );
+
+    /* and the DB password of a provisioned user doesn't let anyone in */
+    assert_eq!(
+        role(user::login(LOCALHOST, "external-grant", "directory-password").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn mock_authenticator_maps_groups_to_roles() {
+    super::server();
+
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["staff", "vault-users", "vault-admins"]),
+                LOCALHOST,
+                "external-mapped",
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        Some(permission::UserRole::Admin)
+    );
+
+    /* the role follows the groups on every login */
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["vault-users"]),
+                LOCALHOST,
+                "external-mapped",
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        Some(permission::UserRole::User)
+    );
+    assert_eq!(
+        db::user::find("external-mapped").unwrap().unwrap().role,
+        permission::UserRole::User
+    );
+
+    /* no mapped group and no default role */
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["staff"]),
+                LOCALHOST,
+                "external-unmapped",
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        None
+    );
+    assert!(db::user::find("external-unmapped").unwrap().is_none());
+
+    let mut mock = external(&["staff"]);
+    mock.provisioning.as_mut().unwrap().default_role = Some(permission::UserRole::User);
+
+    assert_eq!(
+        role(
+            user::login_with(&mock, LOCALHOST, "external-unmapped", "directory-password").unwrap()
+        ),
+        Some(permission::UserRole::User)
+    );
+}
+
+#[test]
+fn local_authenticator_checks_the_db() {
+    super::server();
+
+    user::create_user(
+        "local-auth",
+        "hunter2",
+        &permission::UserRole::User,
+        vec!["127.0.0.1/32".to_owned()],
+    )
+    .unwrap();
+
+    assert_eq!(
+        role(user::login_with(&authenticator::Local, LOCALHOST, "local-auth", "hunter2").unwrap()),
+        Some(permission::UserRole::User)
+    );
+    assert_eq!(
+        role(user::login_with(&authenticator::Local, LOCALHOST, "local-auth", "wrong").unwrap()),
+        None
+    );
+    assert_eq!(
+        role(user::login_with(&authenticator::Local, OUTSIDE, "local-auth", "hunter2").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn external_identity_leaves_local_users_alone() {
+    super::server();
+
+    user::create_user(
+        "local-only",
+        "hunter2",
+        &permission::UserRole::User,
+        vec!["127.0.0.1/32".to_owned()],
+    )
+    .unwrap();
+
+    /* the directory can't log in as, nor promote, a user it didn't create */
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["vault-admins"]),
+                LOCALHOST,
+                "local-only",
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        db::user::find("local-only").unwrap().unwrap().role,
+        permission::UserRole::User
+    );
+
+    /* nor demote the root */
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["vault-users"]),
+                LOCALHOST,
+                super::ROOT_USERNAME,
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        db::user::find(super::ROOT_USERNAME).unwrap().unwrap().role,
+        permission::UserRole::Admin
+    );
+}
+
+#[test]
+fn protected_names_not_provisioned() {
+    super::server();
+
+    assert_eq!(
+        role(
+            user::login_with(
+                &external(&["vault-users"]),
+                LOCALHOST,
+                "protected-admin",
+                "directory-password"
+            )
+            .unwrap()
+        ),
+        None
+    );
+}
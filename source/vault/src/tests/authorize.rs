@@ -0,0 +1,104 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{permission, vault};
+
+use super::create_access_key;
+
+const LOCALHOST: std::net::IpAddr = std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+fn credentials(authorization: &str) -> vault::Credentials {
+    let (access_key, secret_access_key) = vault::parse_signature(authorization).unwrap();
+
+    vault::Credentials::AccessKey(access_key, secret_access_key)
+}
+
+#[test]
+fn parse_authorization() {
+    assert_eq!(
+        vault::parse_signature("VAULTY key:secret"),
+        Some(("key".to_owned(), "secret".to_owned()))
+    );
+    assert_eq!(
+        vault::parse_signature("vaulty  key:secret "),
+        Some(("key".to_owned(), "secret".to_owned()))
+    );
+    assert_eq!(vault::parse_signature("VAULTY key"), None);
+    assert_eq!(vault::parse_signature("Basic a2V5OnNlY3JldA=="), None);
+
+    assert_eq!(
+        vault::parse_bearer("Bearer token"),
+        Some("token".to_owned())
+    );
+    assert_eq!(
+        vault::parse_bearer("bearer token"),
+        Some("token".to_owned())
+    );
+    assert_eq!(vault::parse_bearer("Bearer"), None);
+    assert_eq!(vault::parse_bearer("VAULTY key:secret"), None);
+}
+
+#[test]
+fn authorize_without_a_request() {
+    let authorization = create_access_key("authorize", vec![permission::VaultRoles::ListSecrets]);
+
+    assert!(matches!(
+        vault::authorize(
+            LOCALHOST,
+            credentials(&authorization),
+            permission::VaultRoles::ListSecrets,
+            "authorize"
+        ),
+        Some(vault::CommonAccessResult::Authorized(..))
+    ));
+    assert!(matches!(
+        vault::authorize(
+            LOCALHOST,
+            credentials(&authorization),
+            permission::VaultRoles::DecryptSecrets,
+            "authorize"
+        ),
+        Some(vault::CommonAccessResult::Forbidden(..))
+    ));
+    assert!(matches!(
+        vault::authorize(
+            "10.0.0.1".parse().unwrap(),
+            credentials(&authorization),
+            permission::VaultRoles::ListSecrets,
+            "authorize"
+        ),
+        Some(vault::CommonAccessResult::Unauthorized)
+    ));
+    assert!(matches!(
+        vault::authorize(
+            LOCALHOST,
+            credentials(&format!("{authorization}x")),
+            permission::VaultRoles::ListSecrets,
+            "authorize"
+        ),
+        Some(vault::CommonAccessResult::Unauthorized)
+    ));
+    assert!(matches!(
+        vault::authorize(
+            LOCALHOST,
+            vault::Credentials::Missing,
+            permission::VaultRoles::ListSecrets,
+            "authorize"
+        ),
+        Some(vault::CommonAccessResult::Unauthorized)
+    ));
+}
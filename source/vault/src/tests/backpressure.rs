@@ -0,0 +1,104 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures::SinkExt;
+
+use super::{server, Client};
+use crate::stats;
+
+async fn connect_slow_reader() -> Client {
+    let socket = tokio::net::TcpSocket::new_v4().unwrap();
+    socket.set_recv_buffer_size(4096).unwrap();
+
+    let tcp = socket
+        .connect(server().address.parse().unwrap())
+        .await
+        .expect("failed to connect to the server");
+
+    let (stream, _) = tokio_tungstenite::client_async(
+        format!("ws://{}/", server().address),
+        tokio_tungstenite::MaybeTlsStream::Plain(tcp),
+    )
+    .await
+    .expect("failed to connect to the websocket");
+
+    Client {
+        stream,
+        bytes_sent: 0,
+        bytes_received: 0,
+    }
+}
+
+fn is_connected(username: &str) -> bool {
+    stats::connected_sessions()
+        .iter()
+        .any(|v| v.username.as_deref() == Some(username))
+}
+
+#[tokio::test]
+async fn slow_reader_is_disconnected() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "slow-reader",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let mut reader = connect_slow_reader().await;
+    let response = reader
+        .send(serde_json::json!({
+            "username": "slow-reader",
+            "password": "hunter2",
+            "protocol_version": vaulty_protocol::VERSION
+        }))
+        .await;
+    assert_eq!(response["result"], "Granted");
+    assert!(is_connected("slow-reader"));
+
+    /* every unknown command is answered with the list of the known ones, nothing is read back */
+    let flood = async {
+        while is_connected("slow-reader") {
+            let sent = reader
+                .stream
+                .send(tokio_tungstenite::tungstenite::Message::Text(
+                    r#"{"NoSuchCommand":[]}"#.to_owned(),
+                ))
+                .await;
+
+            if sent.is_err() {
+                break;
+            }
+        }
+    };
+
+    tokio::time::timeout(tokio::time::Duration::from_secs(60), flood)
+        .await
+        .expect("the session wasn't closed");
+
+    assert!(!is_connected("slow-reader"));
+
+    /* the server keeps serving everyone else */
+    let response = client.send(serde_json::json!({ "ListSessions": [] })).await;
+    assert!(response["Sessions"].is_array());
+}
@@ -0,0 +1,186 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use p256::ecdsa::signature::Signer;
+
+use crate::{access_keys, clock, config, db, permission};
+
+use super::{create_access_key, request};
+
+/* other tests move the clock forward by days */
+const VALID_FOR_SECS: i64 = 30 * 24 * 60 * 60;
+
+fn issue(header: serde_json::Value, claims: serde_json::Value) -> String {
+    let public_key = config::get_clone().jwt.unwrap().public_key;
+    let private_key = std::path::Path::new(&public_key).with_file_name("jwt-private.pem");
+    let signing_key = access_keys::load_signing_key(private_key.to_str().unwrap()).unwrap();
+
+    let message = format!(
+        "{}.{}",
+        base64_simd::URL_SAFE_NO_PAD.encode_to_string(header.to_string()),
+        base64_simd::URL_SAFE_NO_PAD.encode_to_string(claims.to_string())
+    );
+    let signature: p256::ecdsa::Signature = signing_key.sign(message.as_bytes());
+
+    format!(
+        "Bearer {message}.{}",
+        base64_simd::URL_SAFE_NO_PAD.encode_to_string(signature.to_bytes())
+    )
+}
+
+fn token(vault: &str, permission: &[&str], expires_in: i64) -> String {
+    issue(
+        serde_json::json!({ "alg": "ES256", "typ": "JWT" }),
+        serde_json::json!({
+            "sub": "ci-runner",
+            "iss": "vaulty-tests",
+            "vault": vault,
+            "permission": permission,
+            "sg": ["127.0.0.1/32"],
+            "exp": clock::now().timestamp() + expires_in
+        }),
+    )
+}
+
+async fn insert_password(vault: &str) {
+    let authorization = create_access_key(vault, vec![permission::VaultRoles::CreateSecrets]);
+
+    let response = request(
+        "POST",
+        &format!("/{vault}/password"),
+        &[("Authorization", authorization.as_str())],
+        b"hunter2",
+    )
+    .await;
+    assert_eq!(response.status, 201);
+}
+
+#[tokio::test]
+async fn valid_token_grants_access() {
+    insert_password("jwt-valid").await;
+
+    let authorization = token(
+        "jwt-valid",
+        &["DecryptSecrets", "CreateSecrets"],
+        VALID_FOR_SECS,
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("GET", "/jwt-valid/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = request("POST", "/jwt-valid/token", &headers, b"hunter3").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(
+        db::secret::find("jwt-valid", "token")
+            .unwrap()
+            .unwrap()
+            .created_by,
+        Some(db::Actor::Token("ci-runner".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn expired_token_is_rejected() {
+    insert_password("jwt-expired").await;
+
+    let authorization = token("jwt-expired", &["DecryptSecrets"], -60);
+
+    let response = request(
+        "GET",
+        "/jwt-expired/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 401);
+}
+
+#[tokio::test]
+async fn token_without_the_scope_is_forbidden() {
+    insert_password("jwt-scope").await;
+
+    let authorization = token("jwt-scope", &["ListSecrets"], VALID_FOR_SECS);
+
+    let response = request(
+        "GET",
+        "/jwt-scope/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 403);
+
+    /* a token is bound to the vault in its claims */
+    let authorization = token("jwt-other", &["DecryptSecrets"], VALID_FOR_SECS);
+
+    let response = request(
+        "GET",
+        "/jwt-scope/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 403);
+}
+
+#[tokio::test]
+async fn forged_tokens_are_rejected() {
+    insert_password("jwt-forged").await;
+
+    let claims = serde_json::json!({
+        "sub": "ci-runner",
+        "iss": "vaulty-tests",
+        "vault": "jwt-forged",
+        "permission": ["DecryptSecrets"],
+        "sg": ["127.0.0.1/32"],
+        "exp": clock::now().timestamp() + VALID_FOR_SECS
+    });
+
+    let unsigned = format!(
+        "Bearer {}.{}.",
+        base64_simd::URL_SAFE_NO_PAD.encode_to_string(r#"{"alg":"none"}"#),
+        base64_simd::URL_SAFE_NO_PAD.encode_to_string(claims.to_string())
+    );
+
+    let mut other_issuer = claims.clone();
+    other_issuer["iss"] = "someone-else".into();
+
+    let mut outside_sg = claims.clone();
+    outside_sg["sg"] = serde_json::json!(["10.0.0.0/8"]);
+
+    let mut tampered = token("jwt-forged", &["DecryptSecrets"], VALID_FOR_SECS);
+    let signature_start = tampered.rfind('.').unwrap() + 1;
+    tampered.replace_range(signature_start..signature_start + 4, "AAAA");
+
+    for authorization in [
+        unsigned,
+        tampered,
+        issue(serde_json::json!({ "alg": "ES256" }), other_issuer),
+        issue(serde_json::json!({ "alg": "ES256" }), outside_sg),
+    ] {
+        let response = request(
+            "GET",
+            "/jwt-forged/password",
+            &[("Authorization", authorization.as_str())],
+            b"",
+        )
+        .await;
+        assert_eq!(response.status, 401, "{authorization}");
+    }
+}
@@ -0,0 +1,182 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use p256::ecdsa::signature::{Signer, Verifier};
+
+use crate::{access_keys, bootstrap, config, db, secrets, user};
+
+use super::write_key;
+
+const LOCALHOST: std::net::IpAddr = std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+#[test]
+fn generated_config_loads() {
+    let directory = tempfile::tempdir().unwrap();
+    let filename = bootstrap::generate_config(&directory.path().join("vaulty")).unwrap();
+
+    let config = config::load(&filename).unwrap();
+    assert!(matches!(
+        config.secrets.scheme,
+        Some(config::SecretsScheme::Rsa)
+    ));
+
+    let engine = secrets::SecretsEngine::load(&config.secrets).unwrap();
+    let encrypted = engine.encrypt(b"hunter2", b"tests||secret").unwrap();
+    assert_eq!(
+        engine.decrypt(&encrypted, b"tests||secret").unwrap(),
+        b"hunter2"
+    );
+
+    let signing_key = access_keys::load_signing_key(&config.access_keys.signing_key).unwrap();
+    let verifying_key = access_keys::load_verifying_key(&config.access_keys.verifying_key).unwrap();
+    let signature: p256::ecdsa::Signature = signing_key.sign(b"access key");
+    assert!(verifying_key.verify(b"access key", &signature).is_ok());
+
+    /* a second run must not replace the keys of the first one */
+    assert!(bootstrap::generate_config(&directory.path().join("vaulty")).is_err());
+    assert!(config::load(&filename).is_ok());
+}
+
+#[test]
+fn verifying_key_formats() {
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let ec = openssl::ec::EcKey::generate(
+        &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+    )
+    .unwrap();
+
+    let public_key = openssl::ec::EcKey::from_public_key(ec.group(), ec.public_key()).unwrap();
+    let filename = write_key(
+        directory.path(),
+        "verifying.pem",
+        &public_key.public_key_to_pem().unwrap(),
+    );
+    let verifying_key = access_keys::load_verifying_key(&filename).unwrap();
+
+    assert_eq!(
+        verifying_key.to_encoded_point(false).as_bytes(),
+        ec.public_key()
+            .to_bytes(
+                ec.group(),
+                openssl::ec::PointConversionForm::UNCOMPRESSED,
+                &mut openssl::bn::BigNumContext::new().unwrap()
+            )
+            .unwrap()
+    );
+
+    for (name, pem) in [
+        ("garbage.pem", b"garbage".to_vec()),
+        /* the private key where the public one is expected */
+        ("private.pem", ec.private_key_to_pem().unwrap()),
+    ] {
+        let filename = write_key(directory.path(), name, &pem);
+        let error = access_keys::load_verifying_key(&filename).unwrap_err();
+
+        assert_eq!(error.message, "failed to load public key");
+        assert!(error.error.is_some());
+    }
+
+    let error =
+        access_keys::load_verifying_key(directory.path().join("missing.pem").to_str().unwrap())
+            .unwrap_err();
+    assert_eq!(error.message, "failed to open for reading");
+}
+
+#[test]
+fn initial_admin_password_from_the_environment() {
+    super::server();
+
+    std::env::set_var("VAULTY_TEST_INITIAL_PASSWORD", "hunter2");
+
+    let bootstrap = config::ConfigUsersBootstrap {
+        username: Some("bootstrap-env".to_owned()),
+        sg: Some(vec!["127.0.0.1/32".to_owned()]),
+        password_env: Some("VAULTY_TEST_INITIAL_PASSWORD".to_owned()),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        bootstrap::create_initial_admin(&bootstrap).unwrap(),
+        bootstrap::InitialAdmin::Created {
+            password: bootstrap::InitialPassword::Provided,
+            ..
+        }
+    ));
+    assert!(matches!(
+        user::login(LOCALHOST, "bootstrap-env", "hunter2").unwrap(),
+        user::LoginResult::Successful(_)
+    ));
+
+    assert!(matches!(
+        bootstrap::create_initial_admin(&bootstrap).unwrap(),
+        bootstrap::InitialAdmin::Exists
+    ));
+
+    let bootstrap = config::ConfigUsersBootstrap {
+        username: Some("bootstrap-unset".to_owned()),
+        password_env: Some("VAULTY_TEST_UNSET_INITIAL_PASSWORD".to_owned()),
+        ..Default::default()
+    };
+
+    assert!(bootstrap::create_initial_admin(&bootstrap).is_err());
+    assert!(db::user::find("bootstrap-unset").unwrap().is_none());
+}
+
+#[test]
+fn initial_admin_password_written_to_a_file() {
+    super::server();
+
+    let directory = tempfile::tempdir().unwrap();
+    let filename = directory.path().join("initial-password");
+
+    let bootstrap = config::ConfigUsersBootstrap {
+        username: Some("bootstrap-file".to_owned()),
+        write_password_to: Some(filename.to_str().unwrap().to_owned()),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        bootstrap::create_initial_admin(&bootstrap).unwrap(),
+        bootstrap::InitialAdmin::Created {
+            password: bootstrap::InitialPassword::Written(_),
+            ..
+        }
+    ));
+
+    let password = std::fs::read_to_string(&filename).unwrap();
+    assert!(matches!(
+        user::login(LOCALHOST, "bootstrap-file", &password).unwrap(),
+        user::LoginResult::Successful(_)
+    ));
+}
+
+#[test]
+fn externally_provisioned_admin() {
+    super::server();
+
+    let bootstrap = config::ConfigUsersBootstrap {
+        username: Some("bootstrap-skipped".to_owned()),
+        externally_provisioned: Some(true),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        bootstrap::create_initial_admin(&bootstrap).unwrap(),
+        bootstrap::InitialAdmin::Skipped
+    ));
+    assert!(db::user::find("bootstrap-skipped").unwrap().is_none());
+}
@@ -0,0 +1,77 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, vault};
+
+use super::server;
+
+fn document() -> db::idempotency::IdempotencyDocument {
+    db::idempotency::IdempotencyDocument {
+        created: crate::clock::now().to_rfc3339(),
+        status: 201,
+    }
+}
+
+#[test]
+fn busy_write_is_retried() {
+    server();
+
+    db::inject_write_failures(db::BEGIN_WRITE_ATTEMPTS - 1);
+
+    db::idempotency::insert("busy", "password", "retried", document())
+        .expect("the write should succeed after the retries");
+
+    assert_eq!(db::injected_write_failures(), 0);
+    assert!(db::idempotency::find("busy", "password", "retried")
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn busy_write_gives_up() {
+    server();
+
+    db::inject_write_failures(db::BEGIN_WRITE_ATTEMPTS);
+
+    let e = db::idempotency::insert("busy", "password", "exhausted", document())
+        .expect_err("the write should give up after the retries");
+
+    assert!(db::is_transient_error(&e), "{e:?}");
+    assert_eq!(db::injected_write_failures(), 0);
+    assert!(db::idempotency::find("busy", "password", "exhausted")
+        .unwrap()
+        .is_none());
+
+    let response = vault::failure_response(&e);
+    assert_eq!(response.status(), 503);
+    assert_eq!(
+        response
+            .headers()
+            .get(actix_web::http::header::RETRY_AFTER)
+            .unwrap(),
+        "1"
+    );
+
+    /* anything else is still a plain failure */
+    let response = vault::failure_response(&crate::app_error::AppError {
+        message: "failed to begin write transaction".to_owned(),
+        error: None,
+        attr: None,
+        code: None,
+    });
+    assert_eq!(response.status(), 500);
+}
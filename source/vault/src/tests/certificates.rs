@@ -0,0 +1,488 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{app_error::AppResult, config, server};
+
+use super::{free_port, write_key, ROOT_PASSWORD, ROOT_USERNAME};
+
+fn ec_key() -> openssl::pkey::PKey<openssl::pkey::Private> {
+    openssl::pkey::PKey::from_ec_key(
+        openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+/* self-signed when there is no issuer */
+fn issue(
+    common_name: &str,
+    key: &openssl::pkey::PKey<openssl::pkey::Private>,
+    issuer: Option<(
+        &openssl::x509::X509,
+        &openssl::pkey::PKey<openssl::pkey::Private>,
+    )>,
+    ca: bool,
+) -> openssl::x509::X509 {
+    let mut name = openssl::x509::X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    let name = name.build();
+
+    let mut serial = openssl::bn::BigNum::new().unwrap();
+    serial
+        .rand(64, openssl::bn::MsbOption::MAYBE_ZERO, false)
+        .unwrap();
+
+    let mut builder = openssl::x509::X509Builder::new().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&serial.to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder
+        .set_issuer_name(issuer.map_or(&name, |(v, _)| v.subject_name()))
+        .unwrap();
+    builder.set_pubkey(key).unwrap();
+    builder
+        .set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&openssl::asn1::Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+
+    if ca {
+        builder
+            .append_extension(
+                openssl::x509::extension::BasicConstraints::new()
+                    .critical()
+                    .ca()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    builder
+        .sign(
+            issuer.map_or(key, |(_, v)| v),
+            openssl::hash::MessageDigest::sha256(),
+        )
+        .unwrap();
+
+    builder.build()
+}
+
+fn tls_config(directory: &std::path::Path) -> config::ConfigServerTls {
+    config::ConfigServerTls {
+        certificate: directory.join("tls.pem").to_str().unwrap().to_owned(),
+        key: directory.join("tls.key").to_str().unwrap().to_owned(),
+        ocsp: None,
+        reload_interval_secs: None,
+        hsts_max_age_secs: None,
+        hsts_include_subdomains: None,
+    }
+}
+
+/* the PEM is loaded the way the server loads it */
+fn verify_chain(
+    chain: &[&openssl::x509::X509],
+    key: &openssl::pkey::PKey<openssl::pkey::Private>,
+) -> AppResult<usize> {
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let tls = tls_config(directory.path());
+
+    let mut pem = Vec::new();
+
+    for certificate in chain {
+        pem.extend(certificate.to_pem().unwrap());
+    }
+
+    write_key(directory.path(), "tls.pem", &pem);
+
+    let chain = server::load_cert(tls.certificate.clone())?;
+    let key = rustls::PrivateKey(key.private_key_to_pkcs8().unwrap());
+
+    server::verify_chain(&chain, &key, &tls).map(|_| chain.len())
+}
+
+async fn serve(tls: config::ConfigServerTls) -> String {
+    let port = free_port();
+    let address = format!("127.0.0.1:{port}");
+
+    let mut server_config = config::get_clone().server;
+    server_config.listen_port = port;
+    server_config.tls = Some(tls);
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    while tokio::net::TcpStream::connect(&address).await.is_err() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    address
+}
+
+async fn served_certificate(address: &str) -> Vec<u8> {
+    let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect("localhost", stream)
+        .await
+        .expect("failed the TLS handshake");
+
+    stream
+        .get_ref()
+        .peer_certificate()
+        .unwrap()
+        .expect("no certificate was served")
+        .to_der()
+        .unwrap()
+}
+
+/* the chain and the stapled OCSP response, as the server hands them out */
+fn handshake(address: &str) -> (Vec<Vec<u8>>, Option<Vec<u8>>) {
+    let mut connector =
+        openssl::ssl::SslConnector::builder(openssl::ssl::SslMethod::tls()).unwrap();
+    connector.set_verify(openssl::ssl::SslVerifyMode::NONE);
+
+    let mut ssl = connector.build().configure().unwrap();
+    ssl.set_status_type(openssl::ssl::StatusType::OCSP).unwrap();
+
+    let stream = ssl
+        .connect("localhost", std::net::TcpStream::connect(address).unwrap())
+        .expect("failed the TLS handshake");
+
+    (
+        stream
+            .ssl()
+            .peer_cert_chain()
+            .expect("no certificate was served")
+            .iter()
+            .map(|v| v.to_der().unwrap())
+            .collect(),
+        stream.ssl().ocsp_status().map(<[u8]>::to_vec),
+    )
+}
+
+#[tokio::test]
+async fn reload_replaced_certificate() {
+    super::server();
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+
+    let write_pair = |certificate: &openssl::x509::X509,
+                      key: &openssl::pkey::PKey<openssl::pkey::Private>| {
+        write_key(directory.path(), "tls.pem", &certificate.to_pem().unwrap());
+        write_key(
+            directory.path(),
+            "tls.key",
+            &key.private_key_to_pem_pkcs8().unwrap(),
+        );
+    };
+
+    let key = ec_key();
+    let original = issue("localhost", &key, None, false);
+    write_pair(&original, &key);
+
+    let address = serve(config::ConfigServerTls {
+        reload_interval_secs: Some(1),
+        ..tls_config(directory.path())
+    })
+    .await;
+
+    assert_eq!(
+        served_certificate(&address).await,
+        original.to_der().unwrap()
+    );
+
+    /* renewed as certbot would, with a new key */
+    let key = ec_key();
+    let renewed = issue("localhost", &key, None, false);
+    write_pair(&renewed, &key);
+
+    let mut served = Vec::new();
+
+    for _ in 0..50 {
+        served = served_certificate(&address).await;
+
+        if served == renewed.to_der().unwrap() {
+            break;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(served, renewed.to_der().unwrap());
+
+    /* a certificate that doesn't match the key is refused, the current one stays */
+    let mismatched = issue("localhost", &ec_key(), None, false);
+    write_key(directory.path(), "tls.pem", &mismatched.to_pem().unwrap());
+
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+
+    assert_eq!(
+        served_certificate(&address).await,
+        renewed.to_der().unwrap()
+    );
+}
+
+#[test]
+fn fullchain_accepted() {
+    let root_key = ec_key();
+    let root = issue("root", &root_key, None, true);
+    let intermediate_key = ec_key();
+    let intermediate = issue(
+        "intermediate",
+        &intermediate_key,
+        Some((&root, &root_key)),
+        true,
+    );
+    let key = ec_key();
+    let leaf = issue(
+        "localhost",
+        &key,
+        Some((&intermediate, &intermediate_key)),
+        false,
+    );
+
+    assert_eq!(verify_chain(&[&leaf, &intermediate], &key).unwrap(), 2);
+    assert_eq!(
+        verify_chain(&[&leaf, &intermediate, &root], &key).unwrap(),
+        3
+    );
+    assert_eq!(verify_chain(&[&leaf], &key).unwrap(), 1);
+}
+
+#[tokio::test]
+async fn fullchain_served() {
+    super::server();
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+
+    let root_key = ec_key();
+    let root = issue("root", &root_key, None, true);
+    let intermediate_key = ec_key();
+    let intermediate = issue(
+        "intermediate",
+        &intermediate_key,
+        Some((&root, &root_key)),
+        true,
+    );
+    let key = ec_key();
+    let leaf = issue(
+        "localhost",
+        &key,
+        Some((&intermediate, &intermediate_key)),
+        false,
+    );
+
+    let mut pem = leaf.to_pem().unwrap();
+    pem.extend(intermediate.to_pem().unwrap());
+    write_key(directory.path(), "tls.pem", &pem);
+    write_key(
+        directory.path(),
+        "tls.key",
+        &key.private_key_to_pem_pkcs8().unwrap(),
+    );
+
+    let address = serve(tls_config(directory.path())).await;
+
+    let (chain, ocsp) = handshake(&address);
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0], leaf.to_der().unwrap());
+    assert_eq!(chain[1], intermediate.to_der().unwrap());
+    assert_eq!(ocsp, None);
+}
+
+#[tokio::test]
+async fn ocsp_stapled() {
+    super::server();
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+
+    let key = ec_key();
+    let certificate = issue("localhost", &key, None, false);
+    write_key(directory.path(), "tls.pem", &certificate.to_pem().unwrap());
+    write_key(
+        directory.path(),
+        "tls.key",
+        &key.private_key_to_pem_pkcs8().unwrap(),
+    );
+
+    /* an OCSPResponse with the tryLater status, it's stapled as it is */
+    let response = [0x30, 0x03, 0x0A, 0x01, 0x03];
+    write_key(directory.path(), "tls.ocsp", &response);
+
+    let address = serve(config::ConfigServerTls {
+        ocsp: Some(
+            directory
+                .path()
+                .join("tls.ocsp")
+                .to_str()
+                .unwrap()
+                .to_owned(),
+        ),
+        ..tls_config(directory.path())
+    })
+    .await;
+
+    let (_, ocsp) = handshake(&address);
+    let ocsp = ocsp.expect("no OCSP response was stapled");
+    assert_eq!(ocsp, response);
+    assert_eq!(
+        openssl::ocsp::OcspResponse::from_der(&ocsp)
+            .unwrap()
+            .status(),
+        openssl::ocsp::OcspResponseStatus::TRY_LATER
+    );
+}
+
+#[test]
+fn broken_chain_rejected() {
+    let root_key = ec_key();
+    let root = issue("root", &root_key, None, true);
+    let intermediate_key = ec_key();
+    let intermediate = issue(
+        "intermediate",
+        &intermediate_key,
+        Some((&root, &root_key)),
+        true,
+    );
+    let key = ec_key();
+    let leaf = issue(
+        "localhost",
+        &key,
+        Some((&intermediate, &intermediate_key)),
+        false,
+    );
+
+    let unrelated_key = ec_key();
+    let unrelated = issue("unrelated", &unrelated_key, None, true);
+
+    /* an intermediate that didn't issue the leaf */
+    let error = verify_chain(&[&leaf, &unrelated], &key).unwrap_err();
+    assert_eq!(
+        error.message,
+        "the certificate chain is not in leaf-first order"
+    );
+    assert_eq!(
+        error.error.as_deref(),
+        Some("certificate 1 is not the issuer of certificate 0")
+    );
+
+    /* out of order */
+    let error = verify_chain(&[&leaf, &root, &intermediate], &key).unwrap_err();
+    assert_eq!(
+        error.error.as_deref(),
+        Some("certificate 1 is not the issuer of certificate 0")
+    );
+
+    /* the leaf isn't first */
+    let error = verify_chain(&[&intermediate, &leaf], &key).unwrap_err();
+    assert_eq!(
+        error.message,
+        "the leaf certificate doesn't match the private key"
+    );
+    assert_eq!(
+        error.error.as_deref(),
+        Some("the matching certificate is not first, the chain must be leaf-first")
+    );
+
+    /* a key of another certificate */
+    let error = verify_chain(&[&leaf, &intermediate], &ec_key()).unwrap_err();
+    assert_eq!(
+        error.message,
+        "the leaf certificate doesn't match the private key"
+    );
+    assert_eq!(error.error, None);
+}
+
+#[tokio::test]
+async fn pinned_certificate() {
+    super::server();
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+
+    /* self-signed and issued to another name, only the pin vouches for it */
+    let key = ec_key();
+    let certificate = issue("vaulty.invalid", &key, None, false);
+    write_key(directory.path(), "tls.pem", &certificate.to_pem().unwrap());
+    write_key(
+        directory.path(),
+        "tls.key",
+        &key.private_key_to_pem_pkcs8().unwrap(),
+    );
+
+    let address = serve(tls_config(directory.path())).await;
+    let (address, port) = address.rsplit_once(':').unwrap();
+
+    let fingerprint = certificate
+        .digest(openssl::hash::MessageDigest::sha256())
+        .unwrap();
+
+    let connect = |tls_pin: String| {
+        vaulty_client::Client::connect(vaulty_client::ConnectOptions {
+            address: address.to_owned(),
+            port: port.parse().unwrap(),
+            tls_pin: Some(tls_pin),
+            ..Default::default()
+        })
+    };
+
+    /* the form openssl prints is accepted too */
+    for tls_pin in [
+        fingerprint.iter().map(|v| format!("{v:02x}")).collect(),
+        fingerprint
+            .iter()
+            .map(|v| format!("{v:02X}"))
+            .collect::<Vec<String>>()
+            .join(":"),
+    ] {
+        let mut client = connect(tls_pin)
+            .await
+            .expect("the pinned certificate was refused");
+        let login = client.login(ROOT_USERNAME, ROOT_PASSWORD).await.unwrap();
+        assert!(matches!(
+            login.role,
+            Some(vaulty_client::permission::UserRole::Admin)
+        ));
+    }
+
+    let mismatched = issue("vaulty.invalid", &ec_key(), None, false)
+        .digest(openssl::hash::MessageDigest::sha256())
+        .unwrap();
+
+    match connect(mismatched.iter().map(|v| format!("{v:02x}")).collect()).await {
+        Err(vaulty_client::Error::Connect(message)) => {
+            assert!(
+                message.contains("doesn't match the pinned one"),
+                "{message}"
+            )
+        }
+        Err(e) => panic!("unexpected error: {e:?}"),
+        Ok(_) => panic!("a mismatched pin was accepted"),
+    }
+}
@@ -0,0 +1,109 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+fn sha256(value: &[u8]) -> String {
+    openssl::sha::sha256(value)
+        .iter()
+        .map(|v| format!("{v:02x}"))
+        .collect()
+}
+
+#[tokio::test]
+async fn rest_expected_sha256() {
+    let authorization = create_access_key(
+        "checksum-rest",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/checksum-rest/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request(
+        "GET",
+        &format!(
+            "/checksum-rest/password?expected_sha256={}",
+            sha256(b"hunter2").to_uppercase()
+        ),
+        &headers,
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 304);
+    assert!(response.body.is_empty());
+
+    let response = request(
+        "GET",
+        &format!(
+            "/checksum-rest/password?expected_sha256={}",
+            sha256(b"hunter1")
+        ),
+        &headers,
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
+
+#[tokio::test]
+async fn find_expected_sha256() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "checksum-cmd",
+                "secret_name": "password",
+                "data": base64_simd::STANDARD.encode_to_string(b"hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "checksum-cmd",
+                "secret_name": "password",
+                "expected_sha256": sha256(b"hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response, "Unchanged");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "checksum-cmd",
+                "secret_name": "password",
+                "expected_sha256": sha256(b"hunter1")
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string(b"hunter2")
+    );
+}
@@ -0,0 +1,170 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use vaulty_client::{permission, request, response, Client, ConnectOptions, Error};
+
+use super::{server, ROOT_PASSWORD, ROOT_USERNAME};
+
+async fn connect() -> Client {
+    let (address, port) = server()
+        .address
+        .rsplit_once(':')
+        .expect("server address has no port");
+
+    Client::connect(ConnectOptions {
+        address: address.to_owned(),
+        port: port.parse().expect("invalid server port"),
+        timeout: Some(std::time::Duration::from_secs(30)),
+        ..Default::default()
+    })
+    .await
+    .expect("failed to connect to the server")
+}
+
+#[tokio::test]
+async fn typed_commands() {
+    let mut client = connect().await;
+
+    let login = client.login(ROOT_USERNAME, ROOT_PASSWORD).await.unwrap();
+    assert!(matches!(login.role, Some(permission::UserRole::Admin)));
+
+    let result = client
+        .create_user(request::RequestCreateUser {
+            username: "client-user".to_owned(),
+            password: "client-password".to_owned(),
+            role: permission::UserRole::User,
+            sg: vec!["127.0.0.1/32".to_owned()],
+        })
+        .await
+        .unwrap();
+    assert!(matches!(result, response::CreateUserResult::Created));
+
+    let users = client.list_users().await.unwrap();
+    assert!(users.iter().any(|v| v.username == "client-user"));
+
+    let result = client
+        .insert_secret("client-vault", "password", b"hunter2")
+        .await
+        .unwrap();
+    assert!(matches!(result, response::InsertSecretResult::Inserted));
+
+    let secret = client
+        .find_secret("client-vault", "password")
+        .await
+        .unwrap()
+        .expect("inserted secret wasn't found");
+    assert_eq!(
+        base64_simd::STANDARD.decode_to_vec(secret.secret).unwrap(),
+        b"hunter2"
+    );
+
+    assert!(client
+        .find_secret("client-vault", "missing")
+        .await
+        .unwrap()
+        .is_none());
+
+    let vaults = client
+        .list_vaults(request::RequestListVaults {
+            name_prefix: Some("client-".to_owned()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(vaults.len(), 1);
+    assert_eq!(vaults[0].vault, "client-vault");
+    assert_eq!(vaults[0].secrets_count, 1);
+
+    let result = client
+        .delete_secret("client-vault", "password")
+        .await
+        .unwrap();
+    assert!(matches!(result, response::DeleteSecretResult::Deleted));
+}
+
+#[tokio::test]
+async fn errors() {
+    let mut client = connect().await;
+
+    assert!(matches!(
+        client.login("client-nobody", "password").await,
+        Err(Error::InvalidCredentials)
+    ));
+
+    let mut client = connect().await;
+
+    client.login(ROOT_USERNAME, ROOT_PASSWORD).await.unwrap();
+
+    /* the typed methods always encode, so go through the generic request to
+    send data the server can't decode */
+    let result = client
+        .request::<_, serde_json::Value>(&request::Request::InsertSecret(
+            request::RequestInsertSecret {
+                vault: "client-vault".to_owned(),
+                secret_name: "invalid".to_owned(),
+                data: "not base64!".to_owned(),
+                alias: None,
+            },
+        ))
+        .await;
+    assert!(
+        matches!(result, Err(Error::Server(response::ErrorCode::InvalidArgument, message)) if message == "failed to decode the data")
+    );
+}
+
+#[tokio::test]
+async fn old_server() {
+    use futures::{SinkExt, StreamExt};
+
+    /* answers the login the way a server from before the version was
+    exchanged did */
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        stream.next().await;
+        stream
+            .send(tokio_tungstenite::tungstenite::Message::Text(
+                serde_json::json!({
+                    "result": "Granted",
+                    "node_name": "old",
+                    "role": "Admin",
+                    "sg": []
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+    });
+
+    let mut client = Client::connect(ConnectOptions {
+        address: "127.0.0.1".to_owned(),
+        port,
+        timeout: Some(std::time::Duration::from_secs(30)),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    assert!(matches!(
+        client.login(ROOT_USERNAME, ROOT_PASSWORD).await,
+        Err(Error::UnsupportedServer(0))
+    ));
+}
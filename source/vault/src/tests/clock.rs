@@ -0,0 +1,74 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, db, permission};
+
+use super::{create_access_key, request};
+
+fn parse(timestamp: &str) -> chrono::DateTime<chrono::FixedOffset> {
+    chrono::DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp")
+}
+
+#[tokio::test]
+async fn advancing_the_clock() {
+    let authorization = create_access_key(
+        "clock",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("POST", "/clock/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let created = db::secret::find("clock", "password")
+        .unwrap()
+        .unwrap()
+        .created;
+    let last_used = db::access::find("clock", access_key)
+        .unwrap()
+        .unwrap()
+        .last_used
+        .unwrap();
+
+    clock::advance(chrono::Duration::days(1));
+
+    let response = request("PUT", "/clock/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/clock/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let advanced_created = db::secret::find("clock", "password")
+        .unwrap()
+        .unwrap()
+        .created;
+    let advanced_last_used = db::access::find("clock", access_key)
+        .unwrap()
+        .unwrap()
+        .last_used
+        .unwrap();
+
+    assert!(parse(&advanced_created) - parse(&created) >= chrono::Duration::days(1));
+    assert!(parse(&advanced_last_used) - parse(&last_used) >= chrono::Duration::days(1));
+}
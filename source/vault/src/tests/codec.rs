@@ -0,0 +1,118 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use db::codec;
+
+fn roundtrip<T>(document: &T)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let mut row = codec::encode(document).unwrap();
+    let encoded = row.clone();
+
+    assert_eq!(codec::Codec::of(&row), Some(codec::Codec::Json));
+
+    let decoded: T = codec::decode(&mut row).unwrap();
+
+    assert_eq!(codec::encode(&decoded).unwrap(), encoded);
+}
+
+#[test]
+fn document_types() {
+    let secret = db::secret::SecretDocument {
+        created: "2024-10-14T12:00:00+00:00".to_owned(),
+        secret: "c2VjcmV0".to_owned(),
+        etag: Some("etag".to_owned()),
+        last_accessed: None,
+        created_by: Some(db::Actor::User("root".to_owned())),
+        alias: None,
+        bound: true,
+    };
+
+    roundtrip(&secret);
+    roundtrip(&db::secret::DeletedSecretDocument {
+        deleted: "2024-10-15T12:00:00+00:00".to_owned(),
+        document: secret,
+    });
+    roundtrip(&db::access::AccessKeyDocument {
+        secret_access_key: "c2lnbmF0dXJl".to_owned(),
+        permission: vec![permission::VaultRoles::DecryptSecrets],
+        sg: vec![db::access::AccessKeySgDocument {
+            network: "127.0.0.1".to_owned(),
+            prefix: 32,
+        }],
+        created: "2024-10-14T12:00:00+00:00".to_owned(),
+        last_used: Some("2024-10-14T13:00:00+00:00".to_owned()),
+        enabled: false,
+        created_by: Some(db::Actor::AccessKey("tHeeFQ8HtyrVTU51YEBj".to_owned())),
+        expires: Some("2025-10-14T12:00:00+00:00".to_owned()),
+        name_prefixes: vec!["app1-".to_owned()],
+    });
+    roundtrip(&db::user::UserDocument {
+        password: "hash".to_owned(),
+        role: permission::UserRole::Admin,
+        last_login: None,
+        sg: vec![db::user::UserSgDocument {
+            network: "::1".to_owned(),
+            prefix: 128,
+        }],
+        manually_locked: true,
+        provisioned: true,
+    });
+    roundtrip(&db::vault::VaultDocument {
+        created: "2024-10-14T12:00:00+00:00".to_owned(),
+        secrets_count: 3,
+        access_keys_count: 1,
+        default_permissions: Some(vec![permission::VaultRoles::ListSecrets]),
+        default_sg: Some(vec!["10.0.0.0/8".to_owned()]),
+        value_schema: db::vault::ValueSchema::Json,
+    });
+    roundtrip(&db::upload::UploadDocument {
+        created: "2024-10-14T12:00:00+00:00".to_owned(),
+        length: 7,
+        data: "aHVudGVyMg==".to_owned(),
+    });
+    roundtrip(&db::idempotency::IdempotencyDocument {
+        created: "2024-10-14T12:00:00+00:00".to_owned(),
+        status: 201,
+    });
+}
+
+#[test]
+fn rows_from_older_releases() {
+    /* written before the optional fields existed */
+    let mut row = r#"{"created":"2024-10-14T12:00:00+00:00","secret":"c2VjcmV0"}"#.to_owned();
+
+    let document: db::secret::SecretDocument = codec::decode(&mut row).unwrap();
+
+    assert_eq!(document.secret, "c2VjcmV0");
+    assert!(document.etag.is_none());
+    assert!(document.created_by.is_none());
+}
+
+#[test]
+fn unknown_codec() {
+    let mut row = "\u{1}payload".to_owned();
+
+    assert_eq!(codec::Codec::of(&row), None);
+
+    let e = codec::decode::<db::idempotency::IdempotencyDocument>(&mut row).unwrap_err();
+
+    assert!(e.to_string().contains("unknown document codec"));
+}
@@ -0,0 +1,104 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cmd;
+
+use super::Client;
+
+#[tokio::test]
+async fn unknown_command() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "NoSuchCommand": {}
+        }))
+        .await;
+    assert_eq!(response["error"], "unknown command NoSuchCommand");
+    assert_eq!(response["code"], "UnknownCommand");
+    assert_eq!(response["command"], "NoSuchCommand");
+    assert!(response["expected"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("CreateAccessKey")));
+
+    /* the session carries on after a bad command */
+    let response = client.send(serde_json::json!({ "ListRoles": [] })).await;
+    assert!(response.get("error").is_none());
+}
+
+#[tokio::test]
+async fn missing_field() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "permission": ["ListSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["command"], "CreateAccessKey");
+    assert_eq!(response["field"], "vault");
+    assert_eq!(response["code"], "InvalidRequest");
+    assert_eq!(
+        response["error"],
+        "invalid vault in CreateAccessKey: missing field `vault`"
+    );
+}
+
+#[test]
+fn invalid_variant_in_a_field() {
+    let error = cmd::Request::parse(
+        r#"{"CreateAccessKey": {"vault": "parse", "permission": ["Everything"]}}"#,
+    )
+    .unwrap_err();
+
+    assert_eq!(error.command.as_deref(), Some("CreateAccessKey"));
+    assert_eq!(error.field.as_deref(), Some("permission"));
+    assert!(error.expected.contains(&"DecryptSecrets".to_owned()));
+}
+
+#[test]
+fn invalid_type_in_a_field() {
+    let error = cmd::Request::parse(r#"{"FindVault": {"vault": 1}}"#).unwrap_err();
+
+    assert_eq!(error.command.as_deref(), Some("FindVault"));
+    assert_eq!(error.field.as_deref(), Some("vault"));
+}
+
+#[test]
+fn invalid_json() {
+    let error = cmd::Request::parse(r#"{"ListRoles": "#).unwrap_err();
+    assert!(error.error.starts_with("invalid JSON"));
+    assert_eq!(error.command, None);
+
+    let error = cmd::Request::parse(r#"{"ListRoles": [], "SessionStats": []}"#).unwrap_err();
+    assert_eq!(
+        error.error,
+        "expected an object with the command as its only key"
+    );
+}
+
+#[test]
+fn input_is_left_untouched() {
+    let data = r#"{"FindVault": {"vault": "parse"}}"#.to_owned();
+
+    assert!(cmd::Request::parse(&data).is_ok());
+    assert_eq!(data, r#"{"FindVault": {"vault": "parse"}}"#);
+}
@@ -0,0 +1,141 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, config, db, permission};
+
+use super::{create_access_key, request};
+
+fn schedule() -> config::ConfigDbCompaction {
+    config::ConfigDbCompaction {
+        interval_hours: Some(24),
+        free_ratio: Some(0.5),
+        idle_secs: Some(300),
+    }
+}
+
+#[test]
+fn compaction_waits_for_the_interval() {
+    let schedule = schedule();
+    let last_compaction = clock::now();
+
+    assert!(!db::compaction::is_due(
+        &schedule,
+        clock::now(),
+        last_compaction,
+        None,
+        None
+    ));
+
+    clock::advance(chrono::Duration::hours(24));
+
+    assert!(db::compaction::is_due(
+        &schedule,
+        clock::now(),
+        last_compaction,
+        None,
+        None
+    ));
+}
+
+#[test]
+fn compaction_waits_for_low_activity() {
+    let schedule = schedule();
+    let now = clock::now();
+    let last_compaction = now - chrono::Duration::days(2);
+
+    assert!(!db::compaction::is_due(
+        &schedule,
+        now,
+        last_compaction,
+        Some(now - chrono::Duration::seconds(10)),
+        None
+    ));
+    assert!(db::compaction::is_due(
+        &schedule,
+        now,
+        last_compaction,
+        Some(now - chrono::Duration::seconds(300)),
+        None
+    ));
+}
+
+#[test]
+fn compaction_on_free_ratio() {
+    let schedule = config::ConfigDbCompaction {
+        interval_hours: None,
+        ..schedule()
+    };
+    let now = clock::now();
+    let last_compaction = now - chrono::Duration::hours(2);
+
+    assert!(!db::compaction::is_due(
+        &schedule,
+        now,
+        last_compaction,
+        None,
+        Some(0.4)
+    ));
+    assert!(db::compaction::is_due(
+        &schedule,
+        now,
+        last_compaction,
+        None,
+        Some(0.6)
+    ));
+
+    /* right after a compaction the ratio alone doesn't trigger another */
+    assert!(!db::compaction::is_due(
+        &schedule,
+        now,
+        now - chrono::Duration::minutes(5),
+        None,
+        Some(0.6)
+    ));
+}
+
+#[tokio::test]
+async fn compact_the_database() {
+    let authorization = create_access_key(
+        "compaction",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::ListSecrets,
+            permission::VaultRoles::DeleteSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    for i in 0..32 {
+        let path = format!("/compaction/password-{}", i);
+
+        let response = request("PUT", &path, &headers, b"hunter2").await;
+        assert_eq!(response.status, 201);
+
+        let response = request("DELETE", &path, &headers, b"").await;
+        assert_eq!(response.status, 200);
+    }
+
+    /* the other tests keep read transactions open now and then */
+    for _ in 0..100 {
+        match db::compaction::compact().unwrap() {
+            db::compaction::CompactResult::Compacted { .. } => return,
+            _ => tokio::time::sleep(tokio::time::Duration::from_millis(20)).await,
+        }
+    }
+
+    panic!("the DB was never compacted");
+}
@@ -0,0 +1,161 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Read;
+
+use crate::{compression::Compression, permission};
+
+use super::{create_access_key, request, Client};
+
+fn value() -> Vec<u8> {
+    /* a RSA wrapped secret holds a block's worth at most */
+    "-----BEGIN CERTIFICATE-----\nMIIBszCCAVmgAwIBAgIUQ\n-----END CERTIFICATE-----\n"
+        .repeat(6)
+        .into_bytes()
+}
+
+fn gunzip(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut result)
+        .unwrap();
+
+    result
+}
+
+#[test]
+fn negotiate() {
+    assert_eq!(Compression::negotiate("gzip"), Some(Compression::Gzip));
+    assert_eq!(
+        Compression::negotiate("gzip, deflate, br, zstd"),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(
+        Compression::negotiate("zstd;q=0.5, gzip"),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(Compression::negotiate("gzip;q=0, zstd;q=0"), None);
+    assert_eq!(Compression::negotiate("identity, br"), None);
+    assert_eq!(Compression::negotiate(""), None);
+}
+
+#[tokio::test]
+async fn compressed_get() {
+    let authorization = create_access_key(
+        "compression",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let value = value();
+
+    let response = request("PUT", "/compression/bundle", &headers, &value).await;
+    assert_eq!(response.status, 201);
+
+    let plain = request("GET", "/compression/bundle", &headers, b"").await;
+    assert_eq!(plain.status, 200);
+    assert_eq!(plain.header("Content-Encoding"), None);
+    assert_eq!(plain.body, value);
+
+    let gzip_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Accept-Encoding", "gzip"),
+    ];
+
+    let response = request("GET", "/compression/bundle", &gzip_headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.header("Vary"), Some("Accept-Encoding"));
+    assert!(response.body.len() < value.len());
+    assert_eq!(gunzip(&response.body), value);
+
+    /* the compressed representation has its own validator */
+    let etag = response.header("ETag").unwrap().to_owned();
+    assert_ne!(Some(etag.as_str()), plain.header("ETag"));
+
+    let conditional_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Accept-Encoding", "gzip"),
+        ("If-None-Match", etag.as_str()),
+    ];
+
+    let response = request("GET", "/compression/bundle", &conditional_headers, b"").await;
+    assert_eq!(response.status, 304);
+
+    let zstd_headers = [
+        ("Authorization", authorization.as_str()),
+        ("Accept-Encoding", "gzip;q=0.8, zstd"),
+    ];
+
+    let response = request("GET", "/compression/bundle", &zstd_headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), Some("zstd"));
+    assert!(response.body.len() < value.len());
+    assert_eq!(zstd::decode_all(response.body.as_slice()).unwrap(), value);
+}
+
+#[tokio::test]
+async fn compressed_find() {
+    let mut client = Client::login_root().await;
+    let value = value();
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "compression-cmd",
+                "secret_name": "bundle",
+                "data": base64_simd::STANDARD.encode_to_string(&value)
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "compression-cmd",
+                "secret_name": "bundle"
+            }
+        }))
+        .await;
+    assert!(response["Found"].get("compression").is_none());
+
+    for (compression, decompress) in [
+        ("Gzip", gunzip as fn(&[u8]) -> Vec<u8>),
+        ("Zstd", |v: &[u8]| zstd::decode_all(v).unwrap()),
+    ] {
+        let response = client
+            .send(serde_json::json!({
+                "FindSecret": {
+                    "vault": "compression-cmd",
+                    "secret_name": "bundle",
+                    "compression": compression
+                }
+            }))
+            .await;
+        assert_eq!(response["Found"]["compression"], compression);
+
+        let secret = base64_simd::STANDARD
+            .decode_to_vec(response["Found"]["secret"].as_str().unwrap())
+            .unwrap();
+        assert!(secret.len() < value.len());
+        assert_eq!(decompress(&secret), value);
+    }
+}
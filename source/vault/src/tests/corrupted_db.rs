@@ -0,0 +1,90 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::db;
+
+const TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("table");
+
+fn open(content: &[u8], repair: bool) -> (crate::app_error::AppResult<redb::Database>, Vec<u8>) {
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let database_path = directory.path().join("vaulty.redb");
+
+    std::fs::write(&database_path, content).unwrap();
+
+    let result = db::open(&database_path, repair);
+
+    (result, std::fs::read(&database_path).unwrap())
+}
+
+fn valid_db() -> Vec<u8> {
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let database_path = directory.path().join("vaulty.redb");
+
+    {
+        let database = redb::Database::create(&database_path).unwrap();
+        let txn = database.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TABLE).unwrap();
+
+            for i in 0..1000 {
+                table.insert(i.to_string().as_str(), "value").unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+
+    std::fs::read(&database_path).unwrap()
+}
+
+#[test]
+fn garbage_refused() {
+    super::server();
+
+    let mut garbage = vec![0u8; 64 * 1024];
+    openssl::rand::rand_bytes(&mut garbage).unwrap();
+
+    for content in [garbage.as_slice(), b"", b"redb"] {
+        let (result, after) = open(content, true);
+        let Err(error) = result else {
+            panic!("a corrupted DB was opened");
+        };
+
+        assert_eq!(error.message, "the DB file is corrupted or truncated");
+        assert!(error.attr.unwrap().contains_key("filename"));
+        /* never recreated over */
+        assert_eq!(after, content);
+    }
+}
+
+#[test]
+fn truncated_refused() {
+    super::server();
+
+    let content = valid_db();
+    assert!(open(&content, false).0.is_ok());
+
+    for length in [content.len() / 2, 1024, 512] {
+        let truncated = &content[..length];
+        let (result, after) = open(truncated, false);
+        let Err(error) = result else {
+            panic!("a truncated DB was opened");
+        };
+
+        assert_eq!(error.message, "the DB file is corrupted or truncated");
+        assert_eq!(after, truncated);
+    }
+}
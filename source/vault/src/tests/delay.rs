@@ -0,0 +1,102 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, clock, permission};
+
+use super::{create_access_key, create_scoped_access_key, request};
+
+#[test]
+fn jitter_band() {
+    let samples: Vec<std::time::Duration> = (0..1000).map(|_| clock::jittered(100, 50)).collect();
+
+    assert!(samples.iter().all(|v| {
+        *v >= std::time::Duration::from_millis(100) && *v <= std::time::Duration::from_millis(150)
+    }));
+
+    /* a thousand draws from 51 values landing on one would mean no jitter */
+    assert!(samples.iter().any(|v| *v != samples[0]));
+}
+
+#[test]
+fn no_jitter() {
+    for _ in 0..100 {
+        assert_eq!(
+            clock::jittered(100, 0),
+            std::time::Duration::from_millis(100)
+        );
+    }
+}
+
+#[tokio::test]
+async fn delay_sleeps_at_least_the_base() {
+    super::server();
+
+    for _ in 0..5 {
+        let started = std::time::Instant::now();
+
+        access_keys::delay().await;
+
+        assert!(started.elapsed() >= std::time::Duration::from_millis(10));
+    }
+
+    /* without jitter configured a granted request isn't held back */
+    let started = std::time::Instant::now();
+
+    access_keys::delay_granted().await;
+
+    assert!(started.elapsed() < std::time::Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn forbidden_is_delayed() {
+    let missing_permission =
+        create_access_key("delay-forbidden", vec![permission::VaultRoles::ListSecrets]);
+    let out_of_scope = create_scoped_access_key(
+        "delay-forbidden",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+        vec!["app1-".to_owned()],
+    );
+
+    for (method, path, authorization) in [
+        ("GET", "/delay-forbidden/app1-db", &missing_permission),
+        ("POST", "/delay-forbidden/app1-db", &missing_permission),
+        ("GET", "/delay-forbidden/app2-db", &out_of_scope),
+        ("HEAD", "/delay-forbidden/app2-db", &out_of_scope),
+        ("POST", "/delay-forbidden/app2-db", &out_of_scope),
+    ] {
+        let started = std::time::Instant::now();
+
+        let response = request(
+            method,
+            path,
+            &[("Authorization", authorization.as_str())],
+            b"",
+        )
+        .await;
+
+        assert_eq!(response.status, 403, "{method} {path}");
+        /* as long as a refusal for a wrong secret access key */
+        assert!(
+            started.elapsed() >= std::time::Duration::from_millis(10),
+            "{method} {path}"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, config, db, permission};
+
+use super::{authorization, create_access_key, request, ROOT_USERNAME};
+
+/* the events of the shared server's log that denied this access key */
+fn denied_events(access_key: &str) -> Vec<serde_json::Value> {
+    let filename = config::get_clone().log.unwrap().filename;
+
+    std::fs::read_to_string(filename)
+        .unwrap()
+        .lines()
+        .filter_map(|v| serde_json::from_str::<serde_json::Value>(v).ok())
+        .filter(|v| v["msg"] == "access was denied" && v["attr"]["access_key"] == access_key)
+        .collect()
+}
+
+#[tokio::test]
+async fn denied_reasons_logged() {
+    let authorization_header =
+        create_access_key("denied-log", vec![permission::VaultRoles::DecryptSecrets]);
+    let (access_key, secret_access_key) = authorization_header
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let elsewhere = access_keys::create(
+        "denied-log",
+        Some(vec!["10.0.0.0/8".to_owned(), "192.168.0.0/16".to_owned()]),
+        Some(vec![permission::VaultRoles::DecryptSecrets]),
+        None,
+        Vec::new(),
+        db::Actor::User(ROOT_USERNAME.to_owned()),
+    )
+    .unwrap();
+
+    let wrong_secret = "x".repeat(secret_access_key.len());
+    let unknown_key = "y".repeat(access_key.len());
+
+    for authorization_header in [
+        authorization(&elsewhere.access_key, &elsewhere.secret_access_key),
+        authorization(access_key, &wrong_secret),
+        authorization(&unknown_key, secret_access_key),
+    ] {
+        let response = request(
+            "GET",
+            "/denied-log/password",
+            &[("Authorization", authorization_header.as_str())],
+            b"",
+        )
+        .await;
+        assert_eq!(response.status, 401);
+    }
+
+    let events = denied_events(&elsewhere.access_key);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0]["err"]["message"],
+        "ip is not in the security group"
+    );
+    assert_eq!(events[0]["attr"]["ip"], "127.0.0.1");
+    assert_eq!(events[0]["attr"]["vault"], "denied-log");
+    assert_eq!(
+        events[0]["attr"]["sg"],
+        serde_json::json!(["10.0.0.0/8", "192.168.0.0/16"])
+    );
+
+    let events = denied_events(access_key);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["err"]["message"], "invalid secret access key");
+    assert_eq!(events[0]["attr"]["ip"], "127.0.0.1");
+    assert!(events[0]["attr"].get("sg").is_none());
+    /* neither the presented secret nor the stored one */
+    assert!(!events[0].to_string().contains(&wrong_secret));
+    assert!(!events[0].to_string().contains(secret_access_key));
+
+    let events = denied_events(&unknown_key);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0]["err"]["message"],
+        "access key doesn't exist in the vault"
+    );
+
+    /* a key that is let in but lacks the permission */
+    let read_only = create_access_key("denied-log", vec![permission::VaultRoles::DecryptSecrets]);
+    let read_only_key = read_only
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap()
+        .0;
+
+    let response = request(
+        "PUT",
+        "/denied-log/password",
+        &[("Authorization", read_only.as_str())],
+        b"hunter2",
+    )
+    .await;
+    assert_eq!(response.status, 403);
+
+    let events = denied_events(read_only_key);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0]["err"]["message"],
+        "access key doesn't have the required permission"
+    );
+    assert_eq!(events[0]["attr"]["ip"], "127.0.0.1");
+    assert_eq!(events[0]["attr"]["vault"], "denied-log");
+    assert_eq!(events[0]["attr"]["permission"], "CreateSecrets");
+}
@@ -0,0 +1,53 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, log};
+
+#[test]
+fn database_in_missing_directory() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("nested/data/database.bin");
+
+    let database = db::create(&path).unwrap();
+    drop(database);
+
+    assert!(path.is_file());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
+
+#[test]
+fn log_in_missing_directory() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("nested/logs/vault.log");
+
+    log::open(path.to_str().unwrap()).unwrap();
+
+    assert!(path.is_file());
+
+    /* an existing directory is reused as it is */
+    log::open(path.to_str().unwrap()).unwrap();
+}
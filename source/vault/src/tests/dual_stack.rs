@@ -0,0 +1,95 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::sg;
+
+use super::Client;
+
+fn network(sg: &str) -> ipnetwork::IpNetwork {
+    sg::expand(sg).parse().unwrap()
+}
+
+fn ip(ip: &str) -> std::net::IpAddr {
+    ip.parse().unwrap()
+}
+
+#[test]
+fn dual_stack_client_matches_ipv4_sg() {
+    let sg = network("10.1.0.0/16");
+
+    assert!(!sg::contains_with(&sg, ip("::ffff:10.1.2.3"), false));
+    assert!(sg::contains_with(&sg, ip("::ffff:10.1.2.3"), true));
+    assert!(!sg::contains_with(&sg, ip("::ffff:10.2.2.3"), true));
+    assert!(!sg::contains_with(&sg, ip("2001:db8::1"), true));
+
+    let loopback = network("127.0.0.1/32");
+
+    assert!(!sg::contains_with(&loopback, ip("::1"), false));
+    assert!(sg::contains_with(&loopback, ip("::1"), true));
+}
+
+#[test]
+fn ipv4_client_matches_ipv6_sg() {
+    let sg = network("::ffff:10.1.0.0/112");
+
+    assert!(!sg::contains_with(&sg, ip("10.1.2.3"), false));
+    assert!(sg::contains_with(&sg, ip("10.1.2.3"), true));
+
+    assert!(sg::contains_with(
+        &network("::1/128"),
+        ip("127.0.0.1"),
+        true
+    ));
+}
+
+#[test]
+fn any_shorthands() {
+    assert!(sg::contains_with(&network("any4"), ip("192.0.2.1"), false));
+    assert!(!sg::contains_with(
+        &network("any4"),
+        ip("2001:db8::1"),
+        false
+    ));
+    assert!(sg::contains_with(
+        &network("any6"),
+        ip("2001:db8::1"),
+        false
+    ));
+    assert!(!sg::contains_with(&network("any6"), ip("192.0.2.1"), false));
+    assert!(sg::contains_with(&network("any6"), ip("192.0.2.1"), true));
+}
+
+#[tokio::test]
+async fn any4_user_login() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "any4-user",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["any4"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (_, response) = Client::login("any4-user", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+    assert_eq!(response["sg"], serde_json::json!(["0.0.0.0/0"]));
+}
@@ -0,0 +1,216 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, db, permission, secrets};
+
+use super::{create_access_key, request, Client};
+
+#[test]
+fn ciphertext_bound_to_its_name() {
+    super::server();
+
+    let encrypted = secrets::encrypt("context", "first", b"hunter2").unwrap();
+
+    assert_eq!(
+        secrets::decrypt("context", "first", &encrypted).unwrap(),
+        b"hunter2"
+    );
+    assert!(secrets::decrypt("context", "second", &encrypted).is_err());
+    assert!(secrets::decrypt("other", "first", &encrypted).is_err());
+    /* the separator keeps the two names apart */
+    assert!(secrets::decrypt("context|", "|first", &encrypted).is_err());
+}
+
+/* the way secrets were encrypted before they were bound to their name */
+fn legacy_encrypt(plain: &[u8]) -> Vec<u8> {
+    secrets::SecretsEngine::load(&config::get_clone().secrets)
+        .unwrap()
+        .encrypt(plain, b"")
+        .unwrap()
+}
+
+#[test]
+fn unbound_ciphertext_refused() {
+    super::server();
+
+    assert!(secrets::decrypt("context", "first", &legacy_encrypt(b"hunter2")).is_err());
+}
+
+#[tokio::test]
+async fn legacy_secret_bound() {
+    let authorization = create_access_key(
+        "context-legacy",
+        vec![permission::VaultRoles::DecryptSecrets],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    db::secret::insert(
+        "context-legacy",
+        "password",
+        db::secret::SecretDocument {
+            created: "2024-01-01T00:00:00+00:00".to_owned(),
+            secret: base64_simd::STANDARD.encode_to_string(legacy_encrypt(b"hunter2")),
+            etag: None,
+            last_accessed: None,
+            created_by: None,
+            alias: None,
+            bound: false,
+        },
+        false,
+    )
+    .unwrap();
+
+    secrets::bind_legacy().unwrap();
+
+    let document = db::secret::find("context-legacy", "password")
+        .unwrap()
+        .unwrap();
+    assert!(document.bound);
+
+    let response = request("GET", "/context-legacy/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    /* once bound it's no use under another name */
+    db::secret::insert("context-legacy", "moved", document, false).unwrap();
+
+    let response = request("GET", "/context-legacy/moved", &headers, b"").await;
+    assert_eq!(response.status, 500);
+}
+
+#[tokio::test]
+async fn undecryptable_legacy_secret_left_unbound() {
+    for (secret_name, secret) in [
+        ("broken", vec![0u8; 600]),
+        ("password", legacy_encrypt(b"hunter2")),
+    ] {
+        db::secret::insert(
+            "context-legacy-broken",
+            secret_name,
+            db::secret::SecretDocument {
+                created: "2024-01-01T00:00:00+00:00".to_owned(),
+                secret: base64_simd::STANDARD.encode_to_string(secret),
+                etag: None,
+                last_accessed: None,
+                created_by: None,
+                alias: None,
+                bound: false,
+            },
+            false,
+        )
+        .unwrap();
+    }
+
+    /* one secret that can't be sealed doesn't hold back the others */
+    secrets::bind_legacy().unwrap();
+
+    let find = |secret_name| {
+        db::secret::find("context-legacy-broken", secret_name)
+            .unwrap()
+            .unwrap()
+    };
+    assert!(!find("broken").bound);
+    assert!(find("password").bound);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "VerifyIntegrity": {
+                "vault": "context-legacy-broken"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"]["Failed"]["secret_name"], "broken");
+
+    let response = client.receive().await;
+    assert_eq!(response["result"]["Done"]["secrets"], 2);
+    assert_eq!(response["result"]["Done"]["failed"], 1);
+}
+
+#[tokio::test]
+async fn swapped_secret_fails() {
+    let authorization = create_access_key(
+        "context-swap",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/context-swap/first", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let document = db::secret::find("context-swap", "first").unwrap().unwrap();
+    db::secret::insert("context-swap", "second", document, false).unwrap();
+
+    let response = request("GET", "/context-swap/second", &headers, b"").await;
+    assert_eq!(response.status, 500);
+
+    let response = request("GET", "/context-swap/first", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
+
+#[tokio::test]
+async fn renamed_vault_keeps_its_secrets() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateVault": {
+                "vault": "context-before"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "context-before",
+                "secret_name": "password",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "RenameVault": {
+                "vault": "context-before",
+                "name": "context-after"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Renamed");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "context-after",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string("hunter2")
+    );
+}
@@ -0,0 +1,336 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use aes_gcm::{aead::Aead, KeyInit};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+
+use crate::{
+    config,
+    secrets::{SecretsEngine, SecretsKeys},
+};
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut result = vec![0u8; len];
+
+    openssl::rand::rand_bytes(&mut result).unwrap();
+
+    result
+}
+
+fn rsa_keys() -> (rsa::RsaPrivateKey, rsa::RsaPublicKey) {
+    rsa_keys_from(&openssl::rsa::Rsa::generate(4096).unwrap())
+}
+
+fn rsa_keys_from(
+    key: &openssl::rsa::Rsa<openssl::pkey::Private>,
+) -> (rsa::RsaPrivateKey, rsa::RsaPublicKey) {
+    let key = openssl::pkey::PKey::from_rsa(key.clone()).unwrap();
+
+    let private_key = String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+    let public_key = String::from_utf8(key.public_key_to_pem().unwrap()).unwrap();
+
+    (
+        rsa::RsaPrivateKey::from_pkcs8_pem(&private_key).unwrap(),
+        rsa::RsaPublicKey::from_public_key_pem(&public_key).unwrap(),
+    )
+}
+
+fn ec_keys() -> (p256::SecretKey, p256::PublicKey) {
+    let private_key = p256::SecretKey::random(&mut rand::rngs::OsRng);
+    let public_key = private_key.public_key();
+
+    (private_key, public_key)
+}
+
+fn engine(scheme: config::SecretsScheme, keys: &SecretsKeys) -> SecretsEngine {
+    SecretsEngine::new(
+        scheme,
+        SecretsKeys {
+            rsa: keys.rsa.clone(),
+            ec: keys.ec.clone(),
+            aes_key: keys.aes_key.clone(),
+            aes_iv: keys.aes_iv.clone(),
+        },
+    )
+    .unwrap()
+}
+
+fn keys() -> SecretsKeys {
+    SecretsKeys {
+        rsa: Some(rsa_keys()),
+        ec: Some(ec_keys()),
+        aes_key: random_bytes(32),
+        aes_iv: random_bytes(12),
+    }
+}
+
+#[test]
+fn engine_round_trip() {
+    let keys = keys();
+
+    for scheme in [config::SecretsScheme::Rsa, config::SecretsScheme::Ec] {
+        let engine = engine(scheme, &keys);
+
+        for plain in [b"hunter2".to_vec(), random_bytes(400)] {
+            let encrypted = engine.encrypt(&plain, b"tests||secret").unwrap();
+
+            assert_ne!(encrypted, plain);
+            assert_eq!(engine.decrypt(&encrypted, b"tests||secret").unwrap(), plain);
+        }
+    }
+}
+
+#[test]
+fn engine_ec_round_trip_without_rsa_keys() {
+    let engine = SecretsEngine::new(
+        config::SecretsScheme::Ec,
+        SecretsKeys {
+            rsa: None,
+            ec: Some(ec_keys()),
+            aes_key: random_bytes(32),
+            aes_iv: random_bytes(12),
+        },
+    )
+    .unwrap();
+
+    let plain = random_bytes(4096);
+    let encrypted = engine.encrypt(&plain, b"tests||secret").unwrap();
+
+    assert_eq!(encrypted[0], 0xEC);
+    assert_eq!(engine.decrypt(&encrypted, b"tests||secret").unwrap(), plain);
+}
+
+#[test]
+fn engine_dispatches_on_scheme_tag() {
+    let keys = keys();
+
+    let rsa_engine = engine(config::SecretsScheme::Rsa, &keys);
+    let ec_engine = engine(config::SecretsScheme::Ec, &keys);
+
+    let rsa_encrypted = rsa_engine.encrypt(b"hunter2", b"tests||secret").unwrap();
+    let ec_encrypted = ec_engine.encrypt(b"hunter2", b"tests||secret").unwrap();
+
+    assert_eq!(rsa_encrypted[0], 0x52);
+    assert_eq!(rsa_encrypted.len() % 512, 1);
+    assert_eq!(ec_encrypted[0], 0xEC);
+
+    for engine in [&rsa_engine, &ec_engine] {
+        assert_eq!(
+            engine.decrypt(&rsa_encrypted, b"tests||secret").unwrap(),
+            b"hunter2"
+        );
+        assert_eq!(
+            engine.decrypt(&ec_encrypted, b"tests||secret").unwrap(),
+            b"hunter2"
+        );
+    }
+}
+
+/* the way RSA blobs were written before the schemes were tagged, retried
+until `first_byte` accepts the blob's first byte */
+fn untagged_rsa_blob(
+    key: &openssl::rsa::Rsa<openssl::pkey::Private>,
+    keys: &SecretsKeys,
+    first_byte: impl Fn(u8) -> bool,
+) -> Vec<u8> {
+    let data = aes_gcm::Aes256Gcm::new_from_slice(&keys.aes_key)
+        .unwrap()
+        .encrypt(
+            aes_gcm::Nonce::from_slice(&keys.aes_iv),
+            aes_gcm::aead::Payload {
+                msg: b"hunter2",
+                aad: b"tests||secret",
+            },
+        )
+        .unwrap();
+
+    let mut encrypted = vec![0u8; 512];
+
+    while {
+        key.public_encrypt(&data, &mut encrypted, openssl::rsa::Padding::PKCS1)
+            .unwrap();
+
+        !first_byte(encrypted[0])
+    } {}
+
+    encrypted
+}
+
+#[test]
+fn engine_reads_untagged_rsa_blobs() {
+    /* a RSA blob is smaller than the modulus, so only some keys can produce the tags */
+    let key = std::iter::repeat_with(|| openssl::rsa::Rsa::generate(4096).unwrap())
+        .find(|v| v.n().to_vec()[0] > 0xEC)
+        .unwrap();
+    let keys = SecretsKeys {
+        rsa: Some(rsa_keys_from(&key)),
+        ec: Some(ec_keys()),
+        aes_key: random_bytes(32),
+        aes_iv: random_bytes(12),
+    };
+    let engine = engine(config::SecretsScheme::Ec, &keys);
+
+    for encrypted in [
+        untagged_rsa_blob(&key, &keys, |v| v != 0x52 && v != 0xEC),
+        untagged_rsa_blob(&key, &keys, |v| v == 0x52),
+        untagged_rsa_blob(&key, &keys, |v| v == 0xEC),
+    ] {
+        assert_eq!(
+            engine.decrypt(&encrypted, b"tests||secret").unwrap(),
+            b"hunter2"
+        );
+    }
+}
+
+#[test]
+fn engines_do_not_cross_decrypt() {
+    for scheme in [config::SecretsScheme::Rsa, config::SecretsScheme::Ec] {
+        let first = engine(scheme.clone(), &keys());
+        let second = engine(scheme, &keys());
+
+        assert!(second
+            .decrypt(
+                &first.encrypt(b"hunter2", b"tests||secret").unwrap(),
+                b"tests||secret"
+            )
+            .is_err());
+        assert!(first
+            .decrypt(
+                &second.encrypt(b"hunter2", b"tests||secret").unwrap(),
+                b"tests||secret"
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn engines_with_different_aes_keys_do_not_cross_decrypt() {
+    let first_keys = keys();
+    let second_keys = SecretsKeys {
+        rsa: first_keys.rsa.clone(),
+        ec: first_keys.ec.clone(),
+        aes_key: random_bytes(32),
+        aes_iv: first_keys.aes_iv.clone(),
+    };
+
+    for scheme in [config::SecretsScheme::Rsa, config::SecretsScheme::Ec] {
+        let first = engine(scheme.clone(), &first_keys);
+        let second = engine(scheme, &second_keys);
+
+        assert!(second
+            .decrypt(
+                &first.encrypt(b"hunter2", b"tests||secret").unwrap(),
+                b"tests||secret"
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn engine_rejects_invalid_keys() {
+    let keys = keys();
+
+    assert!(SecretsEngine::new(
+        config::SecretsScheme::Rsa,
+        SecretsKeys {
+            rsa: keys.rsa.clone(),
+            ec: None,
+            aes_key: random_bytes(16),
+            aes_iv: random_bytes(12),
+        }
+    )
+    .is_err());
+    assert!(SecretsEngine::new(
+        config::SecretsScheme::Rsa,
+        SecretsKeys {
+            rsa: keys.rsa.clone(),
+            ec: None,
+            aes_key: random_bytes(32),
+            aes_iv: random_bytes(8),
+        }
+    )
+    .is_err());
+    assert!(SecretsEngine::new(
+        config::SecretsScheme::Rsa,
+        SecretsKeys {
+            rsa: None,
+            ec: keys.ec.clone(),
+            aes_key: random_bytes(32),
+            aes_iv: random_bytes(12),
+        }
+    )
+    .is_err());
+    assert!(SecretsEngine::new(
+        config::SecretsScheme::Ec,
+        SecretsKeys {
+            rsa: keys.rsa,
+            ec: None,
+            aes_key: random_bytes(32),
+            aes_iv: random_bytes(12),
+        }
+    )
+    .is_err());
+}
+
+#[test]
+fn engine_rejects_empty_data() {
+    let engine = engine(config::SecretsScheme::Ec, &keys());
+
+    assert!(engine.encrypt(b"", b"tests||secret").is_err());
+    assert!(engine.decrypt(b"", b"tests||secret").is_err());
+    assert!(engine.decrypt(&[0xEC], b"tests||secret").is_err());
+    assert!(engine.decrypt(&[0x52], b"tests||secret").is_err());
+    /* neither tagged nor whole RSA blocks */
+    assert!(engine.decrypt(&[0x01; 100], b"tests||secret").is_err());
+}
+
+#[test]
+fn self_test_catches_mismatched_pairs() {
+    let keys = keys();
+
+    engine(config::SecretsScheme::Rsa, &keys)
+        .self_test()
+        .unwrap();
+
+    let (private_key, _) = rsa_keys();
+    let (_, public_key) = keys.rsa.clone().unwrap();
+    let mismatched = SecretsKeys {
+        rsa: Some((private_key, public_key)),
+        ec: keys.ec.clone(),
+        aes_key: keys.aes_key.clone(),
+        aes_iv: keys.aes_iv.clone(),
+    };
+
+    /* the RSA pair is checked even when new secrets go to EC */
+    for scheme in [config::SecretsScheme::Rsa, config::SecretsScheme::Ec] {
+        let error = engine(scheme, &mismatched).self_test().unwrap_err();
+        assert_eq!(error.message, "secrets self-test failed");
+        assert!(error.error.unwrap().contains("RSA"));
+    }
+
+    let mismatched = SecretsKeys {
+        rsa: None,
+        ec: Some((ec_keys().0, keys.ec.clone().unwrap().1)),
+        aes_key: keys.aes_key.clone(),
+        aes_iv: keys.aes_iv.clone(),
+    };
+
+    let error = engine(config::SecretsScheme::Ec, &mismatched)
+        .self_test()
+        .unwrap_err();
+    assert!(error.error.unwrap().contains("EC"));
+}
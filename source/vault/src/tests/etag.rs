@@ -0,0 +1,90 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request};
+
+#[tokio::test]
+async fn not_modified_with_etag() {
+    let authorization = create_access_key(
+        "etag",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/etag/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/etag/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let etag = response.header("ETag").expect("missing ETag").to_owned();
+    let last_modified = response
+        .header("Last-Modified")
+        .expect("missing Last-Modified")
+        .to_owned();
+
+    let response = request(
+        "GET",
+        "/etag/password",
+        &[headers[0], ("If-None-Match", etag.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 304);
+    assert!(response.body.is_empty());
+    assert_eq!(response.header("ETag"), Some(etag.as_str()));
+
+    let response = request(
+        "GET",
+        "/etag/password",
+        &[headers[0], ("If-Modified-Since", last_modified.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 304);
+
+    let response = request(
+        "GET",
+        "/etag/password",
+        &[headers[0], ("If-None-Match", "\"another\"")],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    /* a new value, a new tag */
+    let response = request("PUT", "/etag/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+
+    let response = request(
+        "GET",
+        "/etag/password",
+        &[headers[0], ("If-None-Match", etag.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter3");
+    assert_ne!(response.header("ETag"), Some(etag.as_str()));
+}
@@ -0,0 +1,102 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, create_scoped_access_key, request, Client};
+
+#[tokio::test]
+async fn head_secret() {
+    let authorization = create_access_key(
+        "exists",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    for name in ["app1-db", "app2-db"] {
+        let response = request("POST", &format!("/exists/{name}"), &headers, b"hunter2").await;
+        assert_eq!(response.status, 201);
+    }
+
+    let response = request("HEAD", "/exists/app1-db", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert!(response.body.is_empty());
+
+    /* the same validators as the value itself */
+    let etag = response.header("ETag").expect("missing ETag").to_owned();
+    let response = request("GET", "/exists/app1-db", &headers, b"").await;
+    assert_eq!(response.header("ETag"), Some(etag.as_str()));
+
+    let response = request("HEAD", "/exists/missing", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let scoped = create_scoped_access_key(
+        "exists",
+        vec![permission::VaultRoles::ListSecrets],
+        vec!["app1-".to_owned()],
+    );
+    let scoped = [("Authorization", scoped.as_str())];
+
+    let response = request("HEAD", "/exists/app1-db", &scoped, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("HEAD", "/exists/app2-db", &scoped, b"").await;
+    assert_eq!(response.status, 403);
+
+    /* out of scope whether it exists or not */
+    let response = request("HEAD", "/exists/app2-missing", &scoped, b"").await;
+    assert_eq!(response.status, 403);
+}
+
+#[tokio::test]
+async fn exists_secret_command() {
+    let authorization = create_access_key(
+        "exists-command",
+        vec![permission::VaultRoles::CreateSecrets],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/exists-command/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "ExistsSecret": {
+                "vault": "exists-command",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(response["exists"], true);
+    assert!(response["created"].is_string(), "{response}");
+
+    let response = client
+        .send(serde_json::json!({
+            "ExistsSecret": {
+                "vault": "exists-command",
+                "secret_name": "missing"
+            }
+        }))
+        .await;
+    assert_eq!(response["exists"], false);
+}
@@ -0,0 +1,138 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+async fn generate(
+    client: &mut Client,
+    secret_name: &str,
+    arguments: serde_json::Value,
+) -> serde_json::Value {
+    let mut request = serde_json::json!({
+        "vault": "generate",
+        "secret_name": secret_name
+    });
+
+    request
+        .as_object_mut()
+        .unwrap()
+        .extend(arguments.as_object().unwrap().clone());
+
+    client
+        .send(serde_json::json!({ "GenerateSecret": request }))
+        .await
+}
+
+async fn read_back(secret_name: &str) -> Vec<u8> {
+    let authorization = create_access_key("generate", vec![permission::VaultRoles::DecryptSecrets]);
+
+    let response = request(
+        "GET",
+        &format!("/generate/{secret_name}"),
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+
+    response.body
+}
+
+#[tokio::test]
+async fn generated_secret_has_the_requested_shape() {
+    let mut client = Client::login_root().await;
+
+    let response = generate(
+        &mut client,
+        "hex",
+        serde_json::json!({ "length": 48, "charset": "Hex" }),
+    )
+    .await;
+    assert_eq!(response, serde_json::json!({ "result": "Inserted" }));
+
+    let secret = read_back("hex").await;
+    assert_eq!(secret.len(), 48);
+    assert!(secret
+        .iter()
+        .all(|v| v.is_ascii_hexdigit() && !v.is_ascii_uppercase()));
+
+    let response = generate(
+        &mut client,
+        "numeric",
+        serde_json::json!({ "length": 6, "charset": "Numeric" }),
+    )
+    .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let secret = read_back("numeric").await;
+    assert_eq!(secret.len(), 6);
+    assert!(secret.iter().all(|v| v.is_ascii_digit()));
+
+    /* a second generate replaces the value */
+    let response = generate(
+        &mut client,
+        "numeric",
+        serde_json::json!({ "length": 12, "charset": "Numeric" }),
+    )
+    .await;
+    assert_eq!(response["result"], "Updated");
+    assert_eq!(read_back("numeric").await.len(), 12);
+}
+
+#[tokio::test]
+async fn generated_secret_defaults() {
+    let mut client = Client::login_root().await;
+
+    let response = generate(&mut client, "default", serde_json::json!({})).await;
+    assert_eq!(response["result"], "Inserted");
+
+    let secret = read_back("default").await;
+    assert_eq!(secret.len(), 32);
+    assert!(secret.iter().all(|v| v.is_ascii_alphanumeric()));
+
+    let first = read_back("default").await;
+    let response = generate(&mut client, "default", serde_json::json!({})).await;
+    assert_eq!(response["result"], "Updated");
+    assert_ne!(read_back("default").await, first);
+}
+
+#[tokio::test]
+async fn generated_secret_invalid_length() {
+    let mut client = Client::login_root().await;
+
+    for length in [0, 4097] {
+        let response = generate(
+            &mut client,
+            "invalid",
+            serde_json::json!({ "length": length }),
+        )
+        .await;
+        assert_eq!(response["result"], "InvalidLength");
+    }
+
+    let response = client
+        .send(serde_json::json!({
+            "ExistsSecret": {
+                "vault": "generate",
+                "secret_name": "invalid"
+            }
+        }))
+        .await;
+    assert_eq!(response["exists"], false);
+}
@@ -0,0 +1,139 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::Client;
+
+async fn insert(client: &mut Client, vault: &str, secret_name: &str, data: &[u8]) {
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": vault,
+                "secret_name": secret_name,
+                "data": base64_simd::STANDARD.encode_to_string(data)
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+}
+
+#[tokio::test]
+async fn existing_and_missing_names() {
+    let mut client = Client::login_root().await;
+
+    insert(&mut client, "batch", "db-password", b"hunter2").await;
+    insert(&mut client, "batch", "db-username", b"admin").await;
+    insert(&mut client, "batch", "api-token", b"token").await;
+
+    let response = client
+        .send(serde_json::json!({
+            "GetSecrets": {
+                "vault": "batch",
+                "names": ["api-token", "missing", "db-*", "nothing-*", "api-token"]
+            }
+        }))
+        .await;
+
+    let entries = response["Secrets"].as_array().unwrap();
+    let names: Vec<&str> = entries
+        .iter()
+        .map(|v| v["secret_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "api-token",
+            "missing",
+            "db-password",
+            "db-username",
+            "nothing-*"
+        ]
+    );
+
+    assert_eq!(
+        entries[0]["result"]["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string("token")
+    );
+    assert_eq!(entries[1]["result"], "NotFound");
+    assert_eq!(
+        entries[2]["result"]["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string("hunter2")
+    );
+    assert_eq!(
+        entries[3]["result"]["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string("admin")
+    );
+    assert_eq!(entries[4]["result"], "NotFound");
+}
+
+#[tokio::test]
+async fn aggregate_size_is_limited() {
+    let mut client = Client::login_root().await;
+
+    /* the test server allows 2048 bytes per response */
+    for secret_name in ["first", "second", "third", "fourth", "fifth", "sixth"] {
+        insert(&mut client, "batch-large", secret_name, &[b'x'; 400]).await;
+    }
+
+    let response = client
+        .send(serde_json::json!({
+            "GetSecrets": {
+                "vault": "batch-large",
+                "names": ["first", "second", "third", "fourth", "fifth"]
+            }
+        }))
+        .await;
+    assert_eq!(response["Secrets"].as_array().unwrap().len(), 5);
+
+    let response = client
+        .send(serde_json::json!({
+            "GetSecrets": {
+                "vault": "batch-large",
+                "names": ["*"]
+            }
+        }))
+        .await;
+    assert_eq!(response["TooLarge"]["max_secret_bytes"], 2048);
+}
+
+#[tokio::test]
+async fn non_admin_is_denied() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "batch-reader",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, _) = Client::login("batch-reader", "hunter2").await;
+
+    let response = client
+        .send(serde_json::json!({
+            "GetSecrets": {
+                "vault": "batch",
+                "names": ["api-token"]
+            }
+        }))
+        .await;
+    assert_eq!(response, "Denied");
+}
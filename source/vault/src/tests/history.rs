@@ -0,0 +1,132 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{create_access_key, request, Client, ROOT_USERNAME};
+
+async fn history(client: &mut Client, vault: &str) -> serde_json::Value {
+    client
+        .send(serde_json::json!({
+            "SecretHistory": {
+                "vault": vault,
+                "secret_name": "password"
+            }
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn insert_then_overwrite() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "history",
+                "secret_name": "password",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let authorization = create_access_key("history", vec![permission::VaultRoles::CreateSecrets]);
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request(
+        "PUT",
+        "/history/password",
+        &[("Authorization", authorization.as_str())],
+        b"hunter3",
+    )
+    .await;
+    assert!(response.status < 300);
+
+    let response = history(&mut client, "history").await;
+    let entries = response["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0]["action"], "Inserted");
+    assert_eq!(
+        entries[0]["actor"],
+        serde_json::json!({ "User": ROOT_USERNAME })
+    );
+    assert_eq!(entries[1]["action"], "Updated");
+    assert_eq!(
+        entries[1]["actor"],
+        serde_json::json!({ "AccessKey": access_key })
+    );
+    assert!(entries[0]["timestamp"].as_str().unwrap() <= entries[1]["timestamp"].as_str().unwrap());
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteSecret": {
+                "vault": "history",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+
+    /* the history outlives the secret */
+    let response = history(&mut client, "history").await;
+    assert_eq!(response["entries"][2]["action"], "Deleted");
+
+    let response = history(&mut client, "history-missing").await;
+    assert_eq!(response["entries"], serde_json::json!([]));
+}
+
+#[test]
+fn history_is_capped() {
+    super::server();
+
+    for i in 0..db::history::MAX_ENTRIES + 5 {
+        let result = db::secret::insert(
+            "history-cap",
+            "password",
+            db::secret::SecretDocument {
+                created: crate::clock::now().to_rfc3339(),
+                secret: String::new(),
+                etag: None,
+                last_accessed: None,
+                created_by: Some(db::Actor::User(format!("user-{i}"))),
+                alias: None,
+                bound: true,
+            },
+            false,
+        )
+        .unwrap();
+        assert!(!matches!(result, db::secret::InsertSecretResult::NameTaken));
+    }
+
+    let entries = db::history::find("history-cap", "password").unwrap();
+    assert_eq!(entries.len(), db::history::MAX_ENTRIES);
+
+    /* the oldest ones go first */
+    assert_eq!(entries[0].actor, Some(db::Actor::User("user-5".to_owned())));
+    assert_eq!(
+        entries.last().unwrap().actor,
+        Some(db::Actor::User(format!(
+            "user-{}",
+            db::history::MAX_ENTRIES + 4
+        )))
+    );
+}
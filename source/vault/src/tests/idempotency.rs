@@ -0,0 +1,123 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, db, permission, vault};
+
+use super::{create_access_key, request};
+
+#[tokio::test]
+async fn retried_insert_returns_the_cached_outcome() {
+    let authorization = create_access_key(
+        "idempotency",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Idempotency-Key", "first-attempt"),
+    ];
+
+    let response = request("PUT", "/idempotency/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(response.header("Idempotent-Replayed"), None);
+
+    let etag = db::secret::find("idempotency", "password")
+        .unwrap()
+        .unwrap()
+        .etag;
+
+    /* a retry with the same key is answered without inserting again */
+    let response = request("PUT", "/idempotency/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(response.header("Idempotent-Replayed"), Some("true"));
+
+    let document = db::secret::find("idempotency", "password")
+        .unwrap()
+        .unwrap();
+    assert_eq!(document.etag, etag);
+
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Idempotency-Key", "second-attempt"),
+    ];
+
+    let response = request("PUT", "/idempotency/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Idempotent-Replayed"), None);
+
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("GET", "/idempotency/password", &headers, b"").await;
+    assert_eq!(response.body, b"hunter3");
+
+    /* the same key on another secret is a different request */
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Idempotency-Key", "first-attempt"),
+    ];
+
+    let response = request("PUT", "/idempotency/username", &headers, b"admin").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(response.header("Idempotent-Replayed"), None);
+}
+
+#[tokio::test]
+async fn expired_keys_are_swept() {
+    let authorization = create_access_key(
+        "idempotency-expired",
+        vec![permission::VaultRoles::CreateSecrets],
+    );
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Idempotency-Key", "expiring"),
+    ];
+
+    let response = request("PUT", "/idempotency-expired/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    clock::advance(chrono::Duration::days(2));
+
+    assert!(vault::sweep_idempotency_keys().unwrap() >= 1);
+    assert!(
+        db::idempotency::find("idempotency-expired", "password", "expiring")
+            .unwrap()
+            .is_none()
+    );
+
+    let response = request("PUT", "/idempotency-expired/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Idempotent-Replayed"), None);
+}
+
+#[tokio::test]
+async fn invalid_idempotency_key() {
+    let authorization = create_access_key(
+        "idempotency-invalid",
+        vec![permission::VaultRoles::CreateSecrets],
+    );
+    let idempotency_key = "x".repeat(256);
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Idempotency-Key", idempotency_key.as_str()),
+    ];
+
+    let response = request("PUT", "/idempotency-invalid/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 400);
+}
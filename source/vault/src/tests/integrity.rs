@@ -0,0 +1,69 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::db;
+
+use super::Client;
+
+#[tokio::test]
+async fn corrupted_secret_is_reported() {
+    let mut client = Client::login_root().await;
+
+    for secret_name in ["first", "second"] {
+        let response = client
+            .send(serde_json::json!({
+                "InsertSecret": {
+                    "vault": "integrity",
+                    "secret_name": secret_name,
+                    "data": base64_simd::STANDARD.encode_to_string("hunter2")
+                }
+            }))
+            .await;
+        assert_eq!(response["result"], "Inserted");
+    }
+
+    db::secret::insert(
+        "integrity",
+        "broken",
+        db::secret::SecretDocument {
+            created: chrono::Utc::now().to_rfc3339(),
+            etag: None,
+            last_accessed: None,
+            created_by: None,
+            secret: base64_simd::STANDARD.encode_to_string([0u8; 600]),
+            alias: None,
+            bound: true,
+        },
+        false,
+    )
+    .expect("failed to insert a secret");
+
+    let response = client
+        .send(serde_json::json!({
+            "VerifyIntegrity": {
+                "vault": "integrity"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"]["Failed"]["vault"], "integrity");
+    assert_eq!(response["result"]["Failed"]["secret_name"], "broken");
+
+    let response = client.receive().await;
+    assert_eq!(response["result"]["Done"]["secrets"], 3);
+    assert_eq!(response["result"]["Done"]["failed"], 1);
+    assert_eq!(response["result"]["Done"]["drifted"], 0);
+}
@@ -0,0 +1,90 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{bootstrap, config};
+
+fn lookup(name: &str) -> Option<String> {
+    match name {
+        "VAULTY_PORT" => Some("8443".to_owned()),
+        "VAULTY_DIR" => Some("/var/lib/vaulty".to_owned()),
+        _ => None,
+    }
+}
+
+#[test]
+fn interpolate_variables() {
+    assert_eq!(
+        config::interpolate(
+            "listen_port: ${VAULTY_PORT}\nlocation: ${VAULTY_DIR}/database.bin",
+            lookup
+        )
+        .unwrap(),
+        "listen_port: 8443\nlocation: /var/lib/vaulty/database.bin"
+    );
+
+    /* a set variable wins over the default */
+    assert_eq!(
+        config::interpolate("${VAULTY_PORT:-8080}", lookup).unwrap(),
+        "8443"
+    );
+}
+
+#[test]
+fn interpolate_default() {
+    assert_eq!(
+        config::interpolate("listen_port: ${VAULTY_UNSET:-8080}", lookup).unwrap(),
+        "listen_port: 8080"
+    );
+    assert_eq!(
+        config::interpolate("${VAULTY_UNSET:-}", lookup).unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn interpolate_escaped_dollar() {
+    assert_eq!(
+        config::interpolate("secret: $${VAULTY_PORT} costs $5", lookup).unwrap(),
+        "secret: ${VAULTY_PORT} costs $5"
+    );
+}
+
+#[test]
+fn interpolate_undefined_variable() {
+    let error = config::interpolate("listen_port: ${VAULTY_UNSET}", lookup).unwrap_err();
+    assert_eq!(error.message, "undefined variable");
+    assert_eq!(error.attr.unwrap()["variable"], "VAULTY_UNSET");
+
+    assert!(config::interpolate("${VAULTY_PORT", lookup).is_err());
+    assert!(config::interpolate("${1PORT}", lookup).is_err());
+}
+
+#[test]
+fn load_interpolates_the_environment() {
+    let directory = tempfile::tempdir().unwrap();
+    let filename = bootstrap::generate_config(&directory.path().join("vaulty")).unwrap();
+
+    let content = std::fs::read_to_string(&filename)
+        .unwrap()
+        .replace("node_name: Vaulty", "node_name: ${VAULTY_TEST_NODE_NAME}");
+    std::fs::write(&filename, content).unwrap();
+
+    assert!(config::load(&filename).is_err());
+
+    std::env::set_var("VAULTY_TEST_NODE_NAME", "interpolated");
+    assert_eq!(config::load(&filename).unwrap().node_name, "interpolated");
+}
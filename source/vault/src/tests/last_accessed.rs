@@ -0,0 +1,76 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, permission};
+
+use super::{create_access_key, request, Client};
+
+async fn last_accessed(headers: &[(&str, &str)]) -> serde_json::Value {
+    let response = request("GET", "/accessed", headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+
+    response["secrets"][0]["last_accessed"].clone()
+}
+
+#[tokio::test]
+async fn read_updates_last_accessed() {
+    let authorization = create_access_key(
+        "accessed",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::ListSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/accessed/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+    assert!(last_accessed(&headers).await.is_null());
+
+    let response = request("GET", "/accessed/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let first = last_accessed(&headers).await;
+    assert!(first.is_string());
+
+    /* within the interval the timestamp stays put */
+    let response = request("GET", "/accessed/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(last_accessed(&headers).await, first);
+
+    clock::advance(chrono::Duration::seconds(120));
+
+    let response = request("GET", "/accessed/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let second = last_accessed(&headers).await;
+    assert_ne!(second, first);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "ListSecrets": {
+                "vault": "accessed"
+            }
+        }))
+        .await;
+    assert_eq!(response["secrets"][0]["last_accessed"], second);
+}
@@ -0,0 +1,274 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{create_access_key, request, Client};
+
+const SECRETS_COUNT: usize = 5000;
+
+#[tokio::test]
+async fn large_list_does_not_starve_other_requests() {
+    let authorization = create_access_key(
+        "large",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::ListSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/large/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    for i in 0..SECRETS_COUNT {
+        db::secret::insert(
+            "large",
+            &format!("secret-{i}"),
+            db::secret::SecretDocument {
+                created: "2024-01-01T00:00:00+00:00".to_owned(),
+                secret: String::new(),
+                etag: None,
+                last_accessed: None,
+                created_by: None,
+                alias: None,
+                bound: true,
+            },
+            false,
+        )
+        .unwrap();
+    }
+
+    let (list, gets) = tokio::join!(request("GET", "/large", &headers, b""), async {
+        let mut statuses = Vec::new();
+
+        for _ in 0..10 {
+            statuses.push(
+                request("GET", "/large/password", &headers, b"")
+                    .await
+                    .status,
+            );
+        }
+
+        statuses
+    });
+
+    assert_eq!(list.status, 200);
+    assert!(gets.iter().all(|v| *v == 200));
+
+    let list: serde_json::Value = serde_json::from_slice(&list.body).unwrap();
+    assert_eq!(list["vault"], "large");
+    assert_eq!(list["secrets"].as_array().unwrap().len(), SECRETS_COUNT + 1);
+}
+
+/* the test client doesn't decode chunked bodies itself */
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+
+    loop {
+        let line_end = body.windows(2).position(|v| v == b"\r\n").unwrap();
+        let size =
+            usize::from_str_radix(std::str::from_utf8(&body[..line_end]).unwrap(), 16).unwrap();
+
+        if size == 0 {
+            return result;
+        }
+
+        let chunk = &body[line_end + 2..];
+        result.extend_from_slice(&chunk[..size]);
+        body = &chunk[size + 2..];
+    }
+}
+
+#[tokio::test]
+async fn streamed_list_matches_the_listing() {
+    let authorization = create_access_key(
+        "streamed",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/streamed/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    for i in 0..1000 {
+        db::secret::insert(
+            "streamed",
+            &format!("secret-{i}"),
+            db::secret::SecretDocument {
+                created: "2024-01-01T00:00:00+00:00".to_owned(),
+                secret: String::new(),
+                etag: None,
+                last_accessed: None,
+                created_by: None,
+                alias: None,
+                bound: true,
+            },
+            false,
+        )
+        .unwrap();
+    }
+
+    let listed = request("GET", "/streamed", &headers, b"").await;
+    assert_eq!(listed.status, 200);
+
+    let listed: serde_json::Value = serde_json::from_slice(&listed.body).unwrap();
+    let listed = listed["secrets"].as_array().unwrap().clone();
+
+    let streamed = request("GET", "/streamed?stream=1", &headers, b"").await;
+    assert_eq!(streamed.status, 200);
+    assert_eq!(streamed.header("Transfer-Encoding"), Some("chunked"));
+    assert_eq!(
+        streamed.header("Content-Type"),
+        Some("application/x-ndjson")
+    );
+
+    let body = dechunk(&streamed.body);
+    let streamed: Vec<serde_json::Value> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|v| serde_json::from_str(v).unwrap())
+        .collect();
+
+    assert_eq!(streamed.len(), 1001);
+    assert_eq!(streamed, listed);
+}
+
+fn names(response: &serde_json::Value, list: &str, field: &str) -> Vec<String> {
+    response[list]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v[field].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn sorted_and_filtered_vaults() {
+    /* created in the order b, c, a with 1, 2 and 3 secrets */
+    for (vault, secrets) in [("sorted-b", 1), ("sorted-c", 2), ("sorted-a", 3)] {
+        let authorization = create_access_key(vault, vec![permission::VaultRoles::CreateSecrets]);
+        let headers = [("Authorization", authorization.as_str())];
+
+        for i in 0..secrets {
+            let path = format!("/{vault}/secret-{i}");
+            let response = request("PUT", &path, &headers, b"hunter2").await;
+            assert_eq!(response.status, 201);
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let mut client = Client::login_root().await;
+
+    for (sort_by, order, expected) in [
+        ("Name", "Ascending", ["sorted-a", "sorted-b", "sorted-c"]),
+        ("Name", "Descending", ["sorted-c", "sorted-b", "sorted-a"]),
+        ("Created", "Ascending", ["sorted-b", "sorted-c", "sorted-a"]),
+        (
+            "SecretsCount",
+            "Descending",
+            ["sorted-a", "sorted-c", "sorted-b"],
+        ),
+    ] {
+        let response = client
+            .send(serde_json::json!({
+                "ListVaults": {
+                    "sort_by": sort_by,
+                    "order": order,
+                    "name_prefix": "sorted-"
+                }
+            }))
+            .await;
+        assert_eq!(names(&response, "vaults", "vault"), expected);
+    }
+
+    let response = client
+        .send(serde_json::json!({
+            "ListVaults": {
+                "name_prefix": "sorted-b"
+            }
+        }))
+        .await;
+    assert_eq!(names(&response, "vaults", "vault"), ["sorted-b"]);
+
+    /* older clients send no arguments */
+    let response = client.send(serde_json::json!({ "ListVaults": [] })).await;
+    assert!(names(&response, "vaults", "vault").len() >= 3);
+}
+
+#[tokio::test]
+async fn sorted_and_filtered_access_keys() {
+    let mut access_keys = Vec::new();
+
+    for _ in 0..3 {
+        let authorization =
+            create_access_key("keys-sorted", vec![permission::VaultRoles::ListSecrets]);
+        let (access_key, _) = authorization
+            .trim_start_matches("VAULTY ")
+            .split_once(':')
+            .unwrap();
+
+        access_keys.push(access_key.to_owned());
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "ListAccessKeys": {
+                "vault": "keys-sorted",
+                "sort_by": "Created",
+                "order": "Descending"
+            }
+        }))
+        .await;
+    let mut expected = access_keys.clone();
+    expected.reverse();
+    assert_eq!(names(&response, "access_keys", "access_key"), expected);
+
+    let response = client
+        .send(serde_json::json!({
+            "ListAccessKeys": {
+                "vault": "keys-sorted",
+                "sort_by": "Name"
+            }
+        }))
+        .await;
+    let mut expected = access_keys.clone();
+    expected.sort();
+    assert_eq!(names(&response, "access_keys", "access_key"), expected);
+
+    let response = client
+        .send(serde_json::json!({
+            "ListAccessKeys": {
+                "vault": "keys-sorted",
+                "name_prefix": access_keys[1]
+            }
+        }))
+        .await;
+    assert_eq!(
+        names(&response, "access_keys", "access_key"),
+        [access_keys[1].clone()]
+    );
+}
@@ -0,0 +1,97 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, log};
+
+const CHILD_ENV: &str = "VAULTY_TEST_LOG_MODULES";
+const CHILD_TEST: &str = "tests::log_modules::child";
+
+/* like log_stdout, the module filter is process wide so it's set in a child */
+#[test]
+fn child() {
+    let Ok(directory) = std::env::var(CHILD_ENV) else {
+        return;
+    };
+
+    let filename = std::path::Path::new(&directory).join("vault.log");
+
+    let object: config::Config = serde_yaml::from_str(&format!(
+        "node_name: Test
+log:
+  filename: {}
+  stdout: false
+  modules: [Vault, Db]
+db:
+  location: database.bin
+secrets:
+  aes_key: aes.key
+  aes_iv: iv.key
+access_keys:
+  signing_key: private.pem
+  verifying_key: public.pem
+server:
+  listen_address: 127.0.0.1
+  listen_port: 0
+",
+        filename.to_str().unwrap()
+    ))
+    .unwrap();
+
+    unsafe {
+        config::CONFIG_OBJECT = Some(object);
+    }
+
+    log::initialize().unwrap();
+
+    crate::log!({
+        mod: log::Module::Api,
+        ctx: "testing",
+        msg: "api event",
+        tags: ["api"],
+    });
+
+    crate::log!({
+        "mod": log::Module::Vault,
+        "ctx": "testing",
+        "msg": "vault event",
+        "tags": ["vault"],
+    });
+
+    crate::log!({
+        mod: log::Module::Db,
+        ctx: "testing",
+        msg: "db event",
+        tags: ["db"],
+    });
+}
+
+#[test]
+fn disabled_module() {
+    let directory = tempfile::tempdir().unwrap();
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", CHILD_TEST, "--test-threads=1"])
+        .env(CHILD_ENV, directory.path().to_str().unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let file = std::fs::read_to_string(directory.path().join("vault.log")).unwrap();
+    assert!(!file.contains("api event"));
+    assert!(file.contains("vault event"));
+    assert!(file.contains("db event"));
+}
@@ -0,0 +1,103 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, log};
+
+const CHILD_ENV: &str = "VAULTY_TEST_LOG_STDOUT";
+const CHILD_TEST: &str = "tests::log_stdout::child";
+const MESSAGE: &str = "stdout logging test event";
+
+/* the log macro writes to the process' stdout, which the test harness
+doesn't capture, so the events are emitted in a child process */
+#[test]
+fn child() {
+    let Ok(value) = std::env::var(CHILD_ENV) else {
+        return;
+    };
+
+    let (directory, stdout) = value.split_once(';').unwrap();
+    let filename = std::path::Path::new(directory).join("vault.log");
+
+    let object: config::Config = serde_yaml::from_str(&format!(
+        "node_name: Test
+log:
+  filename: {}
+  stdout: {stdout}
+db:
+  location: database.bin
+secrets:
+  aes_key: aes.key
+  aes_iv: iv.key
+access_keys:
+  signing_key: private.pem
+  verifying_key: public.pem
+  delay_unsuccessful_attempts_millis: 0
+  acces_key_length: 20
+  secret_access_key_length: 40
+users:
+  delay_unsuccessful_attempts_millis: 0
+server:
+  listen_address: 127.0.0.1
+  listen_port: 0
+",
+        filename.to_str().unwrap()
+    ))
+    .unwrap();
+
+    unsafe {
+        config::CONFIG_OBJECT = Some(object);
+    }
+
+    log::initialize().unwrap();
+
+    crate::log!({
+        mod: log::Module::Log,
+        ctx: "testing",
+        msg: MESSAGE,
+        tags: ["log"],
+    });
+}
+
+fn run_child(stdout: bool) -> (String, String) {
+    let directory = tempfile::tempdir().unwrap();
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", CHILD_TEST, "--test-threads=1"])
+        .env(
+            CHILD_ENV,
+            format!("{};{stdout}", directory.path().to_str().unwrap()),
+        )
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        std::fs::read_to_string(directory.path().join("vault.log")).unwrap(),
+    )
+}
+
+#[test]
+fn disabled_stdout() {
+    let (stdout, file) = run_child(true);
+    assert!(stdout.contains(MESSAGE));
+    assert!(file.contains(MESSAGE));
+
+    let (stdout, file) = run_child(false);
+    assert!(!stdout.contains(MESSAGE));
+    assert!(file.contains(MESSAGE));
+}
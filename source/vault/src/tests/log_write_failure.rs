@@ -0,0 +1,136 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, log, server};
+
+use super::{free_port, request_over};
+
+const CHILD_ENV: &str = "VAULTY_TEST_LOG_WRITE_FAILURE";
+const CHILD_TEST: &str = "tests::log_write_failure::child";
+
+/* the reaction is process wide and may exit, so it's triggered in a child */
+#[test]
+fn child() {
+    let Ok(value) = std::env::var(CHILD_ENV) else {
+        return;
+    };
+
+    let (directory, reaction) = value.split_once(';').unwrap();
+    let filename = std::path::Path::new(directory).join("vault.log");
+    let port = free_port();
+
+    let object: config::Config = serde_yaml::from_str(&format!(
+        "node_name: Test
+log:
+  filename: {}
+  stdout: false
+  on_write_failure: {reaction}
+db:
+  location: database.bin
+secrets:
+  aes_key: aes.key
+  aes_iv: iv.key
+access_keys:
+  signing_key: private.pem
+  verifying_key: public.pem
+server:
+  listen_address: 127.0.0.1
+  listen_port: {port}
+",
+        filename.to_str().unwrap()
+    ))
+    .unwrap();
+
+    unsafe {
+        config::CONFIG_OBJECT = Some(object);
+    }
+
+    log::initialize().unwrap();
+
+    /* as if the disk filled up, a handle opened for reading can't be written */
+    unsafe {
+        log::STREAM_OUTPUT = Some(std::fs::File::open(&filename).unwrap());
+    }
+
+    for _ in 0..3 {
+        crate::log!({
+            mod: log::Module::Vault,
+            ctx: "testing",
+            msg: "lost event",
+            tags: ["vault"],
+        });
+    }
+
+    assert!(server::in_maintenance());
+
+    let server_config = config::get_clone().server;
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let address = format!("127.0.0.1:{port}");
+
+        let stream = loop {
+            match tokio::net::TcpStream::connect(&address).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+
+        let response = request_over(stream, &address, "GET", "/healthz", &[], b"").await;
+        assert_eq!(response.status, 503);
+        assert_eq!(response.body, br#"{"status":"maintenance"}"#);
+    });
+}
+
+fn run_child(reaction: &str) -> std::process::Output {
+    let directory = tempfile::tempdir().unwrap();
+
+    std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", CHILD_TEST, "--test-threads=1"])
+        .env(
+            CHILD_ENV,
+            format!("{};{reaction}", directory.path().to_str().unwrap()),
+        )
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn maintenance() {
+    let output = run_child("Maintenance");
+    assert!(output.status.success());
+
+    /* the failure is reported on STDOUT even though it was turned off */
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("the log file can't be written"));
+}
+
+#[test]
+fn exit() {
+    let output = run_child("Exit");
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("the log file can't be written"));
+    assert!(!stdout.contains("test result"));
+}
@@ -0,0 +1,498 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use futures::{SinkExt, StreamExt};
+
+use crate::{
+    access_keys, authenticator, config, db, jwt, log, permission, secrets, server, sg, user,
+    webhook,
+};
+
+mod access;
+mod access_log;
+mod actors;
+mod admin_tls;
+mod aliases;
+mod authenticators;
+mod authorize;
+mod backpressure;
+mod bearer;
+mod bootstrap;
+mod busy;
+mod certificates;
+mod checksum;
+mod client;
+mod clock;
+mod codec;
+mod commands;
+mod compaction;
+mod compression;
+mod corrupted_db;
+mod delay;
+mod denied_log;
+mod directories;
+mod dual_stack;
+mod encryption_context;
+mod engine;
+mod etag;
+mod exists;
+mod generate;
+mod get_secrets;
+mod history;
+mod idempotency;
+mod integrity;
+mod interpolation;
+mod keys;
+mod last_accessed;
+mod list;
+mod log_modules;
+mod log_stdout;
+mod log_write_failure;
+mod name_prefixes;
+mod names;
+mod owner;
+mod padding;
+mod path_prefix;
+mod pepper;
+#[cfg(unix)]
+mod permissions;
+mod protocol;
+mod recycle_bin;
+mod roles;
+mod roundtrip;
+mod routes;
+mod schema;
+mod security_headers;
+mod sessions;
+mod signing_key_rotation;
+mod simulate;
+mod stale;
+mod stats;
+mod timestamps;
+mod unique_names;
+mod unix_socket;
+mod upload;
+mod users;
+mod validation;
+mod value_schema;
+mod vaults;
+mod verify_secret;
+mod webhooks;
+
+pub const ROOT_USERNAME: &str = "root";
+pub const ROOT_PASSWORD: &str = "WgzS3rY8Rt6VGNEbAbMz";
+
+static SERVER: std::sync::OnceLock<Server> = std::sync::OnceLock::new();
+
+pub struct Server {
+    pub address: String,
+    _directory: tempfile::TempDir,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct Client {
+    stream: tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+}
+
+impl Client {
+    pub async fn connect() -> Client {
+        Client::connect_to(&server().address).await
+    }
+
+    pub async fn connect_to(address: &str) -> Client {
+        let (stream, _) = tokio_tungstenite::connect_async(format!("ws://{address}/"))
+            .await
+            .expect("failed to connect to the websocket");
+
+        Client {
+            stream,
+            bytes_sent: 0,
+            bytes_received: 0,
+        }
+    }
+
+    pub async fn login(username: &str, password: &str) -> (Client, serde_json::Value) {
+        Client::login_to(&server().address, username, password).await
+    }
+
+    pub async fn login_to(
+        address: &str,
+        username: &str,
+        password: &str,
+    ) -> (Client, serde_json::Value) {
+        let mut client = Client::connect_to(address).await;
+        let response = client
+            .send(serde_json::json!({
+                "username": username,
+                "password": password,
+                "protocol_version": vaulty_protocol::VERSION
+            }))
+            .await;
+
+        (client, response)
+    }
+
+    pub async fn login_root() -> Client {
+        let (client, response) = Client::login(ROOT_USERNAME, ROOT_PASSWORD).await;
+
+        assert_eq!(response["result"], "Granted");
+
+        client
+    }
+
+    pub async fn send(&mut self, request: serde_json::Value) -> serde_json::Value {
+        let request = request.to_string();
+        self.bytes_sent += request.len();
+
+        self.stream
+            .send(tokio_tungstenite::tungstenite::Message::Text(request))
+            .await
+            .expect("failed to send a request");
+
+        self.receive().await
+    }
+
+    pub async fn receive(&mut self) -> serde_json::Value {
+        loop {
+            match self.stream.next().await {
+                Some(Ok(tokio_tungstenite::tungstenite::Message::Text(data))) => {
+                    self.bytes_received += data.len();
+
+                    return serde_json::from_str(&data).expect("invalid response");
+                }
+                Some(Ok(tokio_tungstenite::tungstenite::Message::Ping(_))) => {}
+                message => panic!("unexpected message: {message:?}"),
+            }
+        }
+    }
+}
+
+fn write_key(directory: &std::path::Path, filename: &str, content: &[u8]) -> String {
+    let path = directory.join(filename);
+
+    std::fs::write(&path, content).expect("failed to write a key");
+
+    path.to_str().unwrap().to_owned()
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("failed to find a free port")
+        .port()
+}
+
+fn build_config(directory: &std::path::Path, port: u16) -> config::Config {
+    let rsa = openssl::pkey::PKey::from_rsa(openssl::rsa::Rsa::generate(4096).unwrap()).unwrap();
+    let ec = openssl::pkey::PKey::from_ec_key(
+        openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let jwt = openssl::pkey::PKey::from_ec_key(
+        openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    /* kept next to the public key for the tests that issue tokens */
+    write_key(
+        directory,
+        "jwt-private.pem",
+        &jwt.private_key_to_pem_pkcs8().unwrap(),
+    );
+
+    let mut aes_key = [0u8; 32];
+    let mut aes_iv = [0u8; 12];
+
+    openssl::rand::rand_bytes(&mut aes_key).unwrap();
+    openssl::rand::rand_bytes(&mut aes_iv).unwrap();
+
+    config::Config {
+        node_name: "Test".to_owned(),
+        log: Some(config::ConfigLog {
+            filename: directory.join("vault.log").to_str().unwrap().to_owned(),
+            stdout: None,
+            access_log: Some(directory.join("access.log").to_str().unwrap().to_owned()),
+            access_log_format: None,
+            modules: None,
+            on_write_failure: None,
+        }),
+        db: config::ConfigDb {
+            location: directory.join("database.bin").to_str().unwrap().to_owned(),
+            repair: None,
+            compaction: None,
+            #[cfg(debug_assertions)]
+            debug_populate: None,
+        },
+        secrets: config::ConfigSecrets {
+            scheme: None,
+            rsa_private_key: Some(write_key(
+                directory,
+                "secret-rsa-private.pem",
+                &rsa.private_key_to_pem_pkcs8().unwrap(),
+            )),
+            rsa_public_key: Some(write_key(
+                directory,
+                "secret-rsa-public.pem",
+                &rsa.public_key_to_pem().unwrap(),
+            )),
+            ec_private_key: None,
+            ec_public_key: None,
+            aes_key: write_key(
+                directory,
+                "secret-aes.key",
+                base64_simd::STANDARD.encode_to_string(aes_key).as_bytes(),
+            ),
+            aes_iv: write_key(
+                directory,
+                "secret-iv.key",
+                base64_simd::STANDARD.encode_to_string(aes_iv).as_bytes(),
+            ),
+            max_secret_bytes: Some(2048),
+            last_accessed_interval_secs: Some(60),
+            pad_not_found: Some(true),
+            soft_delete_retention_secs: Some(30 * 24 * 60 * 60),
+            upload_ttl_secs: None,
+            max_upload_bytes: Some(4096),
+            unique_names: None,
+            self_test: None,
+        },
+        access_keys: config::ConfigAccessKeys {
+            signing_key: write_key(
+                directory,
+                "access-private.pem",
+                &ec.private_key_to_pem_pkcs8().unwrap(),
+            ),
+            verifying_key: write_key(
+                directory,
+                "access-public.pem",
+                &ec.public_key_to_pem().unwrap(),
+            ),
+            delay_unsuccessful_attempts_millis: 10,
+            delay_jitter_millis: None,
+            acces_key_length: 20,
+            secret_access_key_length: 40,
+            pepper: Some(write_key(directory, "access-pepper.key", b"test pepper\n")),
+            accept_unpeppered: Some(true),
+            previous_verifying_key: None,
+            stale_after_days: None,
+            stale_check_interval_secs: None,
+            disable_stale: None,
+            self_test: None,
+        },
+        users: config::ConfigUsers {
+            delay_unsuccessful_attempts_millis: 10,
+            delay_jitter_millis: None,
+            bootstrap: None,
+            authenticator: None,
+            protected: Some(vec!["protected-admin".to_owned()]),
+        },
+        server: config::ConfigServer {
+            listen_address: "127.0.0.1".to_owned(),
+            listen_port: port,
+            unix_socket: None,
+            max_sessions: None,
+            min_client_version: None,
+            path_prefix: None,
+            security_headers: None,
+            admin_requires_tls: None,
+            dual_stack_sg: None,
+            tls: None,
+        },
+        webhook: None,
+        jwt: Some(config::ConfigJwt {
+            public_key: write_key(
+                directory,
+                "jwt-public.pem",
+                &jwt.public_key_to_pem().unwrap(),
+            ),
+            issuer: Some("vaulty-tests".to_owned()),
+            audience: None,
+        }),
+    }
+}
+
+fn start() -> Server {
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let port = free_port();
+
+    unsafe {
+        config::CONFIG_OBJECT = Some(build_config(directory.path(), port));
+    }
+
+    log::initialize().expect("failed to initialize the log module");
+    db::initialize().expect("failed to initialize the DB module");
+    secrets::initialize().expect("failed to initialize the secrets module");
+    secrets::bind_legacy().expect("failed to seal the legacy secrets");
+    access_keys::initialize().expect("failed to initialize the access keys module");
+    jwt::initialize().expect("failed to initialize the JWT module");
+    user::initialize();
+    sg::initialize();
+    authenticator::initialize().expect("failed to initialize the authenticator");
+    webhook::initialize().expect("failed to initialize the webhook module");
+
+    user::create_user(
+        ROOT_USERNAME,
+        ROOT_PASSWORD,
+        &permission::UserRole::Admin,
+        vec!["127.0.0.1/32".to_owned()],
+    )
+    .expect("failed to create the root user");
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::start())
+            .expect("failed to start the server");
+    });
+
+    let address = format!("127.0.0.1:{port}");
+
+    while std::net::TcpStream::connect(&address).is_err() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    Server {
+        address,
+        _directory: directory,
+    }
+}
+
+pub fn server() -> &'static Server {
+    SERVER.get_or_init(start)
+}
+
+pub fn create_access_key(vault: &str, permission: Vec<permission::VaultRoles>) -> String {
+    create_scoped_access_key(vault, permission, Vec::new())
+}
+
+pub fn create_scoped_access_key(
+    vault: &str,
+    permission: Vec<permission::VaultRoles>,
+    name_prefixes: Vec<String>,
+) -> String {
+    server();
+
+    let result = access_keys::create(
+        vault,
+        Some(vec!["127.0.0.1/32".to_owned()]),
+        Some(permission),
+        None,
+        name_prefixes,
+        db::Actor::User(ROOT_USERNAME.to_owned()),
+    )
+    .expect("failed to create an access key");
+
+    authorization(&result.access_key, &result.secret_access_key)
+}
+
+pub fn authorization(access_key: &str, secret_access_key: &str) -> String {
+    format!("VAULTY {access_key}:{secret_access_key}")
+}
+
+pub async fn request(method: &str, path: &str, headers: &[(&str, &str)], body: &[u8]) -> Response {
+    let stream = tokio::net::TcpStream::connect(&server().address)
+        .await
+        .expect("failed to connect to the server");
+
+    request_over(stream, &server().address, method, path, headers, body).await
+}
+
+pub async fn request_over<S>(
+    mut stream: S,
+    host: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Response
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let mut head = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(body).await.unwrap();
+
+    let mut response = Vec::new();
+
+    stream.read_to_end(&mut response).await.unwrap();
+
+    let split = response
+        .windows(4)
+        .position(|v| v == b"\r\n\r\n")
+        .expect("invalid HTTP response");
+
+    let head = String::from_utf8_lossy(&response[..split]).to_string();
+    let mut lines = head.split("\r\n");
+
+    let status = lines
+        .next()
+        .and_then(|v| v.split(' ').nth(1))
+        .and_then(|v| v.parse().ok())
+        .expect("invalid HTTP status line");
+
+    let headers = lines
+        .filter_map(|v| v.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    Response {
+        status,
+        headers,
+        body: if method == "HEAD" {
+            Vec::new()
+        } else {
+            response[split + 4..].to_vec()
+        },
+    }
+}
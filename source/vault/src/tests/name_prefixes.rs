@@ -0,0 +1,109 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_scoped_access_key, request};
+
+fn scoped_key(vault: &str) -> String {
+    create_scoped_access_key(
+        vault,
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::DeleteSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+        vec!["app1-".to_owned()],
+    )
+}
+
+#[tokio::test]
+async fn in_prefix_allowed() {
+    let authorization = scoped_key("prefixes-allowed");
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/prefixes-allowed/app1-db", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/prefixes-allowed/app1-db", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = request("DELETE", "/prefixes-allowed/app1-db", &headers, b"").await;
+    assert_eq!(response.status, 200);
+}
+
+#[tokio::test]
+async fn out_of_prefix_forbidden() {
+    let authorization = scoped_key("prefixes-forbidden");
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/prefixes-forbidden/app2-db", &headers, b"hunter2").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("GET", "/prefixes-forbidden/app2-db", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("HEAD", "/prefixes-forbidden/app2-db", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("DELETE", "/prefixes-forbidden/app2-db", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("POST", "/prefixes-forbidden/app2-db/uploads", &headers, b"").await;
+    assert_eq!(response.status, 403);
+}
+
+#[tokio::test]
+async fn list_filtered() {
+    let unrestricted =
+        super::create_access_key("prefixes-list", vec![permission::VaultRoles::CreateSecrets]);
+
+    for name in ["app1-db", "app1-cache", "app2-db"] {
+        let response = request(
+            "POST",
+            &format!("/prefixes-list/{name}"),
+            &[("Authorization", unrestricted.as_str())],
+            b"hunter2",
+        )
+        .await;
+        assert_eq!(response.status, 201);
+    }
+
+    let authorization = scoped_key("prefixes-list");
+
+    let response = request(
+        "GET",
+        "/prefixes-list",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+
+    let list: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    let mut names: Vec<&str> = list["secrets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["secret_name"].as_str().unwrap())
+        .collect();
+    names.sort();
+
+    assert_eq!(names, vec!["app1-cache", "app1-db"]);
+}
@@ -0,0 +1,85 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, db, permission};
+
+use super::{create_access_key, request, Client, ROOT_USERNAME};
+
+#[tokio::test]
+async fn invalid_secret_names() {
+    let authorization = create_access_key("names", vec![permission::VaultRoles::CreateSecrets]);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let path = format!("/names/{}", "x".repeat(db::MAX_NAME_LENGTH + 1));
+    let response = request("PUT", &path, &headers, b"hunter2").await;
+    assert_eq!(response.status, 400);
+
+    let response = request("PUT", "/names/pass%0Aword", &headers, b"hunter2").await;
+    assert_eq!(response.status, 400);
+
+    let path = format!("/names/{}", "x".repeat(db::MAX_NAME_LENGTH));
+    let response = request("PUT", &path, &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "names",
+                "secret_name": "pass\nword",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "InvalidName");
+
+    let response = client
+        .send(serde_json::json!({
+            "RenameVault": {
+                "vault": "names",
+                "name": "na mes"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "InvalidName");
+
+    assert!(db::secret::list("names")
+        .unwrap()
+        .iter()
+        .all(|v| db::is_valid_name(&v.secret_name)));
+}
+
+#[test]
+fn invalid_vault_names() {
+    super::server();
+
+    for vault in ["", "..", "vault\n", "vault/name"] {
+        assert!(access_keys::create(
+            vault,
+            Some(vec!["127.0.0.1/32".to_owned()]),
+            Some(vec![permission::VaultRoles::ListSecrets]),
+            None,
+            Vec::new(),
+            db::Actor::User(ROOT_USERNAME.to_owned()),
+        )
+        .is_err());
+    }
+
+    assert!(db::is_valid_name("db-primary.example:5432"));
+    assert!(!db::is_valid_name(&"x".repeat(db::MAX_NAME_LENGTH + 1)));
+}
@@ -0,0 +1,75 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request};
+
+#[test]
+fn owner_implies_every_role() {
+    for role in permission::VaultRoles::ALL {
+        assert!(permission::contains_or_owner(
+            &[permission::VaultRoles::Owner],
+            role
+        ));
+    }
+
+    for role in permission::VaultRoles::ALL {
+        assert_eq!(
+            permission::contains_or_owner(&[permission::VaultRoles::ListSecrets], role),
+            role == permission::VaultRoles::ListSecrets
+        );
+    }
+
+    assert!(permission::validate_vault_roles(&[permission::VaultRoles::Owner]).is_ok());
+}
+
+#[tokio::test]
+async fn owner_key_passes_every_check() {
+    let authorization = create_access_key("owner", vec![permission::VaultRoles::Owner]);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/owner/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/owner/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("HEAD", "/owner/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/owner", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("DELETE", "/owner/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+}
+
+#[tokio::test]
+async fn other_keys_need_explicit_roles() {
+    let authorization = create_access_key("not-owner", vec![permission::VaultRoles::ListSecrets]);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/not-owner/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("GET", "/not-owner/password", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    let response = request("GET", "/not-owner", &headers, b"").await;
+    assert_eq!(response.status, 200);
+}
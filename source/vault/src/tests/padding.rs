@@ -0,0 +1,65 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request};
+
+const SAMPLES: usize = 15;
+
+async fn median_latency(path: &str, headers: &[(&str, &str)], status: u16) -> std::time::Duration {
+    let mut samples = Vec::with_capacity(SAMPLES);
+
+    for _ in 0..SAMPLES {
+        let started = std::time::Instant::now();
+        let response = request("GET", path, headers, b"").await;
+        samples.push(started.elapsed());
+
+        assert_eq!(response.status, status);
+    }
+
+    samples.sort();
+    samples[SAMPLES / 2]
+}
+
+#[tokio::test]
+async fn not_found_padded() {
+    let authorization = create_access_key(
+        "padding",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/padding/present", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    /* warms up the decoy so its one-off encryption isn't measured */
+    request("GET", "/padding/missing", &headers, b"").await;
+
+    let found = median_latency("/padding/present", &headers, 200).await;
+    let not_found = median_latency("/padding/missing", &headers, 404).await;
+
+    /* generous, the point is that a missing name isn't answered in a fraction
+    of the time a decrypt takes */
+    assert!(
+        not_found * 2 >= found,
+        "found took {found:?}, not found took {not_found:?}"
+    );
+}
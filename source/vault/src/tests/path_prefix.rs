@@ -0,0 +1,101 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, permission, server};
+
+use super::{create_access_key, free_port, request, request_over};
+
+async fn prefixed_request(
+    address: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> super::Response {
+    let stream = tokio::net::TcpStream::connect(address)
+        .await
+        .expect("failed to connect to the server");
+
+    request_over(stream, address, method, path, headers, body).await
+}
+
+#[tokio::test]
+async fn prefixed_routes() {
+    let authorization = create_access_key(
+        "prefixed",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/prefixed/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let port = free_port();
+    let address = format!("127.0.0.1:{port}");
+
+    let mut server_config = config::get_clone().server;
+    server_config.listen_port = port;
+    server_config.path_prefix = Some("v1/".to_owned());
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    while tokio::net::TcpStream::connect(&address).await.is_err() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let response = prefixed_request(&address, "GET", "/v1/prefixed/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = prefixed_request(&address, "HEAD", "/v1/prefixed/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    /* without the prefix the REST API isn't there */
+    let response = prefixed_request(&address, "GET", "/prefixed/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "error": "not found", "path": "/prefixed/password" })
+    );
+
+    let response = prefixed_request(&address, "GET", "/prefixed", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    /* the health check stays at the root */
+    let response = prefixed_request(&address, "GET", "/healthz", &[], b"").await;
+    assert_eq!(response.status, 200);
+}
+
+#[tokio::test]
+async fn invalid_prefix() {
+    let mut server_config = config::get_clone().server;
+    server_config.listen_port = free_port();
+    server_config.path_prefix = Some("/v1/{vault}".to_owned());
+
+    assert!(server::serve(server_config).await.is_err());
+}
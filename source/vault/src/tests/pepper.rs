@@ -0,0 +1,121 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, config, db, permission};
+
+use super::{authorization, request, server, ROOT_USERNAME};
+
+fn keys() -> (p256::ecdsa::SigningKey, p256::ecdsa::VerifyingKey) {
+    server();
+
+    let config = config::get_clone();
+
+    (
+        access_keys::load_signing_key(&config.access_keys.signing_key).unwrap(),
+        access_keys::load_verifying_key(&config.access_keys.verifying_key).unwrap(),
+    )
+}
+
+fn decode(signature: &str) -> Vec<u8> {
+    base64_simd::STANDARD.decode_to_vec(signature).unwrap()
+}
+
+#[test]
+fn peppered_signatures() {
+    let (signing_key, verifying_key) = keys();
+
+    let signature = decode(&access_keys::sign_with(&signing_key, b"pepper", "secret"));
+
+    assert!(
+        access_keys::verify_signature(&verifying_key, b"pepper", false, "secret", &signature)
+            .unwrap()
+    );
+    assert!(
+        !access_keys::verify_signature(&verifying_key, b"pepper", false, "other", &signature)
+            .unwrap()
+    );
+    assert!(
+        !access_keys::verify_signature(&verifying_key, b"other", true, "secret", &signature)
+            .unwrap()
+    );
+    assert!(
+        !access_keys::verify_signature(&verifying_key, b"", false, "secret", &signature).unwrap()
+    );
+}
+
+#[test]
+fn unpeppered_signatures() {
+    let (signing_key, verifying_key) = keys();
+
+    let signature = decode(&access_keys::sign_with(&signing_key, b"", "secret"));
+
+    assert!(
+        !access_keys::verify_signature(&verifying_key, b"pepper", false, "secret", &signature)
+            .unwrap()
+    );
+    assert!(
+        access_keys::verify_signature(&verifying_key, b"pepper", true, "secret", &signature)
+            .unwrap()
+    );
+    assert!(
+        !access_keys::verify_signature(&verifying_key, b"pepper", true, "other", &signature)
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn unpeppered_access_key() {
+    let (signing_key, _) = keys();
+
+    let result = access_keys::create(
+        "pepper-legacy",
+        Some(vec!["127.0.0.1/32".to_owned()]),
+        Some(vec![permission::VaultRoles::CreateSecrets]),
+        None,
+        Vec::new(),
+        db::Actor::User(ROOT_USERNAME.to_owned()),
+    )
+    .unwrap();
+
+    /* stored the way it was before the pepper was configured */
+    assert!(matches!(
+        db::access::rotate_secret(
+            "pepper-legacy",
+            &result.access_key,
+            access_keys::sign_with(&signing_key, b"", &result.secret_access_key),
+        )
+        .unwrap(),
+        db::access::RotateSecretForAccessKeyResult::Updated
+    ));
+
+    let authorization = authorization(&result.access_key, &result.secret_access_key);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("PUT", "/pepper-legacy/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+}
+
+#[test]
+fn self_test_catches_mismatched_keys() {
+    let (signing_key, verifying_key) = keys();
+
+    access_keys::self_test(&signing_key, &verifying_key).unwrap();
+
+    let other_key = p256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+
+    assert!(access_keys::self_test(&other_key, &verifying_key).is_err());
+}
@@ -0,0 +1,67 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::os::unix::fs::PermissionsExt;
+
+use crate::{bootstrap, db, fs, log};
+
+fn mode(path: &std::path::Path) -> u32 {
+    std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[test]
+fn created_database_is_private() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("database.bin");
+
+    drop(db::create(&path).unwrap());
+
+    assert_eq!(mode(&path), 0o600);
+    assert!(!fs::is_exposed(&path));
+}
+
+#[test]
+fn created_log_is_private() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("vault.log");
+
+    log::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(mode(&path), 0o600);
+
+    /* an existing file keeps what it had, it's only reported */
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    log::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(mode(&path), 0o644);
+    assert!(fs::is_exposed(&path));
+}
+
+#[test]
+fn generated_keys_are_private() {
+    let directory = tempfile::tempdir().unwrap();
+    let directory = directory.path().join("vaulty");
+
+    bootstrap::generate_config(&directory).unwrap();
+
+    for entry in std::fs::read_dir(&directory).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().is_some_and(|v| v == "pem" || v == "key") {
+            assert_eq!(mode(&path), 0o600, "{}", path.display());
+        }
+    }
+}
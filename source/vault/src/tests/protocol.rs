@@ -0,0 +1,58 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures::StreamExt;
+
+use super::{Client, ROOT_PASSWORD, ROOT_USERNAME};
+
+#[tokio::test]
+async fn compatible_handshake() {
+    let (_client, response) = Client::login(ROOT_USERNAME, ROOT_PASSWORD).await;
+
+    assert_eq!(response["result"], "Granted");
+    assert_eq!(response["protocol_version"], vaulty_protocol::VERSION);
+}
+
+#[tokio::test]
+async fn incompatible_handshake() {
+    let mut client = Client::connect().await;
+
+    /* what a CLI from before the version was exchanged sends */
+    let response = client
+        .send(serde_json::json!({
+            "username": ROOT_USERNAME,
+            "password": ROOT_PASSWORD
+        }))
+        .await;
+
+    assert_eq!(response["result"], "UnsupportedVersion");
+    assert_eq!(
+        response["min_client_version"],
+        vaulty_protocol::MIN_CLIENT_VERSION
+    );
+    assert!(response["node_name"].is_null());
+
+    match client.stream.next().await {
+        Some(Ok(tokio_tungstenite::tungstenite::Message::Close(Some(frame)))) => {
+            assert_eq!(
+                frame.code,
+                tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode::Policy
+            );
+        }
+        message => panic!("expected the session to be closed, got {message:?}"),
+    }
+}
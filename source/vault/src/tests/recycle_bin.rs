@@ -0,0 +1,144 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, db, permission};
+
+use super::{create_access_key, request, Client};
+
+/* the sweeps below purge every expired copy, not only the ones of their own test */
+static RECYCLE_BIN: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn secrets_count(vault: &str) -> i64 {
+    db::vault::find(vault).unwrap().unwrap().secrets_count
+}
+
+async fn restore(client: &mut Client, vault: &str, secret_name: &str) -> serde_json::Value {
+    client
+        .send(serde_json::json!({
+            "RestoreSecret": {
+                "vault": vault,
+                "secret_name": secret_name
+            }
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn delete_then_restore() {
+    let _guard = RECYCLE_BIN.lock().await;
+
+    let authorization = create_access_key(
+        "recycle-restore",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::DeleteSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let mut client = Client::login_root().await;
+
+    let response = request("POST", "/recycle-restore/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(secrets_count("recycle-restore"), 1);
+
+    let response = request("DELETE", "/recycle-restore/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(secrets_count("recycle-restore"), 0);
+
+    let response = request("GET", "/recycle-restore/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let response = restore(&mut client, "recycle-restore", "password").await;
+    assert_eq!(response["result"], "Restored");
+    assert_eq!(secrets_count("recycle-restore"), 1);
+
+    let response = request("GET", "/recycle-restore/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    /* the bin is emptied by a restore */
+    let response = restore(&mut client, "recycle-restore", "password").await;
+    assert_eq!(response["result"], "NotFound");
+
+    /* a newer secret under the same name isn't overwritten */
+    let response = client
+        .send(serde_json::json!({
+            "DeleteSecret": {
+                "vault": "recycle-restore",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+
+    let response = request("POST", "/recycle-restore/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 201);
+
+    let response = restore(&mut client, "recycle-restore", "password").await;
+    assert_eq!(response["result"], "Exists");
+    assert_eq!(secrets_count("recycle-restore"), 1);
+
+    let response = request("GET", "/recycle-restore/password", &headers, b"").await;
+    assert_eq!(response.body, b"hunter3");
+}
+
+#[tokio::test]
+async fn delete_then_expire() {
+    let _guard = RECYCLE_BIN.lock().await;
+
+    let authorization = create_access_key(
+        "recycle-expire",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::DeleteSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let mut client = Client::login_root().await;
+
+    let response = request("POST", "/recycle-expire/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("DELETE", "/recycle-expire/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(secrets_count("recycle-expire"), 0);
+
+    /* past the window a copy the sweeper hasn't reached yet is no longer restorable */
+    let past_window = clock::now() + chrono::Duration::seconds(1);
+    assert!(matches!(
+        db::secret::restore(
+            "recycle-expire",
+            "password",
+            past_window,
+            false,
+            db::Actor::User(super::ROOT_USERNAME.to_owned())
+        )
+        .unwrap(),
+        db::secret::RestoreSecretResult::NotFound
+    ));
+
+    assert!(db::secret::sweep_deleted(past_window).unwrap() >= 1);
+
+    let response = restore(&mut client, "recycle-expire", "password").await;
+    assert_eq!(response["result"], "NotFound");
+    assert_eq!(secrets_count("recycle-expire"), 0);
+
+    let response = request("GET", "/recycle-expire/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+}
@@ -0,0 +1,71 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::Client;
+
+/* fails to compile when a role is added, so it can't be forgotten here */
+fn vault_role_name(role: permission::VaultRoles) -> &'static str {
+    match role {
+        permission::VaultRoles::ListSecrets => "ListSecrets",
+        permission::VaultRoles::DeleteSecrets => "DeleteSecrets",
+        permission::VaultRoles::CreateSecrets => "CreateSecrets",
+        permission::VaultRoles::DecryptSecrets => "DecryptSecrets",
+        permission::VaultRoles::Owner => "Owner",
+    }
+}
+
+#[tokio::test]
+async fn every_role_is_listed() {
+    let mut client = Client::login_root().await;
+
+    let response = client.send(serde_json::json!({ "ListRoles": [] })).await;
+
+    let vault_roles: Vec<&str> = response["vault_roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| {
+            assert!(!v["description"].as_str().unwrap().is_empty());
+
+            v["name"].as_str().unwrap()
+        })
+        .collect();
+
+    for role in [
+        permission::VaultRoles::ListSecrets,
+        permission::VaultRoles::DeleteSecrets,
+        permission::VaultRoles::CreateSecrets,
+        permission::VaultRoles::DecryptSecrets,
+        permission::VaultRoles::Owner,
+    ] {
+        assert!(vault_roles.contains(&vault_role_name(role)));
+    }
+    assert_eq!(vault_roles.len(), permission::VaultRoles::ALL.len());
+
+    let user_roles: Vec<&str> = response["user_roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(user_roles, ["Admin", "User"]);
+
+    assert_eq!(response["presets"][0]["name"], "WriteOnly");
+    assert_eq!(response["presets"][0]["roles"][0], "CreateSecrets");
+}
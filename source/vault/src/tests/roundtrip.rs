@@ -0,0 +1,199 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+#[tokio::test]
+async fn rest_insert_get_delete() {
+    let authorization = create_access_key(
+        "rest",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+            permission::VaultRoles::DeleteSecrets,
+            permission::VaultRoles::ListSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/rest/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("PUT", "/rest/password", &headers, b"hunter3").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/rest/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter3");
+
+    let etag = response.header("ETag").expect("missing ETag").to_owned();
+    let response = request(
+        "GET",
+        "/rest/password",
+        &[headers[0], ("If-None-Match", etag.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 304);
+
+    let response = request("HEAD", "/rest/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/rest", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    let list: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(list["secrets"][0]["secret_name"], "password");
+
+    let response = request("DELETE", "/rest/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+
+    let response = request("GET", "/rest/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+}
+
+#[tokio::test]
+async fn rest_denied() {
+    let authorization = create_access_key("denied", vec![permission::VaultRoles::ListSecrets]);
+
+    let response = request("GET", "/denied/password", &[], b"").await;
+    assert_eq!(response.status, 401);
+
+    let response = request(
+        "GET",
+        "/denied/password",
+        &[("Authorization", "VAULTY tHeeFQ8HtyrVTU51YEBj:invalid")],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 401);
+
+    let response = request(
+        "GET",
+        "/denied/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 403);
+}
+
+#[tokio::test]
+async fn oversized_body_rejected_early() {
+    let authorization = create_access_key(
+        "oversized",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    /* the test server allows 2048 bytes per request */
+    let response = request("PUT", "/oversized/password", &headers, &[b'x'; 2049]).await;
+    assert_eq!(response.status, 413);
+
+    /* refused before the handler would check the credentials */
+    let response = request("PUT", "/oversized/password", &[], &[b'x'; 2049]).await;
+    assert_eq!(response.status, 413);
+
+    let response = request("GET", "/oversized/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let response = request("PUT", "/oversized/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+}
+
+#[tokio::test]
+async fn websocket_login_denied() {
+    let (_, response) = Client::login(super::ROOT_USERNAME, "invalid").await;
+
+    assert_eq!(response["result"], "Denied");
+}
+
+#[tokio::test]
+async fn websocket_insert_find_delete() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "admin",
+                "secret_name": "password",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindSecret": {
+                "vault": "admin",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["Found"]["secret"],
+        base64_simd::STANDARD.encode_to_string("hunter2")
+    );
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateAccessKey": {
+                "vault": "admin",
+                "permission": ["DecryptSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    let authorization = super::authorization(
+        response["access_key"].as_str().unwrap(),
+        response["secret_access_key"].as_str().unwrap(),
+    );
+
+    let response = request(
+        "GET",
+        "/admin/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteSecret": {
+                "vault": "admin",
+                "secret_name": "password"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+
+    let response = request(
+        "GET",
+        "/admin/password",
+        &[("Authorization", authorization.as_str())],
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 404);
+}
@@ -0,0 +1,134 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cmd;
+
+/* serde lists every variant when it's given one it doesn't know */
+fn variants() -> Vec<String> {
+    let error = serde_json::from_value::<cmd::Request>(serde_json::json!({ "Unknown": {} }))
+        .unwrap_err()
+        .to_string();
+
+    error
+        .split("expected one of ")
+        .nth(1)
+        .unwrap()
+        .split(", ")
+        .map(|v| {
+            v.trim_matches(|c: char| c == '`' || c.is_whitespace())
+                .to_owned()
+        })
+        .collect()
+}
+
+/* the least a schema allows, only the required fields are filled in */
+fn sample(schema: &serde_json::Value, root: &serde_json::Value) -> serde_json::Value {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/$defs/");
+
+        return sample(&root["$defs"][name], root);
+    }
+
+    if let Some(values) = schema["enum"].as_array() {
+        return values[0].clone();
+    }
+
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+
+    if let Some(schemas) = schema["oneOf"].as_array() {
+        return sample(&schemas[0], root);
+    }
+
+    let kind = match &schema["type"] {
+        serde_json::Value::Array(kinds) => kinds[0].as_str().unwrap().to_owned(),
+        kind => kind.as_str().unwrap().to_owned(),
+    };
+
+    match kind.as_str() {
+        "object" => {
+            let mut result = serde_json::Map::new();
+
+            for field in schema["required"].as_array().into_iter().flatten() {
+                let field = field.as_str().unwrap();
+
+                result.insert(field.to_owned(), sample(&schema["properties"][field], root));
+            }
+
+            serde_json::Value::Object(result)
+        }
+        "array" => serde_json::json!([]),
+        "string" => serde_json::json!("sample"),
+        "integer" => serde_json::json!(1),
+        "boolean" => serde_json::json!(false),
+        kind => panic!("unexpected type {kind}"),
+    }
+}
+
+#[test]
+fn schema_covers_every_request() {
+    let schema = cmd::schema();
+    let requests = schema["oneOf"].as_array().unwrap();
+
+    let variants = variants();
+    assert!(variants.contains(&"InsertSecret".to_owned()));
+    assert_eq!(requests.len(), variants.len());
+
+    for variant in &variants {
+        let request = requests
+            .iter()
+            .find(|v| v["required"] == serde_json::json!([variant]))
+            .unwrap_or_else(|| panic!("{variant} is missing from the schema"));
+
+        let payload = sample(request, &schema);
+        /* parsed the way the websocket does it */
+        cmd::Request::parse(&payload.to_string())
+            .unwrap_or_else(|e| panic!("{payload} doesn't match the request: {}", e.error));
+
+        /* every required field really is required */
+        let fields = payload[variant].as_object().cloned().unwrap_or_default();
+
+        for field in fields.keys() {
+            let mut payload = payload.clone();
+            payload[variant].as_object_mut().unwrap().remove(field);
+
+            assert!(
+                cmd::Request::parse(&payload.to_string()).is_err(),
+                "{variant}.{field} isn't required"
+            );
+        }
+    }
+}
+
+#[test]
+fn schema_lists_required_fields() {
+    let schema = cmd::schema();
+
+    assert_eq!(
+        schema["$defs"]["RequestCreateUser"]["required"],
+        serde_json::json!(["username", "password", "role", "sg"])
+    );
+    assert_eq!(
+        schema["$defs"]["RequestInsertSecret"]["required"],
+        serde_json::json!(["secret_name", "vault"])
+    );
+    assert_eq!(
+        schema["$defs"]["RequestCreateAccessKey"]["required"],
+        serde_json::json!(["vault"])
+    );
+}
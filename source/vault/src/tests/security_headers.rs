@@ -0,0 +1,158 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, permission, server};
+
+use super::{create_access_key, free_port, request, request_over, write_key};
+
+fn self_signed(directory: &std::path::Path) -> (String, String) {
+    let key = openssl::pkey::PKey::from_ec_key(
+        openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let mut name = openssl::x509::X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+
+    let mut builder = openssl::x509::X509Builder::new().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&openssl::asn1::Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+
+    let san = openssl::x509::extension::SubjectAlternativeName::new()
+        .dns("localhost")
+        .build(&builder.x509v3_context(None, None))
+        .unwrap();
+    builder.append_extension(san).unwrap();
+    builder
+        .sign(&key, openssl::hash::MessageDigest::sha256())
+        .unwrap();
+
+    (
+        write_key(directory, "tls.pem", &builder.build().to_pem().unwrap()),
+        write_key(
+            directory,
+            "tls.key",
+            &key.private_key_to_pem_pkcs8().unwrap(),
+        ),
+    )
+}
+
+#[tokio::test]
+async fn secret_get_over_tls() {
+    let authorization = create_access_key(
+        "headers",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/headers/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let (certificate, key) = self_signed(directory.path());
+
+    let port = free_port();
+    let address = format!("127.0.0.1:{port}");
+
+    let mut server_config = config::get_clone().server;
+    server_config.listen_port = port;
+    server_config.tls = Some(config::ConfigServerTls {
+        certificate,
+        key,
+        ocsp: None,
+        reload_interval_secs: None,
+        hsts_max_age_secs: Some(600),
+        hsts_include_subdomains: Some(true),
+    });
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    let stream = loop {
+        match tokio::net::TcpStream::connect(&address).await {
+            Ok(value) => break value,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+        }
+    };
+
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect("localhost", stream)
+        .await
+        .expect("failed the TLS handshake");
+
+    let response = request_over(
+        stream,
+        "localhost",
+        "GET",
+        "/headers/password",
+        &headers,
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+    assert_eq!(
+        response.header("Strict-Transport-Security"),
+        Some("max-age=600; includeSubDomains")
+    );
+    assert_eq!(response.header("X-Content-Type-Options"), Some("nosniff"));
+    assert_eq!(response.header("Cache-Control"), Some("no-store"));
+}
+
+#[tokio::test]
+async fn no_hsts_over_plaintext() {
+    let authorization = create_access_key(
+        "headers-plain",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/headers-plain/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/headers-plain/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Strict-Transport-Security"), None);
+    assert_eq!(response.header("X-Content-Type-Options"), Some("nosniff"));
+    assert_eq!(response.header("Cache-Control"), Some("no-store"));
+}
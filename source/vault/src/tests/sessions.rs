@@ -0,0 +1,159 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures::StreamExt;
+
+use crate::server;
+
+use super::{free_port, Client, ROOT_PASSWORD, ROOT_USERNAME};
+
+fn find_session<'a>(
+    response: &'a serde_json::Value,
+    username: &str,
+) -> Option<&'a serde_json::Value> {
+    response["Sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["username"] == username)
+}
+
+#[tokio::test]
+async fn list_and_kill_sessions() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "session-victim",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut victim, response) = Client::login("session-victim", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    let response = victim.send(serde_json::json!({ "ListSessions": [] })).await;
+    assert_eq!(response, "Denied");
+
+    let response = client.send(serde_json::json!({ "ListSessions": [] })).await;
+    let session = find_session(&response, "session-victim").unwrap();
+    assert_eq!(session["ip"], "127.0.0.1");
+    assert!(session["connected"].is_string());
+    assert!(session["last_command"].is_string());
+
+    let id = session["id"].as_u64().unwrap();
+
+    let response = client
+        .send(serde_json::json!({ "KillSession": { "id": id } }))
+        .await;
+    assert_eq!(response["result"], "Killed");
+
+    loop {
+        match victim.stream.next().await {
+            Some(Ok(tokio_tungstenite::tungstenite::Message::Ping(_))) => {}
+            Some(Ok(tokio_tungstenite::tungstenite::Message::Close(Some(frame)))) => {
+                assert_eq!(
+                    frame.code,
+                    tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode::Policy
+                );
+                break;
+            }
+            message => panic!("expected the session to be closed, got {message:?}"),
+        }
+    }
+
+    /* the entry goes away once the server side of the session is done */
+    let mut listed = true;
+
+    for _ in 0..50 {
+        let response = client.send(serde_json::json!({ "ListSessions": [] })).await;
+        listed = find_session(&response, "session-victim").is_some();
+
+        if !listed {
+            break;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(!listed);
+
+    let response = client
+        .send(serde_json::json!({ "KillSession": { "id": id } }))
+        .await;
+    assert_eq!(response["result"], "NotFound");
+}
+
+#[tokio::test]
+async fn max_sessions() {
+    super::server();
+
+    let port = free_port();
+    let address = format!("127.0.0.1:{port}");
+
+    let mut server_config = crate::config::get_clone().server;
+    server_config.listen_port = port;
+    server_config.max_sessions = Some(2);
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    while tokio::net::TcpStream::connect(&address).await.is_err() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let mut sessions = Vec::new();
+
+    for _ in 0..2 {
+        let (client, response) = Client::login_to(&address, ROOT_USERNAME, ROOT_PASSWORD).await;
+        assert_eq!(response["result"], "Granted");
+
+        sessions.push(client);
+    }
+
+    let (_, response) = Client::login_to(&address, ROOT_USERNAME, ROOT_PASSWORD).await;
+    assert_eq!(response["result"], "SessionLimitReached");
+
+    drop(sessions.pop());
+
+    /* the slot is given back once the server side of the session is done */
+    let mut result = serde_json::Value::Null;
+
+    for _ in 0..50 {
+        let (client, response) = Client::login_to(&address, ROOT_USERNAME, ROOT_PASSWORD).await;
+        result = response["result"].clone();
+
+        if result == "Granted" {
+            sessions.push(client);
+            break;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(result, "Granted");
+
+    let (_, response) = Client::login_to(&address, ROOT_USERNAME, ROOT_PASSWORD).await;
+    assert_eq!(response["result"], "SessionLimitReached");
+}
@@ -0,0 +1,98 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, config};
+
+use super::server;
+
+fn keys() -> (p256::ecdsa::SigningKey, p256::ecdsa::VerifyingKey) {
+    server();
+
+    let config = config::get_clone();
+
+    (
+        access_keys::load_signing_key(&config.access_keys.signing_key).unwrap(),
+        access_keys::load_verifying_key(&config.access_keys.verifying_key).unwrap(),
+    )
+}
+
+fn decode(signature: &str) -> Vec<u8> {
+    base64_simd::STANDARD.decode_to_vec(signature).unwrap()
+}
+
+#[test]
+fn dual_verify_accepts_both_keys() {
+    let (signing_key, verifying_key) = keys();
+    let previous_signing_key = p256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+    let previous_verifying_key = *previous_signing_key.verifying_key();
+
+    let signature = decode(&access_keys::sign_with(&signing_key, b"pepper", "secret"));
+    let previous_signature = decode(&access_keys::sign_with(
+        &previous_signing_key,
+        b"pepper",
+        "secret",
+    ));
+
+    let verify = |signature: &[u8], key: &str| {
+        access_keys::verify_rotating(
+            &verifying_key,
+            Some(&previous_verifying_key),
+            b"pepper",
+            false,
+            key,
+            signature,
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        verify(&signature, "secret"),
+        access_keys::SignatureMatch::Current
+    );
+    assert_eq!(
+        verify(&previous_signature, "secret"),
+        access_keys::SignatureMatch::Previous
+    );
+    assert_eq!(
+        verify(&previous_signature, "other"),
+        access_keys::SignatureMatch::Mismatch
+    );
+}
+
+#[test]
+fn single_verify_after_cutover() {
+    let (signing_key, verifying_key) = keys();
+    let previous_signing_key = p256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+
+    let signature = decode(&access_keys::sign_with(&signing_key, b"pepper", "secret"));
+    let previous_signature = decode(&access_keys::sign_with(
+        &previous_signing_key,
+        b"pepper",
+        "secret",
+    ));
+
+    let verify = |signature: &[u8]| {
+        access_keys::verify_rotating(&verifying_key, None, b"pepper", false, "secret", signature)
+            .unwrap()
+    };
+
+    assert_eq!(verify(&signature), access_keys::SignatureMatch::Current);
+    assert_eq!(
+        verify(&previous_signature),
+        access_keys::SignatureMatch::Mismatch
+    );
+}
@@ -0,0 +1,133 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{authorization as authorization_for, create_access_key, request, Client};
+
+async fn simulate(
+    client: &mut Client,
+    access_key: &str,
+    ip: &str,
+    permission: &str,
+) -> serde_json::Value {
+    client
+        .send(serde_json::json!({
+            "SimulateAccess": {
+                "vault": "simulate",
+                "access_key": access_key,
+                "ip": ip,
+                "permission": permission
+            }
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn simulation_matches_requests() {
+    let authorization = create_access_key("simulate", vec![permission::VaultRoles::CreateSecrets]);
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+    let mut client = Client::login_root().await;
+
+    let response = request("POST", "/simulate/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let response = simulate(&mut client, access_key, "127.0.0.1", "CreateSecrets").await;
+    assert_eq!(response["result"], "Authorized");
+    assert!(response.get("reason").is_none());
+
+    let response = request("GET", "/simulate/password", &headers, b"").await;
+    assert_eq!(response.status, 403);
+
+    let response = simulate(&mut client, access_key, "127.0.0.1", "DecryptSecrets").await;
+    assert_eq!(response["result"], "Forbidden");
+    assert_eq!(response["reason"], "MissingPermission");
+
+    let unknown = authorization_for("simulate-unknown", "secret");
+    let response = request(
+        "POST",
+        "/simulate/password",
+        &[("Authorization", unknown.as_str())],
+        b"hunter2",
+    )
+    .await;
+    assert_eq!(response.status, 401);
+
+    let response = simulate(
+        &mut client,
+        "simulate-unknown",
+        "127.0.0.1",
+        "CreateSecrets",
+    )
+    .await;
+    assert_eq!(response["result"], "Unauthorized");
+    assert_eq!(response["reason"], "UnknownAccessKey");
+
+    /* the tests only reach the server from 127.0.0.1, this one is simulated alone */
+    let response = simulate(&mut client, access_key, "10.0.0.1", "CreateSecrets").await;
+    assert_eq!(response["result"], "Unauthorized");
+    assert_eq!(response["reason"], "NotInSecurityGroup");
+    assert_eq!(response["sg"], serde_json::json!(["127.0.0.1/32"]));
+
+    let response = client
+        .send(serde_json::json!({
+            "SetAccessKeyEnabled": {
+                "vault": "simulate",
+                "access_key": access_key,
+                "enabled": false
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Updated");
+
+    let response = request("POST", "/simulate/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 401);
+
+    let response = simulate(&mut client, access_key, "127.0.0.1", "CreateSecrets").await;
+    assert_eq!(response["result"], "Unauthorized");
+    assert_eq!(response["reason"], "Disabled");
+
+    let response = simulate(&mut client, access_key, "not an ip", "CreateSecrets").await;
+    assert_eq!(response["result"], "InvalidIp");
+}
+
+#[tokio::test]
+async fn simulate_access_denied() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "simulate-user",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, response) = Client::login("simulate-user", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    let response = simulate(&mut client, "any", "127.0.0.1", "CreateSecrets").await;
+    assert_eq!(response["result"], "Denied");
+}
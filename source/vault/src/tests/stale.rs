@@ -0,0 +1,109 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, clock, config, db, permission};
+
+use super::server;
+
+fn insert_key(vault: &str, access_key: &str, created_days_ago: i64, used_days_ago: Option<i64>) {
+    server();
+
+    let days_ago = |v| (clock::now() - chrono::Duration::days(v)).to_rfc3339();
+
+    db::access::insert(
+        vault,
+        access_key,
+        db::access::AccessKeyDocument {
+            secret_access_key: String::new(),
+            permission: vec![permission::VaultRoles::ListSecrets],
+            sg: Vec::new(),
+            created: days_ago(created_days_ago),
+            last_used: used_days_ago.map(days_ago),
+            enabled: true,
+            created_by: None,
+            expires: None,
+            name_prefixes: Vec::new(),
+        },
+    )
+    .unwrap();
+}
+
+fn stale_in(vault: &str, disable: bool) -> Vec<access_keys::StaleAccessKey> {
+    access_keys::check_stale(clock::now() - chrono::Duration::days(30), disable)
+        .unwrap()
+        .into_iter()
+        .filter(|v| v.vault == vault)
+        .collect()
+}
+
+#[test]
+fn stale_key_warned() {
+    insert_key("stale-warned", "stale-never-used", 90, None);
+    insert_key("stale-warned", "stale-used-long-ago", 90, Some(60));
+    insert_key("stale-warned", "stale-used-recently", 90, Some(1));
+
+    let mut stale: Vec<String> = stale_in("stale-warned", false)
+        .into_iter()
+        .map(|v| {
+            assert!(!v.disabled);
+            v.access_key
+        })
+        .collect();
+    stale.sort();
+
+    assert_eq!(stale, vec!["stale-never-used", "stale-used-long-ago"]);
+
+    assert!(
+        db::access::find("stale-warned", "stale-never-used")
+            .unwrap()
+            .unwrap()
+            .enabled
+    );
+
+    let log = std::fs::read_to_string(config::get_clone().log.unwrap().filename).unwrap();
+    assert!(log
+        .lines()
+        .any(|v| v.contains("\"access key is stale\"") && v.contains("stale-never-used")));
+}
+
+#[test]
+fn stale_key_disabled() {
+    insert_key("stale-disabled", "stale-idle", 90, Some(45));
+    insert_key("stale-disabled", "stale-active", 90, Some(2));
+
+    let stale = stale_in("stale-disabled", true);
+
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].access_key, "stale-idle");
+    assert!(stale[0].disabled);
+
+    assert!(
+        !db::access::find("stale-disabled", "stale-idle")
+            .unwrap()
+            .unwrap()
+            .enabled
+    );
+    assert!(
+        db::access::find("stale-disabled", "stale-active")
+            .unwrap()
+            .unwrap()
+            .enabled
+    );
+
+    /* disabled keys can't be used, so they aren't reported again */
+    assert!(stale_in("stale-disabled", true).is_empty());
+}
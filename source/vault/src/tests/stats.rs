@@ -0,0 +1,108 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+#[tokio::test]
+async fn byte_counters() {
+    let authorization = create_access_key(
+        "stats",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+    let secret = vec![b'a'; 400];
+
+    let response = request("POST", "/stats/password", &headers, &secret).await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/stats/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, secret);
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "stats",
+                "secret_name": "websocket",
+                "data": base64_simd::STANDARD.encode_to_string(&secret)
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Inserted");
+
+    let bytes_received = client.bytes_received;
+    let response = client
+        .send(serde_json::json!({
+            "SessionStats": []
+        }))
+        .await;
+
+    let access_keys = response["Stats"]["access_keys"].as_array().unwrap();
+    let entry = access_keys
+        .iter()
+        .find(|v| v["vault"] == "stats" && v["access_key"] == access_key)
+        .expect("missing access key stats");
+    assert_eq!(entry["requests"], 2);
+    assert_eq!(entry["bytes_received"], 400);
+    assert_eq!(entry["bytes_sent"], 400);
+
+    /* other tests can have root sessions open as well */
+    let sessions = response["Stats"]["sessions"].as_array().unwrap();
+    assert!(sessions
+        .iter()
+        .any(|v| v["username"] == super::ROOT_USERNAME
+            && v["requests"] == 2
+            && v["bytes_received"] == client.bytes_sent
+            && v["bytes_sent"] == bytes_received));
+}
+
+#[tokio::test]
+async fn session_stats_denied() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "stats",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, _) = Client::login("stats", "hunter2").await;
+
+    let response = client
+        .send(serde_json::json!({
+            "SessionStats": []
+        }))
+        .await;
+    assert_eq!(response, "Denied");
+}
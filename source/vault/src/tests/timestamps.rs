@@ -0,0 +1,103 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{create_access_key, request, Client, ROOT_USERNAME};
+
+fn assert_utc(timestamp: &str) {
+    let parsed = chrono::DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp");
+    assert_eq!(parsed.offset().local_minus_utc(), 0, "{timestamp}");
+}
+
+#[tokio::test]
+async fn written_timestamps_are_utc() {
+    let authorization =
+        create_access_key("timestamps", vec![permission::VaultRoles::CreateSecrets]);
+    let headers = [("Authorization", authorization.as_str())];
+    let (access_key, _) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let response = request("POST", "/timestamps/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    assert_utc(&db::vault::find("timestamps").unwrap().unwrap().created);
+    assert_utc(
+        &db::secret::find("timestamps", "password")
+            .unwrap()
+            .unwrap()
+            .created,
+    );
+
+    let access_key = db::access::find("timestamps", access_key).unwrap().unwrap();
+    assert_utc(&access_key.created);
+    assert_utc(&access_key.last_used.unwrap());
+
+    let _ = Client::login_root().await;
+
+    assert_utc(
+        &db::user::find(ROOT_USERNAME)
+            .unwrap()
+            .unwrap()
+            .last_login
+            .unwrap(),
+    );
+}
+
+#[tokio::test]
+async fn migrate_local_timestamps() {
+    let _ = super::server();
+
+    let result = db::user::insert(
+        "local-offset",
+        db::user::UserDocument {
+            password: "N/A".to_owned(),
+            role: permission::UserRole::User,
+            last_login: Some("2024-01-01T12:00:00+02:00".to_owned()),
+            sg: Vec::new(),
+            manually_locked: false,
+            provisioned: false,
+        },
+    )
+    .unwrap();
+    assert!(matches!(result, db::user::InsertUserResult::Created));
+
+    db::migrate_timestamps().unwrap();
+
+    assert_eq!(
+        db::user::find("local-offset")
+            .unwrap()
+            .unwrap()
+            .last_login
+            .as_deref(),
+        Some("2024-01-01T10:00:00+00:00")
+    );
+
+    /* already migrated documents are left untouched */
+    db::migrate_timestamps().unwrap();
+
+    assert_eq!(
+        db::user::find("local-offset")
+            .unwrap()
+            .unwrap()
+            .last_login
+            .as_deref(),
+        Some("2024-01-01T10:00:00+00:00")
+    );
+}
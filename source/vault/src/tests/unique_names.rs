@@ -0,0 +1,101 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::create_access_key;
+
+fn document() -> db::secret::SecretDocument {
+    db::secret::SecretDocument {
+        created: "2024-01-01T00:00:00+00:00".to_owned(),
+        secret: String::new(),
+        etag: None,
+        last_accessed: None,
+        created_by: None,
+        alias: None,
+        bound: true,
+    }
+}
+
+fn insert(vault: &str, secret_name: &str, unique_names: bool) -> db::secret::InsertSecretResult {
+    db::secret::insert(vault, secret_name, document(), unique_names).unwrap()
+}
+
+#[tokio::test]
+async fn cross_vault_duplicate_rejected() {
+    for vault in ["unique-first", "unique-second", "unique-renamed"] {
+        create_access_key(vault, vec![permission::VaultRoles::CreateSecrets]);
+    }
+
+    assert!(matches!(
+        insert("unique-first", "unique-shared", true),
+        db::secret::InsertSecretResult::Inserted
+    ));
+    assert!(matches!(
+        insert("unique-second", "unique-shared", true),
+        db::secret::InsertSecretResult::NameTaken
+    ));
+    assert!(db::secret::find("unique-second", "unique-shared")
+        .unwrap()
+        .is_none());
+
+    /* the holder itself can still update it */
+    assert!(matches!(
+        insert("unique-first", "unique-shared", true),
+        db::secret::InsertSecretResult::Updated
+    ));
+
+    /* a deleted secret gives its name back */
+    db::secret::delete(
+        "unique-first",
+        "unique-shared",
+        false,
+        db::Actor::User(super::ROOT_USERNAME.to_owned()),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        insert("unique-second", "unique-shared", true),
+        db::secret::InsertSecretResult::Inserted
+    ));
+    assert!(matches!(
+        insert("unique-first", "unique-shared", true),
+        db::secret::InsertSecretResult::NameTaken
+    ));
+}
+
+#[tokio::test]
+async fn cross_vault_duplicate_allowed_when_off() {
+    for vault in ["unique-off-first", "unique-off-second"] {
+        create_access_key(vault, vec![permission::VaultRoles::CreateSecrets]);
+    }
+
+    assert!(matches!(
+        insert("unique-off-first", "unique-loose", false),
+        db::secret::InsertSecretResult::Inserted
+    ));
+    assert!(matches!(
+        insert("unique-off-second", "unique-loose", false),
+        db::secret::InsertSecretResult::Inserted
+    ));
+
+    /* duplicates made before the policy was turned on can still be updated */
+    assert!(matches!(
+        insert("unique-off-first", "unique-loose", true),
+        db::secret::InsertSecretResult::Updated
+    ));
+}
@@ -0,0 +1,64 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{config, permission, server};
+
+use super::{create_access_key, request, request_over};
+
+#[tokio::test]
+async fn unix_socket_get() {
+    let authorization = create_access_key(
+        "unix",
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    );
+    let headers = [("Authorization", authorization.as_str())];
+
+    let response = request("POST", "/unix/password", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+
+    let directory = tempfile::tempdir().expect("failed to create a temporary directory");
+    let path = directory
+        .path()
+        .join("vaulty.sock")
+        .to_str()
+        .unwrap()
+        .to_owned();
+
+    let mut server_config = config::get_clone().server;
+    server_config.unix_socket = Some(path.clone());
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server::serve(server_config))
+            .expect("failed to start the server");
+    });
+
+    let stream = loop {
+        match tokio::net::UnixStream::connect(&path).await {
+            Ok(value) => break value,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+        }
+    };
+
+    let response = request_over(stream, "localhost", "GET", "/unix/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
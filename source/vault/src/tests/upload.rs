@@ -0,0 +1,228 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{clock, db, permission};
+
+use super::{create_access_key, request};
+
+fn authorization(vault: &str) -> String {
+    create_access_key(
+        vault,
+        vec![
+            permission::VaultRoles::CreateSecrets,
+            permission::VaultRoles::DecryptSecrets,
+        ],
+    )
+}
+
+async fn start(vault: &str, headers: &[(&str, &str)]) -> String {
+    let response = request("POST", &format!("/{vault}/password/uploads"), headers, b"").await;
+    assert_eq!(response.status, 201);
+    assert_eq!(response.header("Upload-Offset"), Some("0"));
+
+    let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    let id = body["id"].as_str().unwrap().to_owned();
+
+    assert_eq!(
+        response.header("Location"),
+        Some(format!("/{vault}/password/uploads/{id}").as_str())
+    );
+
+    id
+}
+
+#[tokio::test]
+async fn two_chunk_upload() {
+    let authorization = authorization("upload");
+    let headers = [("Authorization", authorization.as_str())];
+
+    let id = start("upload", &headers).await;
+    let path = format!("/upload/password/uploads/{id}");
+
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "0")],
+        b"hunter",
+    )
+    .await;
+    assert_eq!(response.status, 204);
+    assert_eq!(response.header("Upload-Offset"), Some("6"));
+
+    /* nothing is stored until the upload completes */
+    let response = request("GET", "/upload/password", &headers, b"").await;
+    assert_eq!(response.status, 404);
+
+    let response = request("PATCH", &path, &[headers[0], ("Upload-Offset", "6")], b"2").await;
+    assert_eq!(response.status, 204);
+    assert_eq!(response.header("Upload-Offset"), Some("7"));
+
+    let response = request("POST", &format!("{path}/complete"), &headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/upload/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    /* a completed upload is gone */
+    let response = request("POST", &format!("{path}/complete"), &headers, b"").await;
+    assert_eq!(response.status, 404);
+}
+
+#[tokio::test]
+async fn out_of_order_chunk() {
+    let authorization = authorization("upload-order");
+    let headers = [("Authorization", authorization.as_str())];
+
+    let id = start("upload-order", &headers).await;
+    let path = format!("/upload-order/password/uploads/{id}");
+
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "3")],
+        b"ter2",
+    )
+    .await;
+    assert_eq!(response.status, 409);
+    assert_eq!(response.header("Upload-Offset"), Some("0"));
+
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "0")],
+        b"hun",
+    )
+    .await;
+    assert_eq!(response.status, 204);
+
+    /* a resent chunk isn't appended twice */
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "0")],
+        b"hun",
+    )
+    .await;
+    assert_eq!(response.status, 409);
+    assert_eq!(response.header("Upload-Offset"), Some("3"));
+
+    let response = request("PATCH", &path, &headers, b"ter2").await;
+    assert_eq!(response.status, 400);
+
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "3")],
+        b"ter2",
+    )
+    .await;
+    assert_eq!(response.status, 204);
+
+    let response = request("POST", &format!("{path}/complete"), &headers, b"").await;
+    assert_eq!(response.status, 201);
+
+    let response = request("GET", "/upload-order/password", &headers, b"").await;
+    assert_eq!(response.body, b"hunter2");
+}
+
+#[tokio::test]
+async fn upload_limits() {
+    let authorization = authorization("upload-limits");
+    let headers = [("Authorization", authorization.as_str())];
+
+    let id = start("upload-limits", &headers).await;
+    let path = format!("/upload-limits/password/uploads/{id}");
+
+    /* an empty upload has nothing to store */
+    let response = request("POST", &format!("{path}/complete"), &headers, b"").await;
+    assert_eq!(response.status, 422);
+
+    /* a chunk is held to the request body limit, the upload to its own */
+    for offset in ["0", "2048"] {
+        let response = request(
+            "PATCH",
+            &path,
+            &[headers[0], ("Upload-Offset", offset)],
+            &[b'x'; 2048],
+        )
+        .await;
+        assert_eq!(response.status, 204);
+    }
+
+    let response = request(
+        "PATCH",
+        &path,
+        &[headers[0], ("Upload-Offset", "4096")],
+        b"x",
+    )
+    .await;
+    assert_eq!(response.status, 413);
+
+    let response = request(
+        "PATCH",
+        "/upload-limits/password/uploads/unknown",
+        &[headers[0], ("Upload-Offset", "0")],
+        b"hunter2",
+    )
+    .await;
+    assert_eq!(response.status, 404);
+
+    let response = request("POST", "/upload-limits/password/uploads", &[], b"").await;
+    assert_eq!(response.status, 401);
+}
+
+#[tokio::test]
+async fn expired_upload() {
+    let authorization = authorization("upload-expired");
+    let headers = [("Authorization", authorization.as_str())];
+
+    db::upload::insert(
+        "upload-expired",
+        "password",
+        "stale",
+        db::upload::UploadDocument {
+            created: (clock::now() - chrono::Duration::hours(2)).to_rfc3339(),
+            length: 7,
+            data: base64_simd::STANDARD.encode_to_string(b"hunter2"),
+        },
+    )
+    .unwrap();
+
+    let response = request(
+        "PATCH",
+        "/upload-expired/password/uploads/stale",
+        &[headers[0], ("Upload-Offset", "7")],
+        b"!",
+    )
+    .await;
+    assert_eq!(response.status, 404);
+
+    let response = request(
+        "POST",
+        "/upload-expired/password/uploads/stale/complete",
+        &headers,
+        b"",
+    )
+    .await;
+    assert_eq!(response.status, 404);
+
+    assert!(db::upload::sweep(clock::now() - chrono::Duration::hours(1)).unwrap() >= 1);
+    assert!(db::upload::find("upload-expired", "password", "stale")
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,485 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{db, permission};
+
+use super::{Client, ROOT_USERNAME};
+
+#[tokio::test]
+async fn locked_user_login() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "locked",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let response = client
+        .send(serde_json::json!({
+            "LockUser": {
+                "username": "locked"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Locked");
+
+    let (_, response) = Client::login("locked", "hunter2").await;
+    assert_eq!(response["result"], "Denied");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindUser": {
+                "username": "locked"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["locked"], true);
+
+    let response = client
+        .send(serde_json::json!({
+            "UnlockUser": {
+                "username": "locked"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Unlocked");
+
+    let (_, response) = Client::login("locked", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+}
+
+#[tokio::test]
+async fn lock_user_denied() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "unprivileged",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, response) = Client::login("unprivileged", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    let response = client
+        .send(serde_json::json!({
+            "LockUser": {
+                "username": super::ROOT_USERNAME
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Denied");
+}
+
+#[tokio::test]
+async fn login_response_role() {
+    let (mut client, response) = Client::login(super::ROOT_USERNAME, super::ROOT_PASSWORD).await;
+    assert_eq!(response["result"], "Granted");
+    assert_eq!(response["role"], "Admin");
+    assert_eq!(response["sg"], serde_json::json!(["127.0.0.1/32"]));
+    assert!(response.get("password").is_none());
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "viewer",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32", "10.0.0.0/8"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (_, response) = Client::login("viewer", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+    assert_eq!(response["role"], "User");
+    assert_eq!(
+        response["sg"],
+        serde_json::json!(["127.0.0.1/32", "10.0.0.0/8"])
+    );
+    assert!(response.get("password").is_none());
+
+    let (_, response) = Client::login("viewer", "invalid").await;
+    assert_eq!(response["result"], "Denied");
+    assert!(response["role"].is_null());
+}
+
+async fn create_paged_users(client: &mut Client) {
+    for (username, role) in [
+        ("zz-page-1", "User"),
+        ("zz-page-2", "Admin"),
+        ("zz-page-3", "User"),
+        ("zz-page-4", "Admin"),
+        ("zz-page-5", "User"),
+    ] {
+        let response = client
+            .send(serde_json::json!({
+                "CreateUser": {
+                    "username": username,
+                    "password": "hunter2",
+                    "role": role,
+                    "sg": ["127.0.0.1/32"]
+                }
+            }))
+            .await;
+        assert!(
+            response["result"] == "Created" || response["result"] == "Exists",
+            "{response}"
+        );
+    }
+}
+
+fn usernames(response: &serde_json::Value) -> Vec<&str> {
+    response["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["username"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn list_users_pages() {
+    let mut client = Client::login_root().await;
+
+    create_paged_users(&mut client).await;
+
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": "zz-page-",
+                "limit": 2
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-1", "zz-page-2"]);
+    assert_eq!(response["next"], "zz-page-2");
+
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": response["next"],
+                "limit": 2
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-3", "zz-page-4"]);
+    assert_eq!(response["next"], "zz-page-4");
+
+    /* the last page is shorter than the limit and doesn't point any further */
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": response["next"],
+                "limit": 2
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-5"]);
+    assert!(response.get("next").is_none());
+
+    /* a page exactly filled by the rest has no next page either */
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": "zz-page-3",
+                "limit": 2
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-4", "zz-page-5"]);
+    assert!(response.get("next").is_none());
+
+    /* older clients send no arguments and get everyone */
+    let response = client.send(serde_json::json!({ "ListUsers": [] })).await;
+    let all = usernames(&response);
+    assert!(all.contains(&"root") && all.contains(&"zz-page-5"));
+    assert!(response.get("next").is_none());
+}
+
+#[tokio::test]
+async fn list_users_role_filter() {
+    let mut client = Client::login_root().await;
+
+    create_paged_users(&mut client).await;
+
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": "zz-page-",
+                "role": "Admin"
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-2", "zz-page-4"]);
+
+    /* the limit counts only the users that pass the filter */
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "after": "zz-page-",
+                "limit": 1,
+                "role": "Admin"
+            }
+        }))
+        .await;
+    assert_eq!(usernames(&response), ["zz-page-2"]);
+    assert_eq!(response["next"], "zz-page-2");
+
+    let response = client
+        .send(serde_json::json!({
+            "ListUsers": {
+                "role": "Admin"
+            }
+        }))
+        .await;
+    assert!(response["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|v| v["role"] == "Admin"));
+}
+
+/* the last admin check sees every admin, none may be promoted while it runs */
+static ADMINS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn change_role(client: &mut Client, username: &str, role: &str) -> serde_json::Value {
+    client
+        .send(serde_json::json!({
+            "ChangeUserRole": {
+                "username": username,
+                "role": role
+            }
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn change_user_role() {
+    let _guard = ADMINS.lock().await;
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "role-change",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let response = change_role(&mut client, "role-change", "Admin").await;
+    assert_eq!(response["result"], "Promoted");
+
+    let response = change_role(&mut client, "role-change", "Admin").await;
+    assert_eq!(response["result"], "NoChange");
+
+    let response = change_role(&mut client, "role-change", "User").await;
+    assert_eq!(response["result"], "Demoted");
+
+    let response = change_role(&mut client, "role-change", "User").await;
+    assert_eq!(response["result"], "NoChange");
+
+    /* the older commands answer the same way */
+    let response = client
+        .send(serde_json::json!({
+            "PromoteUser": {
+                "username": "role-change"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Promoted");
+
+    let response = client
+        .send(serde_json::json!({
+            "DemoteUser": {
+                "username": "role-change"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Demoted");
+
+    let response = change_role(&mut client, "role-missing", "Admin").await;
+    assert_eq!(response["result"], "NotFound");
+}
+
+#[tokio::test]
+async fn change_user_role_last_admin() {
+    let _guard = ADMINS.lock().await;
+    let mut client = Client::login_root().await;
+
+    /* every other admin is locked for a moment, which leaves root as the last one */
+    create_paged_users(&mut client).await;
+
+    let locked: Vec<String> = db::user::list_paged(None, None, Some(permission::UserRole::Admin))
+        .unwrap()
+        .users
+        .into_iter()
+        .filter(|v| v.username != ROOT_USERNAME && !v.manually_locked)
+        .map(|v| v.username)
+        .collect();
+
+    for username in &locked {
+        db::user::set_locked(username, true).unwrap();
+    }
+
+    let response = change_role(&mut client, ROOT_USERNAME, "User").await;
+    let alias = client
+        .send(serde_json::json!({
+            "DemoteUser": {
+                "username": ROOT_USERNAME
+            }
+        }))
+        .await;
+
+    for username in &locked {
+        db::user::set_locked(username, false).unwrap();
+    }
+
+    assert_eq!(response["result"], "LastAdmin");
+    assert_eq!(alias["result"], "LastAdmin");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindUser": {
+                "username": ROOT_USERNAME
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["role"], "Admin");
+}
+
+#[tokio::test]
+async fn protected_user() {
+    let _guard = ADMINS.lock().await;
+    let mut client = Client::login_root().await;
+
+    for username in ["protected-admin", "unprotected-admin"] {
+        let response = client
+            .send(serde_json::json!({
+                "CreateUser": {
+                    "username": username,
+                    "password": "hunter2",
+                    "role": "Admin",
+                    "sg": ["127.0.0.1/32"]
+                }
+            }))
+            .await;
+        assert_eq!(response["result"], "Created");
+    }
+
+    let response = change_role(&mut client, "protected-admin", "User").await;
+    assert_eq!(response["result"], "Protected");
+
+    let response = client
+        .send(serde_json::json!({
+            "DemoteUser": {
+                "username": "protected-admin"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Protected");
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteUser": {
+                "username": "protected-admin"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Protected");
+
+    let document = db::user::find("protected-admin").unwrap().unwrap();
+    assert!(matches!(document.role, permission::UserRole::Admin));
+
+    let response = change_role(&mut client, "unprotected-admin", "User").await;
+    assert_eq!(response["result"], "Demoted");
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteUser": {
+                "username": "unprotected-admin"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+}
+
+#[tokio::test]
+async fn change_password_verifies_the_current_one() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "passwd",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, response) = Client::login("passwd", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    let response = client
+        .send(serde_json::json!({
+            "ChangePasswordForUser": {
+                "username": "passwd",
+                "password": "hunter3",
+                "current_password": "wrong"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Denied");
+
+    let response = client
+        .send(serde_json::json!({
+            "ChangePasswordForUser": {
+                "username": "passwd",
+                "password": "hunter3",
+                "current_password": "hunter2"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Changed");
+
+    let (_, response) = Client::login("passwd", "hunter2").await;
+    assert_eq!(response["result"], "Denied");
+
+    let (_, response) = Client::login("passwd", "hunter3").await;
+    assert_eq!(response["result"], "Granted");
+}
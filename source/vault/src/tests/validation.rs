@@ -0,0 +1,130 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config;
+
+fn write_config(content: &str) -> (tempfile::TempDir, String) {
+    let directory = tempfile::tempdir().unwrap();
+    let filename = directory.path().join("config.yml");
+    std::fs::write(&filename, content).unwrap();
+
+    (directory, filename.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn minimal_config_gets_defaults() {
+    let (_directory, filename) = write_config(
+        "db:
+  location: database.bin
+secrets:
+  rsa_private_key: rsa.key
+  rsa_public_key: rsa.pub
+  aes_key: aes.key
+  aes_iv: aes.iv
+access_keys:
+  signing_key: signing.key
+  verifying_key: verifying.pub
+",
+    );
+
+    let config = config::load(&filename).unwrap();
+    assert_eq!(config.node_name, "Vaulty");
+    assert_eq!(config.access_keys.delay_unsuccessful_attempts_millis, 5000);
+    assert_eq!(config.access_keys.acces_key_length, 20);
+    assert_eq!(config.access_keys.secret_access_key_length, 40);
+    assert_eq!(config.users.delay_unsuccessful_attempts_millis, 5000);
+    assert_eq!(config.server.listen_address, "127.0.0.1");
+    assert_eq!(config.server.listen_port, 8080);
+}
+
+#[test]
+fn partial_config_lists_every_gap() {
+    let (_directory, filename) = write_config(
+        "node_name: Vaulty
+secrets:
+  aes_key: aes.key
+server:
+  tls:
+    certificate: server.crt
+",
+    );
+
+    let error = config::load(&filename).unwrap_err();
+    assert_eq!(error.message, "invalid config");
+    assert_eq!(error.attr.unwrap()["filename"], filename);
+
+    let error = error.error.unwrap();
+
+    for field in [
+        "db.location",
+        "secrets.aes_iv",
+        "secrets.rsa_private_key",
+        "secrets.rsa_public_key",
+        "access_keys.signing_key",
+        "access_keys.verifying_key",
+        "server.tls.key",
+    ] {
+        assert!(
+            error.contains(&format!("{field} is missing")),
+            "{field} isn't listed in: {error}"
+        );
+    }
+
+    assert!(!error.contains("secrets.aes_key"));
+    assert!(!error.contains("server.tls.certificate"));
+}
+
+#[test]
+fn invalid_values_are_listed() {
+    let (_directory, filename) = write_config(
+        "db:
+  location: database.bin
+  compaction:
+    free_ratio: 1.5
+secrets:
+  scheme: Ec
+  ec_private_key: ec.key
+  aes_key: aes.key
+  aes_iv: aes.iv
+access_keys:
+  signing_key: signing.key
+  verifying_key: verifying.pub
+  acces_key_length: 0
+  secret_access_key_length: 0
+server:
+  listen_port: 0
+",
+    );
+
+    let error = config::load(&filename).unwrap_err().error.unwrap();
+
+    for problem in [
+        "db.compaction.free_ratio must be between 0 and 1",
+        "secrets.ec_public_key is missing",
+        "access_keys.acces_key_length must be greater than 0",
+        "access_keys.secret_access_key_length must be greater than 0",
+        "server.listen_port must be greater than 0",
+    ] {
+        assert!(
+            error.contains(problem),
+            "{problem} isn't listed in: {error}"
+        );
+    }
+
+    /* the RSA keys are only needed for the Rsa scheme */
+    assert!(!error.contains("rsa"));
+}
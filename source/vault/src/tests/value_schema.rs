@@ -0,0 +1,120 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+async fn set_value_schema(client: &mut Client, vault: &str, value_schema: &str) {
+    let response = client
+        .send(serde_json::json!({
+            "SetVaultValueSchema": {
+                "vault": vault,
+                "value_schema": value_schema
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Updated");
+}
+
+#[tokio::test]
+async fn utf8_vault_rejects_binary() {
+    let authorization = create_access_key("schema-utf8", vec![permission::VaultRoles::Owner]);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let mut client = Client::login_root().await;
+    set_value_schema(&mut client, "schema-utf8", "Utf8").await;
+
+    let response = request("PUT", "/schema-utf8/binary", &headers, &[0xff, 0xfe, 0x00]).await;
+    assert_eq!(response.status, 422);
+
+    let response = request("PUT", "/schema-utf8/text", &headers, "hunter2 ✓".as_bytes()).await;
+    assert_eq!(response.status, 201);
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "schema-utf8",
+                "secret_name": "binary",
+                "data": base64_simd::STANDARD.encode_to_string([0xff, 0xfe, 0x00])
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "InvalidValue");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindVault": {
+                "vault": "schema-utf8"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["value_schema"], "Utf8");
+}
+
+#[tokio::test]
+async fn json_vault_rejects_non_json() {
+    let authorization = create_access_key("schema-json", vec![permission::VaultRoles::Owner]);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let mut client = Client::login_root().await;
+    set_value_schema(&mut client, "schema-json", "Json").await;
+
+    let response = request("PUT", "/schema-json/config", &headers, b"{\"user\": ").await;
+    assert_eq!(response.status, 422);
+
+    let response = request(
+        "PUT",
+        "/schema-json/config",
+        &headers,
+        b"{\"user\": \"root\"}",
+    )
+    .await;
+    assert_eq!(response.status, 201);
+
+    let response = client
+        .send(serde_json::json!({
+            "InsertSecret": {
+                "vault": "schema-json",
+                "secret_name": "plain",
+                "data": base64_simd::STANDARD.encode_to_string("hunter2")
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "InvalidValue");
+
+    /* back to taking anything */
+    set_value_schema(&mut client, "schema-json", "None").await;
+
+    let response = request("PUT", "/schema-json/plain", &headers, b"hunter2").await;
+    assert_eq!(response.status, 201);
+}
+
+#[tokio::test]
+async fn value_schema_of_a_missing_vault() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "SetVaultValueSchema": {
+                "vault": "schema-missing",
+                "value_schema": "Json"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "NotFound");
+}
@@ -0,0 +1,251 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, request, Client};
+
+#[tokio::test]
+async fn create_vault() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateVault": {
+                "vault": "created-empty",
+                "permission": ["ListSecrets"],
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindVault": {
+                "vault": "created-empty"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["secrets_count"], 0);
+    assert_eq!(response["Found"]["access_keys_count"], 0);
+    assert_eq!(
+        response["Found"]["default_permissions"],
+        serde_json::json!(["ListSecrets"])
+    );
+    assert_eq!(
+        response["Found"]["default_sg"],
+        serde_json::json!(["127.0.0.1/32"])
+    );
+
+    let response = client
+        .send(serde_json::json!({
+            "ListVaults": {
+                "name_prefix": "created-empty"
+            }
+        }))
+        .await;
+    assert_eq!(
+        response["vaults"].as_array().unwrap().len(),
+        1,
+        "{response}"
+    );
+    assert_eq!(response["vaults"][0]["secrets_count"], 0);
+    assert_eq!(response["vaults"][0]["access_keys_count"], 0);
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateVault": {
+                "vault": "created-empty"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "AlreadyExists");
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateVault": {
+                "vault": "created empty"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "InvalidName");
+}
+
+#[tokio::test]
+async fn create_vault_denied() {
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateUser": {
+                "username": "create-vault-user",
+                "password": "hunter2",
+                "role": "User",
+                "sg": ["127.0.0.1/32"]
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Created");
+
+    let (mut client, response) = Client::login("create-vault-user", "hunter2").await;
+    assert_eq!(response["result"], "Granted");
+
+    let response = client
+        .send(serde_json::json!({
+            "CreateVault": {
+                "vault": "created-denied"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Denied");
+}
+
+#[tokio::test]
+async fn delete_vault_confirmation() {
+    let mut client = Client::login_root().await;
+
+    for vault in ["delete-confirmed", "delete-forced"] {
+        let response = client
+            .send(serde_json::json!({
+                "CreateVault": {
+                    "vault": vault
+                }
+            }))
+            .await;
+        assert_eq!(response["result"], "Created");
+    }
+
+    /* missing, then another vault's name */
+    for confirm in [serde_json::Value::Null, serde_json::json!("delete-forced")] {
+        let response = client
+            .send(serde_json::json!({
+                "DeleteVault": {
+                    "vault": "delete-confirmed",
+                    "confirm": confirm
+                }
+            }))
+            .await;
+        assert_eq!(response["result"], "ConfirmationMismatch");
+
+        let response = client
+            .send(serde_json::json!({
+                "FindVault": {
+                    "vault": "delete-confirmed"
+                }
+            }))
+            .await;
+        assert!(response["Found"].is_object(), "{response}");
+    }
+
+    let response = client
+        .send(serde_json::json!({
+            "DeleteVault": {
+                "vault": "delete-confirmed",
+                "confirm": "delete-confirmed"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindVault": {
+                "vault": "delete-confirmed"
+            }
+        }))
+        .await;
+    assert_eq!(response, "NotFound");
+
+    /* an admin may skip the confirmation */
+    let response = client
+        .send(serde_json::json!({
+            "DeleteVault": {
+                "vault": "delete-forced",
+                "force": true
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Deleted");
+}
+
+#[tokio::test]
+async fn renamed_vault_carries_secrets_and_access_keys() {
+    let roles = vec![
+        permission::VaultRoles::CreateSecrets,
+        permission::VaultRoles::DecryptSecrets,
+    ];
+
+    /* shares the prefix and sorts right after, it must stay where it is */
+    let neighbour = create_access_key("rename-before-2", roles.clone());
+    let neighbour = [("Authorization", neighbour.as_str())];
+    let authorization = create_access_key("rename-before", roles);
+    let headers = [("Authorization", authorization.as_str())];
+
+    for (path, headers) in [
+        ("/rename-before/password", &headers),
+        ("/rename-before/token", &headers),
+        ("/rename-before-2/password", &neighbour),
+    ] {
+        let response = request("POST", path, headers, b"hunter2").await;
+        assert_eq!(response.status, 201);
+    }
+
+    let mut client = Client::login_root().await;
+
+    let response = client
+        .send(serde_json::json!({
+            "RenameVault": {
+                "vault": "rename-before",
+                "name": "rename-after"
+            }
+        }))
+        .await;
+    assert_eq!(response["result"], "Renamed");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindVault": {
+                "vault": "rename-after"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["secrets_count"], 2, "{response}");
+    assert_eq!(response["Found"]["access_keys_count"], 1, "{response}");
+
+    let response = client
+        .send(serde_json::json!({
+            "FindVault": {
+                "vault": "rename-before-2"
+            }
+        }))
+        .await;
+    assert_eq!(response["Found"]["secrets_count"], 1, "{response}");
+    assert_eq!(response["Found"]["access_keys_count"], 1, "{response}");
+
+    /* the same access key, under the new name */
+    let response = request("GET", "/rename-after/password", &headers, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+
+    let response = request("GET", "/rename-before/password", &headers, b"").await;
+    assert_ne!(response.status, 200);
+
+    let response = request("GET", "/rename-before-2/password", &neighbour, b"").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hunter2");
+}
@@ -0,0 +1,61 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::permission;
+
+use super::{create_access_key, Client};
+
+async fn verify(
+    client: &mut Client,
+    access_key: &str,
+    secret_access_key: &str,
+) -> serde_json::Value {
+    client
+        .send(serde_json::json!({
+            "VerifyAccessKeySecret": {
+                "vault": "verify-secret",
+                "access_key": access_key,
+                "secret_access_key": secret_access_key
+            }
+        }))
+        .await
+}
+
+#[tokio::test]
+async fn verify_access_key_secret() {
+    let authorization =
+        create_access_key("verify-secret", vec![permission::VaultRoles::ListSecrets]);
+    let (access_key, secret_access_key) = authorization
+        .trim_start_matches("VAULTY ")
+        .split_once(':')
+        .unwrap();
+
+    let mut client = Client::login_root().await;
+
+    let response = verify(&mut client, access_key, secret_access_key).await;
+    assert_eq!(response["result"], "Verified");
+    assert_eq!(response["matches"], true);
+
+    let started = std::time::Instant::now();
+    let response = verify(&mut client, access_key, "wrong").await;
+    assert_eq!(response["result"], "Verified");
+    assert_eq!(response["matches"], false);
+    assert!(started.elapsed() >= std::time::Duration::from_millis(10));
+
+    let response = verify(&mut client, "missing", secret_access_key).await;
+    assert_eq!(response["result"], "NotFound");
+}
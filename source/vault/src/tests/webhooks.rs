@@ -0,0 +1,150 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{config, webhook};
+
+struct Delivery {
+    headers: std::collections::HashMap<String, String>,
+    body: String,
+}
+
+/* answers each delivery with the next status, as a receiver would */
+async fn receiver(listener: tokio::net::TcpListener, statuses: Vec<u16>) -> Vec<Delivery> {
+    let mut deliveries = Vec::new();
+
+    for status in statuses {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        let header_end = loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+
+            if let Some(position) = request.windows(4).position(|v| v == b"\r\n\r\n") {
+                break position + 4;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+        let headers: std::collections::HashMap<String, String> = head
+            .lines()
+            .skip(1)
+            .filter_map(|v| v.split_once(": "))
+            .map(|(name, value)| (name.to_lowercase(), value.to_owned()))
+            .collect();
+
+        let length: usize = headers["content-length"].parse().unwrap();
+
+        while request.len() < header_end + length {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        deliveries.push(Delivery {
+            headers,
+            body: String::from_utf8_lossy(&request[header_end..]).to_string(),
+        });
+    }
+
+    deliveries
+}
+
+fn webhook_to(url: String) -> webhook::Webhook {
+    webhook::Webhook::load(&config::ConfigWebhook {
+        url,
+        secret: "webhook-secret".to_owned(),
+        retries: Some(1),
+        ca_certificate: None,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn signed_delivery_retried() {
+    super::server();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let receiving = tokio::spawn(receiver(listener, vec![500, 204]));
+
+    webhook::deliver(
+        &webhook_to(url),
+        webhook::Event::new("webhook", webhook::Action::InsertSecret, "password"),
+    )
+    .await;
+
+    let deliveries = receiving.await.unwrap();
+    assert_eq!(deliveries.len(), 2);
+
+    for delivery in &deliveries {
+        let event: serde_json::Value = serde_json::from_str(&delivery.body).unwrap();
+        assert_eq!(event["vault"], "webhook");
+        assert_eq!(event["action"], "secret.insert");
+        assert_eq!(event["name"], "password");
+
+        let timestamp: i64 = delivery.headers["x-vaulty-timestamp"].parse().unwrap();
+
+        assert_eq!(
+            delivery.headers["x-vaulty-signature"],
+            format!(
+                "sha256={}",
+                webhook::sign("webhook-secret", timestamp, &delivery.body).unwrap()
+            )
+        );
+        /* the timestamp is signed along with the body */
+        assert_ne!(
+            delivery.headers["x-vaulty-signature"],
+            format!(
+                "sha256={}",
+                webhook::sign("webhook-secret", timestamp + 1, &delivery.body).unwrap()
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn ipv6_url() {
+    super::server();
+
+    /* not every host has an IPv6 loopback */
+    let Ok(listener) = tokio::net::TcpListener::bind("[::1]:0").await else {
+        return;
+    };
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let receiving = tokio::spawn(receiver(listener, vec![200]));
+
+    webhook::deliver(
+        &webhook_to(url),
+        webhook::Event::new("webhook", webhook::Action::DeleteSecret, "password"),
+    )
+    .await;
+
+    assert_eq!(receiving.await.unwrap().len(), 1);
+}
@@ -16,19 +16,31 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use crate::{
-    app_error::{AppError, AppErrorResult, AppResult},
-    config, db, log, permission,
+    app_error::{AppError, AppErrorResult, AppResult, ErrorCode},
+    authenticator, clock, config, db, log, permission, secrets, sg,
 };
 
 use argon2::{password_hash::PasswordHasher, PasswordVerifier};
 use maplit::hashmap;
 
+const PROVISIONED_PASSWORD_LEN: usize = 64;
+
 static mut DELAY_ON_UNSUCCESS: Option<u64> = None;
+static mut DELAY_JITTER: u64 = 0;
+static mut PROTECTED: Vec<String> = Vec::new();
 
 pub async fn delay() {
     let ms = unsafe { DELAY_ON_UNSUCCESS.expect("module IAM is not initialized") };
 
-    tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+    tokio::time::sleep(clock::jittered(ms, unsafe { DELAY_JITTER })).await;
+}
+
+/* with jitter configured a granted request waits as well, so the time taken
+doesn't tell it from a refused one */
+pub async fn delay_granted() {
+    if unsafe { DELAY_JITTER } > 0 {
+        delay().await;
+    }
 }
 
 pub fn initialize() {
@@ -36,9 +48,16 @@ pub fn initialize() {
 
     unsafe {
         DELAY_ON_UNSUCCESS = Some(config_clone.users.delay_unsuccessful_attempts_millis);
+        DELAY_JITTER = config_clone.users.delay_jitter_millis.unwrap_or(0);
+        PROTECTED = config_clone.users.protected.unwrap_or_default();
     }
 }
 
+/// Whether the config lists the user as one that can't be demoted or deleted.
+pub fn is_protected(username: &str) -> bool {
+    unsafe { PROTECTED.iter().any(|v| v == username) }
+}
+
 fn hash_password(password: &str) -> AppResult<String> {
     let config = argon2::Argon2::default();
     let salt =
@@ -50,6 +69,7 @@ fn hash_password(password: &str) -> AppResult<String> {
             message: "failed to hash the password".to_owned(),
             error: Some(e.to_string()),
             attr: None,
+            code: None,
         })?
         .to_string())
 }
@@ -66,6 +86,7 @@ fn verify_password(password: &str, verifying_password: &str) -> AppResult<Verify
             message: "failed to serialize the verifying password".to_owned(),
             error: Some(e.to_string()),
             attr: None,
+            code: None,
         })?;
 
     if config
@@ -79,98 +100,211 @@ fn verify_password(password: &str, verifying_password: &str) -> AppResult<Verify
 }
 
 pub enum LoginResult {
-    Successful,
+    Successful(db::user::UserDocument),
     Failed,
 }
 
-pub fn login(
+fn ip_in_sg(
     requester_ip: std::net::IpAddr,
     username: &str,
-    password: &str,
-) -> AppResult<LoginResult> {
-    match db::user::find(&username)? {
-        Some(user) => {
-            let mut ip_in_sg = false;
-
-            for sg in &user.sg {
-                let network_prefix = sg.prefix;
-                let network: std::net::IpAddr = sg.network.parse().map_app_err(|_| AppError {
-                    message: "invalid network".to_owned(),
-                    error: None,
-                    attr: Some(hashmap! {
-                        "user".to_owned() => username.to_owned(),
-                    }),
-                })?;
-
-                if network_prefix > if network.is_ipv4() { 32 } else { 128 } {
-                    return Err(AppError {
-                        message: "invalid network prefix".to_owned(),
-                        error: None,
-                        attr: Some(hashmap! {
-                            "user".to_owned() => username.to_owned(),
-                            "network".to_owned() => sg.network.to_owned(),
-                            "network_prefix".to_owned() => network_prefix.to_string()
-                        }),
-                    });
-                }
+    user: &db::user::UserDocument,
+) -> AppResult<bool> {
+    for sg in &user.sg {
+        let network_prefix = sg.prefix;
+        let network: std::net::IpAddr = sg.network.parse().map_app_err(|_| AppError {
+            message: "invalid network".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "user".to_owned() => username.to_owned(),
+            }),
+            code: None,
+        })?;
 
-                let network = ipnetwork::IpNetwork::new(network, (network_prefix & 0xFF) as u8)
-                    .map_app_err(|e| AppError {
-                        message: "invalid security group".to_owned(),
-                        error: Some(e.to_string()),
-                        attr: Some(hashmap! {
-                            "user".to_owned() => username.to_owned(),
-                            "ip".to_owned() => sg.network.to_owned(),
-                            "network_prefix".to_owned() => network_prefix.to_string()
-                        }),
-                    })?;
-
-                if network.contains(requester_ip) {
-                    ip_in_sg = true;
-                    break;
-                }
-            }
+        if network_prefix > if network.is_ipv4() { 32 } else { 128 } {
+            return Err(AppError {
+                message: "invalid network prefix".to_owned(),
+                error: None,
+                attr: Some(hashmap! {
+                    "user".to_owned() => username.to_owned(),
+                    "network".to_owned() => sg.network.to_owned(),
+                    "network_prefix".to_owned() => network_prefix.to_string()
+                }),
+                code: None,
+            });
+        }
+
+        let network = ipnetwork::IpNetwork::new(network, (network_prefix & 0xFF) as u8)
+            .map_app_err(|e| AppError {
+                message: "invalid security group".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "user".to_owned() => username.to_owned(),
+                    "ip".to_owned() => sg.network.to_owned(),
+                    "network_prefix".to_owned() => network_prefix.to_string()
+                }),
+                code: None,
+            })?;
+
+        if sg::contains(&network, requester_ip) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+pub enum ChangeRoleResult {
+    Changed(db::user::ChangeUserRoleResult),
+    /// The user is listed as protected, it can't lose the admin role.
+    Protected,
+}
+
+/// Changes a user's role unless it's protected or the last admin.
+pub fn change_role(username: &str, role: &permission::UserRole) -> AppResult<ChangeRoleResult> {
+    if is_protected(username) && !matches!(role, permission::UserRole::Admin) {
+        return Ok(ChangeRoleResult::Protected);
+    }
+
+    Ok(ChangeRoleResult::Changed(db::user::change_role(
+        username, role,
+    )?))
+}
+
+/* a user granted by an external source is created on the first login, and
+its role follows its groups on every one. Local and protected users are left
+to the DB, an external identity doesn't log in as one of them */
+fn provision(
+    provisioning: &authenticator::Provisioning,
+    username: &str,
+    groups: &[String],
+) -> AppResult<Option<db::user::UserDocument>> {
+    let Some(role) = provisioning.role(groups) else {
+        return Ok(None);
+    };
+
+    if is_protected(username) {
+        return Ok(None);
+    }
 
-            if ip_in_sg {
-                match verify_password(&password, &user.password)? {
-                    VerifyPasswordResult::Authorized => {
-                        if let Err(e) = db::user::refresh_last_active(username) {
-                            log!({
-                                mod: log::Module::User,
-                                ctx: "refreshing the user's last login",
-                                msg: "failed to update the DB",
-                                err: e,
-                                tags: [
-                                    "user", "db", "error"
-                                ],
-                                attr: {
-                                    user: username
-                                }
-                            });
-                        }
-
-                        Ok(LoginResult::Successful)
+    match db::user::find(username)? {
+        Some(user) if !user.provisioned => Ok(None),
+        Some(mut user) => {
+            if user.role != role {
+                match change_role(username, &role)? {
+                    ChangeRoleResult::Changed(
+                        db::user::ChangeUserRoleResult::Promoted
+                        | db::user::ChangeUserRoleResult::Demoted,
+                    ) => user.role = role,
+                    ChangeRoleResult::Changed(db::user::ChangeUserRoleResult::LastAdmin) => {
+                        log!({
+                            mod: log::Module::User,
+                            ctx: "provisioning a user",
+                            msg: "refused to demote the last admin",
+                            tags: [
+                                "user", "error"
+                            ],
+                            attr: {
+                                user: username
+                            }
+                        });
                     }
-                    _ => Ok(LoginResult::Failed),
+                    _ => {}
                 }
-            } else {
-                Ok(LoginResult::Failed)
             }
+
+            Ok(Some(user))
+        }
+        None => {
+            /* never used, the password is checked by the external source */
+            let password = secrets::random_string(
+                secrets::Charset::Alphanumeric.chars(),
+                PROVISIONED_PASSWORD_LEN,
+            );
+
+            insert_user(username, &password, &role, provisioning.sg.clone(), true)?;
+
+            db::user::find(username)
         }
-        None => Ok(LoginResult::Failed),
     }
 }
 
+pub fn login(
+    requester_ip: std::net::IpAddr,
+    username: &str,
+    password: &str,
+) -> AppResult<LoginResult> {
+    login_with(authenticator::get(), requester_ip, username, password)
+}
+
+pub fn login_with(
+    authenticator: &dyn authenticator::Authenticator,
+    requester_ip: std::net::IpAddr,
+    username: &str,
+    password: &str,
+) -> AppResult<LoginResult> {
+    /* a known user outside its security groups is refused before the password is looked at */
+    if let Some(user) = db::user::find(username)? {
+        if !ip_in_sg(requester_ip, username, &user)? {
+            return Ok(LoginResult::Failed);
+        }
+    }
+
+    let groups = match authenticator.authenticate(username, password)? {
+        authenticator::Authentication::Granted { groups } => groups,
+        authenticator::Authentication::Denied => return Ok(LoginResult::Failed),
+    };
+
+    let user = match authenticator.provisioning() {
+        Some(provisioning) => provision(provisioning, username, &groups)?,
+        None => db::user::find(username)?,
+    };
+
+    let Some(user) = user else {
+        return Ok(LoginResult::Failed);
+    };
+
+    if user.manually_locked || !ip_in_sg(requester_ip, username, &user)? {
+        return Ok(LoginResult::Failed);
+    }
+
+    if let Err(e) = db::user::refresh_last_active(username) {
+        log!({
+            mod: log::Module::User,
+            ctx: "refreshing the user's last login",
+            msg: "failed to update the DB",
+            err: e,
+            tags: [
+                "user", "db", "error"
+            ],
+            attr: {
+                user: username
+            }
+        });
+    }
+
+    Ok(LoginResult::Successful(user))
+}
+
 pub fn create_user(
     username: &str,
     password: &str,
     role: &permission::UserRole,
     sg: Vec<String>,
+) -> AppResult<db::user::InsertUserResult> {
+    insert_user(username, password, role, sg, false)
+}
+
+fn insert_user(
+    username: &str,
+    password: &str,
+    role: &permission::UserRole,
+    sg: Vec<String>,
+    provisioned: bool,
 ) -> AppResult<db::user::InsertUserResult> {
     let mut parsed_sg = Vec::new();
 
     for v in sg {
-        if let Some((network, ip)) = v.split_once('/') {
+        if let Some((network, ip)) = sg::expand(&v).split_once('/') {
             let value = db::user::UserSgDocument {
                 network: network.to_string(),
                 prefix: ip.parse::<i32>().map_app_err(|_| AppError {
@@ -179,6 +313,7 @@ pub fn create_user(
                     attr: Some(hashmap! {
                         "sg".to_owned() => v.to_owned()
                     }),
+                    code: Some(ErrorCode::InvalidArgument),
                 })?,
             };
 
@@ -190,6 +325,7 @@ pub fn create_user(
                 attr: Some(hashmap! {
                     "sg".to_owned() => v.to_owned()
                 }),
+                code: Some(ErrorCode::InvalidArgument),
             });
         }
     }
@@ -203,10 +339,23 @@ pub fn create_user(
             role: role.clone(),
             last_login: None,
             sg: parsed_sg,
+            manually_locked: false,
+            provisioned,
         },
     )
 }
 
+/// Whether `password` is the user's current one, false for an unknown user.
+pub fn is_current_password(username: &str, password: &str) -> AppResult<bool> {
+    match db::user::find(username)? {
+        Some(user) => Ok(matches!(
+            verify_password(password, &user.password)?,
+            VerifyPasswordResult::Authorized
+        )),
+        None => Ok(false),
+    }
+}
+
 pub fn change_password(
     username: &str,
     password: &str,
@@ -220,7 +369,7 @@ pub fn change_sg(username: &str, sg: Vec<String>) -> AppResult<db::user::ChangeU
     let mut parsed_sg = Vec::new();
 
     for v in sg {
-        if let Some((network, ip)) = v.split_once('/') {
+        if let Some((network, ip)) = sg::expand(&v).split_once('/') {
             let value = db::user::UserSgDocument {
                 network: network.to_string(),
                 prefix: ip.parse::<i32>().map_app_err(|_| AppError {
@@ -229,6 +378,7 @@ pub fn change_sg(username: &str, sg: Vec<String>) -> AppResult<db::user::ChangeU
                     attr: Some(hashmap! {
                         "sg".to_owned() => v.to_owned()
                     }),
+                    code: Some(ErrorCode::InvalidArgument),
                 })?,
             };
 
@@ -240,6 +390,7 @@ pub fn change_sg(username: &str, sg: Vec<String>) -> AppResult<db::user::ChangeU
                 attr: Some(hashmap! {
                     "sg".to_owned() => v.to_owned()
                 }),
+                code: Some(ErrorCode::InvalidArgument),
             });
         }
     }
@@ -0,0 +1,114 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use actix_web::{body::MessageBody, http::header};
+
+use crate::{config, log, vault};
+
+/* quotes, backslashes and control characters would break the quoted fields */
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => result.push_str(&format!("\\x{:02x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[inline]
+fn header_value(req: &actix_web::HttpRequest, name: header::HeaderName) -> String {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(escape)
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+pub fn format_line(
+    format: config::AccessLogFormat,
+    req: &actix_web::HttpRequest,
+    status: u16,
+    bytes: Option<u64>,
+    time: chrono::DateTime<chrono::Local>,
+) -> String {
+    let ip = vault::process_host_ip(req)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| "-".to_owned());
+
+    /* only the access key id, never the secret access key */
+    let access_key = vault::process_sig(req)
+        .map(|(access_key, _)| escape(&access_key))
+        .unwrap_or_else(|| "-".to_owned());
+
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|v| v.as_str())
+        .unwrap_or("/");
+
+    let bytes = match bytes {
+        Some(bytes) if bytes > 0 => bytes.to_string(),
+        _ => "-".to_owned(),
+    };
+
+    let line = format!(
+        "{ip} - {access_key} [{}] \"{} {} {:?}\" {status} {bytes}",
+        time.format("%d/%b/%Y:%H:%M:%S %z"),
+        req.method(),
+        escape(path),
+        req.version(),
+    );
+
+    match format {
+        config::AccessLogFormat::Common => line,
+        config::AccessLogFormat::Combined => format!(
+            "{line} \"{}\" \"{}\"",
+            header_value(req, header::REFERER),
+            header_value(req, header::USER_AGENT)
+        ),
+    }
+}
+
+pub async fn access_log(
+    req: actix_web::dev::ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<actix_web::dev::ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let response = next.call(req).await?;
+
+    if let Some(format) = log::access_format() {
+        let bytes = match response.response().body().size() {
+            actix_web::body::BodySize::Sized(bytes) => Some(bytes),
+            _ => None,
+        };
+
+        log::access(&format_line(
+            format,
+            response.request(),
+            response.status().as_u16(),
+            bytes,
+            chrono::Local::now(),
+        ));
+    }
+
+    Ok(response)
+}
@@ -15,7 +15,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, db, log, permission, vault};
+use crate::{access_keys, db, log, permission, vault, webhook};
 
 #[actix_web::delete("/{vault}/{secret_name}")]
 pub async fn req_delete(
@@ -26,20 +26,40 @@ pub async fn req_delete(
     let secret_name = path.1.clone();
     let mut ip = "N/A".to_owned();
 
-    match vault::initialize_request(&req, permission::VaultRoles::DeleteSecrets, &ns, &mut ip) {
-        Some(vault::CommonAccessResult::Authorized) => {}
+    let actor = match vault::initialize_request(
+        &req,
+        permission::VaultRoles::DeleteSecrets,
+        &ns,
+        &mut ip,
+    ) {
+        Some(vault::CommonAccessResult::Authorized(_, actor, scope)) => {
+            if !vault::in_scope(&scope, &ns, &secret_name, &ip) {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::Forbidden().finish();
+            }
+
+            access_keys::delay_granted().await;
+
+            actor
+        }
         Some(vault::CommonAccessResult::Unauthorized) => {
             access_keys::delay().await;
             return actix_web::HttpResponse::Unauthorized().finish();
         }
+        Some(vault::CommonAccessResult::Forbidden(granted)) => {
+            access_keys::delay().await;
+            return vault::forbidden_response(&granted);
+        }
         None => {
             access_keys::delay().await;
             return actix_web::HttpResponse::InternalServerError().finish();
         }
     };
 
-    match db::secret::delete(&ns, &secret_name) {
+    match db::secret::delete(&ns, &secret_name, vault::soft_delete(), actor) {
         Ok(db::secret::DeleteSecretResult::Deleted) => {
+            webhook::notify(&ns, webhook::Action::DeleteSecret, &secret_name);
+
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to delete secrets",
@@ -60,6 +80,8 @@ pub async fn req_delete(
             actix_web::HttpResponse::NotFound().finish()
         }
         Err(e) => {
+            let response = vault::failure_response(&e);
+
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to delete secrets",
@@ -75,7 +97,7 @@ pub async fn req_delete(
                 }
             });
 
-            return actix_web::HttpResponse::InternalServerError().finish();
+            response
         }
     }
 }
@@ -0,0 +1,78 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{access_keys, db, log, permission, vault};
+
+#[actix_web::route("/{vault}/{secret_name}", method = "HEAD")]
+pub async fn req_head(
+    path: actix_web::web::Path<(String, String)>,
+    req: actix_web::HttpRequest,
+) -> impl actix_web::Responder {
+    let ns = path.0.clone();
+    let secret_name = path.1.clone();
+    let mut ip = "N/A".to_owned();
+
+    match vault::initialize_request(&req, permission::VaultRoles::ListSecrets, &ns, &mut ip) {
+        Some(vault::CommonAccessResult::Authorized(_, _, scope)) => {
+            if !vault::in_scope(&scope, &ns, &secret_name, &ip) {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::Forbidden().finish();
+            }
+
+            access_keys::delay_granted().await;
+        }
+        Some(vault::CommonAccessResult::Unauthorized) => {
+            access_keys::delay().await;
+            return actix_web::HttpResponse::Unauthorized().finish();
+        }
+        Some(vault::CommonAccessResult::Forbidden(granted)) => {
+            access_keys::delay().await;
+            return vault::forbidden_response(&granted);
+        }
+        None => {
+            access_keys::delay().await;
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match db::secret::find(&ns, &secret_name) {
+        Ok(Some(secret_document)) => {
+            let (etag, last_modified) = vault::cache_validators(&secret_document);
+
+            vault::cache_headers(actix_web::HttpResponse::Ok(), etag, last_modified).finish()
+        }
+        Ok(None) => actix_web::HttpResponse::NotFound().finish(),
+        Err(e) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to check a secret",
+                "msg": "failed to retrive secret from the DB",
+                "err": e,
+                "tags": [
+                    "vault", "request", "db", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
+
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -15,32 +15,102 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, app_error::AppError, db, log, permission, secrets, vault};
+use actix_web::{http::header, HttpMessage};
+
+use crate::{
+    access_keys, app_error::AppError, compression::Compression, db, log, permission, secrets, vault,
+};
+
+#[inline]
+fn not_modified(
+    req: &actix_web::HttpRequest,
+    etag: &header::EntityTag,
+    last_modified: Option<header::HttpDate>,
+) -> bool {
+    if let Some(if_none_match) = req.get_header::<header::IfNoneMatch>() {
+        return match if_none_match {
+            header::IfNoneMatch::Any => true,
+            header::IfNoneMatch::Items(items) => items.iter().any(|v| v.weak_eq(etag)),
+        };
+    }
+
+    if let (Some(header::IfModifiedSince(since)), Some(last_modified)) =
+        (req.get_header::<header::IfModifiedSince>(), last_modified)
+    {
+        return std::time::SystemTime::from(last_modified) <= std::time::SystemTime::from(since);
+    }
+
+    false
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct GetQuery {
+    expected_sha256: Option<String>,
+}
 
 #[actix_web::get("/{vault}/{secret_name}")]
 pub async fn req_get(
     path: actix_web::web::Path<(String, String)>,
+    query: actix_web::web::Query<GetQuery>,
     req: actix_web::HttpRequest,
 ) -> impl actix_web::Responder {
     let ns = path.0.clone();
     let secret_name = path.1.clone();
     let mut ip = "N/A".to_owned();
 
-    match vault::initialize_request(&req, permission::VaultRoles::DecryptSecrets, &ns, &mut ip) {
-        Some(vault::CommonAccessResult::Authorized) => {}
-        Some(vault::CommonAccessResult::Unauthorized) => {
-            access_keys::delay().await;
-            return actix_web::HttpResponse::Unauthorized().finish();
+    let (counters, scope) =
+        match vault::initialize_request(&req, permission::VaultRoles::DecryptSecrets, &ns, &mut ip)
+        {
+            Some(vault::CommonAccessResult::Authorized(counters, _, scope)) => {
+                if !vault::in_scope(&scope, &ns, &secret_name, &ip) {
+                    access_keys::delay().await;
+                    return actix_web::HttpResponse::Forbidden().finish();
+                }
+
+                access_keys::delay_granted().await;
+                (counters, scope)
+            }
+            Some(vault::CommonAccessResult::Unauthorized) => {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::Unauthorized().finish();
+            }
+            Some(vault::CommonAccessResult::Forbidden(granted)) => {
+                access_keys::delay().await;
+                return vault::forbidden_response(&granted);
+            }
+            None => {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::InternalServerError().finish();
+            }
+        };
+
+    let (target_vault, target_name, secret_document) = match db::secret::resolve(&ns, &secret_name)
+    {
+        Ok(db::secret::ResolveSecretResult::Found(vault, name, document)) => {
+            (vault, name, *document)
         }
-        None => {
-            access_keys::delay().await;
-            return actix_web::HttpResponse::InternalServerError().finish();
+        Ok(db::secret::ResolveSecretResult::NotFound | db::secret::ResolveSecretResult::Broken) => {
+            vault::pad_not_found(&ns, &secret_name, &ip);
+
+            return actix_web::HttpResponse::NotFound().finish();
         }
-    };
+        Ok(db::secret::ResolveSecretResult::TooDeep) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to retrieve secrets",
+                "msg": "the secret's aliases loop or go too deep",
+                "tags": [
+                    "vault", "request", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
 
-    let secret_document = match db::secret::find(&ns, &secret_name) {
-        Ok(Some(value)) => value,
-        Ok(None) => return actix_web::HttpResponse::NotFound().finish(),
+            return actix_web::HttpResponse::new(actix_web::http::StatusCode::LOOP_DETECTED);
+        }
         Err(e) => {
             log!({
                 "mod": log::Module::Vault,
@@ -61,9 +131,99 @@ pub async fn req_get(
         }
     };
 
+    if (target_vault != ns || target_name != secret_name)
+        && !vault::may_read_alias_target(&req, &scope, &ns, &target_vault, &target_name, &mut ip)
+    {
+        access_keys::delay_refused_after_granted().await;
+        return actix_web::HttpResponse::Forbidden().finish();
+    }
+
+    vault::record_access(&target_vault, &target_name, &secret_document, &ip);
+
+    let compression = req
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Compression::negotiate);
+
+    let (etag, last_modified) = vault::cache_validators(&secret_document);
+
+    /* each encoding is its own representation, so a cache doesn't hand a
+    compressed body to a client that didn't ask for it */
+    let etag = match compression {
+        Some(compression) => {
+            header::EntityTag::new_strong(format!("{}-{}", etag.tag(), compression.name()))
+        }
+        None => etag,
+    };
+
+    if not_modified(&req, &etag, last_modified) {
+        return vault::cache_headers(actix_web::HttpResponse::NotModified(), etag, last_modified)
+            .insert_header((header::VARY, "Accept-Encoding"))
+            .finish();
+    }
+
     match base64_simd::STANDARD.decode_to_vec(secret_document.secret) {
-        Ok(secret) => match secrets::decrypt(&secret) {
+        Ok(secret) => match secrets::decrypt(&target_vault, &target_name, &secret) {
+            Ok(value)
+                if query
+                    .expected_sha256
+                    .as_deref()
+                    .is_some_and(|v| secrets::matches_sha256(&value, v)) =>
+            {
+                log!({
+                    "mod": log::Module::Vault,
+                    "ctx": "request to retrieve secrets",
+                    "msg": "secret matches the expected checksum",
+                    "tags": [
+                        "vault", "request",
+                    ],
+                    "attr": {
+                        "ip": ip,
+                        "ns": ns,
+                        "secret": secret_name
+                    }
+                });
+
+                vault::cache_headers(actix_web::HttpResponse::NotModified(), etag, last_modified)
+                    .insert_header((header::VARY, "Accept-Encoding"))
+                    .finish()
+            }
             Ok(value) => {
+                let mut response =
+                    vault::cache_headers(actix_web::HttpResponse::Ok(), etag, last_modified);
+
+                response.insert_header((header::VARY, "Accept-Encoding"));
+
+                let value = match compression {
+                    Some(compression) => match compression.compress(&value) {
+                        Ok(compressed) => {
+                            response.insert_header((header::CONTENT_ENCODING, compression.name()));
+
+                            compressed
+                        }
+                        Err(e) => {
+                            log!({
+                                "mod": log::Module::Vault,
+                                "ctx": "request to retrieve secrets",
+                                "msg": "failed to compress a secret",
+                                "err": e,
+                                "tags": [
+                                    "vault", "request", "error"
+                                ],
+                                "attr": {
+                                    "ip": ip,
+                                    "ns": ns,
+                                    "secret": secret_name
+                                }
+                            });
+
+                            return actix_web::HttpResponse::InternalServerError().finish();
+                        }
+                    },
+                    None => value,
+                };
+
                 log!({
                     "mod": log::Module::Vault,
                     "ctx": "request to retrieve secrets",
@@ -78,7 +238,9 @@ pub async fn req_get(
                     }
                 });
 
-                actix_web::HttpResponse::Ok().body(value)
+                counters.sent(value.len());
+
+                response.body(value)
             }
             Err(e) => {
                 log!({
@@ -107,7 +269,8 @@ pub async fn req_get(
                 "err": AppError {
                     message: "invalid base64 encoding".to_owned(),
                     error: Some(e.to_string()),
-                    attr: None
+                    attr: None,
+                    code: None
                 },
                 "tags": [
                     "vault", "request", "error"
@@ -0,0 +1,30 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+#[derive(serde::Serialize)]
+struct Response {
+    status: &'static str,
+}
+
+/// Registered ahead of `req_list`, which would otherwise take `/healthz` for
+/// a vault's name.
+#[actix_web::get("/healthz")]
+pub async fn req_health() -> impl actix_web::Responder {
+    actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .body(simd_json::to_string(&Response { status: "ok" }).unwrap_or_default())
+}
@@ -15,7 +15,70 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, db, log, permission, secrets, vault};
+use crate::{
+    access_keys, app_error::AppResult, clock, db, log, permission, secrets, vault, webhook,
+};
+
+/* also where a completed upload ends up */
+pub(super) fn store_secret(
+    ns: &str,
+    secret_name: &str,
+    data: &[u8],
+    actor: db::Actor,
+) -> AppResult<db::secret::InsertSecretResult> {
+    let secret = base64_simd::STANDARD.encode_to_string(secrets::encrypt(ns, secret_name, data)?);
+
+    let secret = db::secret::SecretDocument {
+        created: clock::now().to_rfc3339(),
+        etag: Some(secrets::etag(&secret)),
+        last_accessed: None,
+        created_by: Some(actor),
+        secret,
+        alias: None,
+        bound: true,
+    };
+
+    db::secret::insert(ns, secret_name, secret, vault::unique_names())
+}
+
+pub enum StoreAliasResult {
+    Stored(db::secret::InsertSecretResult),
+    TargetMissing,
+    Circular,
+}
+
+/// Stores `secret_name` as an alias of `target`, which must exist and mustn't
+/// lead back to it.
+pub fn store_alias(
+    ns: &str,
+    secret_name: &str,
+    target: db::secret::SecretAlias,
+    actor: db::Actor,
+) -> AppResult<StoreAliasResult> {
+    match db::secret::check_alias(ns, secret_name, &target)? {
+        db::secret::CheckAliasResult::Valid => {}
+        db::secret::CheckAliasResult::Missing => return Ok(StoreAliasResult::TargetMissing),
+        db::secret::CheckAliasResult::Circular => return Ok(StoreAliasResult::Circular),
+    }
+
+    /* a loop made by two aliases stored at once is still caught when read */
+    let secret = db::secret::SecretDocument {
+        created: clock::now().to_rfc3339(),
+        secret: String::new(),
+        etag: None,
+        last_accessed: None,
+        created_by: Some(actor),
+        alias: Some(target),
+        bound: true,
+    };
+
+    Ok(StoreAliasResult::Stored(db::secret::insert(
+        ns,
+        secret_name,
+        secret,
+        vault::unique_names(),
+    )?))
+}
 
 #[inline]
 async fn insert_secret(
@@ -27,53 +90,140 @@ async fn insert_secret(
     let secret_name = path.1.clone();
     let mut ip = "N/A".to_owned();
 
-    match vault::initialize_request(&req, permission::VaultRoles::CreateSecrets, &ns, &mut ip) {
-        Some(vault::CommonAccessResult::Authorized) => {}
+    let (counters, actor) = match vault::initialize_request(
+        &req,
+        permission::VaultRoles::CreateSecrets,
+        &ns,
+        &mut ip,
+    ) {
+        Some(vault::CommonAccessResult::Authorized(counters, actor, scope)) => {
+            if !vault::in_scope(&scope, &ns, &secret_name, &ip) {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::Forbidden().finish();
+            }
+
+            access_keys::delay_granted().await;
+
+            (counters, actor)
+        }
         Some(vault::CommonAccessResult::Unauthorized) => {
             access_keys::delay().await;
             return actix_web::HttpResponse::Unauthorized().finish();
         }
+        Some(vault::CommonAccessResult::Forbidden(granted)) => {
+            access_keys::delay().await;
+            return vault::forbidden_response(&granted);
+        }
         None => {
             access_keys::delay().await;
             return actix_web::HttpResponse::InternalServerError().finish();
         }
     };
 
-    if data.is_empty() {
-        return actix_web::HttpResponse::UnprocessableEntity().finish();
-    }
+    counters.received(data.len());
 
-    let data = match secrets::encrypt(&data.to_vec()) {
+    let idempotency_key = match vault::idempotency_key(&req) {
         Ok(value) => value,
-        Err(e) => {
-            log!({
-                "mod": log::Module::Vault,
-                "ctx": "request to insert secrets",
-                "msg": "failed to encrypt the secret",
-                "err": e,
-                "tags": [
-                    "vault", "error"
-                ],
-                "attr": {
-                    "ip": ip,
-                    "ns": ns,
-                    "secret": secret_name
-                }
-            });
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
 
-            return actix_web::HttpResponse::InternalServerError().finish();
+    if let Some(idempotency_key) = &idempotency_key {
+        match vault::find_idempotent_status(&ns, &secret_name, idempotency_key) {
+            Ok(Some(status)) => {
+                log!({
+                    "mod": log::Module::Vault,
+                    "ctx": "request to insert secrets",
+                    "msg": "retried insert answered with the previous result",
+                    "tags": [
+                        "vault", "request"
+                    ],
+                    "attr": {
+                        "ip": ip,
+                        "status": status.as_u16(),
+                        "ns": ns,
+                        "secret": secret_name
+                    }
+                });
+
+                return actix_web::HttpResponse::build(status)
+                    .insert_header((vault::IDEMPOTENT_REPLAYED_HEADER, "true"))
+                    .finish();
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log!({
+                    "mod": log::Module::Vault,
+                    "ctx": "request to insert secrets",
+                    "msg": "failed to retrive the idempotency key",
+                    "err": e,
+                    "tags": [
+                        "vault", "db", "error"
+                    ],
+                    "attr": {
+                        "ip": ip,
+                        "ns": ns,
+                        "secret": secret_name
+                    }
+                });
+
+                return actix_web::HttpResponse::InternalServerError().finish();
+            }
         }
+    }
+
+    let alias = match vault::alias_of(&req) {
+        Ok(value) => value,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
     };
 
-    let time_now = chrono::Utc::now();
+    let result = match alias {
+        Some(_) if !data.is_empty() => {
+            return actix_web::HttpResponse::BadRequest().body("an alias can't hold a value");
+        }
+        Some(target) => {
+            if !vault::may_alias_target(&req, &target.vault, &target.secret_name, &mut ip) {
+                access_keys::delay_refused_after_granted().await;
+                return actix_web::HttpResponse::Forbidden().finish();
+            }
 
-    let secret = db::secret::SecretDocument {
-        created: time_now.to_rfc3339(),
-        secret: base64_simd::STANDARD.encode_to_string(data),
+            match store_alias(&ns, &secret_name, target, actor) {
+                Ok(StoreAliasResult::Stored(value)) => Ok(value),
+                Ok(StoreAliasResult::TargetMissing) => {
+                    return actix_web::HttpResponse::UnprocessableEntity()
+                        .body("the alias target doesn't exist");
+                }
+                Ok(StoreAliasResult::Circular) => {
+                    return actix_web::HttpResponse::UnprocessableEntity()
+                        .body("the alias would lead back to itself");
+                }
+                Err(e) => Err(e),
+            }
+        }
+        None => {
+            if data.is_empty() {
+                return actix_web::HttpResponse::UnprocessableEntity().finish();
+            }
+
+            if let Some(response) = vault::value_schema_response(&ns, &secret_name, &data, &ip) {
+                return response;
+            }
+
+            store_secret(&ns, &secret_name, &data, actor)
+        }
     };
 
-    match db::secret::insert(&ns, &secret_name, secret) {
+    match result {
         Ok(db::secret::InsertSecretResult::Inserted) => {
+            webhook::notify(&ns, webhook::Action::InsertSecret, &secret_name);
+
+            vault::store_idempotent_status(
+                &ns,
+                &secret_name,
+                idempotency_key.as_deref(),
+                actix_web::http::StatusCode::CREATED,
+                &ip,
+            );
+
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to insert secrets",
@@ -92,6 +242,16 @@ async fn insert_secret(
             actix_web::HttpResponse::Created().finish()
         }
         Ok(db::secret::InsertSecretResult::Updated) => {
+            webhook::notify(&ns, webhook::Action::InsertSecret, &secret_name);
+
+            vault::store_idempotent_status(
+                &ns,
+                &secret_name,
+                idempotency_key.as_deref(),
+                actix_web::http::StatusCode::OK,
+                &ip,
+            );
+
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to insert secrets",
@@ -109,7 +269,15 @@ async fn insert_secret(
 
             actix_web::HttpResponse::Ok().finish()
         }
+        Ok(db::secret::InsertSecretResult::InvalidName) => {
+            actix_web::HttpResponse::BadRequest().body("invalid vault or secret name")
+        }
+        Ok(db::secret::InsertSecretResult::NameTaken) => {
+            actix_web::HttpResponse::Conflict().body("secret name is used by another vault")
+        }
         Err(e) => {
+            let response = vault::failure_response(&e);
+
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to insert secrets",
@@ -125,7 +293,7 @@ async fn insert_secret(
                 }
             });
 
-            return actix_web::HttpResponse::InternalServerError().finish();
+            response
         }
     }
 }
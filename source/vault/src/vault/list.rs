@@ -15,85 +15,209 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::{access_keys, app_error::AppError, db, log, permission, vault};
+use crate::{
+    access_keys,
+    app_error::{AppError, AppErrorResult, AppResult},
+    db, log, permission, stats, vault,
+};
 
-#[actix_web::get("/{vault}")]
-pub async fn req_list(
-    ns: actix_web::web::Path<String>,
-    req: actix_web::HttpRequest,
-) -> impl actix_web::Responder {
+/// Scans the vault and serializes the listing of the names `scope` allows, runs
+/// on the blocking thread pool since large vaults would otherwise stall the
+/// worker.
+fn list_secrets(vault: &str, scope: &vault::NameScope) -> AppResult<String> {
     #[derive(serde::Serialize)]
-    struct RequestResponseEntry {
-        created: String,
-        secret_name: String,
+    struct RequestResponseEntry<'a> {
+        created: &'a str,
+        secret_name: &'a str,
+        last_accessed: Option<&'a str>,
+        created_by: Option<&'a db::Actor>,
     }
 
     #[derive(serde::Serialize)]
-    struct RequestResponse {
-        vault: String,
-        secrets: Vec<RequestResponseEntry>,
+    struct RequestResponse<'a> {
+        vault: &'a str,
+        secrets: Vec<RequestResponseEntry<'a>>,
     }
 
-    let mut ip = "N/A".to_owned();
-
-    match vault::initialize_request(&req, permission::VaultRoles::ListSecrets, &ns, &mut ip) {
-        Some(vault::CommonAccessResult::Authorized) => {}
-        Some(vault::CommonAccessResult::Unauthorized) => {
-            access_keys::delay().await;
-            return actix_web::HttpResponse::Unauthorized().finish();
-        }
-        None => {
-            access_keys::delay().await;
-            return actix_web::HttpResponse::InternalServerError().finish();
-        }
-    };
+    let secrets_list = db::secret::list(vault)?;
 
-    let secrets_list = match db::secret::list(&ns) {
-        Ok(value) => value,
-        Err(e) => {
-            log!({
-                "mod": log::Module::Vault,
-                "ctx": "request to list secrets",
-                "msg": "failed to retrive secrets from the db",
-                "err": e,
-                "tags": [
-                    "vault", "db", "error"
-                ],
-                "attr": {
-                    "ip": ip,
-                    "vault": ns.to_string(),
-                }
-            });
-
-            return actix_web::HttpResponse::InternalServerError().finish();
-        }
-    };
-
-    match simd_json::to_string(&RequestResponse {
-        vault: ns.to_string(),
+    simd_json::to_string(&RequestResponse {
+        vault,
         secrets: secrets_list
             .iter()
+            .filter(|v| scope.allows(&v.secret_name))
             .map(|v| RequestResponseEntry {
-                created: v.created.clone(),
-                secret_name: v.secret_name.clone(),
+                created: &v.created,
+                secret_name: &v.secret_name,
+                last_accessed: v.last_accessed.as_deref(),
+                created_by: v.created_by.as_ref(),
             })
             .collect(),
-    }) {
-        Ok(response) => actix_web::HttpResponse::Ok()
-            .content_type("application/json")
-            .body(response),
+    })
+    .map_app_err(|e| AppError {
+        message: "serializing data".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })
+}
+
+/* how many lines may wait for a slow client before the scan pauses */
+const STREAM_BUFFERED_LINES: usize = 256;
+
+#[derive(serde::Serialize)]
+struct StreamEntry<'a> {
+    created: &'a str,
+    secret_name: &'a str,
+    last_accessed: Option<&'a str>,
+    created_by: Option<&'a db::Actor>,
+}
+
+/// Writes one JSON line per secret `scope` allows into `sender` as the read
+/// transaction goes, and stops early when the client has gone away.
+fn stream_secrets(
+    vault: &str,
+    scope: &vault::NameScope,
+    counters: &stats::Counters,
+    sender: &tokio::sync::mpsc::Sender<Result<actix_web::web::Bytes, std::io::Error>>,
+) -> AppResult<()> {
+    let mut failed = None;
+
+    db::secret::for_each_in(vault, |entry| {
+        if !scope.allows(&entry.secret_name) {
+            return true;
+        }
+
+        let line = simd_json::to_string(&StreamEntry {
+            created: &entry.created,
+            secret_name: &entry.secret_name,
+            last_accessed: entry.last_accessed.as_deref(),
+            created_by: entry.created_by.as_ref(),
+        });
+
+        match line {
+            Ok(mut line) => {
+                line.push('\n');
+                counters.sent(line.len());
+
+                sender
+                    .blocking_send(Ok(actix_web::web::Bytes::from(line)))
+                    .is_ok()
+            }
+            Err(e) => {
+                failed = Some(e);
+                false
+            }
+        }
+    })?;
+
+    match failed {
+        Some(e) => Err(AppError {
+            message: "serializing data".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ListQuery {
+    stream: Option<String>,
+}
+
+#[actix_web::get("/{vault}")]
+pub async fn req_list(
+    ns: actix_web::web::Path<String>,
+    query: actix_web::web::Query<ListQuery>,
+    req: actix_web::HttpRequest,
+) -> impl actix_web::Responder {
+    let mut ip = "N/A".to_owned();
+
+    let (counters, scope) =
+        match vault::initialize_request(&req, permission::VaultRoles::ListSecrets, &ns, &mut ip) {
+            Some(vault::CommonAccessResult::Authorized(counters, _, scope)) => {
+                access_keys::delay_granted().await;
+                (counters, scope)
+            }
+            Some(vault::CommonAccessResult::Unauthorized) => {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::Unauthorized().finish();
+            }
+            Some(vault::CommonAccessResult::Forbidden(granted)) => {
+                access_keys::delay().await;
+                return vault::forbidden_response(&granted);
+            }
+            None => {
+                access_keys::delay().await;
+                return actix_web::HttpResponse::InternalServerError().finish();
+            }
+        };
+
+    let vault = ns.to_string();
+
+    if matches!(query.stream.as_deref(), Some("1" | "true")) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFERED_LINES);
+
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = stream_secrets(&vault, &scope, &counters, &sender) {
+                log!({
+                    "mod": log::Module::Vault,
+                    "ctx": "request to list secrets",
+                    "msg": "failed to stream the secrets",
+                    "err": e,
+                    "tags": [
+                        "vault", "db", "error"
+                    ],
+                    "attr": {
+                        "ip": ip,
+                        "vault": vault,
+                    }
+                });
+
+                /* the status is already sent, failing the body tells the
+                client the list is incomplete */
+                let _ = sender
+                    .blocking_send(Err(std::io::Error::other("failed to stream the secrets")));
+            }
+        });
+
+        let body = futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|v| (v, receiver))
+        });
+
+        return actix_web::HttpResponse::Ok()
+            .content_type("application/x-ndjson")
+            .streaming(body);
+    }
+
+    let result = actix_web::web::block(move || list_secrets(&vault, &scope))
+        .await
+        .map_err(|e| AppError {
+            message: "the blocking thread pool failed".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })
+        .and_then(|v| v);
+
+    match result {
+        Ok(response) => {
+            counters.sent(response.len());
+
+            actix_web::HttpResponse::Ok()
+                .content_type("application/json")
+                .body(response)
+        }
         Err(e) => {
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "request to list secrets",
-                "msg": "failed to serialize the response",
-                "err": AppError {
-                    message: "serializing data".to_owned(),
-                    error: Some(e.to_string()),
-                    attr: None
-                },
+                "msg": "failed to list the secrets",
+                "err": e,
                 "tags": [
-                    "vault", "error"
+                    "vault", "db", "error"
                 ],
                 "attr": {
                     "ip": ip,
@@ -15,118 +15,440 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use actix_web::http::header;
 use maplit::hashmap;
 
 use crate::{
     access_keys,
     app_error::{AppError, AppErrorOption, AppErrorResult, AppResult},
-    db, log, permission,
+    clock, config, db, jwt, log, permission, secrets, sg, stats,
 };
 
+mod access_log;
 mod delete;
+mod exists;
 mod get;
+mod health;
 mod insert;
 mod list;
+mod not_found;
+mod upload;
 
+pub use access_log::access_log;
 pub use delete::req_delete;
+pub use exists::req_head;
 pub use get::req_get;
+pub use health::req_health;
 pub use insert::req_post;
 pub use insert::req_put;
+pub use insert::{store_alias, StoreAliasResult};
 pub use list::req_list;
+pub use not_found::not_found;
+pub use upload::{req_upload_append, req_upload_complete, req_upload_start};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+pub const ALIAS_OF_HEADER: &str = "Alias-Of";
+const IDEMPOTENCY_KEY_MAX_LEN: usize = 255;
+const IDEMPOTENCY_KEY_TTL_SECS: i64 = 24 * 60 * 60;
+const IDEMPOTENCY_SWEEP_INTERVAL_SECS: u64 = 60 * 60;
+const DELETED_SECRETS_SWEEP_INTERVAL_SECS: u64 = 60;
+const DEFAULT_UPLOAD_TTL_SECS: u64 = 60 * 60;
+const UPLOADS_SWEEP_INTERVAL_SECS: u64 = 60;
 
 pub enum CommonAccessResult {
-    Authorized,
+    /// With the access key, or the bearer token's subject, that was let in and
+    /// the secret names it may touch.
+    Authorized(std::sync::Arc<stats::Counters>, db::Actor, NameScope),
     Unauthorized,
+    /// With the permissions the verified credential does hold in the vault.
+    Forbidden(Vec<permission::VaultRoles>),
+}
+
+/// The secret name prefixes an access key is limited to, none for any name.
+#[derive(Debug, Clone, Default)]
+pub struct NameScope(Vec<String>);
+
+impl NameScope {
+    pub fn new(prefixes: Vec<String>) -> NameScope {
+        NameScope(prefixes)
+    }
+
+    pub fn allows(&self, secret_name: &str) -> bool {
+        self.0.is_empty() || self.0.iter().any(|v| secret_name.starts_with(v.as_str()))
+    }
+}
+
+/* the vault lets the key in, the name may still be outside of its prefixes */
+fn in_scope(scope: &NameScope, vault: &str, secret_name: &str, ip: &str) -> bool {
+    if scope.allows(secret_name) {
+        return true;
+    }
+
+    log!({
+        "mod": log::Module::Vault,
+        "ctx": "initial request processing",
+        "msg": "access was denied",
+        "err": AppError {
+            message: "secret name is outside of the access key's prefixes".to_owned(),
+            error: None,
+            attr: None,
+            code: None
+        },
+        "tags": [
+            "vault", "access", "denied"
+        ],
+        "attr": {
+            "ip": ip,
+            "vault": vault,
+            "secret": secret_name
+        }
+    });
+
+    false
 }
 
 #[inline]
-fn access_check(
+fn cache_validators(
+    document: &db::secret::SecretDocument,
+) -> (header::EntityTag, Option<header::HttpDate>) {
+    let etag = header::EntityTag::new_strong(
+        document
+            .etag
+            .clone()
+            .unwrap_or_else(|| secrets::etag(&document.secret)),
+    );
+
+    /* HTTP dates have a one second resolution */
+    let last_modified = chrono::DateTime::parse_from_rfc3339(&document.created)
+        .ok()
+        .and_then(|v| u64::try_from(v.timestamp()).ok())
+        .map(|v| {
+            header::HttpDate::from(
+                std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(v),
+            )
+        });
+
+    (etag, last_modified)
+}
+
+#[inline]
+fn cache_headers(
+    mut response: actix_web::HttpResponseBuilder,
+    etag: header::EntityTag,
+    last_modified: Option<header::HttpDate>,
+) -> actix_web::HttpResponseBuilder {
+    response.insert_header(header::ETag(etag));
+
+    if let Some(last_modified) = last_modified {
+        response.insert_header(header::LastModified(last_modified));
+    }
+
+    response
+}
+
+/// 403 listing what the credential was granted, so a client sees which
+/// permission it lacks. Only for credentials that were verified.
+#[inline]
+pub fn forbidden_response(granted: &[permission::VaultRoles]) -> actix_web::HttpResponse {
+    #[derive(serde::Serialize)]
+    struct ForbiddenResponse<'a> {
+        granted: &'a [permission::VaultRoles],
+    }
+
+    match simd_json::to_string(&ForbiddenResponse { granted }) {
+        Ok(body) => actix_web::HttpResponse::Forbidden()
+            .content_type("application/json")
+            .body(body),
+        Err(_) => actix_web::HttpResponse::Forbidden().finish(),
+    }
+}
+
+/// 503 with a `Retry-After` for a database that stayed busy through the
+/// retries, 500 for any other failure.
+#[inline]
+pub fn failure_response(e: &AppError) -> actix_web::HttpResponse {
+    if db::is_transient_error(e) {
+        actix_web::HttpResponse::ServiceUnavailable()
+            .insert_header((header::RETRY_AFTER, "1"))
+            .finish()
+    } else {
+        actix_web::HttpResponse::InternalServerError().finish()
+    }
+}
+
+/// Whether `data` matches the vault's value schema, a vault that doesn't
+/// exist yet takes anything.
+pub fn accepts_value(vault: &str, data: &[u8]) -> AppResult<bool> {
+    Ok(db::vault::find(vault)?
+        .map(|v| v.value_schema)
+        .unwrap_or_default()
+        .accepts(data))
+}
+
+/* 422 for a value the vault's schema rejects, None to carry on */
+fn value_schema_response(
+    ns: &str,
+    secret_name: &str,
+    data: &[u8],
+    ip: &str,
+) -> Option<actix_web::HttpResponse> {
+    match accepts_value(ns, data) {
+        Ok(true) => None,
+        Ok(false) => Some(
+            actix_web::HttpResponse::UnprocessableEntity()
+                .body("the value doesn't match the vault's value schema"),
+        ),
+        Err(e) => {
+            let response = failure_response(&e);
+
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "checking the value schema",
+                "msg": "failed to retrive the vault",
+                "err": e,
+                "tags": [
+                    "vault", "db", "error"
+                ],
+                "attr": {
+                    "ip": ip.to_owned(),
+                    "ns": ns.to_owned(),
+                    "secret": secret_name.to_owned()
+                }
+            });
+
+            Some(response)
+        }
+    }
+}
+
+pub enum AccessCheckResult {
+    Authorized,
+    Forbidden,
+    UnknownAccessKey,
+    NotInSecurityGroup(Vec<String>),
+    InvalidSecretAccessKey,
+    Disabled,
+    Expired,
+}
+
+fn in_security_group(
     requester_ip: std::net::IpAddr,
-    access_key: &String,
-    secret_access_key: &String,
-    permission: permission::VaultRoles,
+    access_key: &str,
+    ac_document: &db::access::AccessKeyDocument,
     vault: &str,
-) -> AppResult<CommonAccessResult> {
-    if let Some(ac_document) = db::access::find(vault, &access_key)? {
-        let mut ip_in_sg = false;
-
-        for sg in &ac_document.sg {
-            let network_prefix = sg.prefix;
-            let network: std::net::IpAddr = sg.network.parse().map_app_err(|_| AppError {
-                message: "invalid network".to_owned(),
+) -> AppResult<bool> {
+    for sg in &ac_document.sg {
+        let network_prefix = sg.prefix;
+        let network: std::net::IpAddr = sg.network.parse().map_app_err(|_| AppError {
+            message: "invalid network".to_owned(),
+            error: None,
+            attr: Some(hashmap! {
+                "access_key".to_owned() => access_key.to_owned(),
+                "vault".to_owned() => vault.to_owned(),
+                "ip".to_owned() => sg.network.to_owned()
+            }),
+            code: None,
+        })?;
+
+        if network_prefix > if network.is_ipv4() { 32 } else { 128 } {
+            return Err(AppError {
+                message: "invalid network prefix".to_owned(),
                 error: None,
                 attr: Some(hashmap! {
-                    "access_key".to_owned() => access_key.clone(),
+                    "access_key".to_owned() => access_key.to_owned(),
                     "vault".to_owned() => vault.to_owned(),
-                    "ip".to_owned() => sg.network.to_owned()
+                    "network".to_owned() => sg.network.to_owned(),
+                    "network_prefix".to_owned() => network_prefix.to_string()
                 }),
-            })?;
+                code: None,
+            });
+        }
 
-            if network_prefix > if network.is_ipv4() { 32 } else { 128 } {
-                return Err(AppError {
-                    message: "invalid network prefix".to_owned(),
-                    error: None,
-                    attr: Some(hashmap! {
-                        "access_key".to_owned() => access_key.clone(),
-                        "vault".to_owned() => vault.to_owned(),
-                        "network".to_owned() => sg.network.to_owned(),
-                        "network_prefix".to_owned() => network_prefix.to_string()
-                    }),
-                });
-            }
+        let network = ipnetwork::IpNetwork::new(network, (network_prefix & 0xFF) as u8)
+            .map_app_err(|e| AppError {
+                message: "invalid security group".to_owned(),
+                error: Some(e.to_string()),
+                attr: Some(hashmap! {
+                    "access_key".to_owned() => access_key.to_owned(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "ip".to_owned() => sg.network.to_owned(),
+                    "network_prefix".to_owned() => network_prefix.to_string()
+                }),
+                code: None,
+            })?;
 
-            let network = ipnetwork::IpNetwork::new(network, (network_prefix & 0xFF) as u8)
-                .map_app_err(|e| AppError {
-                    message: "invalid security group".to_owned(),
-                    error: Some(e.to_string()),
-                    attr: Some(hashmap! {
-                        "access_key".to_owned() => access_key.clone(),
-                        "vault".to_owned() => vault.to_owned(),
-                        "ip".to_owned() => sg.network.to_owned(),
-                        "network_prefix".to_owned() => network_prefix.to_string()
-                    }),
-                })?;
-
-            if network.contains(requester_ip) {
-                ip_in_sg = true;
-                break;
-            }
+        if sg::contains(&network, requester_ip) {
+            return Ok(true);
         }
+    }
 
-        if !ip_in_sg {
-            return Ok(CommonAccessResult::Unauthorized);
-        }
+    Ok(false)
+}
+
+#[inline]
+fn sg_list(ac_document: &db::access::AccessKeyDocument) -> Vec<String> {
+    ac_document
+        .sg
+        .iter()
+        .map(|v| format!("{}/{}", v.network, v.prefix))
+        .collect()
+}
 
-        let document_secret_access_key = base64_simd::STANDARD
-            .decode_to_vec(&ac_document.secret_access_key)
+/* what's left once the requester is known to hold the access key */
+fn status_check(
+    ac_document: &db::access::AccessKeyDocument,
+    permission: permission::VaultRoles,
+) -> AccessCheckResult {
+    if !ac_document.enabled {
+        AccessCheckResult::Disabled
+    } else if ac_document.is_expired(clock::now()) {
+        AccessCheckResult::Expired
+    } else if permission::contains_or_owner(&ac_document.permission, permission) {
+        AccessCheckResult::Authorized
+    } else {
+        AccessCheckResult::Forbidden
+    }
+}
+
+#[inline]
+fn access_check(
+    requester_ip: std::net::IpAddr,
+    access_key: &str,
+    secret_access_key: &str,
+    permission: permission::VaultRoles,
+    vault: &str,
+) -> AppResult<(AccessCheckResult, NameScope, Vec<permission::VaultRoles>)> {
+    let Some(ac_document) = db::access::find(vault, access_key)? else {
+        return Ok((
+            AccessCheckResult::UnknownAccessKey,
+            NameScope::default(),
+            Vec::new(),
+        ));
+    };
+
+    if !in_security_group(requester_ip, access_key, &ac_document, vault)? {
+        return Ok((
+            AccessCheckResult::NotInSecurityGroup(sg_list(&ac_document)),
+            NameScope::default(),
+            Vec::new(),
+        ));
+    }
+
+    let document_secret_access_key = base64_simd::STANDARD
+        .decode_to_vec(&ac_document.secret_access_key)
+        .map_app_err(|e| AppError {
+            message: "failed to decode the secret access key".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "access_key".to_owned() => access_key.to_owned(),
+                "vault".to_owned() => vault.to_owned()
+            }),
+            code: None,
+        })?;
+
+    if !access_keys::verify_access_key(
+        vault,
+        access_key,
+        secret_access_key,
+        &document_secret_access_key,
+    )? {
+        return Ok((
+            AccessCheckResult::InvalidSecretAccessKey,
+            NameScope::default(),
+            Vec::new(),
+        ));
+    }
+
+    Ok((
+        status_check(&ac_document, permission),
+        NameScope::new(ac_document.name_prefixes),
+        ac_document.permission,
+    ))
+}
+
+/// The answer a request from `requester_ip` holding the right secret access key
+/// would get, without touching the key's last use or its counters.
+pub fn simulate_access(
+    requester_ip: std::net::IpAddr,
+    access_key: &str,
+    permission: permission::VaultRoles,
+    vault: &str,
+) -> AppResult<AccessCheckResult> {
+    let Some(ac_document) = db::access::find(vault, access_key)? else {
+        return Ok(AccessCheckResult::UnknownAccessKey);
+    };
+
+    if !in_security_group(requester_ip, access_key, &ac_document, vault)? {
+        return Ok(AccessCheckResult::NotInSecurityGroup(sg_list(&ac_document)));
+    }
+
+    Ok(status_check(&ac_document, permission))
+}
+
+fn bearer_check(
+    requester_ip: std::net::IpAddr,
+    claims: &jwt::Claims,
+    permission: permission::VaultRoles,
+    vault: &str,
+) -> AppResult<AccessCheckResult> {
+    let sg = match &claims.sg {
+        Some(sg) => sg.clone(),
+        None => db::vault::find(vault)?
+            .and_then(|v| v.default_sg)
+            .unwrap_or_default(),
+    };
+
+    let mut ip_in_sg = false;
+
+    for network in &sg {
+        let network = sg::expand(network)
+            .parse::<ipnetwork::IpNetwork>()
             .map_app_err(|e| AppError {
-                message: "failed to decode the secret access key".to_owned(),
+                message: "invalid security group".to_owned(),
                 error: Some(e.to_string()),
                 attr: Some(hashmap! {
-                    "access_key".to_owned() => access_key.clone(),
-                    "vault".to_owned() => vault.to_owned()
+                    "subject".to_owned() => claims.sub.clone(),
+                    "vault".to_owned() => vault.to_owned(),
+                    "network".to_owned() => network.to_owned()
                 }),
+                code: None,
             })?;
 
-        if access_keys::verify_access_key(&secret_access_key, &document_secret_access_key)? {
-            if ac_document.permission.contains(&permission) {
-                return Ok(CommonAccessResult::Authorized);
-            }
-        } else {
-            return Ok(CommonAccessResult::Unauthorized);
+        if sg::contains(&network, requester_ip) {
+            ip_in_sg = true;
+            break;
         }
     }
 
-    Ok(CommonAccessResult::Unauthorized)
+    if !ip_in_sg {
+        return Ok(AccessCheckResult::NotInSecurityGroup(sg));
+    }
+
+    /* a token for another vault is a valid identity without the scope */
+    if claims.vault == vault && permission::contains_or_owner(&claims.permission, permission) {
+        Ok(AccessCheckResult::Authorized)
+    } else {
+        Ok(AccessCheckResult::Forbidden)
+    }
 }
 
 #[inline]
-fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
-    let host = host.context_app_err(|| AppError {
-        message: "missing IP from the request".to_owned(),
-        error: None,
-        attr: None,
-    })?;
+pub fn process_host_ip(req: &actix_web::HttpRequest) -> AppResult<std::net::IpAddr> {
+    /* peers on the Unix socket have no address, they're on the same machine */
+    if req.peer_addr().is_none() {
+        return Ok(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+    }
+
+    let connection_info = req.connection_info();
+    let host = connection_info
+        .realip_remote_addr()
+        .context_app_err(|| AppError {
+            message: "missing IP from the request".to_owned(),
+            error: None,
+            attr: None,
+            code: None,
+        })?;
 
     if let Some((host, _port)) = host.split_once(':') {
         Ok(host.parse().map_app_err(|_| AppError {
@@ -135,6 +457,7 @@ fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
             attr: Some(hashmap! {
                 "ip".to_owned() => host.to_string(),
             }),
+            code: None,
         })?)
     } else {
         Ok(host.parse().map_app_err(|_| AppError {
@@ -143,33 +466,206 @@ fn process_host_ip(host: Option<&str>) -> AppResult<std::net::IpAddr> {
             attr: Some(hashmap! {
                 "ip".to_owned() => host.to_string(),
             }),
+            code: None,
         })?)
     }
 }
 
+/// How a request identified itself, taken from its `Authorization` header.
+pub enum Credentials {
+    AccessKey(String, String),
+    Bearer(String),
+    Missing,
+}
+
+/// The `Authorization` header value that `parse_signature` reads back.
+pub fn format_signature(access_key: &str, secret_access_key: &str) -> String {
+    format!("VAULTY {}:{}", access_key, secret_access_key)
+}
+
+/// The access key and secret access key of a `VAULTY [access key]:[secret]` header.
+pub fn parse_signature(authorization: &str) -> Option<(String, String)> {
+    const MAGIC_AUTHORIZATION_WORD: &str = "VAULTY";
+
+    if authorization.len() >= MAGIC_AUTHORIZATION_WORD.len()
+        && authorization[0..MAGIC_AUTHORIZATION_WORD.len()]
+            .to_uppercase()
+            .starts_with(MAGIC_AUTHORIZATION_WORD)
+    {
+        let sig = authorization[MAGIC_AUTHORIZATION_WORD.len()..].trim();
+
+        if let Some((access_key, secret_access_key)) = sig.split_once(':') {
+            return Some((access_key.to_owned(), secret_access_key.to_owned()));
+        }
+    }
+
+    None
+}
+
+/// The token of a `Bearer [token]` header.
+pub fn parse_bearer(authorization: &str) -> Option<String> {
+    const MAGIC_AUTHORIZATION_WORD: &str = "BEARER";
+
+    if authorization.len() > MAGIC_AUTHORIZATION_WORD.len()
+        && authorization[0..MAGIC_AUTHORIZATION_WORD.len()]
+            .eq_ignore_ascii_case(MAGIC_AUTHORIZATION_WORD)
+    {
+        return Some(
+            authorization[MAGIC_AUTHORIZATION_WORD.len()..]
+                .trim()
+                .to_owned(),
+        );
+    }
+
+    None
+}
+
+/* the access key scheme stays the default, tokens only when configured */
+pub fn credentials(authorization: Option<&str>) -> Credentials {
+    let Some(authorization) = authorization else {
+        return Credentials::Missing;
+    };
+
+    if jwt::enabled() {
+        if let Some(token) = parse_bearer(authorization) {
+            return Credentials::Bearer(token);
+        }
+    }
+
+    match parse_signature(authorization) {
+        Some((access_key, secret_access_key)) => {
+            Credentials::AccessKey(access_key, secret_access_key)
+        }
+        None => Credentials::Missing,
+    }
+}
+
+#[inline]
+fn authorization_header(req: &actix_web::HttpRequest) -> Option<&str> {
+    req.headers().get("Authorization")?.to_str().ok()
+}
+
+#[inline]
+pub fn process_sig(req: &actix_web::HttpRequest) -> Option<(String, String)> {
+    parse_signature(authorization_header(req)?)
+}
+
 #[inline]
-fn process_sig(req: &actix_web::HttpRequest) -> Option<(String, String)> {
-    if let Some(authorization) = req.headers().get("Authorization") {
-        if let Ok(authorization) = authorization.to_str() {
-            const MAGIC_AUTHORIZATION_WORD: &str = "VAULTY";
-
-            if authorization.len() >= MAGIC_AUTHORIZATION_WORD.len()
-                && authorization[0..MAGIC_AUTHORIZATION_WORD.len()]
-                    .to_uppercase()
-                    .starts_with(MAGIC_AUTHORIZATION_WORD)
-            {
-                let sig = authorization[MAGIC_AUTHORIZATION_WORD.len()..].trim();
-
-                if let Some((access_key, secret_access_key)) = sig.split_once(':') {
-                    return Some((access_key.to_owned(), secret_access_key.to_owned()));
+pub fn process_bearer(req: &actix_web::HttpRequest) -> Option<String> {
+    parse_bearer(authorization_header(req)?)
+}
+
+fn bearer_request(
+    ip: std::net::IpAddr,
+    token: &str,
+    request_permission: permission::VaultRoles,
+    vault: &str,
+) -> Option<CommonAccessResult> {
+    let claims = match jwt::verify(token) {
+        Ok(claims) => claims,
+        Err(e) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": e,
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault
                 }
+            });
+
+            return Some(CommonAccessResult::Unauthorized);
+        }
+    };
+
+    match bearer_check(ip, &claims, request_permission, vault) {
+        Ok(AccessCheckResult::Authorized) => {
+            let counters = stats::access_key(vault, &claims.sub);
+            counters.request();
+
+            Some(CommonAccessResult::Authorized(
+                counters,
+                db::Actor::Token(claims.sub),
+                NameScope::default(),
+            ))
+        }
+        Ok(AccessCheckResult::NotInSecurityGroup(sg)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "ip is not in the security group".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "subject": claims.sub,
+                    "sg": sg
+                }
+            });
+
+            Some(CommonAccessResult::Unauthorized)
+        }
+        Ok(_) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "token doesn't grant the required permission".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "subject": claims.sub,
+                    "permission": format!("{:?}", request_permission)
+                }
+            });
+
+            /* nothing is granted here by a token for another vault */
+            if claims.vault == vault {
+                Some(CommonAccessResult::Forbidden(claims.permission))
+            } else {
+                Some(CommonAccessResult::Forbidden(Vec::new()))
             }
         }
-    }
+        Err(e) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "failed to check the access",
+                "err": e,
+                "tags": [
+                    "vault", "access", "error"
+                ],
+                "attr": {
+                    "ip": ip.to_string()
+                }
+            });
 
-    None
+            None
+        }
+    }
 }
 
+/* the actix side of `authorize`, `requester_ip` is set as soon as it's known */
 #[inline]
 fn initialize_request(
     req: &actix_web::HttpRequest,
@@ -177,7 +673,7 @@ fn initialize_request(
     vault: &str,
     requester_ip: &mut String,
 ) -> Option<CommonAccessResult> {
-    let ip = match process_host_ip(req.connection_info().realip_remote_addr()) {
+    let ip = match process_host_ip(req) {
         Ok(value) => value,
         Err(e) => {
             log!({
@@ -196,10 +692,26 @@ fn initialize_request(
 
     *requester_ip = ip.to_string();
 
-    let (access_key, secret_access_key) =
-        if let Some((access_key, secret_access_key)) = process_sig(&req) {
-            (access_key, secret_access_key)
-        } else {
+    authorize(
+        ip,
+        credentials(authorization_header(req)),
+        request_permission,
+        vault,
+    )
+}
+
+/// Checks the credentials of a request from `ip`, `None` when the check itself
+/// failed. A granted access key has its last use and counters updated.
+pub fn authorize(
+    ip: std::net::IpAddr,
+    credentials: Credentials,
+    request_permission: permission::VaultRoles,
+    vault: &str,
+) -> Option<CommonAccessResult> {
+    let (access_key, secret_access_key) = match credentials {
+        Credentials::AccessKey(access_key, secret_access_key) => (access_key, secret_access_key),
+        Credentials::Bearer(token) => return bearer_request(ip, &token, request_permission, vault),
+        Credentials::Missing => {
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "initial request processing",
@@ -207,7 +719,8 @@ fn initialize_request(
                 "err": AppError {
                     message: "invalid authrorize headers".to_owned(),
                     error: None,
-                    attr: None
+                    attr: None,
+                    code: None
                 },
                 "tags": [
                     "vault", "access", "denied"
@@ -218,7 +731,8 @@ fn initialize_request(
             });
 
             return Some(CommonAccessResult::Unauthorized);
-        };
+        }
+    };
 
     match access_check(
         ip,
@@ -227,7 +741,7 @@ fn initialize_request(
         request_permission,
         vault,
     ) {
-        Ok(CommonAccessResult::Authorized) => {
+        Ok((AccessCheckResult::Authorized, scope, _)) => {
             if let Err(e) = db::access::refresh_access_time(vault, &access_key) {
                 log!({
                     "mod": log::Module::Vault,
@@ -243,28 +757,155 @@ fn initialize_request(
                 });
             }
 
-            Some(CommonAccessResult::Authorized)
+            let counters = stats::access_key(vault, &access_key);
+            counters.request();
+
+            Some(CommonAccessResult::Authorized(
+                counters,
+                db::Actor::AccessKey(access_key),
+                scope,
+            ))
         }
-        Ok(CommonAccessResult::Unauthorized) => {
+        Ok((AccessCheckResult::UnknownAccessKey, _, _)) => {
             log!({
                 "mod": log::Module::Vault,
                 "ctx": "initial request processing",
                 "msg": "access was denied",
                 "err": AppError {
-                    message: "ip is not in the security group or invalid secret access key".to_owned(),
+                    message: "access key doesn't exist in the vault".to_owned(),
                     error: None,
-                    attr: None
+                    attr: None,
+                    code: None
                 },
                 "tags": [
                     "vault", "access", "denied"
                 ],
                 "attr": {
-                    "ip": ip.to_string()
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key
+                }
+            });
+
+            Some(CommonAccessResult::Unauthorized)
+        }
+        Ok((AccessCheckResult::NotInSecurityGroup(sg), _, _)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "ip is not in the security group".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key,
+                    "sg": sg
+                }
+            });
+
+            Some(CommonAccessResult::Unauthorized)
+        }
+        Ok((AccessCheckResult::InvalidSecretAccessKey, _, _)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "invalid secret access key".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key
                 }
             });
 
             Some(CommonAccessResult::Unauthorized)
         }
+        Ok((AccessCheckResult::Disabled, _, _)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "access key is disabled".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key
+                }
+            });
+
+            Some(CommonAccessResult::Unauthorized)
+        }
+        Ok((AccessCheckResult::Expired, _, _)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "access key has expired".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key
+                }
+            });
+
+            Some(CommonAccessResult::Unauthorized)
+        }
+        Ok((AccessCheckResult::Forbidden, _, granted)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "initial request processing",
+                "msg": "access was denied",
+                "err": AppError {
+                    message: "access key doesn't have the required permission".to_owned(),
+                    error: None,
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "access", "denied"
+                ],
+                "attr": {
+                    "ip": ip.to_string(),
+                    "vault": vault,
+                    "access_key": access_key,
+                    "permission": format!("{:?}", request_permission)
+                }
+            });
+
+            Some(CommonAccessResult::Forbidden(granted))
+        }
         Err(e) => {
             log!({
                 "mod": log::Module::Vault,
@@ -283,3 +924,368 @@ fn initialize_request(
         }
     }
 }
+
+#[inline]
+fn idempotency_key(req: &actix_web::HttpRequest) -> Result<Option<String>, ()> {
+    match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => match value.to_str() {
+            Ok(value) if !value.is_empty() && value.len() <= IDEMPOTENCY_KEY_MAX_LEN => {
+                Ok(Some(value.to_owned()))
+            }
+            _ => Err(()),
+        },
+        None => Ok(None),
+    }
+}
+
+/* "[vault]/[secret name]", split on the first slash as vault names can't hold one */
+fn alias_of(req: &actix_web::HttpRequest) -> Result<Option<db::secret::SecretAlias>, ()> {
+    match req.headers().get(ALIAS_OF_HEADER) {
+        Some(value) => match value.to_str().ok().and_then(|v| v.split_once('/')) {
+            Some((vault, secret_name)) if !vault.is_empty() && !secret_name.is_empty() => {
+                Ok(Some(db::secret::SecretAlias {
+                    vault: vault.to_owned(),
+                    secret_name: secret_name.to_owned(),
+                }))
+            }
+            _ => Err(()),
+        },
+        None => Ok(None),
+    }
+}
+
+/* reading through an alias takes DecryptSecrets where the value actually is,
+and a name within the prefixes the credential has there */
+fn may_read_alias_target(
+    req: &actix_web::HttpRequest,
+    scope: &NameScope,
+    vault: &str,
+    target_vault: &str,
+    target_name: &str,
+    ip: &mut String,
+) -> bool {
+    if target_vault == vault {
+        return in_scope(scope, target_vault, target_name, ip);
+    }
+
+    match initialize_request(
+        req,
+        permission::VaultRoles::DecryptSecrets,
+        target_vault,
+        ip,
+    ) {
+        Some(CommonAccessResult::Authorized(_, _, scope)) => {
+            in_scope(&scope, target_vault, target_name, ip)
+        }
+        _ => false,
+    }
+}
+
+/* an alias is only made to a secret the credential could read through it,
+so the answer doesn't tell whether a name exists where it has no rights */
+fn may_alias_target(
+    req: &actix_web::HttpRequest,
+    target_vault: &str,
+    target_name: &str,
+    ip: &mut String,
+) -> bool {
+    match initialize_request(
+        req,
+        permission::VaultRoles::DecryptSecrets,
+        target_vault,
+        ip,
+    ) {
+        Some(CommonAccessResult::Authorized(_, _, scope)) => {
+            in_scope(&scope, target_vault, target_name, ip)
+        }
+        _ => false,
+    }
+}
+
+fn find_idempotent_status(
+    vault: &str,
+    secret_name: &str,
+    idempotency_key: &str,
+) -> AppResult<Option<actix_web::http::StatusCode>> {
+    let Some(document) = db::idempotency::find(vault, secret_name, idempotency_key)? else {
+        return Ok(None);
+    };
+
+    /* expired records may still be around until the next sweep */
+    let fresh = chrono::DateTime::parse_from_rfc3339(&document.created)
+        .map(|v| v + chrono::Duration::seconds(IDEMPOTENCY_KEY_TTL_SECS) > clock::now())
+        .unwrap_or(false);
+
+    if !fresh {
+        return Ok(None);
+    }
+
+    Ok(actix_web::http::StatusCode::from_u16(document.status).ok())
+}
+
+fn store_idempotent_status(
+    vault: &str,
+    secret_name: &str,
+    idempotency_key: Option<&str>,
+    status: actix_web::http::StatusCode,
+    ip: &str,
+) {
+    let Some(idempotency_key) = idempotency_key else {
+        return;
+    };
+
+    let document = db::idempotency::IdempotencyDocument {
+        created: clock::now().to_rfc3339(),
+        status: status.as_u16(),
+    };
+
+    /* the secret is already stored, a retry without the record inserts it again */
+    if let Err(e) = db::idempotency::insert(vault, secret_name, idempotency_key, document) {
+        log!({
+            "mod": log::Module::Vault,
+            "ctx": "request to insert secrets",
+            "msg": "failed to store the idempotency key",
+            "err": e,
+            "tags": [
+                "vault", "db", "error"
+            ],
+            "attr": {
+                "ip": ip,
+                "ns": vault,
+                "secret": secret_name
+            }
+        });
+    }
+}
+
+#[inline]
+fn record_access(vault: &str, secret_name: &str, document: &db::secret::SecretDocument, ip: &str) {
+    let Some(interval) = config::get_clone().secrets.last_accessed_interval_secs else {
+        return;
+    };
+
+    let time_now = clock::now();
+
+    /* a write per read is costly, the timestamp is only moved once per interval */
+    let recent = document
+        .last_accessed
+        .as_deref()
+        .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+        .is_some_and(|v| {
+            time_now.signed_duration_since(v) < chrono::Duration::seconds(interval as i64)
+        });
+
+    if recent {
+        return;
+    }
+
+    if let Err(e) = db::secret::set_last_accessed(vault, secret_name, time_now.to_rfc3339()) {
+        log!({
+            "mod": log::Module::Vault,
+            "ctx": "request to retrieve secrets",
+            "msg": "failed to store the last access time",
+            "err": e,
+            "tags": [
+                "vault", "db", "error"
+            ],
+            "attr": {
+                "ip": ip,
+                "ns": vault,
+                "secret": secret_name
+            }
+        });
+    }
+}
+
+#[inline]
+fn pad_not_found(vault: &str, secret_name: &str, ip: &str) {
+    if !config::get_clone().secrets.pad_not_found.unwrap_or(false) {
+        return;
+    }
+
+    /* a missing name would otherwise answer without the decrypt a found one pays for */
+    if let Err(e) = secrets::decrypt_decoy() {
+        log!({
+            "mod": log::Module::Vault,
+            "ctx": "request to retrieve secrets",
+            "msg": "failed to decrypt the decoy secret",
+            "err": e,
+            "tags": [
+                "vault", "error"
+            ],
+            "attr": {
+                "ip": ip,
+                "ns": vault,
+                "secret": secret_name
+            }
+        });
+    }
+}
+
+pub fn sweep_idempotency_keys() -> AppResult<usize> {
+    db::idempotency::sweep(clock::now() - chrono::Duration::seconds(IDEMPOTENCY_KEY_TTL_SECS))
+}
+
+pub fn watch_idempotency_keys() {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            IDEMPOTENCY_SWEEP_INTERVAL_SECS,
+        ));
+
+        loop {
+            interval.tick().await;
+
+            match sweep_idempotency_keys() {
+                Ok(0) => {}
+                Ok(swept) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping idempotency keys",
+                        "msg": "expired idempotency keys removed",
+                        "tags": [
+                            "vault", "db"
+                        ],
+                        "attr": {
+                            "swept": swept
+                        }
+                    });
+                }
+                Err(e) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping idempotency keys",
+                        "msg": "failed to remove expired idempotency keys",
+                        "err": e,
+                        "tags": [
+                            "vault", "db", "error"
+                        ]
+                    });
+                }
+            }
+        }
+    });
+}
+
+#[inline]
+pub fn soft_delete() -> bool {
+    config::get_clone()
+        .secrets
+        .soft_delete_retention_secs
+        .is_some()
+}
+
+/// Whether a secret name may be held by one vault only.
+#[inline]
+pub fn unique_names() -> bool {
+    config::get_clone().secrets.unique_names.unwrap_or(false)
+}
+
+/// Secrets deleted before the returned time are past the retention window,
+/// `None` when soft delete is turned off.
+pub fn deleted_secrets_cutoff() -> Option<chrono::DateTime<chrono::Utc>> {
+    config::get_clone()
+        .secrets
+        .soft_delete_retention_secs
+        .map(|v| clock::now() - chrono::Duration::seconds(v as i64))
+}
+
+pub fn sweep_deleted_secrets() -> AppResult<usize> {
+    match deleted_secrets_cutoff() {
+        Some(cutoff) => db::secret::sweep_deleted(cutoff),
+        None => Ok(0),
+    }
+}
+
+pub fn watch_deleted_secrets() {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            DELETED_SECRETS_SWEEP_INTERVAL_SECS,
+        ));
+
+        loop {
+            interval.tick().await;
+
+            match sweep_deleted_secrets() {
+                Ok(0) => {}
+                Ok(swept) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping deleted secrets",
+                        "msg": "expired deleted secrets removed",
+                        "tags": [
+                            "vault", "db"
+                        ],
+                        "attr": {
+                            "swept": swept
+                        }
+                    });
+                }
+                Err(e) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping deleted secrets",
+                        "msg": "failed to remove expired deleted secrets",
+                        "err": e,
+                        "tags": [
+                            "vault", "db", "error"
+                        ]
+                    });
+                }
+            }
+        }
+    });
+}
+
+/// Uploads started before the returned time are past their TTL.
+pub fn uploads_cutoff() -> chrono::DateTime<chrono::Utc> {
+    let ttl = config::get_clone()
+        .secrets
+        .upload_ttl_secs
+        .unwrap_or(DEFAULT_UPLOAD_TTL_SECS);
+
+    clock::now() - chrono::Duration::seconds(ttl as i64)
+}
+
+pub fn sweep_uploads() -> AppResult<usize> {
+    db::upload::sweep(uploads_cutoff())
+}
+
+pub fn watch_uploads() {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            UPLOADS_SWEEP_INTERVAL_SECS,
+        ));
+
+        loop {
+            interval.tick().await;
+
+            match sweep_uploads() {
+                Ok(0) => {}
+                Ok(swept) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping uploads",
+                        "msg": "expired uploads removed",
+                        "tags": [
+                            "vault", "db"
+                        ],
+                        "attr": {
+                            "swept": swept
+                        }
+                    });
+                }
+                Err(e) => {
+                    log!({
+                        "mod": log::Module::Vault,
+                        "ctx": "sweeping uploads",
+                        "msg": "failed to remove expired uploads",
+                        "err": e,
+                        "tags": [
+                            "vault", "db", "error"
+                        ]
+                    });
+                }
+            }
+        }
+    });
+}
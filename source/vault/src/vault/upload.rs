@@ -0,0 +1,400 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    access_keys,
+    app_error::{AppError, AppResult},
+    clock, config, db, log, permission, secrets, stats, vault, webhook,
+};
+
+pub const UPLOAD_OFFSET_HEADER: &str = "Upload-Offset";
+
+const UPLOAD_ID_LENGTH: usize = 32;
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(serde::Serialize)]
+struct ResponseStarted {
+    id: String,
+}
+
+#[inline]
+async fn authorize(
+    req: &actix_web::HttpRequest,
+    ns: &str,
+    secret_name: &str,
+    ip: &mut String,
+) -> Result<(std::sync::Arc<stats::Counters>, db::Actor), actix_web::HttpResponse> {
+    match vault::initialize_request(req, permission::VaultRoles::CreateSecrets, ns, ip) {
+        Some(vault::CommonAccessResult::Authorized(counters, actor, scope)) => {
+            if !vault::in_scope(&scope, ns, secret_name, ip) {
+                access_keys::delay().await;
+                return Err(actix_web::HttpResponse::Forbidden().finish());
+            }
+
+            access_keys::delay_granted().await;
+
+            Ok((counters, actor))
+        }
+        Some(vault::CommonAccessResult::Unauthorized) => {
+            access_keys::delay().await;
+            Err(actix_web::HttpResponse::Unauthorized().finish())
+        }
+        Some(vault::CommonAccessResult::Forbidden(granted)) => {
+            access_keys::delay().await;
+            Err(vault::forbidden_response(&granted))
+        }
+        None => {
+            access_keys::delay().await;
+            Err(actix_web::HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/* an upload past its TTL may still be around until the next sweep */
+#[inline]
+fn find_upload(
+    ns: &str,
+    secret_name: &str,
+    upload_id: &str,
+) -> AppResult<Option<db::upload::UploadDocument>> {
+    let Some(document) = db::upload::find(ns, secret_name, upload_id)? else {
+        return Ok(None);
+    };
+
+    let fresh = chrono::DateTime::parse_from_rfc3339(&document.created)
+        .map(|v| v > vault::uploads_cutoff())
+        .unwrap_or(false);
+
+    Ok(fresh.then_some(document))
+}
+
+#[inline]
+fn upload_offset(req: &actix_web::HttpRequest) -> Option<usize> {
+    req.headers()
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+#[actix_web::post("/{vault}/{secret_name}/uploads")]
+pub async fn req_upload_start(
+    path: actix_web::web::Path<(String, String)>,
+    req: actix_web::HttpRequest,
+) -> impl actix_web::Responder {
+    let ns = path.0.clone();
+    let secret_name = path.1.clone();
+    let mut ip = "N/A".to_owned();
+
+    if let Err(response) = authorize(&req, &ns, &secret_name, &mut ip).await {
+        return response;
+    }
+
+    if !db::is_valid_name(&ns) || !db::is_valid_name(&secret_name) {
+        return actix_web::HttpResponse::BadRequest().body("invalid vault or secret name");
+    }
+
+    let upload_id =
+        secrets::random_string(secrets::Charset::Alphanumeric.chars(), UPLOAD_ID_LENGTH);
+
+    let document = db::upload::UploadDocument {
+        created: clock::now().to_rfc3339(),
+        length: 0,
+        data: String::new(),
+    };
+
+    if let Err(e) = db::upload::insert(&ns, &secret_name, &upload_id, document) {
+        let response = vault::failure_response(&e);
+
+        log!({
+            "mod": log::Module::Vault,
+            "ctx": "request to upload secrets",
+            "msg": "failed to start the upload",
+            "err": e,
+            "tags": [
+                "vault", "db", "error"
+            ],
+            "attr": {
+                "ip": ip,
+                "ns": ns,
+                "secret": secret_name
+            }
+        });
+
+        return response;
+    }
+
+    log!({
+        "mod": log::Module::Vault,
+        "ctx": "request to upload secrets",
+        "msg": "upload started",
+        "tags": [
+            "vault", "request"
+        ],
+        "attr": {
+            "ip": ip,
+            "ns": ns,
+            "secret": secret_name
+        }
+    });
+
+    actix_web::HttpResponse::Created()
+        .insert_header((
+            actix_web::http::header::LOCATION,
+            format!("{}/{}", req.path(), upload_id),
+        ))
+        .insert_header((UPLOAD_OFFSET_HEADER, "0"))
+        .content_type("application/json")
+        .body(simd_json::to_string(&ResponseStarted { id: upload_id }).unwrap_or_default())
+}
+
+#[actix_web::patch("/{vault}/{secret_name}/uploads/{upload_id}")]
+pub async fn req_upload_append(
+    path: actix_web::web::Path<(String, String, String)>,
+    req: actix_web::HttpRequest,
+    data: actix_web::web::Bytes,
+) -> impl actix_web::Responder {
+    let ns = path.0.clone();
+    let secret_name = path.1.clone();
+    let upload_id = path.2.clone();
+    let mut ip = "N/A".to_owned();
+
+    let counters = match authorize(&req, &ns, &secret_name, &mut ip).await {
+        Ok((counters, _)) => counters,
+        Err(response) => return response,
+    };
+
+    counters.received(data.len());
+
+    let Some(offset) = upload_offset(&req) else {
+        return actix_web::HttpResponse::BadRequest().body("missing or invalid Upload-Offset");
+    };
+
+    let max_bytes = config::get_clone()
+        .secrets
+        .max_upload_bytes
+        .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES);
+
+    let result = match find_upload(&ns, &secret_name, &upload_id) {
+        Ok(Some(_)) => db::upload::append(&ns, &secret_name, &upload_id, offset, &data, max_bytes),
+        Ok(None) => Ok(db::upload::AppendUploadResult::NotFound),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(db::upload::AppendUploadResult::Appended(length)) => {
+            actix_web::HttpResponse::NoContent()
+                .insert_header((UPLOAD_OFFSET_HEADER, length.to_string()))
+                .finish()
+        }
+        Ok(db::upload::AppendUploadResult::OffsetMismatch(length)) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to upload secrets",
+                "msg": "chunk doesn't start where the upload ends",
+                "tags": [
+                    "vault", "request", "failed"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name,
+                    "offset": offset,
+                    "expected": length
+                }
+            });
+
+            actix_web::HttpResponse::Conflict()
+                .insert_header((UPLOAD_OFFSET_HEADER, length.to_string()))
+                .finish()
+        }
+        Ok(db::upload::AppendUploadResult::TooLarge) => {
+            actix_web::HttpResponse::PayloadTooLarge().finish()
+        }
+        Ok(db::upload::AppendUploadResult::NotFound) => {
+            actix_web::HttpResponse::NotFound().finish()
+        }
+        Err(e) => {
+            let response = vault::failure_response(&e);
+
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to upload secrets",
+                "msg": "failed to append the chunk",
+                "err": e,
+                "tags": [
+                    "vault", "db", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
+
+            response
+        }
+    }
+}
+
+#[actix_web::post("/{vault}/{secret_name}/uploads/{upload_id}/complete")]
+pub async fn req_upload_complete(
+    path: actix_web::web::Path<(String, String, String)>,
+    req: actix_web::HttpRequest,
+) -> impl actix_web::Responder {
+    let ns = path.0.clone();
+    let secret_name = path.1.clone();
+    let upload_id = path.2.clone();
+    let mut ip = "N/A".to_owned();
+
+    let actor = match authorize(&req, &ns, &secret_name, &mut ip).await {
+        Ok((_, actor)) => actor,
+        Err(response) => return response,
+    };
+
+    let document = match find_upload(&ns, &secret_name, &upload_id) {
+        Ok(Some(document)) => document,
+        Ok(None) => return actix_web::HttpResponse::NotFound().finish(),
+        Err(e) => {
+            let response = vault::failure_response(&e);
+
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to upload secrets",
+                "msg": "failed to retrive the upload",
+                "err": e,
+                "tags": [
+                    "vault", "db", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
+
+            return response;
+        }
+    };
+
+    let data = match base64_simd::STANDARD.decode_to_vec(&document.data) {
+        Ok(value) => value,
+        Err(e) => {
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to upload secrets",
+                "msg": "failed to decode the upload",
+                "err": AppError {
+                    message: "invalid base64 encoding".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None
+                },
+                "tags": [
+                    "vault", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
+
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if data.is_empty() {
+        return actix_web::HttpResponse::UnprocessableEntity().finish();
+    }
+
+    if let Some(response) = vault::value_schema_response(&ns, &secret_name, &data, &ip) {
+        return response;
+    }
+
+    let (response, status) = match super::insert::store_secret(&ns, &secret_name, &data, actor) {
+        Ok(db::secret::InsertSecretResult::Inserted) => {
+            (actix_web::HttpResponse::Created().finish(), "inserted")
+        }
+        Ok(db::secret::InsertSecretResult::Updated) => {
+            (actix_web::HttpResponse::Ok().finish(), "updated")
+        }
+        Ok(db::secret::InsertSecretResult::InvalidName) => {
+            return actix_web::HttpResponse::BadRequest().body("invalid vault or secret name");
+        }
+        Ok(db::secret::InsertSecretResult::NameTaken) => {
+            return actix_web::HttpResponse::Conflict()
+                .body("secret name is used by another vault");
+        }
+        Err(e) => {
+            let response = vault::failure_response(&e);
+
+            log!({
+                "mod": log::Module::Vault,
+                "ctx": "request to upload secrets",
+                "msg": "failed to insert the uploaded secret",
+                "err": e,
+                "tags": [
+                    "vault", "db", "error"
+                ],
+                "attr": {
+                    "ip": ip,
+                    "ns": ns,
+                    "secret": secret_name
+                }
+            });
+
+            return response;
+        }
+    };
+
+    webhook::notify(&ns, webhook::Action::InsertSecret, &secret_name);
+
+    /* the secret is stored, a leftover upload is swept once its TTL passes */
+    if let Err(e) = db::upload::delete(&ns, &secret_name, &upload_id) {
+        log!({
+            "mod": log::Module::Vault,
+            "ctx": "request to upload secrets",
+            "msg": "failed to remove the completed upload",
+            "err": e,
+            "tags": [
+                "vault", "db", "error"
+            ],
+            "attr": {
+                "ip": ip.clone(),
+                "ns": ns.clone(),
+                "secret": secret_name.clone()
+            }
+        });
+    }
+
+    log!({
+        "mod": log::Module::Vault,
+        "ctx": "request to upload secrets",
+        "msg": "uploaded secret inserted",
+        "tags": [
+            "vault", "request"
+        ],
+        "attr": {
+            "ip": ip,
+            "ns": ns,
+            "secret": secret_name,
+            "status": status
+        }
+    });
+
+    response
+}
@@ -0,0 +1,292 @@
+/*
+Copyright (C) 2024  S. Ivanov
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use maplit::hashmap;
+
+use crate::{
+    app_error::{AppError, AppErrorResult, AppResult},
+    clock, config, log,
+};
+
+const DEFAULT_RETRIES: u32 = 3;
+const TIMEOUT_SECS: u64 = 10;
+
+/// Where the events are delivered, with the client holding its connections.
+pub struct Webhook {
+    url: url::Url,
+    secret: String,
+    retries: u32,
+    client: reqwest::Client,
+}
+
+static mut WEBHOOK: Option<Webhook> = None;
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub enum Action {
+    #[serde(rename = "secret.insert")]
+    InsertSecret,
+    #[serde(rename = "secret.delete")]
+    DeleteSecret,
+    #[serde(rename = "secret.restore")]
+    RestoreSecret,
+    #[serde(rename = "access.insert")]
+    InsertAccessKey,
+    #[serde(rename = "access.delete")]
+    DeleteAccessKey,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Event {
+    vault: String,
+    action: Action,
+    name: String,
+    timestamp: String,
+}
+
+impl Event {
+    pub fn new(vault: &str, action: Action, name: &str) -> Event {
+        Event {
+            vault: vault.to_owned(),
+            action,
+            name: name.to_owned(),
+            timestamp: clock::now().to_rfc3339(),
+        }
+    }
+}
+
+fn load_ca_certificates(filename: &str) -> AppResult<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read(filename).map_app_err(|e| AppError {
+        message: "failed to open the CA certificate".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned()
+        }),
+        code: None,
+    })?;
+
+    reqwest::Certificate::from_pem_bundle(&pem).map_app_err(|e| AppError {
+        message: "failed to read the CA certificate".to_owned(),
+        error: Some(e.to_string()),
+        attr: Some(hashmap! {
+            "filename".to_owned() => filename.to_owned()
+        }),
+        code: None,
+    })
+}
+
+impl Webhook {
+    pub fn load(webhook: &config::ConfigWebhook) -> AppResult<Webhook> {
+        let url = url::Url::parse(&webhook.url).map_app_err(|e| AppError {
+            message: "invalid webhook URL".to_owned(),
+            error: Some(e.to_string()),
+            attr: Some(hashmap! {
+                "url".to_owned() => webhook.url.clone()
+            }),
+            code: None,
+        })?;
+
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(AppError {
+                message: "webhook URL must be HTTP or HTTPS".to_owned(),
+                error: None,
+                attr: Some(hashmap! {
+                    "url".to_owned() => webhook.url.clone()
+                }),
+                code: None,
+            });
+        }
+
+        let mut client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+            .use_rustls_tls();
+
+        if let Some(filename) = &webhook.ca_certificate {
+            for certificate in load_ca_certificates(filename)? {
+                client = client.add_root_certificate(certificate);
+            }
+        }
+
+        let client = client.build().map_app_err(|e| AppError {
+            message: "failed to create the webhook client".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+        Ok(Webhook {
+            url,
+            secret: webhook.secret.clone(),
+            retries: webhook.retries.unwrap_or(DEFAULT_RETRIES),
+            client,
+        })
+    }
+}
+
+pub fn initialize() -> AppResult<()> {
+    let config_clone = config::get_clone();
+
+    if let Some(webhook) = &config_clone.webhook {
+        let webhook = Webhook::load(webhook)?;
+
+        unsafe {
+            WEBHOOK = Some(webhook);
+        }
+    }
+
+    Ok(())
+}
+
+fn sign_payload(secret: &str, payload: &str) -> AppResult<String> {
+    let key = openssl::pkey::PKey::hmac(secret.as_bytes()).map_app_err(|e| AppError {
+        message: "failed to create the HMAC key".to_owned(),
+        error: Some(e.to_string()),
+        attr: None,
+        code: None,
+    })?;
+
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)
+        .map_app_err(|e| AppError {
+            message: "failed to create the HMAC signer".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    let signature = signer
+        .sign_oneshot_to_vec(payload.as_bytes())
+        .map_app_err(|e| AppError {
+            message: "failed to sign the webhook event".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    Ok(signature.iter().map(|v| format!("{v:02x}")).collect())
+}
+
+/// The signature a delivery carries, of the timestamp it's sent with and the
+/// body, so a captured delivery can't be replayed once the timestamp is stale.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> AppResult<String> {
+    sign_payload(secret, &format!("{timestamp}.{body}"))
+}
+
+async fn post(webhook: &Webhook, body: &str) -> AppResult<u16> {
+    let timestamp = clock::now().timestamp();
+
+    let response = webhook
+        .client
+        .post(webhook.url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Vaulty-Timestamp", timestamp.to_string())
+        .header(
+            "X-Vaulty-Signature",
+            format!("sha256={}", sign(&webhook.secret, timestamp, body)?),
+        )
+        .body(body.to_owned())
+        .send()
+        .await
+        .map_app_err(|e| AppError {
+            message: "failed to send the webhook request".to_owned(),
+            error: Some(e.to_string()),
+            attr: None,
+            code: None,
+        })?;
+
+    Ok(response.status().as_u16())
+}
+
+/// Posts the event, retrying an unsuccessful delivery with a doubling backoff.
+pub async fn deliver(webhook: &Webhook, event: Event) {
+    let body = match simd_json::to_string(&event) {
+        Ok(value) => value,
+        Err(e) => {
+            log!({
+                mod: log::Module::Webhook,
+                ctx: "delivering an event",
+                msg: "failed to serialize the event",
+                err: AppError {
+                    message: "failed to serialize the event".to_owned(),
+                    error: Some(e.to_string()),
+                    attr: None,
+                    code: None
+                },
+                tags: [
+                    "webhook", "error"
+                ],
+            });
+
+            return;
+        }
+    };
+
+    let mut backoff = tokio::time::Duration::from_secs(1);
+
+    for attempt in 0..=webhook.retries {
+        let e = match post(webhook, &body).await {
+            Ok(status) if (200..300).contains(&status) => return,
+            Ok(status) => AppError {
+                message: "webhook answered with an unsuccessful status".to_owned(),
+                error: None,
+                attr: Some(hashmap! {
+                    "status".to_owned() => status.to_string()
+                }),
+                code: None,
+            },
+            Err(e) => e,
+        };
+
+        let last_attempt = attempt == webhook.retries;
+
+        log!({
+            mod: log::Module::Webhook,
+            ctx: "delivering an event",
+            msg: if last_attempt { "failed to deliver the event, giving up" } else { "failed to deliver the event, retrying" },
+            err: e,
+            tags: [
+                "webhook", "error"
+            ],
+            attr: {
+                vault: event.vault.clone(),
+                name: event.name.clone(),
+                attempt: attempt + 1,
+            }
+        });
+
+        if !last_attempt {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+pub fn notify(vault: &str, action: Action, name: &str) {
+    if unsafe { WEBHOOK.is_none() } {
+        return;
+    }
+
+    let event = Event::new(vault, action, name);
+
+    tokio::spawn(async move {
+        let webhook = unsafe {
+            WEBHOOK
+                .as_ref()
+                .expect("webhook.rs hasn't been initialized")
+        };
+
+        deliver(webhook, event).await;
+    });
+}